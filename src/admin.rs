@@ -0,0 +1,121 @@
+use crate::error::AppError;
+use actix_web::HttpRequest;
+use std::sync::OnceLock;
+
+/// 管理接口配置
+#[derive(Clone, Debug, Default)]
+pub struct AdminConfig {
+    /// 管理令牌，未设置时所有管理接口都会拒绝访问
+    pub token: Option<String>,
+}
+
+impl AdminConfig {
+    /// 从环境变量加载配置
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+
+        let token = std::env::var("ADMIN_TOKEN")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+
+        Self { token }
+    }
+
+    /// 校验请求携带的管理令牌（`Authorization: Bearer <token>` 或 `X-Admin-Token: <token>`）
+    pub fn verify(&self, req: &HttpRequest) -> Result<(), AppError> {
+        let Some(expected) = self.token.as_deref() else {
+            return Err(AppError::Unauthorized(
+                "管理接口未启用（未配置 ADMIN_TOKEN）".to_string(),
+            ));
+        };
+
+        let provided = req
+            .headers()
+            .get("Authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .or_else(|| {
+                req.headers()
+                    .get("X-Admin-Token")
+                    .and_then(|v| v.to_str().ok())
+            })
+            .map(str::trim);
+
+        match provided {
+            Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(()),
+            _ => Err(AppError::Unauthorized("管理令牌无效或缺失".to_string())),
+        }
+    }
+}
+
+// 常量时间比较，避免通过响应耗时猜测令牌
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+static ADMIN_CONFIG: OnceLock<AdminConfig> = OnceLock::new();
+
+/// 获取全局管理接口配置
+pub fn get_admin_config() -> &'static AdminConfig {
+    ADMIN_CONFIG.get_or_init(AdminConfig::from_env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn config_with_token() -> AdminConfig {
+        AdminConfig {
+            token: Some("secret".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_verify_bearer_token() {
+        let req = TestRequest::default()
+            .insert_header(("Authorization", "Bearer secret"))
+            .to_http_request();
+        assert!(config_with_token().verify(&req).is_ok());
+    }
+
+    #[test]
+    fn test_verify_admin_token_header() {
+        let req = TestRequest::default()
+            .insert_header(("X-Admin-Token", "secret"))
+            .to_http_request();
+        assert!(config_with_token().verify(&req).is_ok());
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_or_missing_token() {
+        let req = TestRequest::default()
+            .insert_header(("Authorization", "Bearer wrong"))
+            .to_http_request();
+        assert!(matches!(
+            config_with_token().verify(&req),
+            Err(AppError::Unauthorized(_))
+        ));
+
+        let req = TestRequest::default().to_http_request();
+        assert!(matches!(
+            config_with_token().verify(&req),
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_when_token_not_configured() {
+        let req = TestRequest::default()
+            .insert_header(("Authorization", "Bearer secret"))
+            .to_http_request();
+        assert!(matches!(
+            AdminConfig::default().verify(&req),
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+}
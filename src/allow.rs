@@ -0,0 +1,170 @@
+use crate::endpoints::enabled_openapi;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ResourceDef, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderValue, ALLOW};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{HttpRequest, HttpResponse};
+use std::sync::OnceLock;
+use utoipa::openapi::path::PathItem;
+
+/// 各路由支持的 HTTP 方法
+///
+/// 由 OpenAPI 文档生成，因此与实际注册的处理函数保持一致，新增端点时不需要单独维护。
+pub struct RouteMethods {
+    routes: Vec<(ResourceDef, Vec<&'static str>)>,
+}
+
+impl RouteMethods {
+    /// 从 OpenAPI 文档生成路由方法表
+    pub fn from_openapi(openapi: &utoipa::openapi::OpenApi) -> Self {
+        let routes = openapi
+            .paths
+            .paths
+            .iter()
+            .map(|(path, item)| (ResourceDef::new(path.as_str()), path_item_methods(item)))
+            .filter(|(_, methods)| !methods.is_empty())
+            .collect();
+        Self { routes }
+    }
+
+    /// 获取路径支持的方法（`Allow` 头的值，始终包含 OPTIONS），没有匹配的路由时返回 None
+    ///
+    /// 静态路由优先于带参数的路由，例如 `/repos/batch/map` 只返回批量查询支持的方法，
+    /// 而不会合并 `/repos/{owner}/{repo}` 的方法。
+    pub fn allow_header(&self, path: &str) -> Option<String> {
+        let matched: Vec<_> = self
+            .routes
+            .iter()
+            .filter(|(resource, _)| resource.is_match(path))
+            .collect();
+        let has_static = matched.iter().any(|(resource, _)| is_static(resource));
+        let mut methods: Vec<&str> = matched
+            .into_iter()
+            .filter(|(resource, _)| !has_static || is_static(resource))
+            .flat_map(|(_, methods)| methods.iter().copied())
+            .collect();
+        if methods.is_empty() {
+            return None;
+        }
+        methods.sort_by_key(|method| method_order(method));
+        methods.dedup();
+        methods.push("OPTIONS");
+        Some(methods.join(", "))
+    }
+}
+
+// 判断路由是否不包含动态参数
+fn is_static(resource: &ResourceDef) -> bool {
+    !resource.pattern().is_some_and(|pattern| pattern.contains('{'))
+}
+
+// 列出 OpenAPI 路径项中定义的方法
+fn path_item_methods(item: &PathItem) -> Vec<&'static str> {
+    [
+        ("GET", item.get.is_some()),
+        ("POST", item.post.is_some()),
+        ("PUT", item.put.is_some()),
+        ("PATCH", item.patch.is_some()),
+        ("DELETE", item.delete.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(method, defined)| defined.then_some(method))
+    .collect()
+}
+
+// Allow 头中方法的排列顺序
+fn method_order(method: &str) -> usize {
+    ["GET", "POST", "PUT", "PATCH", "DELETE"]
+        .iter()
+        .position(|m| *m == method)
+        .unwrap_or(usize::MAX)
+}
+
+static ROUTE_METHODS: OnceLock<RouteMethods> = OnceLock::new();
+
+/// 获取全局路由方法表（不包含已禁用的端点）
+pub fn get_route_methods() -> &'static RouteMethods {
+    ROUTE_METHODS.get_or_init(|| RouteMethods::from_openapi(&enabled_openapi()))
+}
+
+/// `Allow` 头中间件
+///
+/// - OPTIONS 请求：路由本身没有处理时返回 204 并带上 `Allow` 头；CORS 预检等已处理的响应补充 `Allow` 头
+/// - 其他请求返回 405 时同样补充 `Allow` 头
+pub async fn allow_header_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let is_options = req.method() == Method::OPTIONS;
+    let allow = get_route_methods().allow_header(req.path());
+
+    let mut res = next.call(req).await?;
+    let Some(allow) = allow.and_then(|allow| HeaderValue::from_str(&allow).ok()) else {
+        return Ok(res.map_into_left_body());
+    };
+
+    let unhandled = matches!(
+        res.status(),
+        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+    );
+    if is_options && unhandled {
+        let response = HttpResponse::NoContent()
+            .insert_header((ALLOW, allow))
+            .finish();
+        return Ok(res.into_response(response).map_into_right_body());
+    }
+
+    if is_options || res.status() == StatusCode::METHOD_NOT_ALLOWED {
+        res.headers_mut().insert(ALLOW, allow);
+    }
+    Ok(res.map_into_left_body())
+}
+
+/// 没有匹配任何路由时的处理函数（`App::default_service`）
+///
+/// 路径存在但方法不支持时返回 405 并带上 `Allow` 头，否则返回 404；响应体与其他错误响应格式一致。
+pub async fn unmatched_route(req: HttpRequest) -> HttpResponse {
+    match get_route_methods().allow_header(req.path()) {
+        Some(allow) => HttpResponse::MethodNotAllowed()
+            .insert_header((ALLOW, allow))
+            .json(serde_json::json!({
+                "error": format!("不支持的请求方法: {} {}", req.method(), req.path()),
+                "code": "method_not_allowed"
+            })),
+        None => HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("路径不存在: {}", req.path()),
+            "code": "not_found"
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allow_header_for_get_routes() {
+        let routes = get_route_methods();
+        assert_eq!(routes.allow_header("/repos/owner/repo").as_deref(), Some("GET, OPTIONS"));
+        assert_eq!(
+            routes.allow_header("/repos/owner/repo/compare/v1...v2").as_deref(),
+            Some("GET, OPTIONS")
+        );
+        assert_eq!(routes.allow_header("/download").as_deref(), Some("GET, OPTIONS"));
+    }
+
+    #[test]
+    fn test_allow_header_prefers_static_routes() {
+        let routes = get_route_methods();
+        assert_eq!(routes.allow_header("/repos/batch").as_deref(), Some("POST, OPTIONS"));
+        assert_eq!(routes.allow_header("/repos/batch/map").as_deref(), Some("POST, OPTIONS"));
+        assert_eq!(routes.allow_header("/repos/batch/latest").as_deref(), Some("POST, OPTIONS"));
+        assert_eq!(routes.allow_header("/repos/watch/sse").as_deref(), Some("GET, OPTIONS"));
+    }
+
+    #[test]
+    fn test_allow_header_unknown_path() {
+        assert_eq!(get_route_methods().allow_header("/unknown/path/here/x/y"), None);
+    }
+}
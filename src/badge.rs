@@ -0,0 +1,108 @@
+/// 徽章左侧的标签文字
+pub const BADGE_LABEL: &str = "release";
+
+/// 没有 release 时右侧显示的文字
+pub const BADGE_NONE: &str = "none";
+
+/// 徽章响应的缓存时间（秒），较短以便新版本发布后徽章尽快更新
+pub const BADGE_MAX_AGE_SECS: u64 = 300;
+
+// 支持的颜色名称（与 shields.io 一致）
+const NAMED_COLORS: &[(&str, &str)] = &[
+    ("brightgreen", "#4c1"),
+    ("green", "#97ca00"),
+    ("yellowgreen", "#a4a61d"),
+    ("yellow", "#dfb317"),
+    ("orange", "#fe7d37"),
+    ("red", "#e05d44"),
+    ("blue", "#007ec6"),
+    ("lightgrey", "#9f9f9f"),
+    ("grey", "#555"),
+];
+
+/// 有 release 时的默认颜色（blue）
+pub const DEFAULT_COLOR: &str = "#007ec6";
+
+/// 没有 release 时的颜色（lightgrey）
+pub const NONE_COLOR: &str = "#9f9f9f";
+
+/// 解析 `color` 查询参数：支持颜色名称（如 `green`）或 3/6 位十六进制颜色（如 `4c1`、`#ff8800`），
+/// 返回 SVG 中使用的颜色值，无法识别时返回 Err
+pub fn parse_color(color: &str) -> Result<String, String> {
+    let normalized = color.trim().to_lowercase();
+    if let Some((_, hex)) = NAMED_COLORS.iter().find(|(name, _)| *name == normalized) {
+        return Ok(hex.to_string());
+    }
+    let hex = normalized.strip_prefix('#').unwrap_or(&normalized);
+    if matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(format!("#{}", hex))
+    } else {
+        Err(color.to_string())
+    }
+}
+
+// 按字符数估算文字宽度（11px Verdana 的平均字符宽度约 7px），两侧各留 5px 边距
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+// 转义 SVG 文本中的特殊字符
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 渲染 shields.io flat 风格的徽章（左侧灰底标签，右侧 `color` 底色的值）
+pub fn render_badge(label: &str, value: &str, color: &str) -> String {
+    let label_width = text_width(label);
+    let value_width = text_width(value);
+    let width = label_width + value_width;
+    let label_x = label_width as f64 / 2.0;
+    let value_x = label_width as f64 + value_width as f64 / 2.0;
+    let label = escape_xml(label);
+    let value = escape_xml(value);
+    let color = escape_xml(color);
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}"><title>{label}: {value}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11"><text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text><text x="{label_x}" y="14">{label}</text><text x="{value_x}" y="15" fill="#010101" fill-opacity=".3">{value}</text><text x="{value_x}" y="14">{value}</text></g></svg>"##
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("Green"), Ok("#97ca00".to_string()));
+        assert_eq!(parse_color("4c1"), Ok("#4c1".to_string()));
+        assert_eq!(parse_color("#FF8800"), Ok("#ff8800".to_string()));
+        assert!(parse_color("ff88").is_err());
+        assert!(parse_color("url(#x)").is_err());
+    }
+
+    #[test]
+    fn test_render_badge() {
+        let svg = render_badge("release", "v1.0.0", "#007ec6");
+        assert!(svg.starts_with("<svg"));
+        // 宽度随文字长度变化：7 * 7 + 10 + 6 * 7 + 10
+        assert!(svg.contains(r#"width="111""#));
+        assert!(svg.contains(r##"fill="#007ec6""##));
+        assert!(svg.contains(">v1.0.0</text>"));
+
+        // tag 中的特殊字符会被转义
+        let svg = render_badge("release", "<v1&2>", "#007ec6");
+        assert!(svg.contains("&lt;v1&amp;2&gt;"));
+        assert!(!svg.contains("<v1"));
+    }
+}
@@ -1,24 +1,319 @@
-use crate::models::{LatestReleaseInfo, ReleaseInfo, RepoInfo};
+use crate::cache_backend::CacheBackend;
+use crate::download::get_download_config;
+use crate::error::AppError;
+use crate::metrics::cache_metrics;
+use crate::models::{
+    CachedFileSort, CachedFileSummary, CachedRepoSummary, ChecksumsInfo, CompareInfo, GeneratedNotes, LatestReleaseInfo,
+    MilestoneInfo, ReleaseInfo, RepoInfo,
+};
+use crate::single_flight::SingleFlight;
+use crate::telemetry::record_cache_hit;
+use async_trait::async_trait;
 use log;
 use moka::future::Cache;
+use moka::policy::EvictionPolicy;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 use tokio::time::interval;
 use sha2::{Sha256, Digest};
 
 // 缓存键类型
 type CacheKey = String;
 
+// 仓库文件内容的内存缓存容量（按 base64 内容的字节数计算），单个文件最大约 1.3MB
+const CONTENTS_CACHE_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+// 文件内容缓存条目的权重：文件按 base64 内容长度计算，目录列表等其他响应计为 1
+fn contents_weight(value: &serde_json::Value) -> u32 {
+    value["content"]
+        .as_str()
+        .map_or(1, |content| content.len().clamp(1, u32::MAX as usize) as u32)
+}
+
+// 解析缓存文件中的一类条目，跳过无法解析的条目（整类格式错误时计为一个）
+fn lenient_section<T: DeserializeOwned>(
+    root: &mut serde_json::Map<String, serde_json::Value>,
+    name: &str,
+    skipped: &mut usize,
+) -> HashMap<String, CachedEntry<T>> {
+    let entries = match root.remove(name) {
+        Some(serde_json::Value::Object(entries)) => entries,
+        Some(_) => {
+            *skipped += 1;
+            return HashMap::new();
+        }
+        None => return HashMap::new(),
+    };
+    entries
+        .into_iter()
+        .filter_map(|(key, value)| match serde_json::from_value(value) {
+            Ok(entry) => Some((key, entry)),
+            Err(e) => {
+                log::debug!("跳过缓存文件中无法解析的条目 {}: {}", key, e);
+                *skipped += 1;
+                None
+            }
+        })
+        .collect()
+}
+
+// 解析缓存文件内容
+//
+// 无法完整解析时把原文件移到 `<文件名>.corrupt.<时间戳>` 供排查（避免每次重启都静默丢失缓存而无人察觉），
+// 然后跳过格式错误的条目尽量恢复其余条目；完全无法解析时返回 None
+fn parse_cache_file(path: &Path, content: &str) -> Option<PersistentCache> {
+    let err = match serde_json::from_str::<PersistentCache>(content) {
+        Ok(cache) => return Some(cache),
+        Err(e) => e,
+    };
+    cache_metrics().record_cache_file_corruption();
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut corrupt_path = path.as_os_str().to_owned();
+    corrupt_path.push(format!(".corrupt.{}", now));
+    let corrupt_path = PathBuf::from(corrupt_path);
+    match std::fs::rename(path, &corrupt_path) {
+        Ok(()) => log::warn!(
+            "无法解析缓存文件 {}: {}，已移动到 {}",
+            path.display(),
+            err,
+            corrupt_path.display()
+        ),
+        Err(e) => log::warn!(
+            "无法解析缓存文件 {}: {}（移动到 {} 失败: {}）",
+            path.display(),
+            err,
+            corrupt_path.display(),
+            e
+        ),
+    }
+
+    match PersistentCache::parse_lenient(content) {
+        Some((cache, skipped)) => {
+            log::warn!(
+                "从损坏的缓存文件中恢复了 {} 个条目，跳过 {} 个无法解析的条目",
+                cache.len(),
+                skipped
+            );
+            Some(cache)
+        }
+        None => {
+            log::warn!("缓存文件不是有效的 JSON，无法恢复任何条目");
+            None
+        }
+    }
+}
+
+// gzip 文件的魔数
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// 将缓存文件内容转为文本：以 gzip 魔数开头时先解压（与文件名无关），解压中途失败时保留已解压的部分
+fn decode_cache_file(path: &Path, bytes: &[u8]) -> String {
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return String::from_utf8_lossy(bytes).into_owned();
+    }
+    let mut decoded = Vec::new();
+    if let Err(e) = GzDecoder::new(bytes).read_to_end(&mut decoded) {
+        log::warn!("无法解压缓存文件 {}: {}", path.display(), e);
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// 持久化缓存实际读写的文件：启用压缩时为 `<CACHE_FILE>.gz`，否则为 `CACHE_FILE` 本身；
+// 第二个值为另一种格式的文件
+fn persistent_cache_paths(base: &Path, compress: bool) -> (PathBuf, PathBuf) {
+    if compress {
+        (gzip_sibling_path(base), base.to_path_buf())
+    } else {
+        (base.to_path_buf(), gzip_sibling_path(base))
+    }
+}
+
+// 读取持久化缓存文件
+//
+// 当前格式的文件不存在时读取另一种格式的文件，切换 `CACHE_FILE_COMPRESS` 后不会丢失已有的缓存
+fn read_persistent_cache(base: &Path, compress: bool) -> Option<PersistentCache> {
+    let (primary, other) = persistent_cache_paths(base, compress);
+    for path in [primary, other] {
+        match std::fs::read(&path) {
+            Ok(bytes) => return parse_cache_file(&path, &decode_cache_file(&path, &bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                log::warn!("无法读取缓存文件 {}: {}", path.display(), e);
+                return None;
+            }
+        }
+    }
+    None
+}
+
+// 写入持久化缓存文件（紧凑 JSON，启用压缩时为 gzip）
+//
+// 写入成功后删除另一种格式的旧文件，避免之后切换配置时读到过时的缓存
+fn write_persistent_cache(base: &Path, compress: bool, cache: &PersistentCache) {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let json = match serde_json::to_vec(cache) {
+        Ok(json) => json,
+        Err(e) => {
+            log::warn!("无法序列化缓存: {}", e);
+            return;
+        }
+    };
+    let contents = if compress {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        match encoder.write_all(&json).and_then(|_| encoder.finish()) {
+            Ok(compressed) => compressed,
+            Err(e) => {
+                log::warn!("无法压缩缓存: {}", e);
+                return;
+            }
+        }
+    } else {
+        json
+    };
+
+    let (path, other) = persistent_cache_paths(base, compress);
+    if let Err(e) = write_cache_file(&path, contents) {
+        log::warn!("无法保存缓存文件: {}", e);
+        return;
+    }
+    if let Err(e) = std::fs::remove_file(&other) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("无法删除旧格式的缓存文件 {}: {}", other.display(), e);
+        }
+    }
+}
+
+/// GitHub 响应中用于重新验证缓存的凭据（Last-Modified 和 ETag）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    pub last_modified: Option<String>,
+    pub etag: Option<String>,
+}
+
 // 持久化缓存条目（带过期时间）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct CachedEntry<T> {
     value: T,
     expires_at: u64, // Unix 时间戳（秒）
+    #[serde(default)]
+    cached_at: u64, // 从 GitHub 获取数据的时间（Unix 时间戳，秒），旧版本缓存文件中为 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>, // GitHub 返回的 Last-Modified（用于 If-Modified-Since 重新验证）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>, // GitHub 返回的 ETag（用于 If-None-Match 重新验证，仅在启用 ENABLE_CONDITIONAL_RESPONSES 时记录）
+}
+
+impl<T> CachedEntry<T> {
+    // （获取时间, 过期时间）
+    fn times(&self) -> (u64, u64) {
+        (self.cached_at, self.expires_at)
+    }
+
+    // 是否应保留：未过期，仍在故障保留期内，或带有 Last-Modified / ETag 且仍在重新验证的保留期内
+    fn should_keep(&self, now: u64, revalidate_grace_secs: u64, outage_grace_secs: u64) -> bool {
+        self.usable_during_outage(now, outage_grace_secs)
+            || (self.validators().is_some()
+                && self.expires_at.saturating_add(revalidate_grace_secs) > now)
+    }
+
+    // 条目记录的重新验证凭据，两者都没有时返回 None
+    fn validators(&self) -> Option<Validators> {
+        if self.last_modified.is_none() && self.etag.is_none() {
+            return None;
+        }
+        Some(Validators {
+            last_modified: self.last_modified.clone(),
+            etag: self.etag.clone(),
+        })
+    }
+
+    // GitHub 故障时是否仍可使用：未过期，或过期不超过 outage_grace_secs 秒
+    fn usable_during_outage(&self, now: u64, outage_grace_secs: u64) -> bool {
+        self.expires_at.saturating_add(outage_grace_secs) > now
+    }
+}
+
+// 查找 GitHub 故障时可以兜底使用的条目（可能已过期）
+fn outage_fallback<T: Clone>(
+    map: &HashMap<String, CachedEntry<T>>,
+    key: &str,
+    outage_grace_secs: u64,
+) -> Option<T> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    map.get(key)
+        .filter(|entry| entry.usable_during_outage(now, outage_grace_secs))
+        .map(|entry| entry.value.clone())
+}
+
+// 插入持久化缓存条目，超出 max_entries 时按过期时间从早到晚淘汰到上限的 90%。
+// 每次淘汰都要扫描整个 map，一次多腾出 1/10 的空间，之后的插入不必每次都扫描
+fn insert_bounded<T>(
+    map: &mut HashMap<String, CachedEntry<T>>,
+    key: String,
+    entry: CachedEntry<T>,
+    max_entries: usize,
+) {
+    map.insert(key, entry);
+    if map.len() > max_entries {
+        evict_earliest_expiring(map, max_entries - max_entries / 10);
+    }
+}
+
+// 按过期时间从早到晚淘汰条目，直到最多剩余 target 个
+fn evict_earliest_expiring<T>(map: &mut HashMap<String, CachedEntry<T>>, target: usize) {
+    let excess = map.len().saturating_sub(target);
+    if excess == 0 {
+        return;
+    }
+    let mut by_expiry: Vec<(u64, String)> = map
+        .iter()
+        .map(|(key, entry)| (entry.expires_at, key.clone()))
+        .collect();
+    by_expiry.select_nth_unstable(excess - 1);
+    for (_, key) in &by_expiry[..excess] {
+        map.remove(key);
+    }
+}
+
+// 持久化的文件内容总大小超过 max_bytes 时按过期时间从早到晚淘汰，与内存缓存使用相同的字节预算
+fn enforce_contents_budget(map: &mut HashMap<String, CachedEntry<serde_json::Value>>, max_bytes: u64) {
+    let mut total: u64 = map.values().map(|entry| u64::from(contents_weight(&entry.value))).sum();
+    if total <= max_bytes {
+        return;
+    }
+    let mut by_expiry: Vec<(u64, String, u64)> = map
+        .iter()
+        .map(|(key, entry)| (entry.expires_at, key.clone(), u64::from(contents_weight(&entry.value))))
+        .collect();
+    by_expiry.sort_unstable();
+    for (_, key, weight) in by_expiry {
+        if total <= max_bytes {
+            break;
+        }
+        map.remove(&key);
+        total -= weight;
+    }
 }
 
 // 持久化缓存数据结构
@@ -27,6 +322,155 @@ struct PersistentCache {
     repo_info: HashMap<String, CachedEntry<RepoInfo>>,
     releases: HashMap<String, CachedEntry<Vec<ReleaseInfo>>>,
     latest_release: HashMap<String, CachedEntry<LatestReleaseInfo>>,
+    #[serde(default)]
+    compare: HashMap<String, CachedEntry<CompareInfo>>,
+    #[serde(default)]
+    checksums: HashMap<String, CachedEntry<ChecksumsInfo>>,
+    #[serde(default)]
+    generated_notes: HashMap<String, CachedEntry<GeneratedNotes>>,
+    #[serde(default)]
+    raw_repo: HashMap<String, CachedEntry<serde_json::Value>>,
+    #[serde(default)]
+    contents: HashMap<String, CachedEntry<serde_json::Value>>,
+    #[serde(default)]
+    milestones: HashMap<String, CachedEntry<Vec<MilestoneInfo>>>,
+}
+
+impl PersistentCache {
+    // 宽松解析：逐条解析各类缓存条目，跳过格式错误的条目，返回（恢复的缓存, 跳过的条目数）
+    // 文件不是有效的 JSON 对象（如写入时被截断）时返回 None
+    fn parse_lenient(content: &str) -> Option<(Self, usize)> {
+        let serde_json::Value::Object(mut root) = serde_json::from_str(content).ok()? else {
+            return None;
+        };
+        let mut skipped = 0;
+        let cache = Self {
+            repo_info: lenient_section(&mut root, "repo_info", &mut skipped),
+            releases: lenient_section(&mut root, "releases", &mut skipped),
+            latest_release: lenient_section(&mut root, "latest_release", &mut skipped),
+            compare: lenient_section(&mut root, "compare", &mut skipped),
+            checksums: lenient_section(&mut root, "checksums", &mut skipped),
+            generated_notes: lenient_section(&mut root, "generated_notes", &mut skipped),
+            raw_repo: lenient_section(&mut root, "raw_repo", &mut skipped),
+            contents: lenient_section(&mut root, "contents", &mut skipped),
+            milestones: lenient_section(&mut root, "milestones", &mut skipped),
+        };
+        Some((cache, skipped))
+    }
+
+    // 移除所有已过期的条目，返回移除的数量
+    // 带有 Last-Modified 的条目过期后再保留 revalidate_grace_secs 秒，期间可以通过 304 继续使用；
+    // 所有条目过期后再保留 outage_grace_secs 秒，供 GitHub 故障时兜底
+    fn prune_expired(&mut self, now: u64, revalidate_grace_secs: u64, outage_grace_secs: u64) -> usize {
+        let before = self.len();
+        let (grace, outage) = (revalidate_grace_secs, outage_grace_secs);
+        self.repo_info.retain(|_, entry| entry.should_keep(now, grace, outage));
+        self.releases.retain(|_, entry| entry.should_keep(now, grace, outage));
+        self.latest_release.retain(|_, entry| entry.should_keep(now, grace, outage));
+        self.compare.retain(|_, entry| entry.should_keep(now, grace, outage));
+        self.checksums.retain(|_, entry| entry.should_keep(now, grace, outage));
+        self.generated_notes.retain(|_, entry| entry.should_keep(now, grace, outage));
+        self.raw_repo.retain(|_, entry| entry.should_keep(now, grace, outage));
+        self.contents.retain(|_, entry| entry.should_keep(now, grace, outage));
+        self.milestones.retain(|_, entry| entry.should_keep(now, grace, outage));
+        before - self.len()
+    }
+
+    // 查找缓存键对应条目的（获取时间, 过期时间）
+    fn entry_times(&self, key: &str) -> Option<(u64, u64)> {
+        self.repo_info
+            .get(key)
+            .map(CachedEntry::times)
+            .or_else(|| self.releases.get(key).map(CachedEntry::times))
+            .or_else(|| self.latest_release.get(key).map(CachedEntry::times))
+            .or_else(|| self.compare.get(key).map(CachedEntry::times))
+            .or_else(|| self.checksums.get(key).map(CachedEntry::times))
+            .or_else(|| self.generated_notes.get(key).map(CachedEntry::times))
+            .or_else(|| self.raw_repo.get(key).map(CachedEntry::times))
+            .or_else(|| self.contents.get(key).map(CachedEntry::times))
+            .or_else(|| self.milestones.get(key).map(CachedEntry::times))
+    }
+
+    // 查找缓存键对应条目的过期时间
+    fn expires_at(&self, key: &str) -> Option<u64> {
+        self.entry_times(key).map(|(_, expires_at)| expires_at)
+    }
+
+    // 条目总数
+    fn len(&self) -> usize {
+        self.repo_info.len()
+            + self.releases.len()
+            + self.latest_release.len()
+            + self.compare.len()
+            + self.checksums.len()
+            + self.generated_notes.len()
+            + self.raw_repo.len()
+            + self.contents.len()
+            + self.milestones.len()
+    }
+
+    // 按仓库汇总未过期的条目（通过解析缓存键还原 owner/repo），结果按 owner/repo 排序
+    fn summarize_repos(&self, now: u64) -> Vec<CachedRepoSummary> {
+        let mut summaries: HashMap<(String, String), CachedRepoSummary> = HashMap::new();
+
+        let mut record = |key: &str, prefix: &str, expires_at: u64| {
+            if expires_at <= now {
+                return;
+            }
+            let Some((owner, repo)) = parse_repo_key(key, prefix) else {
+                return;
+            };
+            let summary = summaries
+                .entry((owner.clone(), repo.clone()))
+                .or_insert_with(|| CachedRepoSummary {
+                    owner,
+                    repo,
+                    has_repo_info: false,
+                    has_releases: false,
+                    has_latest_release: false,
+                    oldest_expires_at: expires_at,
+                });
+            summary.oldest_expires_at = summary.oldest_expires_at.min(expires_at);
+            match prefix {
+                "repo_info" => summary.has_repo_info = true,
+                "releases" => summary.has_releases = true,
+                _ => summary.has_latest_release = true,
+            }
+        };
+
+        for (key, entry) in &self.repo_info {
+            record(key, "repo_info", entry.expires_at);
+        }
+        for (key, entry) in &self.releases {
+            record(key, "releases", entry.expires_at);
+        }
+        for (key, entry) in &self.latest_release {
+            record(key, "latest_release", entry.expires_at);
+        }
+
+        let mut summaries: Vec<_> = summaries.into_values().collect();
+        summaries.sort_by(|a, b| (&a.owner, &a.repo).cmp(&(&b.owner, &b.repo)));
+        summaries
+    }
+}
+
+// 解析 `<prefix>:owner:repo` 格式的缓存键
+fn parse_repo_key(key: &str, prefix: &str) -> Option<(String, String)> {
+    let rest = key.strip_prefix(prefix)?.strip_prefix(':')?;
+    let (owner, repo) = rest.split_once(':')?;
+    if owner.is_empty() || repo.is_empty() || repo.contains(':') {
+        return None;
+    }
+    Some((owner.to_string(), repo.to_string()))
+}
+
+// 从任意仓库数据的缓存键（"类型:owner:repo[:...]"）中取出 owner 和 repo
+pub(crate) fn repo_from_key(key: &str) -> Option<(&str, &str)> {
+    let mut parts = key.splitn(4, ':');
+    parts.next()?;
+    let owner = parts.next().filter(|owner| !owner.is_empty())?;
+    let repo = parts.next().filter(|repo| !repo.is_empty())?;
+    Some((owner, repo))
 }
 
 // 缓存配置
@@ -34,6 +478,14 @@ struct PersistentCache {
 pub struct CacheConfig {
     pub enabled: bool,
     pub ttl_seconds: u64,
+    pub negative_ttl_seconds: u64, // 404 结果的缓存时间（秒），0 表示不缓存
+    pub negative_max_entries: u64, // 负缓存最多保留的墓碑标记数（超出时按 LRU 淘汰）
+    pub prune_interval_secs: u64,  // 清理持久化存储中过期条目的间隔（秒）
+    pub file_cache_max_files: usize, // 文件缓存最多保留的文件数（按最近访问时间淘汰）
+    pub file_cache_cleanup_interval_secs: u64, // 后台清理文件缓存的间隔（秒）
+    pub api_cache_control: bool, // 是否为 JSON 响应添加 Cache-Control 和 X-Cache-Expires-At 头
+    pub persistent_max_entries: usize, // 持久化存储中每类缓存最多保留的条目数（超出时淘汰最早过期的条目）
+    pub outage_grace_secs: u64, // 条目过期后在持久化存储中继续保留的时间（秒），仅在 GitHub 故障时兜底使用，0 表示不保留
 }
 
 impl CacheConfig {
@@ -45,18 +497,377 @@ impl CacheConfig {
             .parse::<bool>()
             .unwrap_or(true);
 
-        let ttl_seconds = env::var("CACHE_TTL_SECONDS")
-            .unwrap_or_else(|_| "3600".to_string()) // 默认 1 小时
+        let (min_ttl, max_ttl) = ttl_bounds(
+            env::var("CACHE_MIN_TTL_SECONDS").ok().as_deref(),
+            env::var("CACHE_MAX_TTL_SECONDS").ok().as_deref(),
+        );
+        let ttl_seconds = resolve_ttl(
+            env::var("CACHE_TTL_SECONDS").ok().as_deref(),
+            min_ttl,
+            max_ttl,
+        );
+
+        let negative_ttl_seconds = env::var("NEGATIVE_CACHE_TTL_SECS")
+            .unwrap_or_else(|_| "60".to_string()) // 默认 1 分钟
+            .parse::<u64>()
+            .unwrap_or(60);
+
+        let negative_max_entries = env::var("NEGATIVE_CACHE_MAX_ENTRIES")
+            .unwrap_or_else(|_| "10000".to_string())
+            .parse::<u64>()
+            .ok()
+            .filter(|v| *v > 0)
+            .unwrap_or(10_000);
+
+        let prune_interval_secs = env::var("CACHE_PRUNE_INTERVAL_SECS")
+            .unwrap_or_else(|_| "300".to_string()) // 默认 5 分钟
+            .parse::<u64>()
+            .ok()
+            .filter(|v| *v > 0)
+            .unwrap_or(300);
+
+        let file_cache_max_files = env::var("FILE_CACHE_MAX_FILES")
+            .unwrap_or_else(|_| "50".to_string())
+            .parse::<usize>()
+            .unwrap_or(50);
+
+        let file_cache_cleanup_interval_secs = env::var("FILE_CACHE_CLEANUP_INTERVAL_SECS")
+            .unwrap_or_else(|_| "60".to_string()) // 默认 1 分钟
+            .parse::<u64>()
+            .ok()
+            .filter(|v| *v > 0)
+            .unwrap_or(60);
+
+        let api_cache_control = env::var("API_CACHE_CONTROL")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse::<bool>()
+            .unwrap_or(true);
+
+        let persistent_max_entries = env::var("PERSISTENT_CACHE_MAX_ENTRIES")
+            .unwrap_or_else(|_| "50000".to_string())
+            .parse::<usize>()
+            .ok()
+            .filter(|v| *v > 0)
+            .unwrap_or(50_000);
+
+        let outage_grace_secs = env::var("CACHE_OUTAGE_GRACE_SECS")
+            .unwrap_or_else(|_| "86400".to_string()) // 默认 1 天
             .parse::<u64>()
-            .unwrap_or(3600);
+            .unwrap_or(86_400);
 
         CacheConfig {
             enabled,
             ttl_seconds,
+            negative_ttl_seconds,
+            negative_max_entries,
+            prune_interval_secs,
+            file_cache_max_files,
+            file_cache_cleanup_interval_secs,
+            api_cache_control,
+            persistent_max_entries,
+            outage_grace_secs,
+        }
+    }
+}
+
+/// 缓存目录和文件的权限（仅在 Unix 上生效）
+///
+/// 下载的文件可能来自私有仓库，默认只允许服务进程的用户访问；
+/// 需要同组用户访问时可通过 `CACHE_DIR_MODE`/`CACHE_FILE_MODE`（八进制，如 `0750`/`0640`）放宽。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachePermissions {
+    pub dir_mode: u32,
+    pub file_mode: u32,
+}
+
+impl Default for CachePermissions {
+    fn default() -> Self {
+        Self {
+            dir_mode: 0o700,
+            file_mode: 0o600,
+        }
+    }
+}
+
+impl CachePermissions {
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+        let default = Self::default();
+
+        let parse = |name: &str, default_mode: u32| match env::var(name) {
+            Ok(value) => parse_mode(&value).unwrap_or_else(|| {
+                log::warn!("{} 不是有效的八进制权限: {:?}，使用默认值 {:o}", name, value, default_mode);
+                default_mode
+            }),
+            Err(_) => default_mode,
+        };
+
+        Self {
+            dir_mode: parse("CACHE_DIR_MODE", default.dir_mode),
+            file_mode: parse("CACHE_FILE_MODE", default.file_mode),
+        }
+    }
+}
+
+// 缓存 TTL 的默认值和默认上下限（秒）
+const DEFAULT_TTL_SECONDS: u64 = 3600; // 1 小时
+const DEFAULT_MIN_TTL_SECONDS: u64 = 1;
+const DEFAULT_MAX_TTL_SECONDS: u64 = 30 * 24 * 3600; // 30 天
+
+// 解析 CACHE_MIN_TTL_SECONDS 和 CACHE_MAX_TTL_SECONDS，无法解析时使用默认值；上限不会小于下限
+pub(crate) fn ttl_bounds(min: Option<&str>, max: Option<&str>) -> (u64, u64) {
+    let min_ttl = min
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_MIN_TTL_SECONDS);
+    let max_ttl = max
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_MAX_TTL_SECONDS);
+    (min_ttl, max_ttl.max(min_ttl))
+}
+
+// 将 TTL 限制在 [min_ttl, max_ttl] 内，超出范围时返回截断后的值
+pub(crate) fn clamp_ttl(ttl: u64, min_ttl: u64, max_ttl: u64) -> Option<u64> {
+    let clamped = ttl.clamp(min_ttl, max_ttl);
+    (clamped != ttl).then_some(clamped)
+}
+
+// 解析 CACHE_TTL_SECONDS：无法解析时使用默认值，超出上下限时截断并输出警告，
+// 避免误配置（如 0）在启用缓存的情况下实际禁用了缓存
+fn resolve_ttl(value: Option<&str>, min_ttl: u64, max_ttl: u64) -> u64 {
+    let ttl = match value.map(|v| v.trim().parse::<u64>()) {
+        None => DEFAULT_TTL_SECONDS,
+        Some(Ok(ttl)) => ttl,
+        Some(Err(_)) => {
+            log::warn!(
+                "CACHE_TTL_SECONDS 不是有效的非负整数: {:?}，使用默认值 {} 秒",
+                value.unwrap_or_default(),
+                DEFAULT_TTL_SECONDS
+            );
+            DEFAULT_TTL_SECONDS
+        }
+    };
+    match clamp_ttl(ttl, min_ttl, max_ttl) {
+        Some(clamped) => {
+            log::warn!(
+                "CACHE_TTL_SECONDS={} 超出允许范围 [{}, {}]，使用 {} 秒",
+                ttl, min_ttl, max_ttl, clamped
+            );
+            clamped
+        }
+        None => ttl,
+    }
+}
+
+// 解析八进制权限（支持 `0700`、`700`、`0o700`），超出 0o777 时返回 None
+pub(crate) fn parse_mode(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let digits = value.strip_prefix("0o").unwrap_or(value);
+    u32::from_str_radix(digits, 8).ok().filter(|mode| *mode <= 0o777)
+}
+
+static CACHE_PERMISSIONS: OnceLock<CachePermissions> = OnceLock::new();
+
+/// 获取全局缓存权限配置
+pub fn get_cache_permissions() -> &'static CachePermissions {
+    CACHE_PERMISSIONS.get_or_init(CachePermissions::from_env)
+}
+
+/// 持久化缓存文件路径（使用环境变量 CACHE_FILE，默认当前目录下的 cache.json）
+pub fn cache_file_path() -> PathBuf {
+    env::var("CACHE_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| default_cache_file_path())
+}
+
+#[cfg(not(test))]
+fn default_cache_file_path() -> PathBuf {
+    PathBuf::from("cache.json")
+}
+
+// 单元测试默认写入临时目录，避免在工作目录中留下 cache.json 和 cache_files
+#[cfg(test)]
+fn default_cache_file_path() -> PathBuf {
+    env::temp_dir()
+        .join(format!("gh-info-rs-test-{}", std::process::id()))
+        .join("cache.json")
+}
+
+// 测试共用的缓存配置，其他模块的测试按需覆盖个别字段
+#[cfg(test)]
+pub(crate) fn create_test_cache_config(enabled: bool, ttl_seconds: u64) -> CacheConfig {
+    CacheConfig {
+        enabled,
+        ttl_seconds,
+        negative_ttl_seconds: 60,
+        negative_max_entries: 10_000,
+        prune_interval_secs: 300,
+        file_cache_max_files: 50,
+        file_cache_cleanup_interval_secs: 60,
+        api_cache_control: true,
+        persistent_max_entries: 50_000,
+        outage_grace_secs: 0,
+    }
+}
+
+/// 是否以 gzip 格式保存持久化缓存文件（环境变量 CACHE_FILE_COMPRESS，默认 false），
+/// 启用时写入 `<CACHE_FILE>.gz`
+pub fn cache_file_compress() -> bool {
+    env::var("CACHE_FILE_COMPRESS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// 文件缓存目录（使用环境变量 FILE_CACHE_DIR），未设置时根据 CACHE_FILE 的父目录推断
+pub fn file_cache_dir(cache_file_path: &Path) -> PathBuf {
+    env::var("FILE_CACHE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            // 如果 CACHE_FILE 在 /app/data/ 目录下，则使用 /app/data/cache_files
+            // 否则使用 cache_files（与 cache.json 同级）
+            if let Some(parent) = cache_file_path.parent() {
+                if parent == Path::new("/app/data") {
+                    PathBuf::from("/app/data/cache_files")
+                } else {
+                    parent.join("cache_files")
+                }
+            } else {
+                PathBuf::from("cache_files")
+            }
+        })
+}
+
+/// 按 `CACHE_DIR_MODE` 递归创建缓存目录（已存在的目录不修改权限）
+pub fn create_cache_dir(path: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(get_cache_permissions().dir_mode);
+    }
+    builder.create(path)
+}
+
+// 将已存在的文件或目录的权限设置为指定值（用于收紧旧版本创建的缓存）
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+}
+
+// 按 `CACHE_FILE_MODE` 创建（或截断）缓存文件，覆盖已存在的文件时同样收紧权限
+fn open_cache_file(path: &Path) -> std::io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(get_cache_permissions().file_mode);
+    }
+    let file = options.open(path)?;
+    #[cfg(unix)]
+    set_mode(path, get_cache_permissions().file_mode)?;
+    Ok(file)
+}
+
+/// 按 `CACHE_FILE_MODE` 写入缓存文件（覆盖已存在的文件时同样收紧权限）
+pub fn write_cache_file(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    use std::io::Write;
+
+    open_cache_file(path)?.write_all(contents.as_ref())
+}
+
+/// 缓存文件的 gzip 副本路径（`<文件名>.gz`）
+pub fn gzip_sibling_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".gz");
+    path.with_file_name(file_name)
+}
+
+/// 将缓存文件压缩为同目录下的 `.gz` 副本，返回副本路径
+///
+/// 先写入临时文件再重命名，正在读取旧副本的请求不会读到写了一半的内容。
+pub fn write_gzip_sibling(path: &Path) -> std::io::Result<PathBuf> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    let gzip_path = gzip_sibling_path(path);
+    let mut temp_name = gzip_path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = gzip_path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut source = std::fs::File::open(path)?;
+        let mut encoder = GzEncoder::new(open_cache_file(&temp_path)?, Compression::default());
+        std::io::copy(&mut source, &mut encoder)?;
+        encoder.finish()?.sync_all()?;
+        std::fs::rename(&temp_path, &gzip_path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result.map(|_| gzip_path)
+}
+
+static NEXT_PARTIAL_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// 生成下载过程中使用的临时文件路径（`<缓存文件名>.<序号>.partial`）
+///
+/// 下载完成后再重命名为缓存文件，其他请求不会读到写了一半的文件；
+/// 每次下载使用不同的序号，同一 URL 的并发下载不会写入同一个临时文件。
+pub fn partial_file_path(path: &Path) -> PathBuf {
+    let id = NEXT_PARTIAL_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(format!(".{}.partial", id));
+    path.with_file_name(file_name)
+}
+
+// 超过该时长未写入的临时下载文件视为残留文件。多个副本共享缓存目录时，
+// 其他副本正在写入的临时文件会持续更新修改时间，启动清理不会删除它们
+const STALE_PARTIAL_FILE_AGE: Duration = Duration::from_secs(3600);
+
+// 删除目录中残留的临时下载文件（进程在下载过程中退出时留下），只删除超过 max_age 未修改的文件
+fn remove_partial_files(dir: &Path, max_age: Duration) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "partial") {
+            continue;
+        }
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .map(|modified| now.duration_since(modified).unwrap_or_default() >= max_age)
+            .unwrap_or(false);
+        if stale {
+            if let Err(e) = std::fs::remove_file(&path) {
+                log::warn!("无法删除残留的临时下载文件 {:?}: {}", path, e);
+            }
         }
     }
 }
 
+/// 按 `CACHE_FILE_MODE` 创建（或截断）用于流式写入的缓存文件
+pub async fn create_cache_file(path: &Path) -> std::io::Result<tokio::fs::File> {
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(get_cache_permissions().file_mode);
+    let file = options.open(path).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = get_cache_permissions().file_mode;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).await?;
+    }
+    Ok(file)
+}
+
 // 文件缓存元数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileCacheMetadata {
@@ -66,6 +877,121 @@ pub struct FileCacheMetadata {
     pub content_type: Option<String>,
     pub expires_at: u64,
     pub last_accessed_at: u64, // 最后访问时间（Unix 时间戳，秒）
+    #[serde(default)]
+    pub etag: Option<String>, // 上游返回的 ETag（用于重新验证可变内容）
+    #[serde(default)]
+    pub cached_at: u64, // 写入缓存的时间（Unix 时间戳，秒）
+    #[serde(default)]
+    pub gzip_path: Option<PathBuf>, // 预压缩的 gzip 副本（CACHE_PRECOMPRESS 启用且内容可压缩时存在）
+    #[serde(default)]
+    pub immutable: bool, // URL 指向的内容不可变，忽略 expires_at，只通过 LRV 清理淘汰
+    #[serde(default)]
+    pub size: Option<u64>, // 写入缓存时的文件大小（字节），返回前用于校验文件是否完整
+}
+
+impl FileCacheMetadata {
+    /// 缓存文件是否已过期（不可变内容永不过期）
+    pub fn is_expired(&self, now: u64) -> bool {
+        !self.immutable && self.expires_at <= now
+    }
+
+    /// 缓存文件是否早于指定的秒数写入（旧版本元数据没有写入时间，视为已超过）
+    pub fn is_older_than(&self, max_age_secs: u64) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        now.saturating_sub(self.cached_at) > max_age_secs
+    }
+}
+
+// 负缓存中的墓碑标记：表示 GitHub 近期返回过 404，不携带任何数据，
+// 与真实数据分开存储，避免 404 被当作缓存的空结果
+#[derive(Debug, Clone, Copy)]
+struct NotFoundMarker;
+
+// 文件缓存的过期策略：不可变内容没有过期时间，其他内容在 expires_at 时过期
+struct FileCacheExpiry;
+
+impl FileCacheExpiry {
+    fn ttl(metadata: &FileCacheMetadata) -> Option<Duration> {
+        if metadata.immutable {
+            return None;
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        Some(Duration::from_secs(metadata.expires_at.saturating_sub(now)))
+    }
+}
+
+impl moka::Expiry<CacheKey, FileCacheMetadata> for FileCacheExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &CacheKey,
+        value: &FileCacheMetadata,
+        _created_at: std::time::Instant,
+    ) -> Option<Duration> {
+        Self::ttl(value)
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &CacheKey,
+        value: &FileCacheMetadata,
+        _updated_at: std::time::Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        Self::ttl(value)
+    }
+}
+
+// 进行中的文件下载表（文件缓存键 -> 下载结束通知）
+type DownloadFlightMap = Arc<std::sync::Mutex<HashMap<CacheKey, watch::Receiver<()>>>>;
+
+/// 同一 URL 的下载协调结果
+pub enum DownloadFlight {
+    /// 当前请求负责下载，守卫在下载结束（无论成功与否）时被丢弃
+    Leader(DownloadFlightGuard),
+    /// 相同 URL 正在被其他请求下载
+    Follower(DownloadFlightWaiter),
+}
+
+/// 下载负责方持有的守卫，被丢弃时移除进行中的记录并唤醒等待者
+pub struct DownloadFlightGuard {
+    key: CacheKey,
+    flights: DownloadFlightMap,
+    _done: watch::Sender<()>,
+}
+
+impl Drop for DownloadFlightGuard {
+    fn drop(&mut self) {
+        self.flights.lock().unwrap().remove(&self.key);
+    }
+}
+
+/// 文件缓存中为一个待写入文件预留的位置，写入结束（登记元数据或放弃写入）后被丢弃时释放。
+/// 覆盖已登记的缓存文件时不占用新的位置（内部为 None）
+pub struct FileSlot(Option<Arc<AtomicUsize>>);
+
+impl Drop for FileSlot {
+    fn drop(&mut self) {
+        if let Some(count) = &self.0 {
+            count.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// 等待进行中的下载结束
+pub struct DownloadFlightWaiter(watch::Receiver<()>);
+
+impl DownloadFlightWaiter {
+    /// 等待下载结束（成功或失败），之后调用方应重新检查文件缓存
+    pub async fn wait(mut self) {
+        // 发送端只会被丢弃，changed 在下载结束时返回错误
+        let _ = self.0.changed().await;
+    }
 }
 
 // 缓存管理器
@@ -74,59 +1000,72 @@ pub struct CacheManager {
     repo_info_cache: Cache<CacheKey, RepoInfo>,
     releases_cache: Cache<CacheKey, Vec<ReleaseInfo>>,
     latest_release_cache: Cache<CacheKey, LatestReleaseInfo>,
+    compare_cache: Cache<CacheKey, CompareInfo>,
+    checksums_cache: Cache<CacheKey, ChecksumsInfo>,
+    generated_notes_cache: Cache<CacheKey, GeneratedNotes>,
+    raw_repo_cache: Cache<CacheKey, serde_json::Value>,
+    // 仓库文件内容（GitHub contents API 的原始 JSON），按内容大小计算容量
+    contents_cache: Cache<CacheKey, serde_json::Value>,
+    milestones_cache: Cache<CacheKey, Vec<MilestoneInfo>>,
+    // 负缓存：记录近期返回 404 的请求（墓碑标记，与真实数据分开存储）
+    // 容量单独限制并按 LRU 淘汰，避免探测大量不存在的仓库时占满内存
+    negative_cache: Cache<CacheKey, NotFoundMarker>,
     file_cache: Cache<CacheKey, FileCacheMetadata>,
     // 持久化存储（用于保存和加载）
     persistent_store: Arc<RwLock<PersistentCache>>,
     cache_file_path: PathBuf,
+    // 是否以 gzip 格式保存持久化缓存（CACHE_FILE_COMPRESS）
+    compress: bool,
     file_cache_dir: PathBuf,
     // 文件路径到缓存键的映射（用于清理时查找）
     file_path_to_key: Arc<RwLock<HashMap<PathBuf, CacheKey>>>,
+    // 已登记的缓存文件数加上预留的位置数，写入新文件前检查，保证不超过 FILE_CACHE_MAX_FILES
+    file_count: Arc<AtomicUsize>,
+    // 进行中的文件下载（用于合并同一 URL 的并发下载）
+    download_flights: DownloadFlightMap,
+    // 进行中的 GitHub 请求（用于合并并发的相同请求）
+    repo_info_flight: SingleFlight<RepoInfo>,
+    releases_flight: SingleFlight<Vec<ReleaseInfo>>,
+    latest_release_flight: SingleFlight<LatestReleaseInfo>,
+    compare_flight: SingleFlight<CompareInfo>,
+    milestones_flight: SingleFlight<Vec<MilestoneInfo>>,
 }
 
 impl CacheManager {
     pub async fn new(config: CacheConfig) -> Self {
         let ttl = Duration::from_secs(config.ttl_seconds);
 
-        // 确定缓存文件路径（使用环境变量 CACHE_FILE，默认当前目录下的 cache.json）
-        let cache_file_path = env::var("CACHE_FILE")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| PathBuf::from("cache.json"));
-
-        // 确定文件缓存目录（使用环境变量 FILE_CACHE_DIR）
-        // 如果未设置，则根据 CACHE_FILE 的父目录智能推断
-        let file_cache_dir = env::var("FILE_CACHE_DIR")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| {
-                // 如果 CACHE_FILE 在 /app/data/ 目录下，则使用 /app/data/cache_files
-                // 否则使用 cache_files（与 cache.json 同级）
-                if let Some(parent) = cache_file_path.parent() {
-                    if parent == PathBuf::from("/app/data") {
-                        PathBuf::from("/app/data/cache_files")
-                    } else {
-                        parent.join("cache_files")
-                    }
-                } else {
-                    PathBuf::from("cache_files")
-                }
-            });
+        let cache_file_path = cache_file_path();
+        let file_cache_dir = file_cache_dir(&cache_file_path);
 
-        // 确保缓存目录存在
+        // 确保缓存目录存在（父目录可能是共享目录，只对新建的目录应用权限）
         if let Some(parent) = cache_file_path.parent() {
-            if let Err(e) = std::fs::create_dir_all(parent) {
+            if let Err(e) = create_cache_dir(parent) {
                 log::warn!("无法创建缓存目录: {:?}, 错误: {}", parent, e);
             }
         }
 
-        // 确保文件缓存目录存在
-        if let Err(e) = std::fs::create_dir_all(&file_cache_dir) {
+        // 确保文件缓存目录存在，并收紧已存在目录的权限
+        if let Err(e) = create_cache_dir(&file_cache_dir) {
             log::warn!("无法创建文件缓存目录: {:?}, 错误: {}", file_cache_dir, e);
         }
+        #[cfg(unix)]
+        if let Err(e) = set_mode(&file_cache_dir, get_cache_permissions().dir_mode) {
+            log::warn!("无法设置文件缓存目录权限: {:?}, 错误: {}", file_cache_dir, e);
+        }
+        remove_partial_files(&file_cache_dir, STALE_PARTIAL_FILE_AGE);
 
         // 创建持久化存储
         let persistent_store = Arc::new(RwLock::new(PersistentCache {
             repo_info: HashMap::new(),
             releases: HashMap::new(),
             latest_release: HashMap::new(),
+            compare: HashMap::new(),
+            checksums: HashMap::new(),
+            generated_notes: HashMap::new(),
+            raw_repo: HashMap::new(),
+            contents: HashMap::new(),
+            milestones: HashMap::new(),
         }));
 
         // 创建缓存管理器
@@ -144,31 +1083,92 @@ impl CacheManager {
                 .max_capacity(10_000)
                 .time_to_live(ttl)
                 .build(),
-            file_cache: Cache::builder()
+            compare_cache: Cache::builder()
                 .max_capacity(10_000)
                 .time_to_live(ttl)
                 .build(),
-            persistent_store: persistent_store.clone(),
-            cache_file_path: cache_file_path.clone(),
-            file_cache_dir: file_cache_dir.clone(),
-            file_path_to_key: Arc::new(RwLock::new(HashMap::new())),
-        };
-
-        if config.enabled {
-            log::info!("缓存已启用，TTL: {} 秒", config.ttl_seconds);
-
-            // 从磁盘加载缓存
-            manager.load_from_disk().await;
-
-            // 启动后台保存任务（每30秒保存一次）
-            let manager_clone = manager.clone_for_background();
-            tokio::spawn(async move {
+            checksums_cache: Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(ttl)
+                .build(),
+            generated_notes_cache: Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(ttl)
+                .build(),
+            raw_repo_cache: Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(ttl)
+                .build(),
+            contents_cache: Cache::builder()
+                .max_capacity(CONTENTS_CACHE_MAX_BYTES)
+                .weigher(|_key, value: &serde_json::Value| contents_weight(value))
+                .time_to_live(ttl)
+                .build(),
+            milestones_cache: Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(ttl)
+                .build(),
+            negative_cache: Cache::builder()
+                .max_capacity(config.negative_max_entries)
+                .eviction_policy(EvictionPolicy::lru())
+                .time_to_live(Duration::from_secs(config.negative_ttl_seconds.max(1)))
+                .build(),
+            file_cache: Cache::builder()
+                .max_capacity(10_000)
+                .expire_after(FileCacheExpiry)
+                .build(),
+            persistent_store: persistent_store.clone(),
+            cache_file_path: cache_file_path.clone(),
+            compress: cache_file_compress(),
+            file_cache_dir: file_cache_dir.clone(),
+            file_path_to_key: Arc::new(RwLock::new(HashMap::new())),
+            file_count: Arc::new(AtomicUsize::new(0)),
+            download_flights: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            repo_info_flight: SingleFlight::new(),
+            releases_flight: SingleFlight::new(),
+            latest_release_flight: SingleFlight::new(),
+            compare_flight: SingleFlight::new(),
+            milestones_flight: SingleFlight::new(),
+        };
+
+        if config.enabled {
+            log::info!("缓存已启用，TTL: {} 秒", config.ttl_seconds);
+
+            // 从磁盘加载缓存
+            manager.load_from_disk().await;
+
+            // 启动后台保存任务（每30秒保存一次）
+            let manager_clone = manager.clone_for_background();
+            tokio::spawn(async move {
                 let mut interval = interval(Duration::from_secs(30));
                 loop {
                     interval.tick().await;
                     manager_clone.save_to_disk().await;
                 }
             });
+
+            // 启动后台清理任务，定期移除持久化存储中已过期的条目（moka 的 TTL 淘汰不会影响持久化存储）
+            let manager_clone = manager.clone_for_background();
+            let prune_interval_secs = config.prune_interval_secs;
+            tokio::spawn(async move {
+                let mut interval = interval(Duration::from_secs(prune_interval_secs));
+                loop {
+                    interval.tick().await;
+                    manager_clone.prune_expired().await;
+                }
+            });
+
+            // 启动后台文件缓存清理任务（不在每次下载完成时扫描缓存目录）
+            let cleaner = manager.clone_for_file_cleanup();
+            let max_files = config.file_cache_max_files;
+            let cleanup_interval_secs = config.file_cache_cleanup_interval_secs;
+            tokio::spawn(async move {
+                let mut interval = interval(Duration::from_secs(cleanup_interval_secs));
+                loop {
+                    interval.tick().await;
+                    cleaner.cleanup(max_files).await;
+                }
+            });
         } else {
             log::info!("缓存已禁用");
         }
@@ -181,88 +1181,902 @@ impl CacheManager {
         BackgroundCacheManager {
             persistent_store: self.persistent_store.clone(),
             cache_file_path: self.cache_file_path.clone(),
+            compress: self.compress,
             config: self.config.clone(),
         }
     }
 
+    // 克隆用于后台清理文件缓存
+    fn clone_for_file_cleanup(&self) -> FileCacheCleaner {
+        FileCacheCleaner {
+            file_cache: self.file_cache.clone(),
+            file_path_to_key: self.file_path_to_key.clone(),
+            file_cache_dir: self.file_cache_dir.clone(),
+            file_count: self.file_count.clone(),
+        }
+    }
+
     // 从磁盘加载缓存
     async fn load_from_disk(&self) {
         if !self.config.enabled {
             return;
         }
 
-        match std::fs::read_to_string(&self.cache_file_path) {
-            Ok(content) => {
-                match serde_json::from_str::<PersistentCache>(&content) {
-                    Ok(persistent_cache) => {
-                        let now = SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs();
-
-                        let mut loaded_count = 0;
-                        let mut store = self.persistent_store.write().await;
-
-                        // 加载 repo_info 缓存
-                        for (key, entry) in persistent_cache.repo_info.iter() {
-                            if entry.expires_at > now {
-                                // 计算剩余 TTL
-                                let remaining_ttl = entry.expires_at - now;
-                                if remaining_ttl > 0 {
-                                    self.repo_info_cache
-                                        .insert(key.clone(), entry.value.clone())
-                                        .await;
-                                    store.repo_info.insert(key.clone(), entry.clone());
-                                    loaded_count += 1;
-                                }
-                            }
-                        }
+        let Some(persistent_cache) = read_persistent_cache(&self.cache_file_path, self.compress) else {
+            return;
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let mut loaded_count = 0;
+        let mut grace_count = 0;
+        let mut store = self.persistent_store.write().await;
+        // 未过期的条目同时载入内存缓存；已过期但仍在保留期内的条目只放入持久化存储，
+        // 用于 If-Modified-Since 重新验证和 GitHub 故障时兜底
+        let revalidate_grace = self.config.ttl_seconds;
+        let outage_grace = self.config.outage_grace_secs;
+
+        // 加载 repo_info 缓存
+        for (key, entry) in persistent_cache.repo_info.iter() {
+            if entry.expires_at > now {
+                self.repo_info_cache
+                    .insert(key.clone(), entry.value.clone())
+                    .await;
+                loaded_count += 1;
+            } else if entry.should_keep(now, revalidate_grace, outage_grace) {
+                grace_count += 1;
+            } else {
+                continue;
+            }
+            store.repo_info.insert(key.clone(), entry.clone());
+        }
 
-                        // 加载 releases 缓存
-                        for (key, entry) in persistent_cache.releases.iter() {
-                            if entry.expires_at > now {
-                                let remaining_ttl = entry.expires_at - now;
-                                if remaining_ttl > 0 {
-                                    self.releases_cache
-                                        .insert(key.clone(), entry.value.clone())
-                                        .await;
-                                    store.releases.insert(key.clone(), entry.clone());
-                                    loaded_count += 1;
+        // 加载 releases 缓存
+        for (key, entry) in persistent_cache.releases.iter() {
+            if entry.expires_at > now {
+                self.releases_cache
+                    .insert(key.clone(), entry.value.clone())
+                    .await;
+                loaded_count += 1;
+            } else if entry.should_keep(now, revalidate_grace, outage_grace) {
+                grace_count += 1;
+            } else {
+                continue;
+            }
+            store.releases.insert(key.clone(), entry.clone());
+        }
+
+        // 加载 latest_release 缓存
+        for (key, entry) in persistent_cache.latest_release.iter() {
+            if entry.expires_at > now {
+                self.latest_release_cache
+                    .insert(key.clone(), entry.value.clone())
+                    .await;
+                loaded_count += 1;
+            } else if entry.should_keep(now, revalidate_grace, outage_grace) {
+                grace_count += 1;
+            } else {
+                continue;
+            }
+            store.latest_release.insert(key.clone(), entry.clone());
+        }
+
+        // 加载 compare 缓存
+        for (key, entry) in persistent_cache.compare.iter() {
+            if entry.expires_at > now {
+                self.compare_cache
+                    .insert(key.clone(), entry.value.clone())
+                    .await;
+                store.compare.insert(key.clone(), entry.clone());
+                loaded_count += 1;
+            }
+        }
+
+        // 加载 checksums 缓存
+        for (key, entry) in persistent_cache.checksums.iter() {
+            if entry.expires_at > now {
+                self.checksums_cache
+                    .insert(key.clone(), entry.value.clone())
+                    .await;
+                store.checksums.insert(key.clone(), entry.clone());
+                loaded_count += 1;
+            }
+        }
+
+        // 加载生成的 release notes 缓存
+        for (key, entry) in persistent_cache.generated_notes.iter() {
+            if entry.expires_at > now {
+                self.generated_notes_cache
+                    .insert(key.clone(), entry.value.clone())
+                    .await;
+                store.generated_notes.insert(key.clone(), entry.clone());
+                loaded_count += 1;
+            }
+        }
+
+        // 加载原始仓库信息缓存
+        for (key, entry) in persistent_cache.raw_repo.iter() {
+            if entry.expires_at > now {
+                self.raw_repo_cache
+                    .insert(key.clone(), entry.value.clone())
+                    .await;
+                store.raw_repo.insert(key.clone(), entry.clone());
+                loaded_count += 1;
+            }
+        }
+
+        // 加载仓库文件内容缓存
+        for (key, entry) in persistent_cache.contents.iter() {
+            if entry.expires_at > now {
+                self.contents_cache
+                    .insert(key.clone(), entry.value.clone())
+                    .await;
+                store.contents.insert(key.clone(), entry.clone());
+                loaded_count += 1;
+            }
+        }
+        enforce_contents_budget(&mut store.contents, CONTENTS_CACHE_MAX_BYTES);
+
+        // 加载里程碑缓存
+        for (key, entry) in persistent_cache.milestones.iter() {
+            if entry.expires_at > now {
+                self.milestones_cache
+                    .insert(key.clone(), entry.value.clone())
+                    .await;
+                store.milestones.insert(key.clone(), entry.clone());
+                loaded_count += 1;
+            }
+        }
+
+        // 缓存文件可能由更大的 PERSISTENT_CACHE_MAX_ENTRIES 写入，载入后同样按上限淘汰
+        let max_entries = self.config.persistent_max_entries;
+        evict_earliest_expiring(&mut store.repo_info, max_entries);
+        evict_earliest_expiring(&mut store.releases, max_entries);
+        evict_earliest_expiring(&mut store.latest_release, max_entries);
+        evict_earliest_expiring(&mut store.compare, max_entries);
+        evict_earliest_expiring(&mut store.checksums, max_entries);
+        evict_earliest_expiring(&mut store.generated_notes, max_entries);
+        evict_earliest_expiring(&mut store.raw_repo, max_entries);
+        evict_earliest_expiring(&mut store.contents, max_entries);
+        evict_earliest_expiring(&mut store.milestones, max_entries);
+
+        if grace_count > 0 {
+            log::info!("从磁盘加载了 {} 个已过期但仍在保留期内的缓存条目", grace_count);
+        }
+        log::info!("从磁盘加载了 {} 个缓存条目", loaded_count);
+    }
+
+    // 保存缓存到磁盘（保留用于可能的手动调用）
+    #[allow(dead_code)]
+    async fn save_to_disk(&self) {
+        if !self.config.enabled {
+            return;
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let store = self.persistent_store.read().await;
+
+        // 过滤掉已过期的条目
+        let persistent_cache = PersistentCache {
+            repo_info: store
+                .repo_info
+                .iter()
+                .filter(|(_, entry)| entry.expires_at > now)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            releases: store
+                .releases
+                .iter()
+                .filter(|(_, entry)| entry.expires_at > now)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            latest_release: store
+                .latest_release
+                .iter()
+                .filter(|(_, entry)| entry.expires_at > now)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            compare: store
+                .compare
+                .iter()
+                .filter(|(_, entry)| entry.expires_at > now)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            checksums: store
+                .checksums
+                .iter()
+                .filter(|(_, entry)| entry.expires_at > now)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            generated_notes: store
+                .generated_notes
+                .iter()
+                .filter(|(_, entry)| entry.expires_at > now)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            raw_repo: store
+                .raw_repo
+                .iter()
+                .filter(|(_, entry)| entry.expires_at > now)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            contents: store
+                .contents
+                .iter()
+                .filter(|(_, entry)| entry.expires_at > now)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            milestones: store
+                .milestones
+                .iter()
+                .filter(|(_, entry)| entry.expires_at > now)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        };
+
+        write_persistent_cache(&self.cache_file_path, self.compress, &persistent_cache);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 缓存配置
+    pub fn config(&self) -> &CacheConfig {
+        &self.config
+    }
+
+    /// 列出缓存中的仓库及其新鲜度
+    ///
+    /// 指定 `expiring_within_secs` 时只返回最早过期时间在该秒数内的仓库（用于提前刷新）。
+    /// `include_private` 为 false 时不返回缓存的仓库信息中标记为私有的仓库。
+    pub async fn list_cached_repos(
+        &self,
+        expiring_within_secs: Option<u64>,
+        include_private: bool,
+    ) -> Vec<CachedRepoSummary> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let store = self.persistent_store.read().await;
+        let mut summaries = store.summarize_repos(now);
+        if let Some(within) = expiring_within_secs {
+            let deadline = now.saturating_add(within);
+            summaries.retain(|summary| summary.oldest_expires_at <= deadline);
+        }
+        if !include_private {
+            summaries.retain(|summary| {
+                !store
+                    .repo_info
+                    .get(&Self::repo_info_key(&summary.owner, &summary.repo))
+                    .is_some_and(|entry| entry.value.private)
+            });
+        }
+        summaries
+    }
+
+    /// 列出文件缓存中未过期的文件（来自内存中的文件缓存元数据）
+    pub async fn list_cached_files(&self, sort: CachedFileSort) -> Vec<CachedFileSummary> {
+        let mut files: Vec<CachedFileSummary> = self
+            .file_cache
+            .iter()
+            .map(|(_, metadata)| CachedFileSummary {
+                url: metadata.url,
+                original_filename: metadata.original_filename,
+                size: metadata.size,
+                content_type: metadata.content_type,
+                expires_at: metadata.expires_at,
+                last_accessed_at: metadata.last_accessed_at,
+                immutable: metadata.immutable,
+            })
+            .collect();
+        match sort {
+            CachedFileSort::Accessed => files.sort_by_key(|f| std::cmp::Reverse(f.last_accessed_at)),
+            CachedFileSort::Size => files.sort_by_key(|f| std::cmp::Reverse(f.size)),
+            CachedFileSort::Expires => files.sort_by_key(|f| f.expires_at),
+        }
+        files
+    }
+
+    // 生成缓存键
+    pub(crate) fn repo_info_key(owner: &str, repo: &str) -> CacheKey {
+        format!("repo_info:{}:{}", owner, repo)
+    }
+
+    pub(crate) fn releases_key(owner: &str, repo: &str) -> CacheKey {
+        format!("releases:{}:{}", owner, repo)
+    }
+
+    pub(crate) fn latest_release_key(owner: &str, repo: &str) -> CacheKey {
+        format!("latest_release:{}:{}", owner, repo)
+    }
+
+    pub(crate) fn compare_key(owner: &str, repo: &str, base: &str, head: &str) -> CacheKey {
+        format!("compare:{}:{}:{}:{}", owner, repo, base, head)
+    }
+
+    pub(crate) fn checksums_key(owner: &str, repo: &str) -> CacheKey {
+        format!("checksums:{}:{}", owner, repo)
+    }
+
+    pub(crate) fn raw_repo_key(owner: &str, repo: &str) -> CacheKey {
+        format!("raw_repo:{}:{}", owner, repo)
+    }
+
+    // 未指定 ref 时使用空字符串（GitHub 默认分支）
+    pub(crate) fn contents_key(owner: &str, repo: &str, path: &str, git_ref: Option<&str>) -> CacheKey {
+        format!("contents:{}:{}:{}:{}", owner, repo, git_ref.unwrap_or(""), path)
+    }
+
+    pub(crate) fn milestones_key(owner: &str, repo: &str, state: &str) -> CacheKey {
+        format!("milestones:{}:{}:{}", owner, repo, state)
+    }
+
+    pub(crate) fn generated_notes_key(
+        owner: &str,
+        repo: &str,
+        tag: &str,
+        previous_tag: Option<&str>,
+    ) -> CacheKey {
+        format!(
+            "generated_notes:{}:{}:{}:{}",
+            owner,
+            repo,
+            tag,
+            previous_tag.unwrap_or_default()
+        )
+    }
+
+    // 生成负缓存键
+    fn not_found_key(key: &str) -> CacheKey {
+        format!("not_found:{}", key)
+    }
+
+    // 检查是否近期已确认该数据不存在
+    async fn is_not_found(&self, key: &str) -> bool {
+        if !self.is_enabled() || self.config.negative_ttl_seconds == 0 {
+            return false;
+        }
+        self.negative_cache
+            .get(&Self::not_found_key(key))
+            .await
+            .is_some()
+    }
+
+    // 记录该数据不存在（在 NEGATIVE_CACHE_TTL_SECS 内不再请求 GitHub）
+    async fn set_not_found(&self, key: &str) {
+        if self.is_enabled() && self.config.negative_ttl_seconds > 0 {
+            self.negative_cache
+                .insert(Self::not_found_key(key), NotFoundMarker)
+                .await;
+        }
+    }
+
+    // 检查仓库信息是否近期返回过 404
+    pub async fn is_repo_info_not_found(&self, owner: &str, repo: &str) -> bool {
+        self.is_not_found(&Self::repo_info_key(owner, repo)).await
+    }
+
+    // 记录仓库信息返回 404
+    pub async fn set_repo_info_not_found(&self, owner: &str, repo: &str) {
+        self.set_not_found(&Self::repo_info_key(owner, repo)).await
+    }
+
+    // 检查 releases 是否近期返回过 404
+    pub async fn is_releases_not_found(&self, owner: &str, repo: &str) -> bool {
+        self.is_not_found(&Self::releases_key(owner, repo)).await
+    }
+
+    // 记录 releases 返回 404
+    pub async fn set_releases_not_found(&self, owner: &str, repo: &str) {
+        self.set_not_found(&Self::releases_key(owner, repo)).await
+    }
+
+    // 获取带有 Last-Modified 或 ETag 的仓库信息（可能已过期），用于向 GitHub 发送条件请求
+    pub async fn revalidatable_repo_info(&self, owner: &str, repo: &str) -> Option<(RepoInfo, Validators)> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let store = self.persistent_store.read().await;
+        let entry = store.repo_info.get(&Self::repo_info_key(owner, repo))?;
+        Some((entry.value.clone(), entry.validators()?))
+    }
+
+    // 记录仓库信息对应的 Last-Modified 和 ETag（在写入缓存之后调用）
+    pub async fn set_repo_info_validators(&self, owner: &str, repo: &str, validators: Validators) {
+        let mut store = self.persistent_store.write().await;
+        if let Some(entry) = store.repo_info.get_mut(&Self::repo_info_key(owner, repo)) {
+            entry.last_modified = validators.last_modified;
+            entry.etag = validators.etag;
+        }
+    }
+
+    // 获取带有 Last-Modified 或 ETag 的 releases（可能已过期），用于向 GitHub 发送条件请求
+    pub async fn revalidatable_releases(&self, owner: &str, repo: &str) -> Option<(Vec<ReleaseInfo>, Validators)> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let store = self.persistent_store.read().await;
+        let entry = store.releases.get(&Self::releases_key(owner, repo))?;
+        Some((entry.value.clone(), entry.validators()?))
+    }
+
+    // 记录 releases 对应的 Last-Modified 和 ETag（在写入缓存之后调用）
+    pub async fn set_releases_validators(&self, owner: &str, repo: &str, validators: Validators) {
+        let mut store = self.persistent_store.write().await;
+        if let Some(entry) = store.releases.get_mut(&Self::releases_key(owner, repo)) {
+            entry.last_modified = validators.last_modified;
+            entry.etag = validators.etag;
+        }
+    }
+
+    // 检查最新 release 是否近期返回过 404
+    pub async fn is_latest_release_not_found(&self, owner: &str, repo: &str) -> bool {
+        self.is_not_found(&Self::latest_release_key(owner, repo)).await
+    }
+
+    // 记录最新 release 返回 404
+    pub async fn set_latest_release_not_found(&self, owner: &str, repo: &str) {
+        self.set_not_found(&Self::latest_release_key(owner, repo)).await
+    }
+
+    // 获取过期不超过 CACHE_OUTAGE_GRACE_SECS 的仓库信息，仅在请求 GitHub 出现临时性错误时兜底使用
+    pub async fn outage_repo_info(&self, owner: &str, repo: &str) -> Option<RepoInfo> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let store = self.persistent_store.read().await;
+        outage_fallback(&store.repo_info, &Self::repo_info_key(owner, repo), self.config.outage_grace_secs)
+    }
+
+    // 获取过期不超过 CACHE_OUTAGE_GRACE_SECS 的 releases，仅在请求 GitHub 出现临时性错误时兜底使用
+    pub async fn outage_releases(&self, owner: &str, repo: &str) -> Option<Vec<ReleaseInfo>> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let store = self.persistent_store.read().await;
+        outage_fallback(&store.releases, &Self::releases_key(owner, repo), self.config.outage_grace_secs)
+    }
+
+    // 获取过期不超过 CACHE_OUTAGE_GRACE_SECS 的最新 release，仅在请求 GitHub 出现临时性错误时兜底使用
+    pub async fn outage_latest_release(&self, owner: &str, repo: &str) -> Option<LatestReleaseInfo> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let store = self.persistent_store.read().await;
+        outage_fallback(
+            &store.latest_release,
+            &Self::latest_release_key(owner, repo),
+            self.config.outage_grace_secs,
+        )
+    }
+
+    /// 缓存键对应的条目是否为 GitHub 故障时兜底返回的过期数据，是则返回剩余可兜底的秒数
+    ///
+    /// 请求 GitHub 成功时条目总会被刷新，因此持久化存储中条目已过期但仍在
+    /// `CACHE_OUTAGE_GRACE_SECS` 内，说明本次返回的正是 `outage_*` 取出的过期数据。
+    pub async fn outage_grace_remaining(&self, key: &str) -> Option<u64> {
+        if !self.is_enabled() || self.config.outage_grace_secs == 0 {
+            return None;
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let expires_at = self.persistent_store.read().await.expires_at(key)?;
+        let grace_ends_at = expires_at.saturating_add(self.config.outage_grace_secs);
+        (expires_at <= now && grace_ends_at > now).then(|| grace_ends_at - now)
+    }
+
+    /// 自启动以来被请求合并（single-flight）合并的请求总数
+    pub fn coalesced_requests(&self) -> u64 {
+        self.repo_info_flight.coalesced_count()
+            + self.releases_flight.coalesced_count()
+            + self.latest_release_flight.coalesced_count()
+            + self.compare_flight.coalesced_count()
+            + self.milestones_flight.coalesced_count()
+    }
+
+    // 合并并发的仓库信息请求（同一仓库同一时间只会向 GitHub 发起一次请求）
+    pub async fn coalesce_repo_info<Fut>(
+        &self,
+        owner: &str,
+        repo: &str,
+        fetch: Fut,
+    ) -> Result<RepoInfo, AppError>
+    where
+        Fut: Future<Output = Result<RepoInfo, AppError>> + Send + 'static,
+    {
+        self.repo_info_flight
+            .run(Self::repo_info_key(owner, repo), fetch)
+            .await
+    }
+
+    // 合并并发的 releases 请求
+    pub async fn coalesce_releases<Fut>(
+        &self,
+        owner: &str,
+        repo: &str,
+        fetch: Fut,
+    ) -> Result<Vec<ReleaseInfo>, AppError>
+    where
+        Fut: Future<Output = Result<Vec<ReleaseInfo>, AppError>> + Send + 'static,
+    {
+        self.releases_flight
+            .run(Self::releases_key(owner, repo), fetch)
+            .await
+    }
+
+    // 合并并发的最新 release 请求
+    pub async fn coalesce_latest_release<Fut>(
+        &self,
+        owner: &str,
+        repo: &str,
+        fetch: Fut,
+    ) -> Result<LatestReleaseInfo, AppError>
+    where
+        Fut: Future<Output = Result<LatestReleaseInfo, AppError>> + Send + 'static,
+    {
+        self.latest_release_flight
+            .run(Self::latest_release_key(owner, repo), fetch)
+            .await
+    }
+
+    // 合并并发的版本对比请求
+    pub async fn coalesce_compare<Fut>(
+        &self,
+        owner: &str,
+        repo: &str,
+        base: &str,
+        head: &str,
+        fetch: Fut,
+    ) -> Result<CompareInfo, AppError>
+    where
+        Fut: Future<Output = Result<CompareInfo, AppError>> + Send + 'static,
+    {
+        self.compare_flight
+            .run(Self::compare_key(owner, repo, base, head), fetch)
+            .await
+    }
+
+    // 合并并发的里程碑请求
+    pub async fn coalesce_milestones<Fut>(
+        &self,
+        owner: &str,
+        repo: &str,
+        state: &str,
+        fetch: Fut,
+    ) -> Result<Vec<MilestoneInfo>, AppError>
+    where
+        Fut: Future<Output = Result<Vec<MilestoneInfo>, AppError>> + Send + 'static,
+    {
+        self.milestones_flight
+            .run(Self::milestones_key(owner, repo, state), fetch)
+            .await
+    }
+
+    // 生成文件缓存键（基于URL的hash）
+    fn file_cache_key(url: &str) -> CacheKey {
+        let mut hasher = Sha256::new();
+        hasher.update(url.as_bytes());
+        format!("file:{}", hex::encode(hasher.finalize()))
+    }
+
+    // 获取文件缓存元数据
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "cache.get_file_cache", skip_all, fields(url = %url, cache.hit = tracing::field::Empty))
+    )]
+    pub async fn get_file_cache(&self, url: &str) -> Option<FileCacheMetadata> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let key = Self::file_cache_key(url);
+        if let Some(mut metadata) = self.file_cache.get(&key).await {
+            // 检查文件是否仍然存在，且大小与写入时一致（防止返回被截断或损坏的文件）
+            if let Ok(file_meta) = std::fs::metadata(&metadata.file_path) {
+                if metadata.size.is_some_and(|size| size != file_meta.len()) {
+                    log::warn!(
+                        "缓存文件大小不一致（记录 {:?} 字节，实际 {} 字节），重新下载: {}",
+                        metadata.size,
+                        file_meta.len(),
+                        url
+                    );
+                    self.invalidate_file_cache(url).await;
+                    record_cache_hit(false);
+                    return None;
+                }
+                // 检查是否过期（不可变内容忽略 TTL）
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+                if !metadata.is_expired(now) {
+                    // 更新访问时间
+                    metadata.last_accessed_at = now;
+                    // 更新缓存中的访问时间
+                    let key_clone = key.clone();
+                    let metadata_clone = metadata.clone();
+                    self.file_cache.insert(key_clone, metadata_clone).await;
+                    record_cache_hit(true);
+                    return Some(metadata);
+                }
+            }
+        }
+        record_cache_hit(false);
+        None
+    }
+
+    // 保存文件到缓存
+    pub async fn set_file_cache(
+        &self,
+        url: &str,
+        file_path: PathBuf,
+        original_filename: String,
+        content_type: Option<String>,
+        etag: Option<String>,
+        gzip_path: Option<PathBuf>,
+    ) {
+        if self.is_enabled() {
+            let key = Self::file_cache_key(url);
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let expires_at = now + self.config.ttl_seconds;
+
+            let metadata = FileCacheMetadata {
+                url: url.to_string(),
+                file_path: file_path.clone(),
+                original_filename,
+                content_type,
+                expires_at,
+                last_accessed_at: now, // 设置初始访问时间为当前时间
+                etag,
+                cached_at: now,
+                gzip_path,
+                immutable: get_download_config().is_immutable_url(url),
+                size: std::fs::metadata(&file_path).ok().map(|m| m.len()),
+            };
+
+            self.file_cache.insert(key.clone(), metadata.clone()).await;
+
+            // 更新文件路径到缓存键的映射（重新下载覆盖同一文件时文件数不变）
+            let mut mapping = self.file_path_to_key.write().await;
+            if mapping.insert(file_path.clone(), key).is_none() {
+                self.file_count.fetch_add(1, Ordering::SeqCst);
+            }
+            drop(mapping);
+
+            log::debug!("文件已缓存: {} -> {:?}", url, file_path);
+        }
+    }
+
+    // 开始下载 URL：没有进行中的相同下载时由当前请求负责下载，否则返回等待者。
+    // 协调键与文件缓存键相同（URL 的 SHA-256）
+    pub fn begin_download(&self, url: &str) -> DownloadFlight {
+        let key = Self::file_cache_key(url);
+        let mut flights = self.download_flights.lock().unwrap();
+        if let Some(done) = flights.get(&key) {
+            return DownloadFlight::Follower(DownloadFlightWaiter(done.clone()));
+        }
+        let (sender, receiver) = watch::channel(());
+        flights.insert(key.clone(), receiver);
+        DownloadFlight::Leader(DownloadFlightGuard {
+            key,
+            flights: self.download_flights.clone(),
+            _done: sender,
+        })
+    }
+
+    // 移除文件缓存元数据（缓存文件损坏或不完整时调用，之后的请求会重新下载）
+    pub async fn invalidate_file_cache(&self, url: &str) {
+        let key = Self::file_cache_key(url);
+        if let Some(metadata) = self.file_cache.remove(&key).await {
+            if self.file_path_to_key.write().await.remove(&metadata.file_path).is_some() {
+                self.file_count.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    // 获取文件缓存目录
+    pub fn get_file_cache_dir(&self) -> &PathBuf {
+        &self.file_cache_dir
+    }
+
+    // 清理文件缓存，使用 LRV (Least Recently Visited) 算法保留最常访问的 N 个文件
+    pub async fn cleanup_file_cache(&self, max_files: usize) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.clone_for_file_cleanup().cleanup(max_files).await;
+    }
+
+    /// 写入新的缓存文件前预留位置。文件数已达到 `FILE_CACHE_MAX_FILES` 时先删除最久未访问的文件，
+    /// 保证磁盘上的缓存文件数不超过上限；所有位置都被正在写入的文件占用时返回 None（本次下载不写入缓存）。
+    /// `file_path` 已登记时（重新下载覆盖同一文件）文件数不变，不需要预留
+    pub async fn reserve_file_slot(&self, file_path: &Path) -> Option<FileSlot> {
+        if !self.is_enabled() {
+            return None;
+        }
+        if self.file_path_to_key.read().await.contains_key(file_path) {
+            return Some(FileSlot(None));
+        }
+        self.clone_for_file_cleanup()
+            .reserve(self.config.file_cache_max_files)
+            .await
+    }
+}
+
+// 后台清理文件缓存使用的句柄（与缓存管理器共享文件缓存元数据）
+struct FileCacheCleaner {
+    file_cache: Cache<CacheKey, FileCacheMetadata>,
+    file_path_to_key: Arc<RwLock<HashMap<PathBuf, CacheKey>>>,
+    file_cache_dir: PathBuf,
+    file_count: Arc<AtomicUsize>,
+}
+
+impl FileCacheCleaner {
+    // 文件数低于上限时预留一个位置，否则先淘汰最久未访问的文件再重试
+    async fn reserve(&self, max_files: usize) -> Option<FileSlot> {
+        loop {
+            let count = self.file_count.load(Ordering::SeqCst);
+            if count < max_files {
+                if self
+                    .file_count
+                    .compare_exchange(count, count + 1, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+                {
+                    return Some(FileSlot(Some(self.file_count.clone())));
+                }
+                continue;
+            }
+            if !self.evict_least_recently_visited().await {
+                return None;
+            }
+        }
+    }
+
+    // 删除最久未访问的一个缓存文件（元数据已过期的文件优先），没有已登记的文件时返回 false。
+    // 先在读锁下复制映射再查询元数据，只在移除选中的条目时短暂持有写锁，删除文件不持有锁
+    async fn evict_least_recently_visited(&self) -> bool {
+        let candidates: Vec<(PathBuf, CacheKey)> = self
+            .file_path_to_key
+            .read()
+            .await
+            .iter()
+            .map(|(file_path, cache_key)| (file_path.clone(), cache_key.clone()))
+            .collect();
+        if candidates.is_empty() {
+            return false;
+        }
+
+        let mut oldest: Option<(PathBuf, CacheKey, Option<FileCacheMetadata>)> = None;
+        for (file_path, cache_key) in candidates {
+            let metadata = self.file_cache.get(&cache_key).await;
+            let accessed_at = metadata.as_ref().map_or(0, |m| m.last_accessed_at);
+            let older = oldest
+                .as_ref()
+                .is_none_or(|(_, _, m)| accessed_at < m.as_ref().map_or(0, |m| m.last_accessed_at));
+            if older {
+                oldest = Some((file_path, cache_key, metadata));
+            }
+        }
+        let Some((file_path, cache_key, metadata)) = oldest else {
+            return false;
+        };
+
+        // 复制映射之后条目可能已被其他请求移除或替换，此时不删除文件，由调用方重新检查文件数
+        {
+            let mut mapping = self.file_path_to_key.write().await;
+            if mapping.get(&file_path) != Some(&cache_key) {
+                return true;
+            }
+            mapping.remove(&file_path);
+        }
+
+        // 先删除文件再减少计数，避免其他请求在文件删除前写入新文件
+        if let Err(e) = tokio::fs::remove_file(&file_path).await {
+            log::warn!("无法删除缓存文件 {:?}: {}", file_path, e);
+        }
+        if let Some(gzip_path) = metadata.as_ref().and_then(|m| m.gzip_path.as_ref()) {
+            let _ = tokio::fs::remove_file(gzip_path).await;
+        }
+        self.file_cache.invalidate(&cache_key).await;
+        self.file_count.fetch_sub(1, Ordering::SeqCst);
+        log::debug!("文件缓存已满，淘汰最久未访问的文件: {:?}", file_path);
+        true
+    }
+
+    async fn cleanup(&self, max_files: usize) {
+        // 收集所有有效的文件缓存元数据
+        let mut file_metadatas: Vec<(PathBuf, FileCacheMetadata)> = Vec::new();
+        let mapping = self.file_path_to_key.read().await;
+
+        // 扫描文件缓存目录，收集所有文件的元数据
+        match std::fs::read_dir(&self.file_cache_dir) {
+            Ok(entries) => {
+                for entry in entries.flatten() {
+                    let file_path = entry.path();
+                    if file_path.is_file() {
+                        // 通过文件路径查找对应的缓存键
+                        if let Some(cache_key) = mapping.get(&file_path) {
+                            // 从缓存中获取元数据
+                            if let Some(metadata) = self.file_cache.get(cache_key).await {
+                                // 检查文件是否仍然存在且未过期
+                                let now = SystemTime::now()
+                                    .duration_since(UNIX_EPOCH)
+                                    .unwrap()
+                                    .as_secs();
+                                if metadata.file_path.exists() && !metadata.is_expired(now) {
+                                    file_metadatas.push((file_path.clone(), metadata));
                                 }
                             }
                         }
+                    }
+                }
+            }
+            Err(e) => {
+                log::warn!("无法读取文件缓存目录: {}", e);
+                return;
+            }
+        }
+
+        drop(mapping);
+
+        // 按访问时间排序（最近访问的在前）
+        file_metadatas.sort_by_key(|b| std::cmp::Reverse(b.1.last_accessed_at));
 
-                        // 加载 latest_release 缓存
-                        for (key, entry) in persistent_cache.latest_release.iter() {
-                            if entry.expires_at > now {
-                                let remaining_ttl = entry.expires_at - now;
-                                if remaining_ttl > 0 {
-                                    self.latest_release_cache
-                                        .insert(key.clone(), entry.value.clone())
-                                        .await;
-                                    store.latest_release.insert(key.clone(), entry.clone());
-                                    loaded_count += 1;
-                                }
-                            }
-                        }
+        // 如果文件数量超过限制，删除最旧的文件
+        if file_metadatas.len() > max_files {
+            let files_to_delete = &file_metadatas[max_files..];
+            let mut deleted_count = 0;
+            let mut mapping = self.file_path_to_key.write().await;
 
-                        log::info!("从磁盘加载了 {} 个缓存条目", loaded_count);
+            for (file_path, metadata) in files_to_delete {
+                // 删除文件
+                if let Err(e) = std::fs::remove_file(file_path) {
+                    log::warn!("无法删除缓存文件 {:?}: {}", file_path, e);
+                } else {
+                    deleted_count += 1;
+                    log::debug!("已删除缓存文件: {:?} (URL: {})", file_path, metadata.url);
+                    if let Some(gzip_path) = &metadata.gzip_path {
+                        let _ = std::fs::remove_file(gzip_path);
                     }
-                    Err(e) => {
-                        log::warn!("无法解析缓存文件: {}", e);
+
+                    // 从映射中删除
+                    if mapping.remove(file_path).is_some() {
+                        self.file_count.fetch_sub(1, Ordering::SeqCst);
                     }
+
+                    // 从缓存中删除（通过缓存键）
+                    let cache_key = CacheManager::file_cache_key(&metadata.url);
+                    self.file_cache.invalidate(&cache_key).await;
                 }
             }
-            Err(e) => {
-                if e.kind() != std::io::ErrorKind::NotFound {
-                    log::warn!("无法读取缓存文件: {}", e);
-                }
-            }
+
+            log::info!("文件缓存清理完成: 保留 {} 个文件，删除 {} 个文件", max_files, deleted_count);
         }
     }
+}
 
-    // 保存缓存到磁盘（保留用于可能的手动调用）
-    #[allow(dead_code)]
+// 后台任务使用的缓存管理器（只用于保存）
+struct BackgroundCacheManager {
+    persistent_store: Arc<RwLock<PersistentCache>>,
+    cache_file_path: PathBuf,
+    compress: bool,
+    config: CacheConfig,
+}
+
+impl BackgroundCacheManager {
     async fn save_to_disk(&self) {
         if !self.config.enabled {
             return;
@@ -273,354 +2087,552 @@ impl CacheManager {
             .unwrap()
             .as_secs();
 
+        // 从内存缓存同步到持久化存储
+        // 注意：moka 不提供遍历方法，所以我们只能保存持久化存储中的内容
         let store = self.persistent_store.read().await;
 
-        // 过滤掉已过期的条目
+        // 过滤掉已过期且超出保留期的条目
+        let (grace, outage) = (self.config.ttl_seconds, self.config.outage_grace_secs);
         let persistent_cache = PersistentCache {
             repo_info: store
                 .repo_info
                 .iter()
-                .filter(|(_, entry)| entry.expires_at > now)
+                .filter(|(_, entry)| entry.should_keep(now, grace, outage))
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect(),
             releases: store
                 .releases
                 .iter()
-                .filter(|(_, entry)| entry.expires_at > now)
+                .filter(|(_, entry)| entry.should_keep(now, grace, outage))
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect(),
             latest_release: store
                 .latest_release
                 .iter()
-                .filter(|(_, entry)| entry.expires_at > now)
+                .filter(|(_, entry)| entry.should_keep(now, grace, outage))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            compare: store
+                .compare
+                .iter()
+                .filter(|(_, entry)| entry.should_keep(now, grace, outage))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            checksums: store
+                .checksums
+                .iter()
+                .filter(|(_, entry)| entry.should_keep(now, grace, outage))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            generated_notes: store
+                .generated_notes
+                .iter()
+                .filter(|(_, entry)| entry.should_keep(now, grace, outage))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            raw_repo: store
+                .raw_repo
+                .iter()
+                .filter(|(_, entry)| entry.should_keep(now, grace, outage))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            contents: store
+                .contents
+                .iter()
+                .filter(|(_, entry)| entry.should_keep(now, grace, outage))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            milestones: store
+                .milestones
+                .iter()
+                .filter(|(_, entry)| entry.should_keep(now, grace, outage))
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect(),
         };
 
-        match serde_json::to_string_pretty(&persistent_cache) {
-            Ok(json) => {
-                if let Err(e) = std::fs::write(&self.cache_file_path, json) {
-                    log::warn!("无法保存缓存文件: {}", e);
-                }
-            }
-            Err(e) => {
-                log::warn!("无法序列化缓存: {}", e);
-            }
-        }
-    }
-
-    pub fn is_enabled(&self) -> bool {
-        self.config.enabled
-    }
-
-    // 生成缓存键
-    fn repo_info_key(owner: &str, repo: &str) -> CacheKey {
-        format!("repo_info:{}:{}", owner, repo)
+        write_persistent_cache(&self.cache_file_path, self.compress, &persistent_cache);
     }
+}
 
-    fn releases_key(owner: &str, repo: &str) -> CacheKey {
-        format!("releases:{}:{}", owner, repo)
-    }
+impl BackgroundCacheManager {
+    // 清理持久化存储中已过期的条目，释放内存
+    async fn prune_expired(&self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
 
-    fn latest_release_key(owner: &str, repo: &str) -> CacheKey {
-        format!("latest_release:{}:{}", owner, repo)
+        let mut store = self.persistent_store.write().await;
+        // 带有 Last-Modified 的条目过期后再保留一个 TTL，用于 If-Modified-Since 重新验证；
+        // 所有条目过期后再保留 CACHE_OUTAGE_GRACE_SECS，用于 GitHub 故障时兜底
+        let pruned = store.prune_expired(now, self.config.ttl_seconds, self.config.outage_grace_secs);
+        if pruned > 0 {
+            log::info!("已清理 {} 个过期的持久化缓存条目，剩余 {} 个", pruned, store.len());
+        }
     }
+}
 
+// 默认的缓存后端：进程内 moka 缓存 + 本地持久化文件
+#[async_trait]
+impl CacheBackend for CacheManager {
     // 获取仓库信息（带缓存）
-    pub async fn get_repo_info(&self, owner: &str, repo: &str) -> Option<RepoInfo> {
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "cache.get_repo_info", skip_all, fields(repo = %format_args!("{}/{}", owner, repo), cache.hit = tracing::field::Empty))
+    )]
+    async fn get_repo_info(&self, owner: &str, repo: &str) -> Option<RepoInfo> {
         if !self.is_enabled() {
             return None;
         }
         let key = Self::repo_info_key(owner, repo);
-        self.repo_info_cache.get(&key).await
+        let value = self.repo_info_cache.get(&key).await;
+        record_cache_hit(value.is_some());
+        value
     }
 
     // 存储仓库信息到缓存
-    pub async fn set_repo_info(&self, owner: &str, repo: &str, info: RepoInfo) {
+    async fn set_repo_info(&self, owner: &str, repo: &str, info: RepoInfo) {
         if self.is_enabled() {
             let key = Self::repo_info_key(owner, repo);
             self.repo_info_cache.insert(key.clone(), info.clone()).await;
 
             // 更新持久化存储
-            let expires_at = SystemTime::now()
+            let cached_at = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
-                .as_secs()
-                + self.config.ttl_seconds;
+                .as_secs();
+            let expires_at = cached_at + self.config.ttl_seconds;
 
             let mut store = self.persistent_store.write().await;
-            store.repo_info.insert(key, CachedEntry {
-                value: info,
-                expires_at,
-            });
+            insert_bounded(
+                &mut store.repo_info,
+                key,
+                CachedEntry {
+                    value: info,
+                    expires_at,
+                    cached_at,
+                    last_modified: None,
+                    etag: None,
+                },
+                self.config.persistent_max_entries,
+            );
         }
     }
 
     // 获取 releases（带缓存）
-    pub async fn get_releases(&self, owner: &str, repo: &str) -> Option<Vec<ReleaseInfo>> {
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "cache.get_releases", skip_all, fields(repo = %format_args!("{}/{}", owner, repo), cache.hit = tracing::field::Empty))
+    )]
+    async fn get_releases(&self, owner: &str, repo: &str) -> Option<Vec<ReleaseInfo>> {
         if !self.is_enabled() {
             return None;
         }
         let key = Self::releases_key(owner, repo);
-        self.releases_cache.get(&key).await
+        let value = self.releases_cache.get(&key).await;
+        record_cache_hit(value.is_some());
+        value
     }
 
     // 存储 releases 到缓存
-    pub async fn set_releases(&self, owner: &str, repo: &str, releases: Vec<ReleaseInfo>) {
+    async fn set_releases(&self, owner: &str, repo: &str, releases: Vec<ReleaseInfo>) {
         if self.is_enabled() {
             let key = Self::releases_key(owner, repo);
             self.releases_cache.insert(key.clone(), releases.clone()).await;
 
             // 更新持久化存储
-            let expires_at = SystemTime::now()
+            let cached_at = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
-                .as_secs()
-                + self.config.ttl_seconds;
+                .as_secs();
+            let expires_at = cached_at + self.config.ttl_seconds;
 
             let mut store = self.persistent_store.write().await;
-            store.releases.insert(key, CachedEntry {
-                value: releases,
-                expires_at,
-            });
+            insert_bounded(
+                &mut store.releases,
+                key,
+                CachedEntry {
+                    value: releases,
+                    expires_at,
+                    cached_at,
+                    last_modified: None,
+                    etag: None,
+                },
+                self.config.persistent_max_entries,
+            );
         }
     }
 
     // 获取最新 release（带缓存）
-    pub async fn get_latest_release(&self, owner: &str, repo: &str) -> Option<LatestReleaseInfo> {
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "cache.get_latest_release", skip_all, fields(repo = %format_args!("{}/{}", owner, repo), cache.hit = tracing::field::Empty))
+    )]
+    async fn get_latest_release(&self, owner: &str, repo: &str) -> Option<LatestReleaseInfo> {
         if !self.is_enabled() {
             return None;
         }
         let key = Self::latest_release_key(owner, repo);
-        self.latest_release_cache.get(&key).await
+        let value = self.latest_release_cache.get(&key).await;
+        record_cache_hit(value.is_some());
+        value
     }
 
     // 存储最新 release 到缓存
-    pub async fn set_latest_release(&self, owner: &str, repo: &str, release: LatestReleaseInfo) {
+    async fn set_latest_release(&self, owner: &str, repo: &str, release: LatestReleaseInfo) {
         if self.is_enabled() {
             let key = Self::latest_release_key(owner, repo);
             self.latest_release_cache.insert(key.clone(), release.clone()).await;
 
             // 更新持久化存储
-            let expires_at = SystemTime::now()
+            let cached_at = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
-                .as_secs()
-                + self.config.ttl_seconds;
+                .as_secs();
+            let expires_at = cached_at + self.config.ttl_seconds;
 
             let mut store = self.persistent_store.write().await;
-            store.latest_release.insert(key, CachedEntry {
-                value: release,
-                expires_at,
-            });
+            insert_bounded(
+                &mut store.latest_release,
+                key,
+                CachedEntry {
+                    value: release,
+                    expires_at,
+                    cached_at,
+                    last_modified: None,
+                    etag: None,
+                },
+                self.config.persistent_max_entries,
+            );
         }
     }
 
-    // 生成文件缓存键（基于URL的hash）
-    fn file_cache_key(url: &str) -> CacheKey {
-        let mut hasher = Sha256::new();
-        hasher.update(url.as_bytes());
-        format!("file:{}", hex::encode(hasher.finalize()))
-    }
-
-    // 获取文件缓存元数据
-    pub async fn get_file_cache(&self, url: &str) -> Option<FileCacheMetadata> {
+    // 获取版本对比信息（带缓存）
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "cache.get_compare", skip_all, fields(repo = %format_args!("{}/{}", owner, repo), cache.hit = tracing::field::Empty))
+    )]
+    async fn get_compare(
+        &self,
+        owner: &str,
+        repo: &str,
+        base: &str,
+        head: &str,
+    ) -> Option<CompareInfo> {
         if !self.is_enabled() {
             return None;
         }
-        let key = Self::file_cache_key(url);
-        if let Some(mut metadata) = self.file_cache.get(&key).await {
-            // 检查文件是否仍然存在
-            if metadata.file_path.exists() {
-                // 检查是否过期
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
-                if metadata.expires_at > now {
-                    // 更新访问时间
-                    metadata.last_accessed_at = now;
-                    // 更新缓存中的访问时间
-                    let key_clone = key.clone();
-                    let metadata_clone = metadata.clone();
-                    self.file_cache.insert(key_clone, metadata_clone).await;
-                    return Some(metadata);
-                }
-            }
-        }
-        None
+        let key = Self::compare_key(owner, repo, base, head);
+        let value = self.compare_cache.get(&key).await;
+        record_cache_hit(value.is_some());
+        value
     }
 
-    // 保存文件到缓存
-    pub async fn set_file_cache(
+    // 存储版本对比信息到缓存
+    async fn set_compare(
         &self,
-        url: &str,
-        file_path: PathBuf,
-        original_filename: String,
-        content_type: Option<String>,
+        owner: &str,
+        repo: &str,
+        base: &str,
+        head: &str,
+        compare: CompareInfo,
     ) {
         if self.is_enabled() {
-            let key = Self::file_cache_key(url);
-            let now = SystemTime::now()
+            let key = Self::compare_key(owner, repo, base, head);
+            self.compare_cache.insert(key.clone(), compare.clone()).await;
+
+            // 更新持久化存储
+            let cached_at = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs();
-            let expires_at = now + self.config.ttl_seconds;
+            let expires_at = cached_at + self.config.ttl_seconds;
 
-            let metadata = FileCacheMetadata {
-                url: url.to_string(),
-                file_path: file_path.clone(),
-                original_filename,
-                content_type,
-                expires_at,
-                last_accessed_at: now, // 设置初始访问时间为当前时间
-            };
+            let mut store = self.persistent_store.write().await;
+            insert_bounded(
+                &mut store.compare,
+                key,
+                CachedEntry {
+                    value: compare,
+                    expires_at,
+                    cached_at,
+                    last_modified: None,
+                    etag: None,
+                },
+                self.config.persistent_max_entries,
+            );
+        }
+    }
 
-            self.file_cache.insert(key.clone(), metadata.clone()).await;
+    // 获取最新 release 的校验和（带缓存）
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "cache.get_checksums", skip_all, fields(repo = %format_args!("{}/{}", owner, repo), cache.hit = tracing::field::Empty))
+    )]
+    async fn get_checksums(&self, owner: &str, repo: &str) -> Option<ChecksumsInfo> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let key = Self::checksums_key(owner, repo);
+        let value = self.checksums_cache.get(&key).await;
+        record_cache_hit(value.is_some());
+        value
+    }
 
-            // 更新文件路径到缓存键的映射
-            let mut mapping = self.file_path_to_key.write().await;
-            mapping.insert(file_path.clone(), key);
-            drop(mapping);
+    // 存储最新 release 的校验和到缓存
+    async fn set_checksums(&self, owner: &str, repo: &str, checksums: ChecksumsInfo) {
+        if self.is_enabled() {
+            let key = Self::checksums_key(owner, repo);
+            self.checksums_cache.insert(key.clone(), checksums.clone()).await;
 
-            log::debug!("文件已缓存: {} -> {:?}", url, file_path);
+            // 更新持久化存储
+            let cached_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let expires_at = cached_at + self.config.ttl_seconds;
 
-            // 清理旧文件，保留最常访问的50个
-            self.cleanup_file_cache(50).await;
+            let mut store = self.persistent_store.write().await;
+            insert_bounded(
+                &mut store.checksums,
+                key,
+                CachedEntry {
+                    value: checksums,
+                    expires_at,
+                    cached_at,
+                    last_modified: None,
+                    etag: None,
+                },
+                self.config.persistent_max_entries,
+            );
         }
     }
 
-    // 获取文件缓存目录
-    pub fn get_file_cache_dir(&self) -> &PathBuf {
-        &self.file_cache_dir
+    // 获取 GitHub 生成的 release notes（带缓存）
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "cache.get_generated_notes", skip_all, fields(repo = %format_args!("{}/{}", owner, repo), cache.hit = tracing::field::Empty))
+    )]
+    async fn get_generated_notes(
+        &self,
+        owner: &str,
+        repo: &str,
+        tag: &str,
+        previous_tag: Option<&str>,
+    ) -> Option<GeneratedNotes> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let key = Self::generated_notes_key(owner, repo, tag, previous_tag);
+        let value = self.generated_notes_cache.get(&key).await;
+        record_cache_hit(value.is_some());
+        value
     }
 
-    // 清理文件缓存，使用 LRV (Least Recently Visited) 算法保留最常访问的 N 个文件
-    pub async fn cleanup_file_cache(&self, max_files: usize) {
+    // 存储 GitHub 生成的 release notes 到缓存
+    async fn set_generated_notes(
+        &self,
+        owner: &str,
+        repo: &str,
+        tag: &str,
+        previous_tag: Option<&str>,
+        notes: GeneratedNotes,
+    ) {
+        if self.is_enabled() {
+            let key = Self::generated_notes_key(owner, repo, tag, previous_tag);
+            self.generated_notes_cache.insert(key.clone(), notes.clone()).await;
+
+            // 更新持久化存储
+            let cached_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let expires_at = cached_at + self.config.ttl_seconds;
+
+            let mut store = self.persistent_store.write().await;
+            insert_bounded(
+                &mut store.generated_notes,
+                key,
+                CachedEntry {
+                    value: notes,
+                    expires_at,
+                    cached_at,
+                    last_modified: None,
+                    etag: None,
+                },
+                self.config.persistent_max_entries,
+            );
+        }
+    }
+
+    // 获取 GitHub 返回的原始仓库 JSON（带缓存）
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "cache.get_raw_repo", skip_all, fields(repo = %format_args!("{}/{}", owner, repo), cache.hit = tracing::field::Empty))
+    )]
+    async fn get_raw_repo(&self, owner: &str, repo: &str) -> Option<serde_json::Value> {
         if !self.is_enabled() {
-            return;
+            return None;
         }
+        let key = Self::raw_repo_key(owner, repo);
+        let value = self.raw_repo_cache.get(&key).await;
+        record_cache_hit(value.is_some());
+        value
+    }
 
-        // 收集所有有效的文件缓存元数据
-        let mut file_metadatas: Vec<(PathBuf, FileCacheMetadata)> = Vec::new();
-        let mapping = self.file_path_to_key.read().await;
+    // 存储原始仓库 JSON 到缓存
+    async fn set_raw_repo(&self, owner: &str, repo: &str, raw: serde_json::Value) {
+        if self.is_enabled() {
+            let key = Self::raw_repo_key(owner, repo);
+            self.raw_repo_cache.insert(key.clone(), raw.clone()).await;
 
-        // 扫描文件缓存目录，收集所有文件的元数据
-        match std::fs::read_dir(&self.file_cache_dir) {
-            Ok(entries) => {
-                for entry in entries.flatten() {
-                    let file_path = entry.path();
-                    if file_path.is_file() {
-                        // 通过文件路径查找对应的缓存键
-                        if let Some(cache_key) = mapping.get(&file_path) {
-                            // 从缓存中获取元数据
-                            if let Some(metadata) = self.file_cache.get(cache_key).await {
-                                // 检查文件是否仍然存在且未过期
-                                let now = SystemTime::now()
-                                    .duration_since(UNIX_EPOCH)
-                                    .unwrap()
-                                    .as_secs();
-                                if metadata.file_path.exists() && metadata.expires_at > now {
-                                    file_metadatas.push((file_path.clone(), metadata));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                log::warn!("无法读取文件缓存目录: {}", e);
-                return;
-            }
+            // 更新持久化存储
+            let cached_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let expires_at = cached_at + self.config.ttl_seconds;
+
+            let mut store = self.persistent_store.write().await;
+            insert_bounded(
+                &mut store.raw_repo,
+                key,
+                CachedEntry {
+                    value: raw,
+                    expires_at,
+                    cached_at,
+                    last_modified: None,
+                    etag: None,
+                },
+                self.config.persistent_max_entries,
+            );
         }
+    }
 
-        drop(mapping);
+    // 获取仓库文件内容（带缓存）
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "cache.get_contents", skip_all, fields(repo = %format_args!("{}/{}", owner, repo), cache.hit = tracing::field::Empty))
+    )]
+    async fn get_contents(&self, owner: &str, repo: &str, path: &str, git_ref: Option<&str>) -> Option<serde_json::Value> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let key = Self::contents_key(owner, repo, path, git_ref);
+        let value = self.contents_cache.get(&key).await;
+        record_cache_hit(value.is_some());
+        value
+    }
 
-        // 按访问时间排序（最近访问的在前）
-        file_metadatas.sort_by(|a, b| b.1.last_accessed_at.cmp(&a.1.last_accessed_at));
+    // 存储仓库文件内容到缓存
+    async fn set_contents(&self, owner: &str, repo: &str, path: &str, git_ref: Option<&str>, contents: serde_json::Value) {
+        if self.is_enabled() {
+            let key = Self::contents_key(owner, repo, path, git_ref);
+            self.contents_cache.insert(key.clone(), contents.clone()).await;
 
-        // 如果文件数量超过限制，删除最旧的文件
-        if file_metadatas.len() > max_files {
-            let files_to_delete = &file_metadatas[max_files..];
-            let mut deleted_count = 0;
-            let mut mapping = self.file_path_to_key.write().await;
+            // 更新持久化存储
+            let cached_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let expires_at = cached_at + self.config.ttl_seconds;
 
-            for (file_path, metadata) in files_to_delete {
-                // 删除文件
-                if let Err(e) = std::fs::remove_file(file_path) {
-                    log::warn!("无法删除缓存文件 {:?}: {}", file_path, e);
-                } else {
-                    deleted_count += 1;
-                    log::debug!("已删除缓存文件: {:?} (URL: {})", file_path, metadata.url);
+            let mut store = self.persistent_store.write().await;
+            insert_bounded(
+                &mut store.contents,
+                key,
+                CachedEntry {
+                    value: contents,
+                    expires_at,
+                    cached_at,
+                    last_modified: None,
+                    etag: None,
+                },
+                self.config.persistent_max_entries,
+            );
+            enforce_contents_budget(&mut store.contents, CONTENTS_CACHE_MAX_BYTES);
+        }
+    }
 
-                    // 从映射中删除
-                    mapping.remove(file_path);
+    // 获取仓库里程碑列表（带缓存）
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "cache.get_milestones", skip_all, fields(repo = %format_args!("{}/{}", owner, repo), cache.hit = tracing::field::Empty))
+    )]
+    async fn get_milestones(&self, owner: &str, repo: &str, state: &str) -> Option<Vec<MilestoneInfo>> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let key = Self::milestones_key(owner, repo, state);
+        let value = self.milestones_cache.get(&key).await;
+        record_cache_hit(value.is_some());
+        value
+    }
 
-                    // 从缓存中删除（通过缓存键）
-                    let cache_key = Self::file_cache_key(&metadata.url);
-                    self.file_cache.invalidate(&cache_key).await;
-                }
-            }
+    // 存储仓库里程碑列表到缓存（没有里程碑时存储空列表）
+    async fn set_milestones(&self, owner: &str, repo: &str, state: &str, milestones: Vec<MilestoneInfo>) {
+        if self.is_enabled() {
+            let key = Self::milestones_key(owner, repo, state);
+            self.milestones_cache.insert(key.clone(), milestones.clone()).await;
+
+            // 更新持久化存储
+            let cached_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let expires_at = cached_at + self.config.ttl_seconds;
 
-            log::info!("文件缓存清理完成: 保留 {} 个文件，删除 {} 个文件", max_files, deleted_count);
+            let mut store = self.persistent_store.write().await;
+            insert_bounded(
+                &mut store.milestones,
+                key,
+                CachedEntry {
+                    value: milestones,
+                    expires_at,
+                    cached_at,
+                    last_modified: None,
+                    etag: None,
+                },
+                self.config.persistent_max_entries,
+            );
         }
     }
-}
-
-// 后台任务使用的缓存管理器（只用于保存）
-struct BackgroundCacheManager {
-    persistent_store: Arc<RwLock<PersistentCache>>,
-    cache_file_path: PathBuf,
-    config: CacheConfig,
-}
 
-impl BackgroundCacheManager {
-    async fn save_to_disk(&self) {
-        if !self.config.enabled {
-            return;
+    // 从持久化存储中查找条目的过期时间
+    async fn expires_at(&self, key: &str) -> Option<u64> {
+        if !self.is_enabled() {
+            return None;
         }
+        self.persistent_store.read().await.expires_at(key)
+    }
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        // 从内存缓存同步到持久化存储
-        // 注意：moka 不提供遍历方法，所以我们只能保存持久化存储中的内容
-        let store = self.persistent_store.read().await;
-
-        // 过滤掉已过期的条目
-        let persistent_cache = PersistentCache {
-            repo_info: store
-                .repo_info
-                .iter()
-                .filter(|(_, entry)| entry.expires_at > now)
-                .map(|(k, v)| (k.clone(), v.clone()))
-                .collect(),
-            releases: store
-                .releases
-                .iter()
-                .filter(|(_, entry)| entry.expires_at > now)
-                .map(|(k, v)| (k.clone(), v.clone()))
-                .collect(),
-            latest_release: store
-                .latest_release
-                .iter()
-                .filter(|(_, entry)| entry.expires_at > now)
-                .map(|(k, v)| (k.clone(), v.clone()))
-                .collect(),
-        };
+    // 从持久化存储中查找条目的获取时间，旧版本缓存文件中的条目按 过期时间 - TTL 推算
+    async fn cached_at(&self, key: &str) -> Option<u64> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let (cached_at, expires_at) = self.persistent_store.read().await.entry_times(key)?;
+        Some(if cached_at > 0 {
+            cached_at
+        } else {
+            expires_at.saturating_sub(self.config.ttl_seconds)
+        })
+    }
 
-        match serde_json::to_string_pretty(&persistent_cache) {
-            Ok(json) => {
-                if let Err(e) = std::fs::write(&self.cache_file_path, json) {
-                    log::warn!("无法保存缓存文件: {}", e);
-                }
-            }
-            Err(e) => {
-                log::warn!("无法序列化缓存: {}", e);
-            }
+    // 遍历缓存中的 releases 和最新 release，查找包含该附件的条目
+    async fn is_known_release_asset(&self, url: &str) -> bool {
+        if !self.is_enabled() {
+            return false;
         }
+        let in_latest = self
+            .latest_release_cache
+            .iter()
+            .any(|(_, release)| release.attachments.iter().any(|a| a == url));
+        in_latest
+            || self
+                .releases_cache
+                .iter()
+                .any(|(_, releases)| releases.iter().any(|r| r.attachments.iter().any(|a| a == url)))
     }
 }
 
@@ -643,13 +2655,6 @@ mod tests {
     use super::*;
     use crate::models::{LatestReleaseInfo, ReleaseInfo, RepoInfo};
 
-    fn create_test_cache_config(enabled: bool, ttl_seconds: u64) -> CacheConfig {
-        CacheConfig {
-            enabled,
-            ttl_seconds,
-        }
-    }
-
     fn create_test_repo_info() -> RepoInfo {
         RepoInfo {
             repo: "test/test".to_string(),
@@ -659,7 +2664,9 @@ mod tests {
             description: Some("Test repo".to_string()),
             stargazers_count: 100,
             forks_count: 50,
-            updated_at: "2024-01-01T00:00:00Z".to_string(),
+            updated_at: "2024-01-01T00:00:00Z".parse().unwrap(),
+            private: false,
+            moved_to: None,
         }
     }
 
@@ -668,11 +2675,17 @@ mod tests {
             tag_name: "v1.0.0".to_string(),
             name: Some("Release 1.0.0".to_string()),
             changelog: Some("Changelog".to_string()),
-            published_at: "2024-01-01T00:00:00Z".to_string(),
-            attachments: vec![(
-                "file.zip".to_string(),
-                "https://example.com/file.zip".to_string(),
-            )],
+            changelog_truncated: false,
+            published_at: Some("2024-01-01T00:00:00Z".parse().unwrap()),
+            prerelease: false,
+            draft: false,
+            attachments: vec!["https://example.com/file.zip".to_string()],
+            attachment_sizes: vec![1024],
+            author_login: None,
+            author_avatar_url: None,
+            reactions: None,
+            zipball_url: None,
+            tarball_url: None,
         }
     }
 
@@ -681,11 +2694,16 @@ mod tests {
             repo: "test/test".to_string(),
             latest_version: "v1.0.0".to_string(),
             changelog: Some("Changelog".to_string()),
-            published_at: "2024-01-01T00:00:00Z".to_string(),
-            attachments: vec![(
-                "file.zip".to_string(),
-                "https://example.com/file.zip".to_string(),
-            )],
+            changelog_truncated: false,
+            published_at: Some("2024-01-01T00:00:00Z".parse().unwrap()),
+            prerelease: false,
+            attachments: vec!["https://example.com/file.zip".to_string()],
+            attachment_sizes: vec![1024],
+            author_login: None,
+            author_avatar_url: None,
+            reactions: None,
+            zipball_url: None,
+            tarball_url: None,
         }
     }
 
@@ -723,6 +2741,94 @@ mod tests {
         assert_eq!(cached.unwrap().repo, repo_info.repo);
     }
 
+    #[tokio::test]
+    async fn test_cached_at() {
+        let config = create_test_cache_config(true, 3600);
+        let manager = CacheManager::new(config).await;
+        let key = CacheManager::repo_info_key("test", "test");
+        assert_eq!(manager.cached_at(&key).await, None);
+
+        let before = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        manager
+            .set_repo_info("test", "test", create_test_repo_info())
+            .await;
+        let cached_at = manager.cached_at(&key).await.unwrap();
+        assert!(cached_at >= before);
+        assert_eq!(manager.expires_at(&key).await, Some(cached_at + 3600));
+    }
+
+    #[tokio::test]
+    async fn test_persistent_store_max_entries() {
+        let mut config = create_test_cache_config(true, 3600);
+        config.persistent_max_entries = 3;
+        let manager = CacheManager::new(config).await;
+
+        for i in 0..10 {
+            manager
+                .set_repo_info("owner", &format!("repo{}", i), create_test_repo_info())
+                .await;
+        }
+        assert_eq!(manager.persistent_store.read().await.repo_info.len(), 3);
+
+        // 超出上限时淘汰最早过期的条目
+        let mut map = HashMap::new();
+        for (key, expires_at) in [("a", 300), ("b", 100), ("c", 200)] {
+            let entry = CachedEntry {
+                value: (),
+                expires_at,
+                cached_at: 0,
+                last_modified: None,
+                etag: None,
+            };
+            insert_bounded(&mut map, key.to_string(), entry, 2);
+        }
+        assert_eq!(map.len(), 2);
+        assert!(!map.contains_key("b"));
+
+        // 超出上限时一次淘汰到上限的 90%，之后的插入不再触发淘汰
+        let mut map = HashMap::new();
+        for i in 0..21u64 {
+            let entry = CachedEntry {
+                value: (),
+                expires_at: 1000 - i,
+                cached_at: 0,
+                last_modified: None,
+                etag: None,
+            };
+            insert_bounded(&mut map, i.to_string(), entry, 20);
+        }
+        assert_eq!(map.len(), 18);
+        // 最早过期的是最后插入的三个条目
+        assert!((0..18u64).all(|i| map.contains_key(&i.to_string())));
+    }
+
+    #[test]
+    fn test_enforce_contents_budget() {
+        let mut map = HashMap::new();
+        for (key, content, expires_at) in [("a", "aaaa", 300), ("b", "bbbb", 100), ("c", "cc", 200)] {
+            let entry = CachedEntry {
+                value: serde_json::json!({ "content": content }),
+                expires_at,
+                cached_at: 0,
+                last_modified: None,
+                etag: None,
+            };
+            map.insert(key.to_string(), entry);
+        }
+
+        // 总大小 10 字节，预算 6 字节：依次淘汰最早过期的 b（4 字节）
+        enforce_contents_budget(&mut map, 6);
+        assert_eq!(map.len(), 2);
+        assert!(!map.contains_key("b"));
+
+        // 预算足够时不淘汰
+        enforce_contents_budget(&mut map, 6);
+        assert_eq!(map.len(), 2);
+    }
+
     #[tokio::test]
     async fn test_releases_cache() {
         let config = create_test_cache_config(true, 3600);
@@ -761,6 +2867,763 @@ mod tests {
         assert_eq!(cached.unwrap().repo, latest_release.repo);
     }
 
+    #[tokio::test]
+    async fn test_negative_cache() {
+        let config = create_test_cache_config(true, 3600);
+        let manager = CacheManager::new(config).await;
+
+        assert!(!manager.is_repo_info_not_found("test", "missing").await);
+        manager.set_repo_info_not_found("test", "missing").await;
+        assert!(manager.is_repo_info_not_found("test", "missing").await);
+
+        // 墓碑标记与真实数据分开存储，不影响其他类型的数据
+        assert!(manager.get_repo_info("test", "missing").await.is_none());
+        assert!(!manager.is_releases_not_found("test", "missing").await);
+        assert!(!manager.is_latest_release_not_found("test", "missing").await);
+    }
+
+    #[tokio::test]
+    async fn test_negative_cache_disabled_with_zero_ttl() {
+        let mut config = create_test_cache_config(true, 3600);
+        config.negative_ttl_seconds = 0;
+        let manager = CacheManager::new(config).await;
+
+        manager.set_releases_not_found("test", "missing").await;
+        assert!(!manager.is_releases_not_found("test", "missing").await);
+    }
+
+    #[tokio::test]
+    async fn test_negative_cache_is_bounded() {
+        let mut config = create_test_cache_config(true, 3600);
+        config.negative_max_entries = 3;
+        let manager = CacheManager::new(config).await;
+
+        for i in 0..10 {
+            manager
+                .set_repo_info_not_found("test", &format!("missing-{}", i))
+                .await;
+        }
+        manager.negative_cache.run_pending_tasks().await;
+        assert!(manager.negative_cache.entry_count() <= 3);
+        // 最近写入的墓碑标记保留
+        assert!(manager.is_repo_info_not_found("test", "missing-9").await);
+        assert!(!manager.is_repo_info_not_found("test", "missing-0").await);
+    }
+
+    #[test]
+    fn test_persistent_cache_prune_expired() {
+        let mut store = PersistentCache {
+            repo_info: HashMap::new(),
+            releases: HashMap::new(),
+            latest_release: HashMap::new(),
+            compare: HashMap::new(),
+            checksums: HashMap::new(),
+            generated_notes: HashMap::new(),
+            raw_repo: HashMap::new(),
+            contents: HashMap::new(),
+            milestones: HashMap::new(),
+        };
+        store.repo_info.insert(
+            "repo_info:test:expired".to_string(),
+            CachedEntry { value: create_test_repo_info(), expires_at: 100, cached_at: 0, last_modified: None, etag: None },
+        );
+        store.repo_info.insert(
+            "repo_info:test:fresh".to_string(),
+            CachedEntry { value: create_test_repo_info(), expires_at: 300, cached_at: 0, last_modified: None, etag: None },
+        );
+        store.releases.insert(
+            "releases:test:expired".to_string(),
+            CachedEntry { value: vec![create_test_release_info()], expires_at: 200, cached_at: 0, last_modified: None, etag: None },
+        );
+
+        assert_eq!(store.prune_expired(200, 0, 0), 2);
+        assert_eq!(store.len(), 1);
+        assert!(store.repo_info.contains_key("repo_info:test:fresh"));
+
+        // 带有 Last-Modified 的条目在保留期内不会被清理
+        store.repo_info.insert(
+            "repo_info:test:revalidatable".to_string(),
+            CachedEntry {
+                value: create_test_repo_info(),
+                expires_at: 100,
+                cached_at: 0,
+                last_modified: Some("Mon, 01 Jan 2024 00:00:00 GMT".to_string()),
+                etag: None,
+            },
+        );
+        assert_eq!(store.prune_expired(200, 200, 0), 0);
+        assert_eq!(store.prune_expired(299, 200, 0), 0);
+        assert_eq!(store.prune_expired(299, 150, 0), 1);
+        assert!(!store.repo_info.contains_key("repo_info:test:revalidatable"));
+    }
+
+    #[test]
+    fn test_parse_cache_file_recovers_valid_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let path = dir.join("cache.json");
+
+        let valid = CachedEntry {
+            value: create_test_repo_info(),
+            expires_at: u64::MAX,
+            cached_at: 0,
+            last_modified: None,
+            etag: None,
+        };
+        let content = serde_json::json!({
+            "repo_info": {
+                "repo_info:owner/good": valid,
+                "repo_info:owner/bad": { "value": "not a repo", "expires_at": 0 }
+            },
+            "releases": {},
+            "latest_release": []
+        })
+        .to_string();
+        std::fs::write(&path, &content).unwrap();
+
+        // 跳过格式错误的条目，恢复其余条目
+        let cache = parse_cache_file(&path, &content).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.repo_info.contains_key("repo_info:owner/good"));
+
+        // 原文件被移到一旁供排查
+        assert!(!path.exists());
+        let moved = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("cache.json.corrupt."))
+            .count();
+        assert_eq!(moved, 1);
+
+        // 不是有效的 JSON（如写入时被截断）时无法恢复
+        assert!(parse_cache_file(&path, "{\"repo_info\": {").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_revalidatable_entries() {
+        let config = create_test_cache_config(true, 3600);
+        let manager = CacheManager::new(config).await;
+        let last_modified = Validators {
+            last_modified: Some("Mon, 01 Jan 2024 00:00:00 GMT".to_string()),
+            etag: None,
+        };
+
+        // 没有记录 Last-Modified 或 ETag 时不能重新验证
+        manager.set_repo_info("test", "test", create_test_repo_info()).await;
+        assert!(manager.revalidatable_repo_info("test", "test").await.is_none());
+
+        manager
+            .set_repo_info_validators("test", "test", last_modified.clone())
+            .await;
+        let (info, stored) = manager.revalidatable_repo_info("test", "test").await.unwrap();
+        assert_eq!(info.repo, "test/test");
+        assert_eq!(stored, last_modified);
+
+        // 只有 ETag 时同样可以重新验证
+        let etag = Validators { last_modified: None, etag: Some("\"abc\"".to_string()) };
+        manager.set_repo_info_validators("test", "test", etag.clone()).await;
+        let (_, stored) = manager.revalidatable_repo_info("test", "test").await.unwrap();
+        assert_eq!(stored, etag);
+
+        // 重新写入数据会清除旧的 Last-Modified
+        manager.set_releases("test", "test", vec![create_test_release_info()]).await;
+        manager
+            .set_releases_validators("test", "test", last_modified.clone())
+            .await;
+        assert!(manager.revalidatable_releases("test", "test").await.is_some());
+        manager.set_releases("test", "test", vec![]).await;
+        assert!(manager.revalidatable_releases("test", "test").await.is_none());
+    }
+
+    #[test]
+    fn test_file_cache_metadata_is_older_than() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let metadata = FileCacheMetadata {
+            url: "https://example.com/file.txt".to_string(),
+            file_path: PathBuf::from("file.txt"),
+            original_filename: "file.txt".to_string(),
+            content_type: None,
+            expires_at: now + 3600,
+            last_accessed_at: now,
+            etag: None,
+            cached_at: now - 120,
+            gzip_path: None,
+            immutable: false,
+            size: None,
+        };
+        assert!(metadata.is_older_than(60));
+        assert!(!metadata.is_older_than(300));
+    }
+
+    #[tokio::test]
+    async fn test_get_file_cache_ignores_ttl_for_immutable_entries() {
+        let cache = CacheManager::new(create_test_cache_config(true, 3600)).await;
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let file = tmp.path().to_path_buf();
+        std::fs::write(&file, b"data").unwrap();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        for (url, immutable) in [("https://example.com/a.zip", true), ("https://example.com/b.zip", false)] {
+            let metadata = FileCacheMetadata {
+                url: url.to_string(),
+                file_path: file.clone(),
+                original_filename: "a.zip".to_string(),
+                content_type: None,
+                expires_at: now - 10,
+                last_accessed_at: now,
+                etag: None,
+                cached_at: now - 7200,
+                gzip_path: None,
+                immutable,
+                size: None,
+            };
+            cache
+                .file_cache
+                .insert(CacheManager::file_cache_key(url), metadata)
+                .await;
+        }
+
+        assert!(cache.get_file_cache("https://example.com/a.zip").await.is_some());
+        assert!(cache.get_file_cache("https://example.com/b.zip").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_file_cache_rejects_size_mismatch() {
+        let cache = CacheManager::new(create_test_cache_config(true, 3600)).await;
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let file = tmp.path().to_path_buf();
+        std::fs::write(&file, b"short").unwrap();
+        let url = "https://example.com/truncated.zip";
+
+        cache
+            .set_file_cache(url, file.clone(), "truncated.zip".to_string(), None, None, None)
+            .await;
+        let metadata = cache.get_file_cache(url).await.unwrap();
+        assert_eq!(metadata.size, Some(5));
+
+        // 模拟记录的大小与磁盘上的文件不一致（如写入被截断）
+        let key = CacheManager::file_cache_key(url);
+        cache
+            .file_cache
+            .insert(key, FileCacheMetadata { size: Some(1024), ..metadata })
+            .await;
+        assert!(cache.get_file_cache(url).await.is_none());
+        // 不一致的条目已被移除
+        assert!(cache.file_cache.get(&CacheManager::file_cache_key(url)).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_begin_download_coalesces_same_url() {
+        let cache = CacheManager::new(create_test_cache_config(true, 3600)).await;
+        let url = "https://example.com/app.zip";
+
+        let DownloadFlight::Leader(guard) = cache.begin_download(url) else {
+            panic!("第一个请求应负责下载");
+        };
+        let DownloadFlight::Follower(waiter) = cache.begin_download(url) else {
+            panic!("相同 URL 的请求应等待");
+        };
+        assert!(matches!(
+            cache.begin_download("https://example.com/other.zip"),
+            DownloadFlight::Leader(_)
+        ));
+
+        let wait = tokio::spawn(waiter.wait());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!wait.is_finished());
+        drop(guard);
+        tokio::time::timeout(Duration::from_secs(1), wait).await.unwrap().unwrap();
+
+        // 下载结束后新的请求重新负责下载
+        assert!(matches!(cache.begin_download(url), DownloadFlight::Leader(_)));
+    }
+
+    #[tokio::test]
+    async fn test_file_cache_cleaner_keeps_recently_accessed_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let cleaner = FileCacheCleaner {
+            file_cache: Cache::builder().max_capacity(100).build(),
+            file_path_to_key: Arc::new(RwLock::new(HashMap::new())),
+            file_cache_dir: dir.clone(),
+            file_count: Arc::new(AtomicUsize::new(3)),
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        for (i, name) in ["old.bin", "newer.bin", "newest.bin"].iter().enumerate() {
+            let url = format!("https://example.com/{}", name);
+            let file_path = dir.join(name);
+            std::fs::write(&file_path, b"data").unwrap();
+            let key = CacheManager::file_cache_key(&url);
+            cleaner.file_path_to_key.write().await.insert(file_path.clone(), key.clone());
+            let metadata = FileCacheMetadata {
+                url,
+                file_path,
+                original_filename: name.to_string(),
+                content_type: None,
+                expires_at: now + 3600,
+                last_accessed_at: now + i as u64,
+                etag: None,
+                cached_at: now,
+                gzip_path: None,
+                immutable: false,
+                size: None,
+            };
+            cleaner.file_cache.insert(key, metadata).await;
+        }
+
+        cleaner.cleanup(2).await;
+        assert_eq!(cleaner.file_count.load(Ordering::SeqCst), 2);
+        assert!(!dir.join("old.bin").exists());
+        assert!(dir.join("newer.bin").exists());
+        assert!(dir.join("newest.bin").exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_reserve_file_slot_bounds_concurrent_downloads() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let mut config = create_test_cache_config(true, 3600);
+        config.file_cache_max_files = 3;
+        let manager = Arc::new(CacheManager::new(config).await);
+        let count_files = |dir: &Path| std::fs::read_dir(dir).unwrap().count();
+
+        // 并发下载 20 个文件：写入前预留位置，磁盘上的文件数始终不超过上限
+        let tasks: Vec<_> = (0..20)
+            .map(|i| {
+                let manager = manager.clone();
+                let dir = dir.clone();
+                tokio::spawn(async move {
+                    let file_path = dir.join(format!("{}.bin", i));
+                    let Some(slot) = manager.reserve_file_slot(&file_path).await else {
+                        return;
+                    };
+                    std::fs::write(&file_path, b"data").unwrap();
+                    assert!(count_files(&dir) <= 3);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    let url = format!("https://example.com/{}.bin", i);
+                    manager
+                        .set_file_cache(&url, file_path, format!("{}.bin", i), None, None, None)
+                        .await;
+                    drop(slot);
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(count_files(&dir), 3);
+        assert_eq!(manager.file_count.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_reserve_file_slot_evicts_least_recently_visited_first() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let mut config = create_test_cache_config(true, 3600);
+        config.file_cache_max_files = 2;
+        let manager = CacheManager::new(config).await;
+        for name in ["old.bin", "new.bin"] {
+            let file_path = dir.join(name);
+            std::fs::write(&file_path, b"data").unwrap();
+            let url = format!("https://example.com/{}", name);
+            manager.set_file_cache(&url, file_path, name.to_string(), None, None, None).await;
+        }
+        // 访问 old.bin 之后 new.bin 成为最久未访问的文件
+        let key = CacheManager::file_cache_key("https://example.com/old.bin");
+        let mut metadata = manager.file_cache.get(&key).await.unwrap();
+        metadata.last_accessed_at += 10;
+        manager.file_cache.insert(key, metadata).await;
+
+        let slot = manager.reserve_file_slot(&dir.join("a.bin")).await.unwrap();
+        assert!(dir.join("old.bin").exists());
+        assert!(!dir.join("new.bin").exists());
+        assert!(manager.get_file_cache("https://example.com/new.bin").await.is_none());
+
+        // 所有位置都被正在写入的文件占用时不再预留
+        let second = manager.reserve_file_slot(&dir.join("b.bin")).await.unwrap();
+        assert!(manager.reserve_file_slot(&dir.join("c.bin")).await.is_none());
+        drop((slot, second));
+    }
+
+    #[tokio::test]
+    async fn test_reserve_file_slot_for_registered_path_keeps_other_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let mut config = create_test_cache_config(true, 3600);
+        config.file_cache_max_files = 2;
+        let manager = CacheManager::new(config).await;
+        for name in ["a.bin", "b.bin"] {
+            let file_path = dir.join(name);
+            std::fs::write(&file_path, b"data").unwrap();
+            let url = format!("https://example.com/{}", name);
+            manager.set_file_cache(&url, file_path, name.to_string(), None, None, None).await;
+        }
+
+        // 覆盖已登记的文件不增加文件数，不淘汰其他文件
+        let slot = manager.reserve_file_slot(&dir.join("a.bin")).await.unwrap();
+        assert_eq!(manager.file_count.load(Ordering::SeqCst), 2);
+        assert!(dir.join("b.bin").exists());
+        drop(slot);
+        assert_eq!(manager.file_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_partial_file_path_is_unique() {
+        let path = Path::new("/cache/abc.zip");
+        let first = partial_file_path(path);
+        let second = partial_file_path(path);
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), path.parent());
+        let name = first.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("abc.zip.") && name.ends_with(".partial"));
+    }
+
+    #[test]
+    fn test_remove_partial_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        std::fs::write(dir.join("abc.zip"), b"done").unwrap();
+        let stale = partial_file_path(&dir.join("def.zip"));
+        std::fs::write(&stale, b"half").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&stale)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(7200))
+            .unwrap();
+        // 刚写入的临时文件可能属于共享缓存目录的其他副本，不删除
+        let active = partial_file_path(&dir.join("ghi.zip"));
+        std::fs::write(&active, b"writing").unwrap();
+
+        remove_partial_files(&dir, Duration::from_secs(3600));
+        let mut names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec![dir.join("abc.zip"), active]);
+    }
+
+    #[tokio::test]
+    async fn test_is_known_release_asset() {
+        let cache = CacheManager::new(create_test_cache_config(true, 3600)).await;
+        let asset = "https://example.com/file.zip";
+        assert!(!cache.is_known_release_asset(asset).await);
+
+        cache.set_releases("test", "test", vec![create_test_release_info()]).await;
+        assert!(cache.is_known_release_asset(asset).await);
+        assert!(!cache.is_known_release_asset("https://example.com/other.zip").await);
+
+        let disabled = CacheManager::new(create_test_cache_config(false, 3600)).await;
+        disabled.set_releases("test", "test", vec![create_test_release_info()]).await;
+        assert!(!disabled.is_known_release_asset(asset).await);
+    }
+
+    #[test]
+    fn test_write_gzip_sibling() {
+        use std::io::Read;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let path = dir.join("abc.json");
+        let content = "{\"key\": \"value\"}\n".repeat(100);
+        std::fs::write(&path, &content).unwrap();
+
+        let gzip_path = write_gzip_sibling(&path).unwrap();
+        assert_eq!(gzip_path, dir.join("abc.json.gz"));
+        assert_eq!(gzip_path, gzip_sibling_path(&path));
+        assert!(std::fs::metadata(&gzip_path).unwrap().len() < content.len() as u64);
+
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(&gzip_path).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, content);
+    }
+
+    #[test]
+    fn test_persistent_cache_compression() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let path = dir.join("cache.json");
+        let gzip_path = dir.join("cache.json.gz");
+
+        let mut cache: PersistentCache =
+            serde_json::from_str(r#"{"repo_info": {}, "releases": {}, "latest_release": {}}"#).unwrap();
+        cache.repo_info.insert(
+            "repo_info:owner/repo".to_string(),
+            CachedEntry {
+                value: create_test_repo_info(),
+                expires_at: u64::MAX,
+                cached_at: 0,
+                last_modified: None,
+                etag: None,
+            },
+        );
+
+        // 未启用压缩时写入紧凑 JSON
+        write_persistent_cache(&path, false, &cache);
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains('\n'));
+        assert_eq!(read_persistent_cache(&path, false).unwrap().len(), 1);
+
+        // 切换为压缩后仍能读取旧的未压缩文件，下次保存时写入 .gz 并删除旧文件
+        assert_eq!(read_persistent_cache(&path, true).unwrap().len(), 1);
+        write_persistent_cache(&path, true, &cache);
+        assert!(!path.exists());
+        assert!(std::fs::read(&gzip_path).unwrap().starts_with(&GZIP_MAGIC));
+        assert_eq!(read_persistent_cache(&path, true).unwrap().len(), 1);
+        assert_eq!(read_persistent_cache(&path, false).unwrap().len(), 1);
+
+        // 按魔数识别 gzip，与文件名无关
+        std::fs::rename(&gzip_path, &path).unwrap();
+        assert_eq!(read_persistent_cache(&path, false).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_outage_grace_period() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let path = dir.join("cache.json");
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        // 保留期 1000 秒：一个过期 500 秒（保留期内），一个过期 2000 秒（超出保留期）
+        let mut cache: PersistentCache =
+            serde_json::from_str(r#"{"repo_info": {}, "releases": {}, "latest_release": {}}"#).unwrap();
+        for (repo, expires_at) in [("fresh", now + 100), ("within", now - 500), ("beyond", now - 2000)] {
+            cache.repo_info.insert(
+                CacheManager::repo_info_key("outage", repo),
+                CachedEntry { value: create_test_repo_info(), expires_at, cached_at: 0, last_modified: None, etag: None },
+            );
+        }
+        write_persistent_cache(&path, false, &cache);
+
+        let mut config = create_test_cache_config(true, 3600);
+        config.outage_grace_secs = 1000;
+        let mut manager = CacheManager::new(config).await;
+        manager.cache_file_path = path;
+        manager.load_from_disk().await;
+
+        // 正常读取只返回未过期的条目
+        assert!(manager.get_repo_info("outage", "fresh").await.is_some());
+        assert!(manager.get_repo_info("outage", "within").await.is_none());
+        // GitHub 故障时可以使用保留期内的条目，超出保留期的条目已在加载时丢弃
+        assert!(manager.outage_repo_info("outage", "fresh").await.is_some());
+        assert!(manager.outage_repo_info("outage", "within").await.is_some());
+        assert!(manager.outage_repo_info("outage", "beyond").await.is_none());
+        // 只有已过期的兜底条目会被标记为过期数据
+        let key = |repo| CacheManager::repo_info_key("outage", repo);
+        assert_eq!(manager.outage_grace_remaining(&key("fresh")).await, None);
+        let remaining = manager.outage_grace_remaining(&key("within")).await.unwrap();
+        assert!(remaining > 0 && remaining <= 500);
+        assert_eq!(manager.outage_grace_remaining(&key("beyond")).await, None);
+
+        // 清理任务同样保留保留期内的条目
+        let mut store = manager.persistent_store.write().await;
+        assert_eq!(store.prune_expired(now, 0, 1000), 0);
+        assert_eq!(store.prune_expired(now, 0, 0), 1);
+        assert!(!store.repo_info.contains_key(&CacheManager::repo_info_key("outage", "within")));
+        drop(store);
+    }
+
+    #[tokio::test]
+    async fn test_load_from_disk_applies_max_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("cache.json");
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        // 缓存文件由更大的上限写入，包含 5 个未过期的条目
+        let mut cache: PersistentCache =
+            serde_json::from_str(r#"{"repo_info": {}, "releases": {}, "latest_release": {}}"#).unwrap();
+        for i in 0..5u64 {
+            cache.repo_info.insert(
+                CacheManager::repo_info_key("loaded", &format!("repo{}", i)),
+                CachedEntry { value: create_test_repo_info(), expires_at: now + 100 + i, cached_at: 0, last_modified: None, etag: None },
+            );
+        }
+        write_persistent_cache(&path, false, &cache);
+
+        let mut config = create_test_cache_config(true, 3600);
+        config.persistent_max_entries = 3;
+        let mut manager = CacheManager::new(config).await;
+        manager.cache_file_path = path;
+        manager.load_from_disk().await;
+
+        // 载入后只保留最晚过期的 3 个条目
+        let store = manager.persistent_store.read().await;
+        assert_eq!(store.repo_info.len(), 3);
+        assert!((2..5).all(|i| store.repo_info.contains_key(&CacheManager::repo_info_key("loaded", &format!("repo{}", i)))));
+    }
+
+    #[test]
+    fn test_resolve_ttl() {
+        let (min_ttl, max_ttl) = ttl_bounds(None, None);
+        assert_eq!((min_ttl, max_ttl), (1, 30 * 24 * 3600));
+
+        // 0 会实际禁用缓存，截断为下限
+        assert_eq!(resolve_ttl(Some("0"), min_ttl, max_ttl), 1);
+        // 无法解析（包括负数）时使用默认值
+        assert_eq!(resolve_ttl(Some("-5"), min_ttl, max_ttl), 3600);
+        assert_eq!(resolve_ttl(Some("1h"), min_ttl, max_ttl), 3600);
+        assert_eq!(resolve_ttl(None, min_ttl, max_ttl), 3600);
+        // 正常值保持不变
+        assert_eq!(resolve_ttl(Some("600"), min_ttl, max_ttl), 600);
+        assert_eq!(resolve_ttl(Some(" 5 "), min_ttl, max_ttl), 5);
+        // 过大的值截断为上限
+        assert_eq!(resolve_ttl(Some("999999999"), min_ttl, max_ttl), max_ttl);
+
+        // 自定义上下限；上限小于下限时以下限为准
+        let (min_ttl, max_ttl) = ttl_bounds(Some("60"), Some("30"));
+        assert_eq!((min_ttl, max_ttl), (60, 60));
+        assert_eq!(resolve_ttl(Some("10"), min_ttl, max_ttl), 60);
+        assert_eq!(clamp_ttl(60, min_ttl, max_ttl), None);
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(parse_mode("0700"), Some(0o700));
+        assert_eq!(parse_mode("750"), Some(0o750));
+        assert_eq!(parse_mode("0o640"), Some(0o640));
+        assert_eq!(parse_mode(" 0600 "), Some(0o600));
+        assert_eq!(parse_mode("0800"), None);
+        assert_eq!(parse_mode("1777"), None);
+        assert_eq!(parse_mode("rwx"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cache_dir_and_files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let permissions = get_cache_permissions();
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("nested");
+        create_cache_dir(&dir).unwrap();
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        // DirBuilder 的权限受 umask 影响，只能确认没有超出配置的权限
+        assert_eq!(mode(&dir) & !permissions.dir_mode, 0);
+
+        // 覆盖已存在的宽松权限文件时同样收紧
+        let file = dir.join("cache.json");
+        std::fs::write(&file, "{}").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o644)).unwrap();
+        write_cache_file(&file, "{}").unwrap();
+        assert_eq!(mode(&file), permissions.file_mode);
+
+        let streamed = dir.join("download.bin");
+        drop(create_cache_file(&streamed).await.unwrap());
+        assert_eq!(mode(&streamed), permissions.file_mode);
+    }
+
+    #[test]
+    fn test_repo_from_key() {
+        assert_eq!(repo_from_key("repo_info:owner:repo"), Some(("owner", "repo")));
+        assert_eq!(
+            repo_from_key("contents:owner:repo:main:src/lib.rs"),
+            Some(("owner", "repo"))
+        );
+        assert_eq!(repo_from_key("compare:owner:repo:v1:v2"), Some(("owner", "repo")));
+        assert_eq!(repo_from_key("repo_info:owner"), None);
+        assert_eq!(repo_from_key("repo_info::repo"), None);
+    }
+
+    #[test]
+    fn test_parse_repo_key() {
+        assert_eq!(
+            parse_repo_key("repo_info:owner:repo", "repo_info"),
+            Some(("owner".to_string(), "repo".to_string()))
+        );
+        assert_eq!(parse_repo_key("releases:owner:repo", "repo_info"), None);
+        assert_eq!(parse_repo_key("repo_info:owner", "repo_info"), None);
+        assert_eq!(parse_repo_key("repo_info:owner:repo:extra", "repo_info"), None);
+    }
+
+    #[tokio::test]
+    async fn test_list_cached_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let cache = CacheManager::new(create_test_cache_config(true, 3600)).await;
+        for (name, contents) in [("small.txt", "a"), ("large.txt", "abcdef")] {
+            let file_path = dir.join(name);
+            std::fs::write(&file_path, contents).unwrap();
+            let url = format!("https://example.com/{}", name);
+            cache
+                .set_file_cache(&url, file_path, name.to_string(), Some("text/plain".to_string()), None, None)
+                .await;
+        }
+        // small.txt 最近被访问过
+        let key = CacheManager::file_cache_key("https://example.com/small.txt");
+        let mut metadata = cache.file_cache.get(&key).await.unwrap();
+        metadata.last_accessed_at += 10;
+        metadata.expires_at += 10;
+        cache.file_cache.insert(key, metadata).await;
+
+        let names = |files: Vec<CachedFileSummary>| -> Vec<String> {
+            files.into_iter().map(|f| f.original_filename).collect()
+        };
+        let by_size = cache.list_cached_files(CachedFileSort::Size).await;
+        assert_eq!(by_size[0].size, Some(6));
+        assert_eq!(by_size[0].content_type.as_deref(), Some("text/plain"));
+        assert_eq!(names(by_size), ["large.txt", "small.txt"]);
+        assert_eq!(names(cache.list_cached_files(CachedFileSort::Accessed).await), ["small.txt", "large.txt"]);
+        assert_eq!(names(cache.list_cached_files(CachedFileSort::Expires).await), ["large.txt", "small.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_cached_repos() {
+        let cache = CacheManager::new(create_test_cache_config(true, 3600)).await;
+        cache.set_repo_info("owner", "repo", create_test_repo_info()).await;
+        cache
+            .set_releases("owner", "repo", vec![create_test_release_info()])
+            .await;
+        cache
+            .set_latest_release("other", "project", create_test_latest_release_info())
+            .await;
+
+        let repos = cache.list_cached_repos(None, false).await;
+        let summary = repos
+            .iter()
+            .find(|r| r.owner == "owner" && r.repo == "repo")
+            .unwrap();
+        assert!(summary.has_repo_info);
+        assert!(summary.has_releases);
+        assert!(!summary.has_latest_release);
+
+        let summary = repos
+            .iter()
+            .find(|r| r.owner == "other" && r.repo == "project")
+            .unwrap();
+        assert!(summary.has_latest_release);
+        assert!(!summary.has_repo_info);
+
+        // TTL 为 1 小时，10 秒内不会过期
+        let expiring = cache.list_cached_repos(Some(10), false).await;
+        assert!(!expiring.iter().any(|r| r.owner == "owner" && r.repo == "repo"));
+
+        // 私有仓库只在 include_private 为 true 时列出
+        let private = RepoInfo { private: true, ..create_test_repo_info() };
+        cache.set_repo_info("owner", "secret", private).await;
+        let is_secret = |r: &CachedRepoSummary| r.owner == "owner" && r.repo == "secret";
+        assert!(!cache.list_cached_repos(None, false).await.iter().any(is_secret));
+        assert!(cache.list_cached_repos(None, true).await.iter().any(is_secret));
+    }
+
     #[tokio::test]
     async fn test_cache_disabled() {
         let config = create_test_cache_config(false, 3600);
@@ -782,5 +3645,29 @@ mod tests {
 
         let latest_release_key = CacheManager::latest_release_key("owner", "repo");
         assert_eq!(latest_release_key, "latest_release:owner:repo");
+
+        let compare_key = CacheManager::compare_key("owner", "repo", "v1.0.0", "v1.2.0");
+        assert_eq!(compare_key, "compare:owner:repo:v1.0.0:v1.2.0");
+
+        let checksums_key = CacheManager::checksums_key("owner", "repo");
+        assert_eq!(checksums_key, "checksums:owner:repo");
+
+        let notes_key = CacheManager::generated_notes_key("owner", "repo", "v1.1.0", Some("v1.0.0"));
+        assert_eq!(notes_key, "generated_notes:owner:repo:v1.1.0:v1.0.0");
+        let notes_key = CacheManager::generated_notes_key("owner", "repo", "v1.1.0", None);
+        assert_eq!(notes_key, "generated_notes:owner:repo:v1.1.0:");
+        assert_eq!(CacheManager::raw_repo_key("owner", "repo"), "raw_repo:owner:repo");
+        assert_eq!(
+            CacheManager::contents_key("owner", "repo", "config/version.json", Some("v1.0.0")),
+            "contents:owner:repo:v1.0.0:config/version.json"
+        );
+        assert_eq!(
+            CacheManager::contents_key("owner", "repo", "version.json", None),
+            "contents:owner:repo::version.json"
+        );
+        assert_eq!(
+            CacheManager::milestones_key("owner", "repo", "closed"),
+            "milestones:owner:repo:closed"
+        );
     }
 }
@@ -0,0 +1,392 @@
+use crate::cache::get_cache_manager;
+#[cfg(feature = "redis")]
+use crate::cache::{CacheConfig, CacheManager};
+use crate::models::{
+    ChecksumsInfo, CompareInfo, GeneratedNotes, LatestReleaseInfo, MilestoneInfo, ReleaseInfo, RepoInfo,
+};
+use async_trait::async_trait;
+use tokio::sync::OnceCell;
+
+/// 缓存后端：按数据类型读取和写入缓存
+///
+/// 默认使用进程内的 moka 缓存加本地持久化文件（[`crate::cache::CacheManager`]）；
+/// 多实例部署时可以使用 Redis 后端（需要 `redis` 特性）在副本之间共享缓存。
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    async fn get_repo_info(&self, owner: &str, repo: &str) -> Option<RepoInfo>;
+    async fn set_repo_info(&self, owner: &str, repo: &str, info: RepoInfo);
+
+    async fn get_releases(&self, owner: &str, repo: &str) -> Option<Vec<ReleaseInfo>>;
+    async fn set_releases(&self, owner: &str, repo: &str, releases: Vec<ReleaseInfo>);
+
+    async fn get_latest_release(&self, owner: &str, repo: &str) -> Option<LatestReleaseInfo>;
+    async fn set_latest_release(&self, owner: &str, repo: &str, release: LatestReleaseInfo);
+
+    async fn get_compare(&self, owner: &str, repo: &str, base: &str, head: &str) -> Option<CompareInfo>;
+    async fn set_compare(&self, owner: &str, repo: &str, base: &str, head: &str, compare: CompareInfo);
+
+    async fn get_checksums(&self, owner: &str, repo: &str) -> Option<ChecksumsInfo>;
+    async fn set_checksums(&self, owner: &str, repo: &str, checksums: ChecksumsInfo);
+
+    async fn get_generated_notes(&self, owner: &str, repo: &str, tag: &str, previous_tag: Option<&str>) -> Option<GeneratedNotes>;
+    async fn set_generated_notes(&self, owner: &str, repo: &str, tag: &str, previous_tag: Option<&str>, notes: GeneratedNotes);
+
+    async fn get_raw_repo(&self, owner: &str, repo: &str) -> Option<serde_json::Value>;
+    async fn set_raw_repo(&self, owner: &str, repo: &str, raw: serde_json::Value);
+
+    async fn get_contents(&self, owner: &str, repo: &str, path: &str, git_ref: Option<&str>) -> Option<serde_json::Value>;
+    async fn set_contents(&self, owner: &str, repo: &str, path: &str, git_ref: Option<&str>, contents: serde_json::Value);
+
+    async fn get_milestones(&self, owner: &str, repo: &str, state: &str) -> Option<Vec<MilestoneInfo>>;
+    async fn set_milestones(&self, owner: &str, repo: &str, state: &str, milestones: Vec<MilestoneInfo>);
+
+    /// URL 是否是缓存中某个 release（releases 或最新 release）的附件
+    async fn is_known_release_asset(&self, url: &str) -> bool;
+
+    /// 缓存条目的过期时间（Unix 时间戳，秒），条目不存在时返回 None
+    async fn expires_at(&self, key: &str) -> Option<u64>;
+
+    /// 缓存条目从 GitHub 获取的时间（Unix 时间戳，秒），条目不存在时返回 None
+    async fn cached_at(&self, key: &str) -> Option<u64>;
+}
+
+/// 缓存后端类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheBackendKind {
+    /// 进程内缓存（默认）
+    Memory,
+    /// Redis 共享缓存
+    Redis,
+}
+
+impl CacheBackendKind {
+    /// 解析 `CACHE_BACKEND` 的值（不区分大小写），无法识别时返回 None
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "memory" => Some(Self::Memory),
+            "redis" => Some(Self::Redis),
+            _ => None,
+        }
+    }
+}
+
+/// 缓存后端配置
+#[derive(Clone, Debug)]
+pub struct CacheBackendConfig {
+    /// 使用的缓存后端
+    pub kind: CacheBackendKind,
+    /// Redis 连接地址（如 `redis://127.0.0.1:6379/0`），使用 Redis 后端时必须设置
+    pub redis_url: Option<String>,
+}
+
+impl CacheBackendConfig {
+    /// 从环境变量加载配置
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+
+        let kind = match std::env::var("CACHE_BACKEND") {
+            Ok(value) => CacheBackendKind::parse(&value).unwrap_or_else(|| {
+                log::warn!("无法识别的 CACHE_BACKEND: {}，使用内存缓存", value);
+                CacheBackendKind::Memory
+            }),
+            Err(_) => CacheBackendKind::Memory,
+        };
+
+        let redis_url = std::env::var("REDIS_URL")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+
+        Self { kind, redis_url }
+    }
+}
+
+/// Redis 缓存后端：值以 JSON 存储，并通过 `SETEX` 设置与 `CACHE_TTL_SECONDS` 一致的过期时间
+#[cfg(feature = "redis")]
+pub struct RedisBackend {
+    connection: redis::aio::ConnectionManager,
+    ttl_seconds: u64,
+}
+
+#[cfg(feature = "redis")]
+impl RedisBackend {
+    // 所有键都带上前缀，避免与同一 Redis 中的其他数据冲突
+    const KEY_PREFIX: &'static str = "gh-info:";
+
+    /// 连接 Redis
+    pub async fn connect(redis_url: &str, ttl_seconds: u64) -> Result<Self, redis::RedisError> {
+        let client = redis::Client::open(redis_url)?;
+        let connection = redis::aio::ConnectionManager::new(client).await?;
+        Ok(Self {
+            connection,
+            ttl_seconds,
+        })
+    }
+
+    // 读取并反序列化 JSON 值，出错时视为未命中
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, key: String) -> Option<T> {
+        use redis::AsyncCommands;
+
+        let key = format!("{}{}", Self::KEY_PREFIX, key);
+        let mut connection = self.connection.clone();
+        let value: Option<String> = match connection.get(&key).await {
+            Ok(value) => value,
+            Err(e) => {
+                log::warn!("从 Redis 读取缓存失败: {} ({})", key, e);
+                return None;
+            }
+        };
+        let value = value?;
+        match serde_json::from_str(&value) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                log::warn!("Redis 缓存内容无法解析: {} ({})", key, e);
+                None
+            }
+        }
+    }
+
+    // 序列化为 JSON 并使用 SETEX 写入
+    async fn set_json<T: serde::Serialize + Sync>(&self, key: String, value: &T) {
+        use redis::AsyncCommands;
+
+        let key = format!("{}{}", Self::KEY_PREFIX, key);
+        let value = match serde_json::to_string(value) {
+            Ok(value) => value,
+            Err(e) => {
+                log::warn!("序列化缓存内容失败: {} ({})", key, e);
+                return;
+            }
+        };
+        let mut connection = self.connection.clone();
+        if let Err(e) = connection
+            .set_ex::<_, _, ()>(&key, value, self.ttl_seconds)
+            .await
+        {
+            log::warn!("写入 Redis 缓存失败: {} ({})", key, e);
+        }
+    }
+
+    // 已知 release 附件的标记键（URL 取哈希，避免过长的键）
+    fn asset_key(url: &str) -> String {
+        use sha2::{Digest, Sha256};
+        format!("{}asset:{}", Self::KEY_PREFIX, hex::encode(Sha256::digest(url.as_bytes())))
+    }
+
+    // 为 release 附件写入标记，过期时间与 release 缓存一致
+    async fn mark_assets<'a>(&self, urls: impl Iterator<Item = &'a String>) {
+        let mut pipeline = redis::pipe();
+        for url in urls {
+            pipeline.set_ex(Self::asset_key(url), 1, self.ttl_seconds).ignore();
+        }
+        let mut connection = self.connection.clone();
+        if let Err(e) = pipeline.query_async::<()>(&mut connection).await {
+            log::warn!("写入 Redis 附件标记失败: {}", e);
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl CacheBackend for RedisBackend {
+    async fn get_repo_info(&self, owner: &str, repo: &str) -> Option<RepoInfo> {
+        self.get_json(CacheManager::repo_info_key(owner, repo)).await
+    }
+
+    async fn set_repo_info(&self, owner: &str, repo: &str, info: RepoInfo) {
+        self.set_json(CacheManager::repo_info_key(owner, repo), &info).await
+    }
+
+    async fn get_releases(&self, owner: &str, repo: &str) -> Option<Vec<ReleaseInfo>> {
+        self.get_json(CacheManager::releases_key(owner, repo)).await
+    }
+
+    async fn set_releases(&self, owner: &str, repo: &str, releases: Vec<ReleaseInfo>) {
+        self.mark_assets(releases.iter().flat_map(|r| &r.attachments)).await;
+        self.set_json(CacheManager::releases_key(owner, repo), &releases).await
+    }
+
+    async fn get_latest_release(&self, owner: &str, repo: &str) -> Option<LatestReleaseInfo> {
+        self.get_json(CacheManager::latest_release_key(owner, repo)).await
+    }
+
+    async fn set_latest_release(&self, owner: &str, repo: &str, release: LatestReleaseInfo) {
+        self.mark_assets(release.attachments.iter()).await;
+        self.set_json(CacheManager::latest_release_key(owner, repo), &release).await
+    }
+
+    async fn get_compare(&self, owner: &str, repo: &str, base: &str, head: &str) -> Option<CompareInfo> {
+        self.get_json(CacheManager::compare_key(owner, repo, base, head)).await
+    }
+
+    async fn set_compare(&self, owner: &str, repo: &str, base: &str, head: &str, compare: CompareInfo) {
+        self.set_json(CacheManager::compare_key(owner, repo, base, head), &compare).await
+    }
+
+    async fn get_checksums(&self, owner: &str, repo: &str) -> Option<ChecksumsInfo> {
+        self.get_json(CacheManager::checksums_key(owner, repo)).await
+    }
+
+    async fn set_checksums(&self, owner: &str, repo: &str, checksums: ChecksumsInfo) {
+        self.set_json(CacheManager::checksums_key(owner, repo), &checksums).await
+    }
+
+    async fn get_generated_notes(&self, owner: &str, repo: &str, tag: &str, previous_tag: Option<&str>) -> Option<GeneratedNotes> {
+        self.get_json(CacheManager::generated_notes_key(owner, repo, tag, previous_tag)).await
+    }
+
+    async fn set_generated_notes(&self, owner: &str, repo: &str, tag: &str, previous_tag: Option<&str>, notes: GeneratedNotes) {
+        self.set_json(CacheManager::generated_notes_key(owner, repo, tag, previous_tag), &notes).await
+    }
+
+    async fn get_raw_repo(&self, owner: &str, repo: &str) -> Option<serde_json::Value> {
+        self.get_json(CacheManager::raw_repo_key(owner, repo)).await
+    }
+
+    async fn set_raw_repo(&self, owner: &str, repo: &str, raw: serde_json::Value) {
+        self.set_json(CacheManager::raw_repo_key(owner, repo), &raw).await
+    }
+
+    async fn get_contents(&self, owner: &str, repo: &str, path: &str, git_ref: Option<&str>) -> Option<serde_json::Value> {
+        self.get_json(CacheManager::contents_key(owner, repo, path, git_ref)).await
+    }
+
+    async fn set_contents(&self, owner: &str, repo: &str, path: &str, git_ref: Option<&str>, contents: serde_json::Value) {
+        self.set_json(CacheManager::contents_key(owner, repo, path, git_ref), &contents).await
+    }
+
+    async fn get_milestones(&self, owner: &str, repo: &str, state: &str) -> Option<Vec<MilestoneInfo>> {
+        self.get_json(CacheManager::milestones_key(owner, repo, state)).await
+    }
+
+    async fn set_milestones(&self, owner: &str, repo: &str, state: &str, milestones: Vec<MilestoneInfo>) {
+        self.set_json(CacheManager::milestones_key(owner, repo, state), &milestones).await
+    }
+
+    async fn is_known_release_asset(&self, url: &str) -> bool {
+        use redis::AsyncCommands;
+
+        let mut connection = self.connection.clone();
+        match connection.exists(Self::asset_key(url)).await {
+            Ok(exists) => exists,
+            Err(e) => {
+                log::warn!("查询 Redis 附件标记失败: {}", e);
+                false
+            }
+        }
+    }
+
+    // 通过 TTL 命令换算出过期时间（键不存在或没有过期时间时 TTL 返回负数）
+    async fn expires_at(&self, key: &str) -> Option<u64> {
+        use redis::AsyncCommands;
+
+        let key = format!("{}{}", Self::KEY_PREFIX, key);
+        let mut connection = self.connection.clone();
+        let ttl: i64 = match connection.ttl(&key).await {
+            Ok(ttl) => ttl,
+            Err(e) => {
+                log::warn!("查询 Redis 缓存过期时间失败: {} ({})", key, e);
+                return None;
+            }
+        };
+        let ttl = u64::try_from(ttl).ok()?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        Some(now + ttl)
+    }
+
+    // 所有键使用相同的 TTL 写入，获取时间即 过期时间 - TTL
+    async fn cached_at(&self, key: &str) -> Option<u64> {
+        self.expires_at(key)
+            .await
+            .map(|expires_at| expires_at.saturating_sub(self.ttl_seconds))
+    }
+}
+
+static CACHE_BACKEND: OnceCell<&'static dyn CacheBackend> = OnceCell::const_new();
+
+/// 获取全局缓存后端
+///
+/// 根据 `CACHE_BACKEND` 选择；Redis 后端不可用（未启用 `redis` 特性、未设置 `REDIS_URL` 或连接失败）时回退到内存缓存。
+pub async fn get_cache_backend() -> &'static dyn CacheBackend {
+    *CACHE_BACKEND
+        .get_or_init(|| async {
+            let config = CacheBackendConfig::from_env();
+            if config.kind == CacheBackendKind::Redis {
+                if let Some(backend) = connect_redis_backend(&config).await {
+                    return backend;
+                }
+            }
+            get_cache_manager().await as &'static dyn CacheBackend
+        })
+        .await
+}
+
+#[cfg(feature = "redis")]
+async fn connect_redis_backend(config: &CacheBackendConfig) -> Option<&'static dyn CacheBackend> {
+    let cache_config = CacheConfig::from_env();
+    if !cache_config.enabled {
+        log::info!("缓存已禁用，不使用 Redis 缓存后端");
+        return None;
+    }
+    let Some(redis_url) = config.redis_url.as_deref() else {
+        log::error!("CACHE_BACKEND=redis 但未设置 REDIS_URL，使用内存缓存");
+        return None;
+    };
+    match RedisBackend::connect(redis_url, cache_config.ttl_seconds).await {
+        Ok(backend) => {
+            log::info!("已启用 Redis 缓存后端，TTL: {} 秒", cache_config.ttl_seconds);
+            // 全局后端在整个进程生命周期内存在
+            Some(Box::leak(Box::new(backend)))
+        }
+        Err(e) => {
+            log::error!("连接 Redis 失败，使用内存缓存: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "redis"))]
+async fn connect_redis_backend(_config: &CacheBackendConfig) -> Option<&'static dyn CacheBackend> {
+    log::warn!("CACHE_BACKEND=redis 需要使用 --features redis 编译，使用内存缓存");
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{create_test_cache_config, CacheManager};
+
+    #[test]
+    fn test_cache_backend_kind_parse() {
+        assert_eq!(CacheBackendKind::parse("memory"), Some(CacheBackendKind::Memory));
+        assert_eq!(CacheBackendKind::parse(" Redis "), Some(CacheBackendKind::Redis));
+        assert_eq!(CacheBackendKind::parse("memcached"), None);
+    }
+
+    #[tokio::test]
+    async fn test_memory_backend_through_trait_object() {
+        let manager = CacheManager::new(create_test_cache_config(true, 3600)).await;
+        let backend: &dyn CacheBackend = &manager;
+
+        let checksums = ChecksumsInfo {
+            repo: "owner/repo".to_string(),
+            version: "v1.0.0".to_string(),
+            checksums_url: "https://example.com/SHA256SUMS".to_string(),
+            checksums: Default::default(),
+        };
+        backend.set_checksums("owner", "repo", checksums).await;
+        assert!(backend.get_checksums("owner", "repo").await.is_some());
+
+        // 过期时间为写入时间加上 TTL
+        let key = CacheManager::checksums_key("owner", "repo");
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let expires_at = backend.expires_at(&key).await.unwrap();
+        assert!(expires_at >= now + 3599 && expires_at <= now + 3600);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::download::glob_match;
+use crate::error::AppError;
+use crate::models::ReleaseInfo;
+use std::sync::OnceLock;
+
+/// 发布渠道
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReleaseChannel {
+    /// 正式版：既不是 pre-release，tag 也不匹配 beta/nightly 模式
+    Stable,
+    /// 测试版：tag 匹配 `CHANNEL_BETA_PATTERNS`，或标记为 pre-release 且不属于 nightly
+    Beta,
+    /// 每日构建：tag 匹配 `CHANNEL_NIGHTLY_PATTERNS`
+    Nightly,
+    /// 任意渠道
+    Any,
+}
+
+impl ReleaseChannel {
+    /// 解析 `channel` 查询参数，无法识别时返回 Err
+    pub fn parse(channel: &str) -> Result<Self, String> {
+        match channel.trim().to_lowercase().as_str() {
+            "stable" => Ok(Self::Stable),
+            "beta" => Ok(Self::Beta),
+            "nightly" => Ok(Self::Nightly),
+            "any" => Ok(Self::Any),
+            _ => Err(channel.to_string()),
+        }
+    }
+
+    /// 渠道名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Beta => "beta",
+            Self::Nightly => "nightly",
+            Self::Any => "any",
+        }
+    }
+}
+
+/// 发布渠道识别配置
+#[derive(Clone, Debug)]
+pub struct ChannelConfig {
+    /// 属于 beta 渠道的 tag 模式（小写，支持 `*` 和 `?` 通配符，匹配时忽略大小写）
+    pub beta_patterns: Vec<String>,
+    /// 属于 nightly 渠道的 tag 模式，优先于 beta 模式
+    pub nightly_patterns: Vec<String>,
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self {
+            beta_patterns: vec![
+                "*-alpha*".to_string(),
+                "*-beta*".to_string(),
+                "*-rc*".to_string(),
+                "*-preview*".to_string(),
+            ],
+            nightly_patterns: vec![
+                "*-nightly*".to_string(),
+                "nightly*".to_string(),
+                "*-canary*".to_string(),
+            ],
+        }
+    }
+}
+
+impl ChannelConfig {
+    /// 从环境变量加载配置（`CHANNEL_BETA_PATTERNS`、`CHANNEL_NIGHTLY_PATTERNS`，逗号分隔）
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+        let default = Self::default();
+
+        let patterns = |name: &str| {
+            std::env::var(name).ok().map(|patterns| {
+                patterns
+                    .split(',')
+                    .map(|s| s.trim().to_lowercase())
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        Self {
+            beta_patterns: patterns("CHANNEL_BETA_PATTERNS").unwrap_or(default.beta_patterns),
+            nightly_patterns: patterns("CHANNEL_NIGHTLY_PATTERNS")
+                .unwrap_or(default.nightly_patterns),
+        }
+    }
+
+    /// 识别 release 所属的渠道（不会返回 `Any`）
+    pub fn classify(&self, release: &ReleaseInfo) -> ReleaseChannel {
+        let tag = release.tag_name.to_lowercase();
+        let matches = |patterns: &[String]| patterns.iter().any(|p| glob_match(p, &tag));
+
+        if matches(&self.nightly_patterns) {
+            ReleaseChannel::Nightly
+        } else if matches(&self.beta_patterns) || release.prerelease {
+            ReleaseChannel::Beta
+        } else {
+            ReleaseChannel::Stable
+        }
+    }
+
+    /// 从 releases 列表中选出指定渠道发布时间最新的 release（跳过草稿）
+    ///
+    /// 列表为空时返回 `NoReleases`，没有该渠道的 release 时返回 `NoChannelRelease`。
+    pub fn select(
+        &self,
+        releases: Vec<ReleaseInfo>,
+        channel: ReleaseChannel,
+    ) -> Result<ReleaseInfo, AppError> {
+        if releases.is_empty() {
+            return Err(AppError::NoReleases);
+        }
+        releases
+            .into_iter()
+            .filter(|r| !r.draft && r.published_at.is_some())
+            .filter(|r| channel == ReleaseChannel::Any || self.classify(r) == channel)
+            .max_by_key(|r| r.published_at)
+            .ok_or_else(|| AppError::NoChannelRelease(channel.name().to_string()))
+    }
+}
+
+static CHANNEL_CONFIG: OnceLock<ChannelConfig> = OnceLock::new();
+
+/// 获取全局发布渠道配置
+pub fn get_channel_config() -> &'static ChannelConfig {
+    CHANNEL_CONFIG.get_or_init(ChannelConfig::from_env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, prerelease: bool, published_at: &str) -> ReleaseInfo {
+        ReleaseInfo {
+            tag_name: tag.to_string(),
+            name: None,
+            changelog: None,
+            changelog_truncated: false,
+            published_at: Some(published_at.parse().unwrap()),
+            prerelease,
+            draft: false,
+            attachments: vec![],
+            attachment_sizes: vec![],
+            author_login: None,
+            author_avatar_url: None,
+            reactions: None,
+            zipball_url: None,
+            tarball_url: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_channel() {
+        assert_eq!(ReleaseChannel::parse("Beta"), Ok(ReleaseChannel::Beta));
+        assert_eq!(ReleaseChannel::parse("any"), Ok(ReleaseChannel::Any));
+        assert_eq!(ReleaseChannel::parse("lts"), Err("lts".to_string()));
+    }
+
+    #[test]
+    fn test_classify() {
+        let config = ChannelConfig::default();
+        let classify = |tag: &str, prerelease: bool| {
+            config.classify(&release(tag, prerelease, "2024-01-01T00:00:00Z"))
+        };
+        assert_eq!(classify("v1.0.0", false), ReleaseChannel::Stable);
+        assert_eq!(classify("v1.1.0-RC.1", false), ReleaseChannel::Beta);
+        assert_eq!(classify("v1.1.0", true), ReleaseChannel::Beta);
+        assert_eq!(
+            classify("v1.2.0-nightly.20240101", true),
+            ReleaseChannel::Nightly
+        );
+        assert_eq!(classify("nightly", true), ReleaseChannel::Nightly);
+    }
+
+    #[test]
+    fn test_select() {
+        let config = ChannelConfig::default();
+        let releases = vec![
+            release("v1.0.0", false, "2024-01-01T00:00:00Z"),
+            release("v1.1.0-beta.1", true, "2024-02-01T00:00:00Z"),
+            release("v1.1.0-beta.2", true, "2024-03-01T00:00:00Z"),
+        ];
+
+        let select = |channel| config.select(releases.clone(), channel);
+        assert_eq!(select(ReleaseChannel::Stable).unwrap().tag_name, "v1.0.0");
+        assert_eq!(
+            select(ReleaseChannel::Beta).unwrap().tag_name,
+            "v1.1.0-beta.2"
+        );
+        assert_eq!(
+            select(ReleaseChannel::Any).unwrap().tag_name,
+            "v1.1.0-beta.2"
+        );
+        match select(ReleaseChannel::Nightly) {
+            Err(AppError::NoChannelRelease(channel)) => assert_eq!(channel, "nightly"),
+            other => panic!("expected NoChannelRelease, got {:?}", other),
+        }
+        assert!(matches!(
+            config.select(vec![], ReleaseChannel::Stable),
+            Err(AppError::NoReleases)
+        ));
+    }
+}
@@ -0,0 +1,165 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// GitHub 请求客户端的连接池配置
+///
+/// 所有 GitHub 请求（API 和附件下载）共用同一个客户端和连接池。
+#[derive(Clone, Debug)]
+pub struct GithubClientConfig {
+    /// 每个主机最多保留的空闲连接数（实际使用的值不小于最大并发下载数）
+    pub pool_max_idle_per_host: usize,
+    /// 空闲连接的保留时间，超过后关闭，`None` 表示一直保留
+    pub pool_idle_timeout: Option<Duration>,
+    /// 是否启用 HTTP/2（TLS 握手时通过 ALPN 协商，服务器不支持时回退到 HTTP/1.1），关闭时只使用 HTTP/1.1
+    pub http2: bool,
+    /// 启用 HTTP/2 时不经协商直接使用 HTTP/2（只支持 HTTP/1.1 的下载源会请求失败）
+    pub http2_prior_knowledge: bool,
+}
+
+impl Default for GithubClientConfig {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: 32,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            http2: false,
+            http2_prior_knowledge: false,
+        }
+    }
+}
+
+impl GithubClientConfig {
+    /// 从环境变量加载配置（`GITHUB_POOL_MAX_IDLE`、`GITHUB_POOL_IDLE_TIMEOUT_SECS`，超时为 `0` 表示一直保留；
+    /// `GITHUB_HTTP2`、`GITHUB_HTTP2_PRIOR_KNOWLEDGE`）
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+        let default = Self::default();
+
+        let pool_max_idle_per_host = std::env::var("GITHUB_POOL_MAX_IDLE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.pool_max_idle_per_host);
+
+        let pool_idle_timeout = std::env::var("GITHUB_POOL_IDLE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(|secs| (secs > 0).then(|| Duration::from_secs(secs)))
+            .unwrap_or(default.pool_idle_timeout);
+
+        let bool_var = |name: &str, default: bool| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            pool_max_idle_per_host,
+            pool_idle_timeout,
+            http2: bool_var("GITHUB_HTTP2", default.http2),
+            http2_prior_knowledge: bool_var(
+                "GITHUB_HTTP2_PRIOR_KNOWLEDGE",
+                default.http2_prior_knowledge,
+            ),
+        }
+    }
+
+    /// 将 HTTP 版本相关的配置应用到客户端构建器
+    ///
+    /// 启用 HTTP/2 时同时启用自适应流控窗口（按带宽时延积动态调整窗口大小），
+    /// 避免默认的 64KB 初始窗口限制大文件下载的吞吐量。
+    pub fn apply_http_version(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if !self.http2 {
+            return builder.http1_only();
+        }
+        let builder = builder.http2_adaptive_window(true);
+        if self.http2_prior_knowledge {
+            builder.http2_prior_knowledge()
+        } else {
+            builder
+        }
+    }
+
+    /// 每个主机实际保留的空闲连接数：至少能容纳 `max_concurrent_downloads` 个同时进行的下载，
+    /// 避免下载结束后连接被关闭、下一批下载重新建立连接
+    pub fn pool_max_idle_for(&self, max_concurrent_downloads: usize) -> usize {
+        self.pool_max_idle_per_host.max(max_concurrent_downloads)
+    }
+}
+
+static GITHUB_CLIENT_CONFIG: OnceLock<GithubClientConfig> = OnceLock::new();
+
+/// 获取全局 GitHub 请求客户端配置
+pub fn get_github_client_config() -> &'static GithubClientConfig {
+    GITHUB_CLIENT_CONFIG.get_or_init(GithubClientConfig::from_env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_max_idle_for() {
+        let config = GithubClientConfig::default();
+        assert_eq!(config.pool_max_idle_for(10), 32);
+        assert_eq!(config.pool_max_idle_for(64), 64);
+    }
+
+    #[actix_web::test]
+    async fn test_http2_streaming_download() {
+        use actix_web::{web, App, HttpResponse, HttpServer};
+        use futures::StreamExt;
+
+        const CHUNK: usize = 64 * 1024;
+        const CHUNKS: usize = 16;
+
+        // 分块返回 1MB 数据，超过 HTTP/2 默认的流控窗口
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/file", listener.local_addr().unwrap());
+        let server = HttpServer::new(|| {
+            App::new().route(
+                "/file",
+                web::get().to(|| async {
+                    let chunks =
+                        futures::stream::iter((0..CHUNKS).map(|_| {
+                            Ok::<_, actix_web::Error>(web::Bytes::from(vec![b'x'; CHUNK]))
+                        }));
+                    HttpResponse::Ok().streaming(chunks)
+                }),
+            )
+        })
+        .workers(1)
+        .listen_auto_h2c(listener)
+        .unwrap()
+        .run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let config = GithubClientConfig {
+            http2: true,
+            http2_prior_knowledge: true,
+            ..Default::default()
+        };
+        let client = config
+            .apply_http_version(reqwest::Client::builder())
+            .build()
+            .unwrap();
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.version(), reqwest::Version::HTTP_2);
+        let mut stream = response.bytes_stream();
+        let mut total = 0;
+        while let Some(chunk) = stream.next().await {
+            total += chunk.unwrap().len();
+        }
+        assert_eq!(total, CHUNK * CHUNKS);
+
+        // 默认只使用 HTTP/1.1
+        let client = GithubClientConfig::default()
+            .apply_http_version(reqwest::Client::builder())
+            .build()
+            .unwrap();
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.version(), reqwest::Version::HTTP_11);
+
+        handle.stop(false).await;
+    }
+}
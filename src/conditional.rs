@@ -0,0 +1,266 @@
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderValue, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::Next;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::sync::OnceLock;
+
+/// 是否启用条件响应（`ENABLE_CONDITIONAL_RESPONSES`，默认关闭）
+///
+/// 启用后同时作用于两段链路：
+/// - 客户端 → 本服务：JSON 响应带上根据响应体计算的 ETag，请求的 If-None-Match 匹配时返回 304；
+/// - 本服务 → GitHub：缓存过期后重新验证时，除 If-Modified-Since 外还携带 GitHub 返回的 ETag（If-None-Match）。
+pub fn conditional_responses_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        dotenv::dotenv().ok();
+        env::var("ENABLE_CONDITIONAL_RESPONSES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false)
+    })
+}
+
+/// If-None-Match 头是否匹配给定的 ETag（支持 `*`、逗号分隔的列表和弱 ETag）
+pub fn if_none_match_matches(header: &str, etag: &str) -> bool {
+    header.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+/// 缓存数据的版本（缓存键和数据获取时间），由返回缓存数据的处理函数放入响应扩展
+///
+/// 同一请求地址、同一版本的缓存数据总是得到相同的响应体，中间件据此计算 ETag，不需要缓冲和哈希响应体。
+#[derive(Clone, Debug)]
+pub struct CachedVersion(pub String);
+
+// 根据响应体计算强 ETag（SHA-256 的前 16 字节），相同的数据总是得到相同的 ETag
+fn body_etag(body: &[u8]) -> String {
+    format!("\"{}\"", hex::encode(&Sha256::digest(body)[..16]))
+}
+
+// 根据请求地址（路径和查询参数）和缓存数据的版本计算 ETag
+fn version_etag(path_and_query: &str, version: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(path_and_query.as_bytes());
+    hasher.update([0]);
+    hasher.update(version.as_bytes());
+    format!("\"{}\"", hex::encode(&hasher.finalize()[..16]))
+}
+
+// 响应是否为 JSON（不包括 NDJSON、SSE 等流式响应）
+fn is_json(content_type: Option<&HeaderValue>) -> bool {
+    content_type
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|essence| essence.trim().eq_ignore_ascii_case("application/json"))
+}
+
+/// 条件响应中间件：按 `ENABLE_CONDITIONAL_RESPONSES` 决定是否处理，见 [`apply_conditional_response`]
+pub async fn conditional_response_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    apply_conditional_response(req, next, conditional_responses_enabled()).await
+}
+
+/// 为 GET 请求的 200 JSON 响应添加 ETag，If-None-Match 匹配时改为不带响应体的 304
+///
+/// 返回缓存数据的响应带有 [`CachedVersion`]，ETag 由请求地址和数据版本得出；
+/// 其他 JSON 响应才需要缓冲响应体并计算哈希。
+/// enabled 为 false 或处理函数已设置 ETag（如 /health）时不做任何处理。
+/// 304 保留处理函数设置的 Cache-Control 等响应头，客户端可以据此继续使用本地副本。
+pub async fn apply_conditional_response(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+    enabled: bool,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    if !enabled || req.method() != Method::GET {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+    let if_none_match = req
+        .headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string);
+    let path_and_query = req
+        .uri()
+        .path_and_query()
+        .map_or_else(|| req.path().to_string(), |pq| pq.as_str().to_string());
+
+    let res = next.call(req).await?;
+    if res.status() != StatusCode::OK
+        || res.headers().contains_key(ETAG)
+        || !is_json(res.headers().get(CONTENT_TYPE))
+    {
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let version = res.response().extensions().get::<CachedVersion>().cloned();
+    if let Some(CachedVersion(version)) = version {
+        let etag = version_etag(&path_and_query, &version);
+        let (req, mut res) = res.into_parts();
+        if let Ok(value) = HeaderValue::from_str(&etag) {
+            res.headers_mut().insert(ETAG, value);
+        }
+        if if_none_match.is_some_and(|header| if_none_match_matches(&header, &etag)) {
+            *res.status_mut() = StatusCode::NOT_MODIFIED;
+            res.headers_mut().remove(CONTENT_TYPE);
+            return Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(()))));
+        }
+        return Ok(ServiceResponse::new(req, res.map_into_boxed_body()));
+    }
+
+    let (req, res) = res.into_parts();
+    let (mut res, body) = res.into_parts();
+    let body = actix_web::body::to_bytes(body)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e.into().to_string()))?;
+    let etag = body_etag(&body);
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        res.headers_mut().insert(ETAG, value);
+    }
+
+    if if_none_match.is_some_and(|header| if_none_match_matches(&header, &etag)) {
+        *res.status_mut() = StatusCode::NOT_MODIFIED;
+        res.headers_mut().remove(CONTENT_TYPE);
+        return Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(()))));
+    }
+    Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(body))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::test::{call_service, init_service, read_body, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+
+    #[test]
+    fn test_if_none_match_matches() {
+        let etag = "\"health-1.0.0\"";
+        assert!(if_none_match_matches("\"health-1.0.0\"", etag));
+        assert!(if_none_match_matches("W/\"health-1.0.0\"", etag));
+        assert!(if_none_match_matches("\"a\", \"health-1.0.0\"", etag));
+        assert!(if_none_match_matches("*", etag));
+        assert!(!if_none_match_matches("\"health-0.9.0\"", etag));
+    }
+
+    #[test]
+    fn test_is_json() {
+        assert!(is_json(Some(&HeaderValue::from_static("application/json"))));
+        assert!(is_json(Some(&HeaderValue::from_static("application/json; charset=utf-8"))));
+        assert!(!is_json(Some(&HeaderValue::from_static("application/x-ndjson"))));
+        assert!(!is_json(None));
+    }
+
+    #[actix_web::test]
+    async fn test_middleware_returns_not_modified() {
+        let app = init_service(
+            App::new()
+                .wrap(from_fn(|req, next| apply_conditional_response(req, next, true)))
+                .route(
+                    "/json",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("application/json")
+                            .insert_header(("Cache-Control", "public, max-age=60"))
+                            .body(r#"{"a":1}"#)
+                    }),
+                )
+                .route("/text", web::get().to(|| async { HttpResponse::Ok().body("text") })),
+        )
+        .await;
+
+        let resp = call_service(&app, TestRequest::get().uri("/json").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let etag = resp.headers().get(ETAG).unwrap().to_str().unwrap().to_string();
+        assert_eq!(etag, body_etag(br#"{"a":1}"#));
+        assert_eq!(read_body(resp).await, r#"{"a":1}"#);
+
+        // 客户端已有相同数据时返回 304，保留 Cache-Control 和 ETag
+        let req = TestRequest::get()
+            .uri("/json")
+            .insert_header((IF_NONE_MATCH, etag.as_str()))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(resp.headers().get(ETAG).unwrap(), etag.as_str());
+        assert_eq!(resp.headers().get("cache-control").unwrap(), "public, max-age=60");
+        assert!(read_body(resp).await.is_empty());
+
+        // 数据变化（ETag 不匹配）时返回完整响应
+        let req = TestRequest::get()
+            .uri("/json")
+            .insert_header((IF_NONE_MATCH, "\"stale\""))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // 非 JSON 响应不添加 ETag
+        let resp = call_service(&app, TestRequest::get().uri("/text").to_request()).await;
+        assert!(resp.headers().get(ETAG).is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_middleware_uses_cached_version() {
+        let app = init_service(
+            App::new()
+                .wrap(from_fn(|req, next| apply_conditional_response(req, next, true)))
+                .route(
+                    "/cached",
+                    web::get().to(|| async {
+                        let mut response =
+                            HttpResponse::Ok().content_type("application/json").body(r#"{"a":1}"#);
+                        response
+                            .extensions_mut()
+                            .insert(CachedVersion("repo_info:owner:repo:100".to_string()));
+                        response
+                    }),
+                ),
+        )
+        .await;
+
+        // ETag 由请求地址和数据版本得出，不同的查询参数得到不同的 ETag
+        let resp = call_service(&app, TestRequest::get().uri("/cached").to_request()).await;
+        let etag = resp.headers().get(ETAG).unwrap().to_str().unwrap().to_string();
+        assert_eq!(etag, version_etag("/cached", "repo_info:owner:repo:100"));
+        assert_ne!(etag, body_etag(br#"{"a":1}"#));
+        assert_eq!(read_body(resp).await, r#"{"a":1}"#);
+        let resp = call_service(&app, TestRequest::get().uri("/cached?pretty=true").to_request()).await;
+        assert_ne!(resp.headers().get(ETAG).unwrap(), etag.as_str());
+
+        let req = TestRequest::get()
+            .uri("/cached")
+            .insert_header((IF_NONE_MATCH, etag.as_str()))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert!(read_body(resp).await.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_middleware_disabled_passes_through() {
+        let app = init_service(
+            App::new()
+                .wrap(from_fn(|req, next| apply_conditional_response(req, next, false)))
+                .route(
+                    "/json",
+                    web::get().to(|| async {
+                        HttpResponse::Ok().content_type("application/json").body("{}")
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/json")
+            .insert_header((IF_NONE_MATCH, "*"))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().get(ETAG).is_none());
+    }
+}
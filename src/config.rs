@@ -0,0 +1,412 @@
+//! 启动时的配置检查
+//!
+//! 各模块的 `from_env()` 在环境变量无效时会静默回退到默认值，配置错误往往要到处理请求时才会暴露。
+//! 启动时先检查所有环境变量：无法生效或明显错误的配置视为致命错误，服务以非零退出码退出；
+//! 可以运行但可能不符合预期的配置输出警告。
+
+use crate::cache::{
+    cache_file_path, clamp_ttl, create_cache_dir, file_cache_dir, parse_mode, ttl_bounds,
+};
+use crate::cache_backend::CacheBackendKind;
+use crate::download::DownloadFilenameMode;
+use crate::trailing_slash::TrailingSlashMode;
+use std::net::ToSocketAddrs;
+use std::path::Path;
+
+// 非负整数类型的环境变量，以及是否必须大于 0
+const NUMBER_VARS: &[(&str, bool)] = &[
+    ("CACHE_TTL_SECONDS", false),
+    ("CACHE_MIN_TTL_SECONDS", false),
+    ("CACHE_MAX_TTL_SECONDS", true),
+    ("NEGATIVE_CACHE_TTL_SECS", false),
+    ("NEGATIVE_CACHE_MAX_ENTRIES", true),
+    ("PERSISTENT_CACHE_MAX_ENTRIES", true),
+    ("CACHE_OUTAGE_GRACE_SECS", false),
+    ("CACHE_PRUNE_INTERVAL_SECS", true),
+    ("FILE_CACHE_MAX_FILES", false),
+    ("FILE_CACHE_CLEANUP_INTERVAL_SECS", true),
+    ("MAX_CONCURRENT_DOWNLOADS", true),
+    ("DOWNLOAD_MAX_QUEUE_WAIT_SECS", false),
+    ("DOWNLOAD_CLIENT_IDLE_TIMEOUT_SECS", false),
+    ("DOWNLOAD_RETRY_AFTER_MAX_SECS", false),
+    ("DOWNLOAD_CACHE_WRITE_BUFFER", true),
+    ("DOWNLOAD_BUFFER_THRESHOLD_BYTES", false),
+    ("MAX_DOWNLOAD_URL_LEN", true),
+    ("CACHE_FULL_ON_RANGE_MAX_BYTES", false),
+    ("REQUEST_TIMEOUT_SECS", false),
+    ("DOWNLOAD_REQUEST_TIMEOUT_SECS", false),
+    ("RATE_LIMIT_WINDOW_SECS", true),
+    ("API_MAX_REQUESTS_PER_WINDOW", false),
+    ("DOWNLOAD_MAX_REQUESTS_PER_WINDOW", false),
+    ("REFRESH_MAX_REQUESTS_PER_WINDOW", false),
+    ("MAX_CONCURRENT_BATCHES", false),
+    ("GITHUB_MAX_REQUESTS_PER_HOUR", false),
+    ("GITHUB_MAX_CONCURRENT_REQUESTS", false),
+    ("GITHUB_202_MAX_RETRIES", false),
+    ("GITHUB_POOL_MAX_IDLE", false),
+    ("GITHUB_POOL_IDLE_TIMEOUT_SECS", false),
+    ("MAX_CHANGELOG_BYTES", false),
+    ("WATCH_POLL_INTERVAL_SECS", true),
+    ("WATCH_MAX_REPOS", true),
+    ("WATCH_MAX_CONCURRENT_FETCHES", true),
+];
+
+// 布尔类型的环境变量（取值为 true 或 false）
+const BOOL_VARS: &[&str] = &[
+    "CACHE_ENABLED",
+    "CACHE_PRECOMPRESS",
+    "CACHE_FILE_COMPRESS",
+    "API_CACHE_CONTROL",
+    "CACHE_FULL_ON_RANGE",
+    "DOWNLOAD_REQUIRE_RELEASE_ASSET",
+    "TRUST_FORWARDED_HEADERS",
+    "TRUST_PROXY",
+    "PRIVATE_REPOS_ENABLED",
+    "GITHUB_HTTP2",
+    "GITHUB_HTTP2_PRIOR_KNOWLEDGE",
+    "REQUIRE_GITHUB_TOKEN",
+    "ENABLE_CONDITIONAL_RESPONSES",
+];
+
+/// 配置检查结果
+#[derive(Debug, Default)]
+pub struct ConfigReport {
+    /// 可以运行但可能不符合预期的配置
+    pub warnings: Vec<String>,
+    /// 致命错误，服务不应启动
+    pub errors: Vec<String>,
+}
+
+impl ConfigReport {
+    /// 是否没有致命错误
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// 输出所有警告和错误
+    pub fn log(&self) {
+        for warning in &self.warnings {
+            log::warn!("配置警告: {}", warning);
+        }
+        for error in &self.errors {
+            log::error!("配置错误: {}", error);
+        }
+    }
+}
+
+/// 检查当前环境变量中的配置，并确认缓存目录可写
+pub fn validate_config() -> ConfigReport {
+    dotenv::dotenv().ok();
+
+    let mut report = validate_env(|name| std::env::var(name).ok());
+    let cache_enabled = std::env::var("CACHE_ENABLED")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(true);
+    if cache_enabled {
+        let cache_file_path = cache_file_path();
+        if let Some(parent) = cache_file_path.parent() {
+            check_dir_writable("CACHE_FILE 所在目录", parent, &mut report);
+        }
+        check_dir_writable(
+            "FILE_CACHE_DIR",
+            &file_cache_dir(&cache_file_path),
+            &mut report,
+        );
+    }
+    report
+}
+
+// 检查环境变量的取值（不访问文件系统）
+fn validate_env(get: impl Fn(&str) -> Option<String>) -> ConfigReport {
+    let mut report = ConfigReport::default();
+    let get = |name: &str| get(name).map(|v| v.trim().to_string());
+    let is_true = |name: &str| get(name).is_some_and(|v| v == "true" || v == "1");
+
+    for (name, positive) in NUMBER_VARS {
+        let Some(value) = get(name) else { continue };
+        match value.parse::<u64>() {
+            Ok(0) if *positive => report.errors.push(format!("{} 必须大于 0", name)),
+            Ok(_) => {}
+            Err(_) => report
+                .errors
+                .push(format!("{} 必须是非负整数，当前值: {:?}", name, value)),
+        }
+    }
+
+    for name in BOOL_VARS {
+        let Some(value) = get(name) else { continue };
+        if value.parse::<bool>().is_err() {
+            report.errors.push(format!(
+                "{} 必须是 true 或 false，当前值: {:?}",
+                name, value
+            ));
+        }
+    }
+    if let Some(value) = get("BATCH_USE_GRAPHQL") {
+        if !["true", "false", "1", "0"].contains(&value.to_lowercase().as_str()) {
+            report.errors.push(format!(
+                "BATCH_USE_GRAPHQL 必须是 true 或 false，当前值: {:?}",
+                value
+            ));
+        }
+    }
+
+    // 超出上下限的 TTL 会被截断（0 会被提高到下限，避免误将缓存实际禁用）
+    if let Some(ttl) = get("CACHE_TTL_SECONDS").and_then(|v| v.parse::<u64>().ok()) {
+        let (min_ttl, max_ttl) = ttl_bounds(
+            get("CACHE_MIN_TTL_SECONDS").as_deref(),
+            get("CACHE_MAX_TTL_SECONDS").as_deref(),
+        );
+        if let Some(clamped) = clamp_ttl(ttl, min_ttl, max_ttl) {
+            report.warnings.push(format!(
+                "CACHE_TTL_SECONDS={} 超出允许范围 [{}, {}]，将使用 {} 秒",
+                ttl, min_ttl, max_ttl, clamped
+            ));
+        }
+    }
+
+    if let Some(bind_addr) = get("BIND_ADDRESS") {
+        if bind_addr.to_socket_addrs().is_err() {
+            report.errors.push(format!(
+                "BIND_ADDRESS 不是有效的监听地址（格式为 主机:端口，如 0.0.0.0:8080），当前值: {:?}",
+                bind_addr
+            ));
+        }
+    }
+
+    if let Some(version) = get("GITHUB_API_VERSION").filter(|v| !v.is_empty()) {
+        if chrono::NaiveDate::parse_from_str(&version, "%Y-%m-%d").is_err() {
+            report.errors.push(format!(
+                "GITHUB_API_VERSION 必须是 GitHub REST API 的版本日期（如 2022-11-28），当前值: {:?}",
+                version
+            ));
+        }
+    }
+
+    for name in ["CACHE_DIR_MODE", "CACHE_FILE_MODE"] {
+        if let Some(value) = get(name) {
+            if parse_mode(&value).is_none() {
+                report.errors.push(format!(
+                    "{} 必须是不超过 0777 的八进制权限（如 0750），当前值: {:?}",
+                    name, value
+                ));
+            }
+        }
+    }
+
+    if let Some(value) = get("TRAILING_SLASH") {
+        if TrailingSlashMode::parse(&value).is_none() {
+            report.errors.push(format!(
+                "TRAILING_SLASH 必须是 trim、merge 或 keep，当前值: {:?}",
+                value
+            ));
+        }
+    }
+
+    if let Some(value) = get("DOWNLOAD_FILENAME") {
+        if DownloadFilenameMode::parse(&value).is_none() {
+            report.errors.push(format!(
+                "DOWNLOAD_FILENAME 必须是 original、hashed 或 upstream，当前值: {:?}",
+                value
+            ));
+        }
+    }
+
+    match get("CACHE_BACKEND").map(|v| (CacheBackendKind::parse(&v), v)) {
+        Some((None, value)) => report.errors.push(format!(
+            "CACHE_BACKEND 必须是 memory 或 redis，当前值: {:?}",
+            value
+        )),
+        Some((Some(CacheBackendKind::Redis), _))
+            if get("REDIS_URL").is_none_or(|v| v.is_empty()) =>
+        {
+            report
+                .errors
+                .push("CACHE_BACKEND=redis 时必须设置 REDIS_URL".to_string())
+        }
+        Some((Some(CacheBackendKind::Redis), _)) if !cfg!(feature = "redis") => report
+            .warnings
+            .push("CACHE_BACKEND=redis 但编译时未启用 redis 特性，将使用内存缓存".to_string()),
+        _ => {}
+    }
+
+    if !cfg!(feature = "s3") && get("S3_ENDPOINT").is_some_and(|v| !v.trim().is_empty()) {
+        report
+            .warnings
+            .push("设置了 S3_ENDPOINT 但编译时未启用 s3 特性，不会使用 S3 镜像".to_string());
+    }
+
+    if let Some(patterns) = get("DOWNLOAD_IMMUTABLE_URL_PATTERNS") {
+        for pattern in patterns
+            .split(',')
+            .map(str::trim)
+            .filter(|p| p.contains("://"))
+        {
+            report.warnings.push(format!(
+                "DOWNLOAD_IMMUTABLE_URL_PATTERNS 中的模式与 主机/路径 匹配，不应包含协议: {}",
+                pattern
+            ));
+        }
+    }
+
+    let has_token = get("GITHUB_TOKEN").is_some_and(|v| !v.is_empty());
+    if !has_token {
+        if is_true("REQUIRE_GITHUB_TOKEN") {
+            report
+                .errors
+                .push("已设置 REQUIRE_GITHUB_TOKEN=true，但未设置 GITHUB_TOKEN".to_string());
+        } else {
+            report.warnings.push(
+                "未设置 GITHUB_TOKEN，GitHub API 的速率限制为每个出口 IP 每小时 60 次请求，\
+                 稍有负载就会收到 403；生产环境请配置 token，或设置 REQUIRE_GITHUB_TOKEN=true 拒绝在无 token 时启动"
+                    .to_string(),
+            );
+        }
+        if is_true("BATCH_USE_GRAPHQL") {
+            report.warnings.push(
+                "BATCH_USE_GRAPHQL 需要设置 GITHUB_TOKEN，批量查询将不使用 GraphQL 预取"
+                    .to_string(),
+            );
+        }
+    }
+
+    if get("CACHE_ENABLED").as_deref() == Some("false") {
+        if is_true("DOWNLOAD_REQUIRE_RELEASE_ASSET") {
+            report.warnings.push(
+                "DOWNLOAD_REQUIRE_RELEASE_ASSET 依赖缓存中的 release 信息，禁用缓存时所有下载都会被拒绝"
+                    .to_string(),
+            );
+        }
+        if is_true("CACHE_FULL_ON_RANGE") {
+            report
+                .warnings
+                .push("禁用缓存时 CACHE_FULL_ON_RANGE 不会生效".to_string());
+        }
+    }
+
+    report
+}
+
+// 确认目录存在（不存在时创建）且可以写入文件
+fn check_dir_writable(name: &str, dir: &Path, report: &mut ConfigReport) {
+    if dir.as_os_str().is_empty() {
+        return;
+    }
+    if let Err(e) = create_cache_dir(dir) {
+        report
+            .errors
+            .push(format!("{} 无法创建: {:?} ({})", name, dir, e));
+        return;
+    }
+    let probe = dir.join(format!(".write-test-{}", std::process::id()));
+    match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+        }
+        Err(e) => report
+            .errors
+            .push(format!("{} 不可写: {:?} ({})", name, dir, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn validate(vars: &[(&str, &str)]) -> ConfigReport {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        validate_env(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_valid_config() {
+        let report = validate(&[
+            ("GITHUB_TOKEN", "token"),
+            ("CACHE_TTL_SECONDS", "3600"),
+            ("CACHE_ENABLED", "true"),
+            ("BIND_ADDRESS", "127.0.0.1:8080"),
+            ("GITHUB_API_VERSION", "2022-11-28"),
+            ("CACHE_BACKEND", "memory"),
+        ]);
+        assert!(report.is_ok(), "{:?}", report.errors);
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn test_invalid_values_are_errors() {
+        let report = validate(&[
+            ("GITHUB_TOKEN", "token"),
+            ("CACHE_TTL_SECONDS", "1h"),
+            ("MAX_CONCURRENT_DOWNLOADS", "0"),
+            ("CACHE_ENABLED", "yes"),
+            ("BIND_ADDRESS", "8080"),
+            ("GITHUB_API_VERSION", "v3"),
+            ("CACHE_FILE_MODE", "0999"),
+            ("CACHE_BACKEND", "memcached"),
+            ("TRAILING_SLASH", "always"),
+            ("DOWNLOAD_FILENAME", "header"),
+        ]);
+        assert_eq!(report.errors.len(), 9, "{:?}", report.errors);
+        assert!(report.errors[0].contains("CACHE_TTL_SECONDS"));
+        assert!(report.errors[1].contains("MAX_CONCURRENT_DOWNLOADS"));
+    }
+
+    #[test]
+    fn test_ttl_out_of_range_is_warning() {
+        let report = validate(&[("GITHUB_TOKEN", "token"), ("CACHE_TTL_SECONDS", "0")]);
+        assert!(report.is_ok(), "{:?}", report.errors);
+        assert_eq!(report.warnings.len(), 1, "{:?}", report.warnings);
+        assert!(report.warnings[0].contains("CACHE_TTL_SECONDS=0"));
+
+        let report = validate(&[
+            ("GITHUB_TOKEN", "token"),
+            ("CACHE_TTL_SECONDS", "600"),
+            ("CACHE_MAX_TTL_SECONDS", "300"),
+        ]);
+        assert_eq!(report.warnings.len(), 1, "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn test_redis_backend_requires_url() {
+        let report = validate(&[("GITHUB_TOKEN", "token"), ("CACHE_BACKEND", "redis")]);
+        assert!(!report.is_ok());
+        assert!(report.errors[0].contains("REDIS_URL"));
+    }
+
+    #[test]
+    fn test_warnings() {
+        let report = validate(&[
+            ("BATCH_USE_GRAPHQL", "true"),
+            ("CACHE_ENABLED", "false"),
+            ("DOWNLOAD_REQUIRE_RELEASE_ASSET", "true"),
+            ("DOWNLOAD_IMMUTABLE_URL_PATTERNS", "https://github.com/*"),
+        ]);
+        assert!(report.is_ok(), "{:?}", report.errors);
+        assert_eq!(report.warnings.len(), 4, "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn test_require_github_token() {
+        let report = validate(&[("REQUIRE_GITHUB_TOKEN", "true")]);
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+
+        let report = validate(&[("REQUIRE_GITHUB_TOKEN", "true"), ("GITHUB_TOKEN", "token")]);
+        assert!(report.is_ok(), "{:?}", report.errors);
+    }
+
+    #[test]
+    fn test_check_dir_writable() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("cache");
+        let mut report = ConfigReport::default();
+        check_dir_writable("FILE_CACHE_DIR", &dir, &mut report);
+        assert!(report.is_ok(), "{:?}", report.errors);
+        assert!(dir.is_dir());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,1140 @@
+use crate::error::AppError;
+use crate::telemetry::DownloadSpan;
+use actix_web::web::Bytes;
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::OwnedSemaphorePermit;
+
+/// 下载配置
+#[derive(Clone, Debug)]
+pub struct DownloadConfig {
+    /// 内容不可变的主机（缓存命中时无需向上游重新验证）
+    pub immutable_hosts: Vec<String>,
+    /// 内容不可变的 URL 模式（匹配 `主机/路径`，支持 `*` 和 `?` 通配符）
+    pub immutable_url_patterns: Vec<String>,
+    /// 客户端空闲超时（秒）：超过该时间没有消费任何数据时中止下载并释放下载许可，0 表示不限制
+    pub client_idle_timeout_secs: u64,
+    /// 只允许下载缓存中已知 release 的附件（其他 URL 返回 403）
+    pub require_release_asset: bool,
+    /// 为文本类缓存文件额外保存 gzip 压缩的副本，客户端接受 gzip 时直接返回
+    pub precompress: bool,
+    /// 缓存写入队列最多缓冲的数据块数，写入跟不上下载时下载流等待写入（背压）
+    pub cache_write_buffer: usize,
+    /// 未缓存的不可变文件收到 Range 请求时，向客户端返回所请求的范围，同时在后台完整下载并缓存该文件
+    pub cache_full_on_range: bool,
+    /// 后台完整下载的文件大小上限（字节），超过时只返回所请求的范围，0 表示不限制
+    pub cache_full_on_range_max_bytes: u64,
+    /// 上游声明的大小不超过该值（字节）的文件一次性读入内存并写入缓存，不经过流式写入任务，0 表示始终流式下载
+    pub buffer_threshold_bytes: u64,
+    /// `url` 参数的最大长度（字节），超过时直接返回 400，避免超长 URL 进入缓存键
+    pub max_url_len: usize,
+    /// 从下载请求转发给上游的请求头名称（小写），不包含 `NEVER_FORWARDED_HEADERS` 中的请求头
+    pub forward_headers: Vec<String>,
+    /// 下载响应 Content-Disposition 中使用的文件名
+    pub filename_mode: DownloadFilenameMode,
+    /// 按扩展名（小写，不含开头的 `.`）覆盖的 Content-Type，优先于上游返回的类型和根据扩展名的推测
+    pub content_type_overrides: HashMap<String, mime::Mime>,
+    /// 允许下载的 Content-Type（小写，支持 `*` 和 `?` 通配符，如 `application/*`），为空时允许所有类型
+    pub allowed_content_types: Vec<String>,
+    /// 上游触发限流（403/429 带 Retry-After）时最多等待的秒数，不超过时等待后重试一次，0 表示不重试
+    pub retry_after_max_secs: u64,
+}
+
+/// 下载响应 Content-Disposition 中使用的文件名（`DOWNLOAD_FILENAME`）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DownloadFilenameMode {
+    /// URL 路径中的文件名（默认）
+    Original,
+    /// 缓存文件名（`<URL 的 SHA-256>.<扩展名>`），不同 URL 的同名文件不会互相覆盖
+    Hashed,
+    /// 上游响应 Content-Disposition 中的文件名，上游未提供时使用 URL 路径中的文件名
+    Upstream,
+}
+
+impl DownloadFilenameMode {
+    /// 解析 `DOWNLOAD_FILENAME` 的值（不区分大小写），无法识别时返回 None
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "original" => Some(Self::Original),
+            "hashed" => Some(Self::Hashed),
+            "upstream" => Some(Self::Upstream),
+            _ => None,
+        }
+    }
+}
+
+/// 即使出现在 `DOWNLOAD_FORWARD_HEADERS` 中也不会转发的请求头：
+/// 逐跳（hop-by-hop）请求头、凭据（避免客户端的凭据泄露给上游），以及服务自身设置或依赖的请求头
+pub const NEVER_FORWARDED_HEADERS: &[&str] = &[
+    // 逐跳请求头
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "proxy-authenticate",
+    "proxy-authorization",
+    // 凭据
+    "authorization",
+    "cookie",
+    // 由服务设置：上游地址、GitHub 认证、缓存重新验证，以及未启用解压的响应编码
+    "host",
+    "content-length",
+    "user-agent",
+    "if-none-match",
+    "accept-encoding",
+    // 客户端的条件请求头：上游可能返回下载流程无法处理的 304 或 412
+    "if-modified-since",
+    "if-match",
+    "if-unmodified-since",
+    "if-range",
+];
+
+// 解析 DOWNLOAD_FORWARD_HEADERS（逗号分隔），跳过无效或禁止转发的名称
+fn parse_forward_headers(value: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for name in value.split(',').map(|s| s.trim().to_lowercase()) {
+        if name.is_empty() || names.contains(&name) {
+            continue;
+        }
+        if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+            log::warn!("DOWNLOAD_FORWARD_HEADERS 中包含无效的请求头名称: {}", name);
+        } else if NEVER_FORWARDED_HEADERS.contains(&name.as_str()) {
+            log::warn!("DOWNLOAD_FORWARD_HEADERS 中的 {} 不允许转发，已忽略", name);
+        } else {
+            names.push(name);
+        }
+    }
+    names
+}
+
+// 解析 CONTENT_TYPE_OVERRIDES（逗号分隔的 `扩展名:类型`），跳过格式错误或类型无效的条目
+fn parse_content_type_overrides(value: &str) -> HashMap<String, mime::Mime> {
+    let mut overrides = HashMap::new();
+    for entry in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let parsed = entry.split_once(':').and_then(|(ext, content_type)| {
+            let ext = ext.trim().trim_start_matches('.').to_lowercase();
+            let content_type = content_type.trim().parse::<mime::Mime>().ok()?;
+            (!ext.is_empty()).then_some((ext, content_type))
+        });
+        match parsed {
+            Some((ext, content_type)) => {
+                overrides.insert(ext, content_type);
+            }
+            None => log::warn!("CONTENT_TYPE_OVERRIDES 中的条目无效（应为 扩展名:类型）: {}", entry),
+        }
+    }
+    overrides
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            immutable_hosts: vec![
+                "objects.githubusercontent.com".to_string(),
+                "release-assets.githubusercontent.com".to_string(),
+            ],
+            immutable_url_patterns: vec!["github.com/*/*/releases/download/*".to_string()],
+            client_idle_timeout_secs: 60,
+            require_release_asset: false,
+            precompress: false,
+            cache_write_buffer: 100,
+            cache_full_on_range: false,
+            cache_full_on_range_max_bytes: 512 * 1024 * 1024,
+            buffer_threshold_bytes: 1024 * 1024,
+            max_url_len: 2048,
+            forward_headers: Vec::new(),
+            filename_mode: DownloadFilenameMode::Original,
+            content_type_overrides: HashMap::new(),
+            allowed_content_types: Vec::new(),
+            retry_after_max_secs: 10,
+        }
+    }
+}
+
+impl DownloadConfig {
+    /// 从环境变量加载配置
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        let immutable_hosts = std::env::var("DOWNLOAD_IMMUTABLE_HOSTS")
+            .map(|hosts| {
+                hosts
+                    .split(',')
+                    .map(|s| s.trim().to_lowercase())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or(default.immutable_hosts);
+
+        let immutable_url_patterns = std::env::var("DOWNLOAD_IMMUTABLE_URL_PATTERNS")
+            .map(|patterns| {
+                patterns
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or(default.immutable_url_patterns);
+
+        let client_idle_timeout_secs = std::env::var("DOWNLOAD_CLIENT_IDLE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.client_idle_timeout_secs);
+
+        let require_release_asset = std::env::var("DOWNLOAD_REQUIRE_RELEASE_ASSET")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.require_release_asset);
+
+        let precompress = std::env::var("CACHE_PRECOMPRESS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.precompress);
+
+        let cache_write_buffer = std::env::var("DOWNLOAD_CACHE_WRITE_BUFFER")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(default.cache_write_buffer);
+
+        let cache_full_on_range = std::env::var("CACHE_FULL_ON_RANGE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.cache_full_on_range);
+
+        let cache_full_on_range_max_bytes = std::env::var("CACHE_FULL_ON_RANGE_MAX_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.cache_full_on_range_max_bytes);
+
+        let buffer_threshold_bytes = std::env::var("DOWNLOAD_BUFFER_THRESHOLD_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.buffer_threshold_bytes);
+
+        let max_url_len = std::env::var("MAX_DOWNLOAD_URL_LEN")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|len| *len > 0)
+            .unwrap_or(default.max_url_len);
+
+        let forward_headers = std::env::var("DOWNLOAD_FORWARD_HEADERS")
+            .map(|names| parse_forward_headers(&names))
+            .unwrap_or(default.forward_headers);
+
+        let filename_mode = std::env::var("DOWNLOAD_FILENAME")
+            .ok()
+            .and_then(|v| DownloadFilenameMode::parse(&v))
+            .unwrap_or(default.filename_mode);
+
+        let content_type_overrides = std::env::var("CONTENT_TYPE_OVERRIDES")
+            .map(|overrides| parse_content_type_overrides(&overrides))
+            .unwrap_or(default.content_type_overrides);
+
+        let allowed_content_types = std::env::var("DOWNLOAD_ALLOWED_CONTENT_TYPES")
+            .map(|types| {
+                types
+                    .split(',')
+                    .map(|s| s.trim().to_lowercase())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or(default.allowed_content_types);
+
+        let retry_after_max_secs = std::env::var("DOWNLOAD_RETRY_AFTER_MAX_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.retry_after_max_secs);
+
+        Self {
+            immutable_hosts,
+            immutable_url_patterns,
+            client_idle_timeout_secs,
+            require_release_asset,
+            precompress,
+            cache_write_buffer,
+            cache_full_on_range,
+            cache_full_on_range_max_bytes,
+            buffer_threshold_bytes,
+            max_url_len,
+            forward_headers,
+            filename_mode,
+            content_type_overrides,
+            allowed_content_types,
+            retry_after_max_secs,
+        }
+    }
+
+    /// 判断是否允许下载该类型的内容（只比较类型本身，忽略 `charset` 等参数）
+    pub fn is_content_type_allowed(&self, content_type: &mime::Mime) -> bool {
+        let essence = content_type.essence_str().to_lowercase();
+        self.allowed_content_types.is_empty()
+            || self
+                .allowed_content_types
+                .iter()
+                .any(|pattern| glob_match(pattern, &essence))
+    }
+
+    /// 确定下载响应的 Content-Type
+    ///
+    /// 文件扩展名在 `content_type_overrides` 中时使用配置的类型（多段扩展名如 `tar.zst` 优先匹配最长的），
+    /// 否则与 [`resolve_content_type`] 相同。
+    pub fn resolve_content_type(&self, upstream: Option<&str>, filename: &str) -> mime::Mime {
+        let lowercase = filename.to_lowercase();
+        self.content_type_overrides
+            .iter()
+            .filter(|(ext, _)| {
+                lowercase
+                    .strip_suffix(ext.as_str())
+                    .is_some_and(|stem| stem.len() > 1 && stem.ends_with('.'))
+            })
+            .max_by_key(|(ext, _)| ext.len())
+            .map(|(_, content_type)| content_type.clone())
+            .unwrap_or_else(|| resolve_content_type(upstream, filename))
+    }
+
+    /// 下载响应 Content-Disposition 中使用的文件名
+    ///
+    /// `filename` 为记录在缓存元数据中的文件名（URL 路径中的文件名，`upstream` 模式下为上游提供的文件名），
+    /// `cache_filename` 为磁盘上的缓存文件名。
+    pub fn served_filename<'a>(&self, filename: &'a str, cache_filename: &'a str) -> &'a str {
+        match self.filename_mode {
+            DownloadFilenameMode::Hashed => cache_filename,
+            DownloadFilenameMode::Original | DownloadFilenameMode::Upstream => filename,
+        }
+    }
+
+    /// 下载请求中需要转发给上游的请求头（名称, 值），按 `forward_headers` 的顺序，同名请求头保留所有值
+    pub fn forwarded_headers(
+        &self,
+        headers: &actix_web::http::header::HeaderMap,
+    ) -> Vec<(String, Vec<u8>)> {
+        self.forward_headers
+            .iter()
+            .flat_map(|name| {
+                headers
+                    .get_all(name.as_str())
+                    .map(move |value| (name.clone(), value.as_bytes().to_vec()))
+            })
+            .collect()
+    }
+
+    /// 上游声明的文件大小是否适合一次性读入内存（大小未知时始终流式下载）
+    pub fn should_buffer(&self, content_length: Option<u64>) -> bool {
+        self.buffer_threshold_bytes > 0
+            && content_length.is_some_and(|len| len <= self.buffer_threshold_bytes)
+    }
+
+    /// 上游要求等待的秒数不超过 `retry_after_max_secs` 时返回等待时间（等待后重试一次），否则返回 None
+    pub fn retry_wait(&self, retry_after_secs: u64) -> Option<Duration> {
+        (self.retry_after_max_secs > 0 && retry_after_secs <= self.retry_after_max_secs)
+            .then(|| Duration::from_secs(retry_after_secs))
+    }
+
+    /// 客户端空闲超时，未启用时返回 None
+    pub fn client_idle_timeout(&self) -> Option<Duration> {
+        if self.client_idle_timeout_secs == 0 {
+            None
+        } else {
+            Some(Duration::from_secs(self.client_idle_timeout_secs))
+        }
+    }
+
+    /// 检查下载 URL：长度不超过 `max_url_len`，且是带主机名的 http/https 地址
+    ///
+    /// 在处理请求的最开始调用，不访问缓存和网络。
+    pub fn validate_download_url(&self, url: &str) -> Result<(), DownloadUrlError> {
+        if url.len() > self.max_url_len {
+            return Err(DownloadUrlError::TooLong {
+                len: url.len(),
+                max: self.max_url_len,
+            });
+        }
+        let parsed = reqwest::Url::parse(url).map_err(|_| DownloadUrlError::Invalid)?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(DownloadUrlError::UnsupportedScheme(parsed.scheme().to_string()));
+        }
+        if parsed.host_str().is_none_or(str::is_empty) {
+            return Err(DownloadUrlError::Invalid);
+        }
+        Ok(())
+    }
+
+    /// 判断 URL 指向的内容是否不可变
+    ///
+    /// 主机在 `immutable_hosts` 中，或 `主机/路径` 匹配 `immutable_url_patterns` 中任意模式时视为不可变。
+    /// 不可变内容的缓存命中时跳过 ETag 重新验证，缓存文件也不受 TTL 限制。
+    pub fn is_immutable_url(&self, url: &str) -> bool {
+        let Ok(url) = reqwest::Url::parse(url) else {
+            return false;
+        };
+        let Some(host) = url.host_str().map(|h| h.to_lowercase()) else {
+            return false;
+        };
+        if self.immutable_hosts.contains(&host) {
+            return true;
+        }
+        let host_path = format!("{}{}", host, url.path());
+        self.immutable_url_patterns
+            .iter()
+            .any(|pattern| glob_match(pattern, &host_path))
+    }
+}
+
+/// 下载 URL 校验错误
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum DownloadUrlError {
+    #[error("url 参数过长（{len} 字节），最多允许 {max} 字节")]
+    TooLong { len: usize, max: usize },
+    #[error("url 参数不是有效的 URL")]
+    Invalid,
+    #[error("不支持的 URL 协议: {0}（只支持 http 和 https）")]
+    UnsupportedScheme(String),
+}
+
+impl From<DownloadUrlError> for AppError {
+    fn from(err: DownloadUrlError) -> Self {
+        AppError::BadRequest(err.to_string())
+    }
+}
+
+// 下载流的共享状态（下载流与空闲检测任务共享）
+struct DownloadStreamState {
+    client_ip: String,
+    // 下载许可，空闲超时时由检测任务提前释放
+    permit: Mutex<Option<OwnedSemaphorePermit>>,
+    last_activity: Mutex<Instant>,
+    // 客户端正在读取、下载流在等待上游数据，这段时间不计入空闲时间
+    awaiting_upstream: AtomicBool,
+    bytes_sent: AtomicU64,
+    aborted: AtomicBool,
+    span: DownloadSpan,
+}
+
+impl Drop for DownloadStreamState {
+    // 下载流结束（完成、出错或客户端断开）时记录实际发送的字节数
+    fn drop(&mut self) {
+        self.span.record_bytes(self.bytes_sent.load(Ordering::SeqCst));
+    }
+}
+
+/// 包装下载流：在整个下载期间持有下载许可，并在客户端空闲超时时中止下载
+///
+/// 客户端停止读取时 actix 不会再轮询响应流，因此由独立的检测任务负责在超时后释放许可，
+/// 流在下一次被轮询时返回错误以结束响应。空闲时间只统计客户端没有读取的时间，
+/// 上游响应缓慢时不会触发超时。
+pub fn guard_download_stream<S>(
+    stream: S,
+    permit: OwnedSemaphorePermit,
+    idle_timeout: Option<Duration>,
+    client_ip: String,
+) -> impl Stream<Item = Result<Bytes, AppError>>
+where
+    S: Stream<Item = Result<Bytes, AppError>> + 'static,
+{
+    let state = Arc::new(DownloadStreamState {
+        span: DownloadSpan::new(&client_ip),
+        client_ip,
+        permit: Mutex::new(Some(permit)),
+        last_activity: Mutex::new(Instant::now()),
+        awaiting_upstream: AtomicBool::new(false),
+        bytes_sent: AtomicU64::new(0),
+        aborted: AtomicBool::new(false),
+    });
+
+    if let Some(timeout) = idle_timeout {
+        // 检测任务只持有弱引用，下载流结束（被丢弃）后自动退出
+        let weak_state = Arc::downgrade(&state);
+        let check_interval = timeout.min(Duration::from_secs(1));
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(check_interval).await;
+                let Some(state) = weak_state.upgrade() else {
+                    break;
+                };
+                if state.awaiting_upstream.load(Ordering::SeqCst) {
+                    continue;
+                }
+                let idle = state.last_activity.lock().unwrap().elapsed();
+                if idle >= timeout {
+                    state.aborted.store(true, Ordering::SeqCst);
+                    state.permit.lock().unwrap().take();
+                    log::warn!(
+                        "客户端空闲超过 {} 秒，已中止下载 (IP: {}, 已发送 {} 字节)",
+                        timeout.as_secs(),
+                        state.client_ip,
+                        state.bytes_sent.load(Ordering::SeqCst)
+                    );
+                    break;
+                }
+            }
+        });
+    }
+
+    stream::unfold(Some((Box::pin(stream), state)), |current| async move {
+        let (mut stream, state) = current?;
+        if state.aborted.load(Ordering::SeqCst) {
+            return Some((
+                Err(AppError::ApiError("客户端空闲超时，下载已中止".to_string())),
+                None,
+            ));
+        }
+        state.awaiting_upstream.store(true, Ordering::SeqCst);
+        let next = stream.next().await;
+        // 先更新活动时间再清除标记，避免检测任务把等待上游的时间算作空闲
+        *state.last_activity.lock().unwrap() = Instant::now();
+        state.awaiting_upstream.store(false, Ordering::SeqCst);
+        match next {
+            Some(Ok(bytes)) => {
+                state.bytes_sent.fetch_add(bytes.len() as u64, Ordering::SeqCst);
+                Some((Ok(bytes), Some((stream, state))))
+            }
+            Some(Err(e)) => Some((Err(e), None)),
+            None => None,
+        }
+    })
+}
+
+// 根据文件扩展名推测 MIME 类型（只包含适合在浏览器中预览的常见类型）
+fn guess_mime_from_filename(filename: &str) -> Option<mime::Mime> {
+    let extension = std::path::Path::new(filename)
+        .extension()?
+        .to_str()?
+        .to_lowercase();
+    let mime = match extension.as_str() {
+        "png" => mime::IMAGE_PNG,
+        "jpg" | "jpeg" => mime::IMAGE_JPEG,
+        "gif" => mime::IMAGE_GIF,
+        "bmp" => mime::IMAGE_BMP,
+        "webp" => "image/webp".parse().ok()?,
+        "txt" | "log" => mime::TEXT_PLAIN_UTF_8,
+        "md" => "text/markdown; charset=utf-8".parse().ok()?,
+        "csv" => mime::TEXT_CSV_UTF_8,
+        "json" => mime::APPLICATION_JSON,
+        "pdf" => mime::APPLICATION_PDF,
+        _ => return None,
+    };
+    Some(mime)
+}
+
+/// 确定下载响应的 Content-Type
+///
+/// 优先使用上游返回的类型；上游缺失或只返回 `application/octet-stream`（GitHub release 附件的常见情况）时，
+/// 根据文件扩展名推测。
+pub fn resolve_content_type(upstream: Option<&str>, filename: &str) -> mime::Mime {
+    let upstream = upstream.and_then(|ct| ct.parse::<mime::Mime>().ok());
+    match upstream {
+        Some(ct) if ct != mime::APPLICATION_OCTET_STREAM => ct,
+        _ => guess_mime_from_filename(filename).unwrap_or(mime::APPLICATION_OCTET_STREAM),
+    }
+}
+
+/// 根据 Content-Type 选择默认的 Content-Disposition 类型
+///
+/// 图片、文本、PDF 和 JSON 默认 `inline`（可在浏览器中直接预览），其余类型默认 `attachment`。
+/// HTML 和 SVG 可能包含脚本，为避免在本服务的域名下执行，仍然作为附件下载。
+pub fn default_disposition(content_type: &mime::Mime) -> &'static str {
+    let essence = content_type.essence_str();
+    if essence == "text/html" || essence == "image/svg+xml" {
+        return "attachment";
+    }
+    match content_type.type_() {
+        mime::IMAGE | mime::TEXT => "inline",
+        _ if essence == "application/pdf" || essence == "application/json" => "inline",
+        _ => "attachment",
+    }
+}
+
+/// 生成下载响应的 Content-Disposition 头
+pub fn content_disposition(content_type: &mime::Mime, filename: &str) -> String {
+    format!("{}; filename=\"{}\"", default_disposition(content_type), filename)
+}
+
+/// 从上游响应的 Content-Disposition 头中解析文件名
+///
+/// 只使用 `filename` 参数（不解析 RFC 5987 的 `filename*`），去掉路径部分和引号，
+/// 避免文件名中的 `/` 或 `"` 影响客户端保存位置或破坏响应头。
+pub fn filename_from_content_disposition(value: &str) -> Option<String> {
+    let filename = value.split(';').find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("filename")
+            .then(|| value.trim().trim_matches('"'))
+    })?;
+    let filename: String = filename
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| *c != '"' && !c.is_control())
+        .collect();
+    (!filename.is_empty()).then_some(filename)
+}
+
+/// 判断内容是否适合预压缩
+///
+/// 只压缩文本类内容（文本、JSON、XML、脚本、SVG 等）；压缩包、图片、音视频等本身已经压缩的类型
+/// 再压缩几乎没有收益，未知的二进制类型（`application/octet-stream`）同样跳过。
+pub fn is_precompressible(content_type: &mime::Mime) -> bool {
+    if content_type.type_() == mime::TEXT {
+        return true;
+    }
+    if content_type.suffix().is_some_and(|s| s == mime::JSON || s == mime::XML) {
+        return true;
+    }
+    matches!(
+        content_type.essence_str(),
+        "application/json"
+            | "application/javascript"
+            | "application/xml"
+            | "application/x-yaml"
+            | "application/yaml"
+            | "application/toml"
+            | "application/x-sh"
+            | "application/sql"
+            | "application/wasm"
+    )
+}
+
+/// 判断请求路径是否为文件下载端点（`/download` 或 `/repos/{owner}/{repo}/releases/latest/assets/{name}`）
+///
+/// 下载端点使用单独的限流和处理超时。
+pub fn is_download_path(path: &str) -> bool {
+    if path == "/download" {
+        return true;
+    }
+    let segments: Vec<&str> = path.split('/').collect();
+    matches!(
+        segments.as_slice(),
+        ["", "repos", owner, repo, "releases", "latest", "assets", name]
+            if !owner.is_empty() && !repo.is_empty() && !name.is_empty()
+    )
+}
+
+/// 判断请求的 `Accept-Encoding` 头是否接受 gzip（`q=0` 表示明确拒绝）
+pub fn accepts_gzip(accept_encoding: &str) -> bool {
+    accept_encoding.split(',').any(|item| {
+        let mut parts = item.split(';');
+        let coding = parts.next().unwrap_or("").trim();
+        let rejected = parts.any(|param| {
+            param
+                .trim()
+                .strip_prefix("q=")
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .is_some_and(|q| q == 0.0)
+        });
+        !rejected
+            && (coding.eq_ignore_ascii_case("gzip")
+                || coding.eq_ignore_ascii_case("x-gzip")
+                || coding == "*")
+    })
+}
+
+/// 判断请求的 `Cache-Control` 头是否包含 `no-cache` 指令（要求跳过缓存重新下载）
+pub fn has_no_cache_directive(cache_control: &str) -> bool {
+    cache_control
+        .split(',')
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-cache"))
+}
+
+/// 从 `Content-Range` 响应头（如 `bytes 0-1023/4096`）中解析文件总大小，总大小未知（`*`）时返回 None
+pub fn content_range_total(content_range: &str) -> Option<u64> {
+    let (unit, range) = content_range.trim().split_once(' ')?;
+    if !unit.eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+    range.split_once('/')?.1.trim().parse().ok()
+}
+
+/// 从 GitHub 托管的下载地址中解析仓库（owner, repo），用于在附加 token 之前检查仓库是否可见。
+///
+/// 支持 `github.com/{owner}/{repo}/...`、`codeload.github.com/{owner}/{repo}/...`、
+/// `raw.githubusercontent.com/{owner}/{repo}/...` 和 `api.github.com/repos/{owner}/{repo}/...`；
+/// 其他主机（包括 `objects.githubusercontent.com` 等不含仓库信息的签名地址）返回 None
+pub fn github_repo_from_url(url: &str) -> Option<(String, String)> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let host = url.host_str()?.to_ascii_lowercase();
+    let mut segments = url.path_segments()?.filter(|s| !s.is_empty());
+    match host.as_str() {
+        "github.com" | "www.github.com" | "codeload.github.com" | "raw.githubusercontent.com" => {}
+        "api.github.com" => {
+            if segments.next()? != "repos" {
+                return None;
+            }
+        }
+        _ => return None,
+    }
+    let owner = segments.next()?;
+    let repo = segments.next()?;
+    Some((owner.to_string(), repo.to_string()))
+}
+
+// 上游 429 未给出 Retry-After 时建议的等待秒数（GitHub 建议至少等待一分钟）
+const DEFAULT_UPSTREAM_RETRY_AFTER_SECS: u64 = 60;
+
+/// 下载主机触发限流时要求等待的秒数：429，或带 `Retry-After` 的 403（GitHub 的滥用检测）。
+/// `Retry-After` 可以是秒数或 HTTP 日期；不带 `Retry-After` 的 403 是普通的拒绝访问，返回 None
+pub fn upstream_retry_after(status: u16, retry_after: Option<&str>) -> Option<u64> {
+    let secs = retry_after.and_then(|value| {
+        let value = value.trim();
+        value.parse().ok().or_else(|| {
+            let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            Some((date.timestamp() - chrono::Utc::now().timestamp()).max(0) as u64)
+        })
+    });
+    match status {
+        429 => Some(secs.unwrap_or(DEFAULT_UPSTREAM_RETRY_AFTER_SECS)),
+        403 => secs,
+        _ => None,
+    }
+}
+
+// 通配符匹配：`*` 匹配任意数量的字符，`?` 匹配单个字符（区分大小写）
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // 最近一个 `*` 的位置及其匹配到的 name 位置，用于回溯
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+static DOWNLOAD_CONFIG: OnceLock<DownloadConfig> = OnceLock::new();
+
+/// 获取全局下载配置
+pub fn get_download_config() -> &'static DownloadConfig {
+    DOWNLOAD_CONFIG.get_or_init(DownloadConfig::from_env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_immutable_url() {
+        let config = DownloadConfig::default();
+        assert!(config.is_immutable_url(
+            "https://objects.githubusercontent.com/github-production-release-asset/file.zip"
+        ));
+        assert!(!config.is_immutable_url(
+            "https://raw.githubusercontent.com/octocat/Hello-World/master/README"
+        ));
+        assert!(!config.is_immutable_url("not a url"));
+    }
+
+    #[tokio::test]
+    async fn test_guard_download_stream_releases_permit_when_idle() {
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(1));
+        let permit = semaphore.clone().acquire_owned().await.unwrap();
+
+        let source = stream::iter(vec![
+            Ok(Bytes::from_static(b"a")),
+            Ok(Bytes::from_static(b"b")),
+        ]);
+        let mut guarded = Box::pin(guard_download_stream(
+            source,
+            permit,
+            Some(Duration::from_millis(100)),
+            "127.0.0.1".to_string(),
+        ));
+
+        assert!(matches!(guarded.next().await, Some(Ok(_))));
+        assert_eq!(semaphore.available_permits(), 0);
+
+        // 客户端停止读取，超时后许可被释放，下一次读取返回错误
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(semaphore.available_permits(), 1);
+        assert!(matches!(guarded.next().await, Some(Err(_))));
+        assert!(guarded.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_guard_download_stream_ignores_slow_upstream() {
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(1));
+        let permit = semaphore.clone().acquire_owned().await.unwrap();
+
+        // 上游在两个数据块之间停顿的时间超过空闲超时，客户端一直在读取
+        let source = stream::iter([0, 300]).then(|delay| async move {
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            Ok(Bytes::from_static(b"a"))
+        });
+        let guarded = guard_download_stream(
+            source,
+            permit,
+            Some(Duration::from_millis(100)),
+            "127.0.0.1".to_string(),
+        );
+        let items: Vec<_> = guarded.collect().await;
+
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|item| item.is_ok()));
+        assert_eq!(semaphore.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_guard_download_stream_releases_permit_on_completion() {
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(1));
+        let permit = semaphore.clone().acquire_owned().await.unwrap();
+
+        let source = stream::iter(vec![Ok(Bytes::from_static(b"a"))]);
+        let guarded = guard_download_stream(source, permit, None, "127.0.0.1".to_string());
+        let items: Vec<_> = guarded.collect().await;
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(semaphore.available_permits(), 1);
+    }
+
+    #[test]
+    fn test_content_disposition_png_is_inline() {
+        let content_type = resolve_content_type(Some("image/png"), "screenshot.png");
+        assert_eq!(content_type, mime::IMAGE_PNG);
+        assert_eq!(
+            content_disposition(&content_type, "screenshot.png"),
+            "inline; filename=\"screenshot.png\""
+        );
+
+        // 上游只返回 octet-stream 时根据扩展名推测
+        let content_type = resolve_content_type(Some("application/octet-stream"), "icon.PNG");
+        assert_eq!(content_type, mime::IMAGE_PNG);
+        assert_eq!(default_disposition(&content_type), "inline");
+    }
+
+    #[test]
+    fn test_content_disposition_tar_gz_is_attachment() {
+        let content_type = resolve_content_type(Some("application/octet-stream"), "app-linux.tar.gz");
+        assert_eq!(content_type, mime::APPLICATION_OCTET_STREAM);
+        assert_eq!(
+            content_disposition(&content_type, "app-linux.tar.gz"),
+            "attachment; filename=\"app-linux.tar.gz\""
+        );
+
+        let content_type = resolve_content_type(Some("application/gzip"), "app-linux.tar.gz");
+        assert_eq!(default_disposition(&content_type), "attachment");
+    }
+
+    #[test]
+    fn test_filename_from_content_disposition() {
+        assert_eq!(
+            filename_from_content_disposition("attachment; filename=app-linux.tar.gz").as_deref(),
+            Some("app-linux.tar.gz")
+        );
+        assert_eq!(
+            filename_from_content_disposition("attachment; FILENAME=\"My App.zip\"").as_deref(),
+            Some("My App.zip")
+        );
+        assert_eq!(
+            filename_from_content_disposition("attachment; filename=\"../../etc/passwd\"").as_deref(),
+            Some("passwd")
+        );
+        assert_eq!(filename_from_content_disposition("attachment"), None);
+        assert_eq!(filename_from_content_disposition("attachment; filename=\"\""), None);
+    }
+
+    #[test]
+    fn test_served_filename() {
+        let hash = "0f1e2d.zip";
+        assert_eq!(DownloadConfig::default().served_filename("app.zip", hash), "app.zip");
+        for (value, expected) in [("original", "app.zip"), ("HASHED", hash), ("upstream", "app.zip")] {
+            let config = DownloadConfig {
+                filename_mode: DownloadFilenameMode::parse(value).unwrap(),
+                ..Default::default()
+            };
+            assert_eq!(config.served_filename("app.zip", hash), expected);
+        }
+        assert_eq!(DownloadFilenameMode::parse("header"), None);
+    }
+
+    #[test]
+    fn test_content_type_overrides() {
+        let config = DownloadConfig {
+            content_type_overrides: parse_content_type_overrides(
+                "AppImage:application/x-appimage, .dmg:application/x-apple-diskimage,tar.zst:application/zstd,zst:application/x-zstd,bad,exe:",
+            ),
+            ..Default::default()
+        };
+        assert_eq!(config.content_type_overrides.len(), 4);
+
+        // 覆盖上游返回的类型，扩展名不区分大小写
+        assert_eq!(
+            config.resolve_content_type(Some("application/octet-stream"), "Tool-x86_64.AppImage").essence_str(),
+            "application/x-appimage"
+        );
+        assert_eq!(
+            config.resolve_content_type(Some("application/zip"), "app.dmg").essence_str(),
+            "application/x-apple-diskimage"
+        );
+        // 多段扩展名优先匹配最长的
+        assert_eq!(config.resolve_content_type(None, "app.tar.zst").essence_str(), "application/zstd");
+        assert_eq!(config.resolve_content_type(None, "app.zst").essence_str(), "application/x-zstd");
+        // 扩展名必须完整匹配，文件名本身不算扩展名
+        assert_eq!(config.resolve_content_type(None, "dmg"), mime::APPLICATION_OCTET_STREAM);
+        assert_eq!(config.resolve_content_type(None, "appdmg"), mime::APPLICATION_OCTET_STREAM);
+        // 未配置的扩展名行为不变
+        assert_eq!(config.resolve_content_type(Some("application/octet-stream"), "icon.png"), mime::IMAGE_PNG);
+    }
+
+    #[test]
+    fn test_is_content_type_allowed() {
+        let zip: mime::Mime = "application/zip".parse().unwrap();
+        assert!(DownloadConfig::default().is_content_type_allowed(&mime::TEXT_HTML));
+
+        let config = DownloadConfig {
+            allowed_content_types: vec!["application/zip".to_string(), "image/*".to_string()],
+            ..Default::default()
+        };
+        assert!(config.is_content_type_allowed(&zip));
+        assert!(config.is_content_type_allowed(&mime::IMAGE_PNG));
+        assert!(!config.is_content_type_allowed(&mime::TEXT_HTML));
+        assert!(!config.is_content_type_allowed(&mime::APPLICATION_OCTET_STREAM));
+    }
+
+    #[test]
+    fn test_default_disposition() {
+        assert_eq!(default_disposition(&mime::TEXT_PLAIN), "inline");
+        assert_eq!(default_disposition(&mime::APPLICATION_PDF), "inline");
+        assert_eq!(default_disposition(&mime::APPLICATION_JSON), "inline");
+        assert_eq!(default_disposition(&mime::TEXT_HTML), "attachment");
+        assert_eq!(default_disposition(&mime::IMAGE_SVG), "attachment");
+        assert_eq!(default_disposition(&mime::APPLICATION_OCTET_STREAM), "attachment");
+    }
+
+    #[test]
+    fn test_is_download_path() {
+        assert!(is_download_path("/download"));
+        assert!(is_download_path("/repos/owner/repo/releases/latest/assets/app.zip"));
+        assert!(!is_download_path("/repos/owner/repo/releases/latest/assets/"));
+        assert!(!is_download_path("/repos/owner/repo/releases/latest"));
+        assert!(!is_download_path("/repos/owner/repo/assets"));
+    }
+
+    #[test]
+    fn test_has_no_cache_directive() {
+        assert!(has_no_cache_directive("no-cache"));
+        assert!(has_no_cache_directive("max-age=0, No-Cache"));
+        assert!(!has_no_cache_directive("no-store"));
+        assert!(!has_no_cache_directive("max-age=60"));
+    }
+
+    #[test]
+    fn test_is_precompressible() {
+        for content_type in ["text/plain; charset=utf-8", "application/json", "image/svg+xml", "application/vnd.api+json"] {
+            assert!(is_precompressible(&content_type.parse().unwrap()), "{}", content_type);
+        }
+        for content_type in ["application/zip", "application/gzip", "image/png", "video/mp4", "application/octet-stream"] {
+            assert!(!is_precompressible(&content_type.parse().unwrap()), "{}", content_type);
+        }
+    }
+
+    #[test]
+    fn test_accepts_gzip() {
+        assert!(accepts_gzip("gzip"));
+        assert!(accepts_gzip("br, GZIP;q=0.8"));
+        assert!(accepts_gzip("*"));
+        assert!(!accepts_gzip("gzip;q=0, br"));
+        assert!(!accepts_gzip("br, deflate"));
+        assert!(!accepts_gzip(""));
+    }
+
+    #[test]
+    fn test_is_immutable_url_custom_hosts() {
+        let config = DownloadConfig {
+            immutable_hosts: vec!["github.com".to_string()],
+            ..Default::default()
+        };
+        assert!(config.is_immutable_url(
+            "https://GitHub.com/owner/repo/releases/download/v1.0.0/app.zip"
+        ));
+        assert!(!config.is_immutable_url(
+            "https://objects.githubusercontent.com/file.zip"
+        ));
+    }
+
+    #[test]
+    fn test_validate_download_url() {
+        let config = DownloadConfig {
+            max_url_len: 64,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate_download_url("https://github.com/o/r/releases/download/v1/a.zip"),
+            Ok(())
+        );
+
+        let long_url = format!("https://github.com/{}", "a".repeat(64));
+        assert_eq!(
+            config.validate_download_url(&long_url),
+            Err(DownloadUrlError::TooLong {
+                len: long_url.len(),
+                max: 64
+            })
+        );
+        assert_eq!(
+            config.validate_download_url("file:///etc/passwd"),
+            Err(DownloadUrlError::UnsupportedScheme("file".to_string()))
+        );
+        assert_eq!(
+            config.validate_download_url("not a url"),
+            Err(DownloadUrlError::Invalid)
+        );
+    }
+
+    #[test]
+    fn test_parse_forward_headers() {
+        let names = parse_forward_headers(
+            "Referer, Range, Authorization, Connection, Cookie, bad name, referer, X-Cdn-Token, If-Modified-Since, If-Range,",
+        );
+        assert_eq!(names, vec!["referer", "range", "x-cdn-token"]);
+    }
+
+    #[test]
+    fn test_forwarded_headers() {
+        let config = DownloadConfig {
+            forward_headers: vec!["referer".to_string(), "range".to_string()],
+            ..Default::default()
+        };
+        let req = actix_web::test::TestRequest::default()
+            .insert_header(("Referer", "https://example.com/page"))
+            .insert_header(("Authorization", "Bearer secret"))
+            .insert_header(("Cookie", "session=1"))
+            .insert_header(("X-Other", "1"))
+            .to_http_request();
+
+        // 只转发列出的请求头，请求中没有的（Range）不转发
+        let forwarded = config.forwarded_headers(req.headers());
+        assert_eq!(
+            forwarded,
+            vec![("referer".to_string(), b"https://example.com/page".to_vec())]
+        );
+
+        // 默认不转发任何请求头
+        assert!(DownloadConfig::default()
+            .forwarded_headers(req.headers())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_should_buffer() {
+        let config = DownloadConfig {
+            buffer_threshold_bytes: 1024,
+            ..Default::default()
+        };
+        assert!(config.should_buffer(Some(1024)));
+        assert!(!config.should_buffer(Some(1025)));
+        // 大小未知时流式下载
+        assert!(!config.should_buffer(None));
+
+        let disabled = DownloadConfig {
+            buffer_threshold_bytes: 0,
+            ..Default::default()
+        };
+        assert!(!disabled.should_buffer(Some(1)));
+    }
+
+    #[test]
+    fn test_content_range_total() {
+        assert_eq!(content_range_total("bytes 0-1023/4096"), Some(4096));
+        assert_eq!(content_range_total("bytes */4096"), Some(4096));
+        assert_eq!(content_range_total("bytes 0-1023/*"), None);
+        assert_eq!(content_range_total("items 0-1/2"), None);
+        assert_eq!(content_range_total("garbage"), None);
+    }
+
+    #[test]
+    fn test_github_repo_from_url() {
+        let repo = |owner: &str, name: &str| Some((owner.to_string(), name.to_string()));
+        assert_eq!(
+            github_repo_from_url("https://github.com/o/r/releases/download/v1/app.zip"),
+            repo("o", "r")
+        );
+        assert_eq!(
+            github_repo_from_url("https://raw.githubusercontent.com/o/private/main/secret.txt"),
+            repo("o", "private")
+        );
+        assert_eq!(
+            github_repo_from_url("https://API.github.com/repos/o/r/contents/a.txt"),
+            repo("o", "r")
+        );
+        assert_eq!(github_repo_from_url("https://codeload.github.com/o/r/zip/main"), repo("o", "r"));
+        assert_eq!(github_repo_from_url("https://api.github.com/users/o"), None);
+        assert_eq!(github_repo_from_url("https://github.com/o"), None);
+        assert_eq!(github_repo_from_url("https://objects.githubusercontent.com/abc?sig=1"), None);
+        assert_eq!(github_repo_from_url("https://example.com/o/r/file"), None);
+    }
+
+    #[test]
+    fn test_upstream_retry_after() {
+        assert_eq!(upstream_retry_after(429, Some("5")), Some(5));
+        assert_eq!(upstream_retry_after(429, None), Some(60));
+        assert_eq!(upstream_retry_after(403, Some(" 30 ")), Some(30));
+        assert_eq!(upstream_retry_after(403, Some("Wed, 21 Oct 2015 07:28:00 GMT")), Some(0));
+        // 没有 Retry-After 的 403 是普通的拒绝访问
+        assert_eq!(upstream_retry_after(403, None), None);
+        assert_eq!(upstream_retry_after(503, Some("5")), None);
+    }
+
+    #[test]
+    fn test_retry_wait() {
+        let config = DownloadConfig::default();
+        assert_eq!(config.retry_wait(3), Some(Duration::from_secs(3)));
+        assert_eq!(config.retry_wait(120), None);
+        let disabled = DownloadConfig {
+            retry_after_max_secs: 0,
+            ..Default::default()
+        };
+        assert_eq!(disabled.retry_wait(0), None);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*linux-x86_64.tar.gz", "app-1.0-linux-x86_64.tar.gz"));
+        assert!(glob_match("app-*-linux-*.tar.gz", "app-1.0-linux-arm64.tar.gz"));
+        assert!(glob_match("app-?.zip", "app-1.zip"));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("exact.txt", "exact.txt"));
+        assert!(!glob_match("*.zip", "app.tar.gz"));
+        assert!(!glob_match("app-?.zip", "app-10.zip"));
+        assert!(!glob_match("*Linux*", "app-linux.tar.gz"));
+    }
+
+    #[test]
+    fn test_is_immutable_url_by_pattern() {
+        let config = DownloadConfig::default();
+        assert!(config.is_immutable_url(
+            "https://github.com/owner/repo/releases/download/v1.0.0/app.zip"
+        ));
+        // latest/download 会随新版本发布指向不同的文件
+        assert!(!config.is_immutable_url(
+            "https://github.com/owner/repo/releases/latest/download/app.zip"
+        ));
+        assert!(!config.is_immutable_url("https://github.com/owner/repo/archive/refs/heads/main.zip"));
+
+        let config = DownloadConfig {
+            immutable_url_patterns: vec!["cdn.example.com/static/*".to_string()],
+            ..Default::default()
+        };
+        assert!(config.is_immutable_url("https://cdn.example.com/static/app.js"));
+        assert!(!config.is_immutable_url("https://cdn.example.com/api/app.js"));
+    }
+}
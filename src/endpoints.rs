@@ -0,0 +1,252 @@
+use crate::handlers::{
+    batch_get_latest_versions, batch_get_repos, batch_get_repos_map, download_attachment,
+    download_latest_release_asset, get_cache_stats, get_compare, get_milestones,
+    get_generated_release_notes, get_latest_release, get_latest_release_badge,
+    get_latest_release_checksums, get_latest_release_notes, get_latest_release_pre,
+    get_latest_release_pre_tauri, get_latest_release_stable, get_latest_release_tauri,
+    get_raw_repo, get_release_assets, get_repo_contents, get_releases, get_repo_info, get_stats, health,
+    health_check, list_cached_files, list_cached_repos, resolve_latest_asset, watch_releases_sse,
+};
+use crate::ApiDoc;
+use actix_web::web;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use utoipa::OpenApi;
+
+/// 可以禁用的端点名称及其包含的路径（与 OpenAPI 文档中的路径一致）
+///
+/// 健康检查（`/`、`/health`）始终启用。
+pub const ENDPOINT_GROUPS: &[(&str, &[&str])] = &[
+    ("stats", &["/stats"]),
+    ("repo", &["/repos/{owner}/{repo}"]),
+    ("raw", &["/repos/{owner}/{repo}/raw"]),
+    ("contents", &["/repos/{owner}/{repo}/contents/{path}"]),
+    ("releases", &["/repos/{owner}/{repo}/releases"]),
+    (
+        "latest",
+        &[
+            "/repos/{owner}/{repo}/releases/latest",
+            "/repos/{owner}/{repo}/releases/latest/pre",
+            "/repos/{owner}/{repo}/releases/latest/stable",
+        ],
+    ),
+    (
+        "tauri",
+        &[
+            "/repos/{owner}/{repo}/releases/latest/tauri",
+            "/repos/{owner}/{repo}/releases/latest/pre/tauri",
+        ],
+    ),
+    (
+        "checksums",
+        &["/repos/{owner}/{repo}/releases/latest/checksums"],
+    ),
+    (
+        "resolve",
+        &["/repos/{owner}/{repo}/releases/latest/resolve"],
+    ),
+    ("badge", &["/repos/{owner}/{repo}/releases/latest/badge.svg"]),
+    ("assets", &["/repos/{owner}/{repo}/assets"]),
+    ("notes", &["/repos/{owner}/{repo}/releases/latest/notes"]),
+    ("generated_notes", &["/repos/{owner}/{repo}/releases/notes"]),
+    ("compare", &["/repos/{owner}/{repo}/compare/{refs}"]),
+    ("milestones", &["/repos/{owner}/{repo}/milestones"]),
+    (
+        "batch",
+        &["/repos/batch", "/repos/batch/map", "/repos/batch/latest"],
+    ),
+    ("watch", &["/repos/watch/sse"]),
+    (
+        "download",
+        &["/download", "/repos/{owner}/{repo}/releases/latest/assets/{name}"],
+    ),
+    ("cache", &["/cache/repos", "/cache/files", "/cache/stats"]),
+];
+
+/// 端点开关配置
+#[derive(Clone, Debug, Default)]
+pub struct EndpointsConfig {
+    /// 禁用的端点名称（见 `ENDPOINT_GROUPS`），禁用的端点不会注册，请求返回 404
+    pub disabled: HashSet<String>,
+}
+
+impl EndpointsConfig {
+    /// 从环境变量加载配置（`DISABLED_ENDPOINTS`，逗号分隔），无法识别的名称会被忽略并输出警告
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+
+        let disabled = std::env::var("DISABLED_ENDPOINTS")
+            .map(|names| Self::parse(&names))
+            .unwrap_or_default();
+        Self { disabled }
+    }
+
+    // 解析端点名称列表，跳过无法识别的名称
+    fn parse(names: &str) -> HashSet<String> {
+        names
+            .split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .filter(|name| {
+                let known = ENDPOINT_GROUPS.iter().any(|(group, _)| group == name);
+                if !known {
+                    log::warn!("DISABLED_ENDPOINTS 中包含无法识别的端点名称: {}", name);
+                }
+                known
+            })
+            .collect()
+    }
+
+    /// 指定名称的端点是否启用
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.contains(name)
+    }
+
+    /// OpenAPI 文档中的路径是否启用（不属于任何端点组的路径始终启用）
+    pub fn is_path_enabled(&self, path: &str) -> bool {
+        ENDPOINT_GROUPS
+            .iter()
+            .filter(|(_, paths)| paths.contains(&path))
+            .all(|(name, _)| self.is_enabled(name))
+    }
+}
+
+static ENDPOINTS_CONFIG: OnceLock<EndpointsConfig> = OnceLock::new();
+
+/// 获取全局端点开关配置
+pub fn get_endpoints_config() -> &'static EndpointsConfig {
+    ENDPOINTS_CONFIG.get_or_init(EndpointsConfig::from_env)
+}
+
+/// 只包含已启用端点的 OpenAPI 文档
+pub fn enabled_openapi() -> utoipa::openapi::OpenApi {
+    let config = get_endpoints_config();
+    let mut openapi = ApiDoc::openapi();
+    openapi
+        .paths
+        .paths
+        .retain(|path, _| config.is_path_enabled(path));
+    openapi
+}
+
+/// 注册所有已启用的端点
+pub fn configure_services(cfg: &mut web::ServiceConfig) {
+    let config = get_endpoints_config();
+    let enabled = |name: &str| config.is_enabled(name);
+
+    cfg.service(health_check).service(health);
+    if enabled("stats") {
+        cfg.service(get_stats);
+    }
+    // 必须在 /repos/{owner}/{repo} 之前注册，否则会被其匹配
+    if enabled("watch") {
+        cfg.service(watch_releases_sse);
+    }
+    if enabled("repo") {
+        cfg.service(get_repo_info);
+    }
+    if enabled("raw") {
+        cfg.service(get_raw_repo);
+    }
+    if enabled("contents") {
+        cfg.service(get_repo_contents);
+    }
+    if enabled("releases") {
+        cfg.service(get_releases);
+    }
+    if enabled("latest") {
+        cfg.service(get_latest_release)
+            .service(get_latest_release_pre)
+            .service(get_latest_release_stable);
+    }
+    if enabled("tauri") {
+        cfg.service(get_latest_release_tauri)
+            .service(get_latest_release_pre_tauri);
+    }
+    if enabled("checksums") {
+        cfg.service(get_latest_release_checksums);
+    }
+    if enabled("resolve") {
+        cfg.service(resolve_latest_asset);
+    }
+    if enabled("badge") {
+        cfg.service(get_latest_release_badge);
+    }
+    if enabled("assets") {
+        cfg.service(get_release_assets);
+    }
+    if enabled("notes") {
+        cfg.service(get_latest_release_notes);
+    }
+    if enabled("generated_notes") {
+        cfg.service(get_generated_release_notes);
+    }
+    if enabled("compare") {
+        cfg.service(get_compare);
+    }
+    if enabled("milestones") {
+        cfg.service(get_milestones);
+    }
+    if enabled("batch") {
+        cfg.service(batch_get_repos)
+            .service(batch_get_repos_map)
+            .service(batch_get_latest_versions);
+    }
+    if enabled("download") {
+        cfg.service(download_attachment)
+            .service(download_latest_release_asset);
+    }
+    if enabled("cache") {
+        cfg.service(list_cached_repos)
+            .service(list_cached_files)
+            .service(get_cache_stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_disabled_endpoints() {
+        let disabled = EndpointsConfig::parse(" Download, batch ,unknown,,");
+        assert_eq!(disabled.len(), 2);
+        assert!(disabled.contains("download"));
+        assert!(disabled.contains("batch"));
+    }
+
+    #[test]
+    fn test_is_path_enabled() {
+        let config = EndpointsConfig {
+            disabled: EndpointsConfig::parse("download,batch"),
+        };
+        assert!(!config.is_path_enabled("/download"));
+        assert!(!config.is_path_enabled("/repos/batch/map"));
+        assert!(config.is_path_enabled("/repos/{owner}/{repo}"));
+        assert!(config.is_path_enabled("/health"));
+    }
+
+    #[test]
+    fn test_endpoint_groups_match_openapi_paths() {
+        // 每个端点组中的路径都必须存在于 OpenAPI 文档中，新增端点时也需要加入某个端点组
+        let openapi = ApiDoc::openapi();
+        let grouped: Vec<&str> = ENDPOINT_GROUPS
+            .iter()
+            .flat_map(|(_, paths)| paths.iter().copied())
+            .collect();
+        for path in &grouped {
+            assert!(
+                openapi.paths.paths.contains_key(*path),
+                "{} 不在 OpenAPI 文档中",
+                path
+            );
+        }
+        for path in openapi.paths.paths.keys() {
+            assert!(
+                grouped.contains(&path.as_str()) || path == "/" || path == "/health",
+                "{} 不属于任何端点组",
+                path
+            );
+        }
+    }
+}
@@ -8,10 +8,87 @@ pub enum AppError {
     EnvVar(#[from] std::env::VarError),
     #[error("GitHub API 返回错误: {0}")]
     ApiError(String),
+    #[error("GitHub API 返回状态码: {0}")]
+    UpstreamStatus(reqwest::StatusCode),
     #[error("数据未找到")]
     NotFound,
+    #[error("仓库没有 release")]
+    NoReleases,
+    #[error("仓库没有正式版 release")]
+    NoStableRelease,
+    #[error("仓库没有 {0} 渠道的 release")]
+    NoChannelRelease(String),
+    #[error("最新 release 中没有名为 {0} 的附件")]
+    AssetNotFound(String),
+    #[error("文件 {path} 大小为 {size} 字节，超过 GitHub contents API 的 1MB 上限")]
+    FileTooLarge { path: String, size: u64 },
     #[error("请求参数错误: {0}")]
     BadRequest(String),
+    #[error("未授权: {0}")]
+    Unauthorized(String),
+    #[error("禁止访问: {0}")]
+    Forbidden(String),
+    #[error("不支持的内容类型: {0}")]
+    UnsupportedMediaType(String),
+    #[error("请求处理超时（{0} 秒）")]
+    Timeout(u64),
+    #[error("{message}")]
+    ServiceUnavailable { message: String, retry_after_secs: u64 },
+    #[error("上游限流，请在 {retry_after_secs} 秒后重试")]
+    UpstreamRateLimited { retry_after_secs: u64 },
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl AppError {
+    /// 复制错误（用于在多个等待者之间共享同一次请求的结果）
+    ///
+    /// 无法直接克隆的错误（如 reqwest 错误）会转换为保留原始错误信息的 `Internal`。
+    pub fn duplicate(&self) -> AppError {
+        match self {
+            AppError::NotFound => AppError::NotFound,
+            AppError::NoReleases => AppError::NoReleases,
+            AppError::NoStableRelease => AppError::NoStableRelease,
+            AppError::NoChannelRelease(channel) => AppError::NoChannelRelease(channel.clone()),
+            AppError::AssetNotFound(name) => AppError::AssetNotFound(name.clone()),
+            AppError::FileTooLarge { path, size } => AppError::FileTooLarge {
+                path: path.clone(),
+                size: *size,
+            },
+            AppError::ApiError(msg) => AppError::ApiError(msg.clone()),
+            AppError::BadRequest(msg) => AppError::BadRequest(msg.clone()),
+            AppError::Unauthorized(msg) => AppError::Unauthorized(msg.clone()),
+            AppError::Forbidden(msg) => AppError::Forbidden(msg.clone()),
+            AppError::UnsupportedMediaType(msg) => AppError::UnsupportedMediaType(msg.clone()),
+            AppError::Timeout(secs) => AppError::Timeout(*secs),
+            AppError::UpstreamStatus(status) => AppError::UpstreamStatus(*status),
+            AppError::ServiceUnavailable { message, retry_after_secs } => AppError::ServiceUnavailable {
+                message: message.clone(),
+                retry_after_secs: *retry_after_secs,
+            },
+            AppError::UpstreamRateLimited { retry_after_secs } => AppError::UpstreamRateLimited {
+                retry_after_secs: *retry_after_secs,
+            },
+            other => AppError::Internal(other.to_string()),
+        }
+    }
+
+    /// 是否为上游的临时性错误（网络故障、超时、GitHub 返回 5xx 或 429）
+    ///
+    /// 这类错误时可以退回使用已过期但仍在 `CACHE_OUTAGE_GRACE_SECS` 内的缓存；
+    /// 仓库不存在、认证失败（401）、参数错误（422）等确定性结果不属于此类。
+    pub fn is_transient(&self) -> bool {
+        match self {
+            AppError::Reqwest(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            AppError::UpstreamStatus(status) => {
+                status.is_server_error() || *status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            AppError::Timeout(_)
+            | AppError::ServiceUnavailable { .. }
+            | AppError::UpstreamRateLimited { .. } => true,
+            _ => false,
+        }
+    }
 }
 
 impl ResponseError for AppError {
@@ -20,6 +97,34 @@ impl ResponseError for AppError {
             AppError::NotFound => HttpResponse::NotFound().json(serde_json::json!({
                 "error": self.to_string()
             })),
+            // 仓库存在但没有 release：同样返回 404，通过 code 与仓库不存在区分
+            AppError::NoReleases => HttpResponse::NotFound().json(serde_json::json!({
+                "error": self.to_string(),
+                "code": "no_releases"
+            })),
+            // 仓库只有 pre-release 或草稿：同样返回 404，通过 code 区分
+            AppError::NoStableRelease => HttpResponse::NotFound().json(serde_json::json!({
+                "error": self.to_string(),
+                "code": "no_stable_release"
+            })),
+            AppError::NoChannelRelease(channel) => HttpResponse::NotFound().json(serde_json::json!({
+                "error": self.to_string(),
+                "code": "no_channel_release",
+                "channel": channel
+            })),
+            // release 存在但没有指定名称的附件：同样返回 404，通过 code 与没有 release 区分
+            AppError::AssetNotFound(name) => HttpResponse::NotFound().json(serde_json::json!({
+                "error": self.to_string(),
+                "code": "asset_not_found",
+                "asset": name
+            })),
+            // 文件存在但超过 contents API 的大小上限，GitHub 不返回其内容
+            AppError::FileTooLarge { path, size } => HttpResponse::UnprocessableEntity().json(serde_json::json!({
+                "error": self.to_string(),
+                "code": "file_too_large",
+                "path": path,
+                "size": size
+            })),
             AppError::BadRequest(msg) => {
                 // 检查是否是限流错误（包含"请求过于频繁"或"并发下载数已达上限"）
                 if msg.contains("请求过于频繁") || msg.contains("并发下载数已达上限") {
@@ -33,11 +138,44 @@ impl ResponseError for AppError {
                     }))
                 }
             }
+            AppError::Unauthorized(_) => HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": self.to_string()
+            })),
+            AppError::Forbidden(_) => HttpResponse::Forbidden().json(serde_json::json!({
+                "error": self.to_string()
+            })),
+            AppError::UnsupportedMediaType(_) => HttpResponse::UnsupportedMediaType().json(serde_json::json!({
+                "error": self.to_string(),
+                "code": "unsupported_media_type"
+            })),
+            AppError::ServiceUnavailable { message, retry_after_secs } => {
+                HttpResponse::ServiceUnavailable()
+                    .insert_header(("Retry-After", retry_after_secs.to_string()))
+                    .json(serde_json::json!({
+                        "error": message,
+                        "retry_after": retry_after_secs
+                    }))
+            }
+            // 上游（下载主机）限流且要求等待的时间超过 DOWNLOAD_RETRY_AFTER_MAX_SECS
+            AppError::UpstreamRateLimited { retry_after_secs } => HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after_secs.to_string()))
+                .json(serde_json::json!({
+                    "error": self.to_string(),
+                    "code": "upstream_rate_limited",
+                    "retry_after": retry_after_secs
+                })),
+            AppError::Timeout(_) => HttpResponse::GatewayTimeout().json(serde_json::json!({
+                "error": self.to_string(),
+                "code": "request_timeout"
+            })),
             AppError::ApiError(msg) => {
                 HttpResponse::BadGateway().json(serde_json::json!({
                     "error": msg
                 }))
             }
+            AppError::UpstreamStatus(_) => HttpResponse::BadGateway().json(serde_json::json!({
+                "error": self.to_string()
+            })),
             _ => HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": self.to_string()
             })),
@@ -65,6 +203,67 @@ mod tests {
         assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
     }
 
+    #[actix_web::test]
+    async fn test_error_response_no_releases() {
+        let resp = AppError::NoReleases.error_response();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "no_releases");
+    }
+
+    #[actix_web::test]
+    async fn test_error_response_no_stable_release() {
+        let resp = AppError::NoStableRelease.error_response();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "no_stable_release");
+    }
+
+    #[actix_web::test]
+    async fn test_error_response_no_channel_release() {
+        let resp = AppError::NoChannelRelease("nightly".to_string()).error_response();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "no_channel_release");
+        assert_eq!(body["channel"], "nightly");
+    }
+
+    #[actix_web::test]
+    async fn test_error_response_asset_not_found() {
+        let resp = AppError::AssetNotFound("app.zip".to_string()).error_response();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "asset_not_found");
+        assert_eq!(body["asset"], "app.zip");
+    }
+
+    #[actix_web::test]
+    async fn test_error_response_file_too_large() {
+        let resp = AppError::FileTooLarge {
+            path: "data/big.bin".to_string(),
+            size: 5_000_000,
+        }
+        .error_response();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNPROCESSABLE_ENTITY);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "file_too_large");
+        assert_eq!(body["size"], 5_000_000);
+    }
+
+    #[actix_web::test]
+    async fn test_error_response_timeout() {
+        let resp = AppError::Timeout(60).error_response();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::GATEWAY_TIMEOUT);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "request_timeout");
+    }
+
     #[test]
     fn test_error_response_api_error() {
         let error = AppError::ApiError("API错误".to_string());
@@ -72,6 +271,70 @@ mod tests {
         assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_GATEWAY);
     }
 
+    #[test]
+    fn test_error_response_unauthorized() {
+        let error = AppError::Unauthorized("管理令牌无效或缺失".to_string());
+        let resp = error.error_response();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_error_response_forbidden() {
+        let error = AppError::Forbidden("只允许下载已知 release 的附件".to_string());
+        let resp = error.error_response();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    async fn test_error_response_unsupported_media_type() {
+        let resp = AppError::UnsupportedMediaType("text/html".to_string()).error_response();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "unsupported_media_type");
+    }
+
+    #[actix_web::test]
+    async fn test_error_response_upstream_rate_limited() {
+        let resp = AppError::UpstreamRateLimited { retry_after_secs: 120 }.error_response();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.headers().get("Retry-After").unwrap(), "120");
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "upstream_rate_limited");
+        assert_eq!(body["retry_after"], 120);
+    }
+
+    #[test]
+    fn test_app_error_is_transient() {
+        assert!(AppError::UpstreamStatus(reqwest::StatusCode::BAD_GATEWAY).is_transient());
+        assert!(AppError::UpstreamStatus(reqwest::StatusCode::TOO_MANY_REQUESTS).is_transient());
+        assert!(AppError::Timeout(30).is_transient());
+        assert!(AppError::ServiceUnavailable {
+            message: "busy".to_string(),
+            retry_after_secs: 1
+        }
+        .is_transient());
+        assert!(!AppError::NotFound.is_transient());
+        assert!(!AppError::NoReleases.is_transient());
+        assert!(!AppError::BadRequest("bad".to_string()).is_transient());
+        assert!(!AppError::UpstreamStatus(reqwest::StatusCode::UNAUTHORIZED).is_transient());
+        assert!(!AppError::UpstreamStatus(reqwest::StatusCode::UNPROCESSABLE_ENTITY).is_transient());
+        assert!(!AppError::ApiError("JSON 解析失败".to_string()).is_transient());
+    }
+
+    #[test]
+    fn test_app_error_duplicate() {
+        assert!(matches!(AppError::NotFound.duplicate(), AppError::NotFound));
+
+        let error = AppError::EnvVar(std::env::VarError::NotPresent).duplicate();
+        assert!(matches!(error, AppError::Internal(_)));
+        assert_eq!(
+            error.error_response().status(),
+            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
     #[test]
     fn test_error_response_other() {
         // 测试其他错误类型（如Reqwest错误）
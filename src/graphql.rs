@@ -0,0 +1,506 @@
+//! 批量查询的 GitHub GraphQL 预取
+//!
+//! 启用 `BATCH_USE_GRAPHQL` 且配置了 `GITHUB_TOKEN` 时，批量查询会先用一条带别名的 GraphQL 查询
+//! 一次性获取所有仓库的基本信息和最新 release，结果写入缓存后再交给常规的 REST 流程；
+//! GraphQL 无法提供的字段（如 release 列表）以及查询失败的仓库仍按仓库单独请求 REST API。
+
+use crate::cache::get_cache_manager;
+use crate::cache_backend::get_cache_backend;
+use crate::error::AppError;
+use crate::handlers::{
+    create_client, get_github_token, is_repo_visible, parse_repo, send_github_api_request,
+    to_latest_release_info, to_release_info,
+};
+use crate::models::{
+    moved_to, GithubAsset, GithubRelease, GithubUser, LatestReleaseInfo, RepoInfo,
+};
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::sync::OnceLock;
+
+const GRAPHQL_URL: &str = "https://api.github.com/graphql";
+
+// 单条 GraphQL 查询包含的最大仓库数，超过时拆分为多条并发查询
+const GRAPHQL_BATCH_SIZE: usize = 50;
+
+// 单个 release 通过 GraphQL 获取的最大附件数，附件更多时回退到 REST
+const GRAPHQL_MAX_ASSETS: usize = 100;
+
+/// 是否为批量查询启用 GraphQL 预取（BATCH_USE_GRAPHQL）
+pub fn batch_use_graphql() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        dotenv::dotenv().ok();
+        env::var("BATCH_USE_GRAPHQL")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false)
+    })
+}
+
+/// 通过 GraphQL 预取到的单个仓库数据
+#[derive(Debug, Clone)]
+pub struct PrefetchedRepo {
+    pub repo_info: RepoInfo,
+    /// 仓库没有 release 或附件过多（需要 REST 获取完整列表）时为 None
+    pub latest_release: Option<LatestReleaseInfo>,
+}
+
+// GraphQL 查询中单个仓库的结果
+#[derive(Debug)]
+enum RepoOutcome {
+    Found {
+        repo: Box<PrefetchedRepo>,
+        has_release: bool,
+    },
+    NotFound,
+    Failed(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphqlResponse {
+    #[serde(default)]
+    data: Option<HashMap<String, Option<GraphqlRepository>>>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphqlError {
+    message: String,
+    #[serde(rename = "type", default)]
+    error_type: Option<String>,
+    #[serde(default)]
+    path: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphqlRepository {
+    name: String,
+    name_with_owner: String,
+    url: String,
+    description: Option<String>,
+    stargazer_count: u32,
+    fork_count: u32,
+    updated_at: DateTime<Utc>,
+    #[serde(default)]
+    is_private: bool,
+    latest_release: Option<GraphqlRelease>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphqlRelease {
+    tag_name: String,
+    name: Option<String>,
+    description: Option<String>,
+    published_at: Option<DateTime<Utc>>,
+    is_prerelease: bool,
+    author: Option<GraphqlAuthor>,
+    release_assets: GraphqlAssetConnection,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphqlAuthor {
+    login: String,
+    avatar_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphqlAssetConnection {
+    total_count: usize,
+    nodes: Vec<GraphqlAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphqlAsset {
+    name: String,
+    download_url: String,
+    #[serde(default)]
+    size: u64,
+}
+
+// 构建带别名的查询：每个仓库对应别名 r{i}，owner/name 通过变量传递，避免拼接字符串
+fn build_query(repos: &[(String, String)]) -> serde_json::Value {
+    let mut params = Vec::with_capacity(repos.len());
+    let mut fields = Vec::with_capacity(repos.len());
+    let mut variables = serde_json::Map::new();
+
+    for (i, (owner, repo)) in repos.iter().enumerate() {
+        params.push(format!("$o{i}: String!, $n{i}: String!"));
+        fields.push(format!("r{i}: repository(owner: $o{i}, name: $n{i}) {{ ...RepoFields }}"));
+        variables.insert(format!("o{i}"), owner.clone().into());
+        variables.insert(format!("n{i}"), repo.clone().into());
+    }
+
+    let query = format!(
+        "query({}) {{ {} }} \
+         fragment RepoFields on Repository {{ \
+         name nameWithOwner url description stargazerCount forkCount updatedAt isPrivate \
+         latestRelease {{ tagName name description publishedAt isPrerelease \
+         author {{ login avatarUrl }} \
+         releaseAssets(first: {}) {{ totalCount nodes {{ name downloadUrl size }} }} }} }}",
+        params.join(", "),
+        fields.join(" "),
+        GRAPHQL_MAX_ASSETS
+    );
+
+    serde_json::json!({ "query": query, "variables": variables })
+}
+
+// 将 GraphQL 响应按别名映射回各仓库的结果（顺序与 repos 一致）
+fn map_response(repos: &[(String, String)], response: GraphqlResponse) -> Vec<RepoOutcome> {
+    let GraphqlResponse { data, errors } = response;
+    let mut data = data.unwrap_or_default();
+
+    repos
+        .iter()
+        .enumerate()
+        .map(|(i, (owner, repo))| {
+            let alias = format!("r{i}");
+            if let Some(Some(repository)) = data.remove(&alias) {
+                return to_outcome(owner, repo, repository);
+            }
+
+            // 部分失败：GraphQL 的 errors 通过 path 的第一个元素指向对应别名
+            let error = errors
+                .iter()
+                .find(|e| e.path.first().and_then(|p| p.as_str()) == Some(alias.as_str()));
+            match error {
+                Some(e) if e.error_type.as_deref() == Some("NOT_FOUND") => RepoOutcome::NotFound,
+                Some(e) => RepoOutcome::Failed(e.message.clone()),
+                None if errors.is_empty() => RepoOutcome::Failed("GraphQL 未返回该仓库的数据".to_string()),
+                // 没有指向别名的错误时使用整体错误（如限流、查询过于复杂）
+                None => RepoOutcome::Failed(
+                    errors.iter().map(|e| e.message.as_str()).collect::<Vec<_>>().join("; "),
+                ),
+            }
+        })
+        .collect()
+}
+
+// 将 GraphQL 仓库数据转换为与 REST 接口一致的结构
+fn to_outcome(owner: &str, repo: &str, repository: GraphqlRepository) -> RepoOutcome {
+    let repo_info = RepoInfo {
+        repo: format!("{}/{}", owner, repo),
+        moved_to: moved_to(owner, repo, &repository.name_with_owner),
+        name: repository.name,
+        full_name: repository.name_with_owner,
+        html_url: repository.url,
+        description: repository.description,
+        stargazers_count: repository.stargazer_count,
+        forks_count: repository.fork_count,
+        updated_at: repository.updated_at,
+        private: repository.is_private,
+    };
+
+    let has_release = repository.latest_release.is_some();
+    let latest_release = repository
+        .latest_release
+        .filter(|release| release.release_assets.total_count <= release.release_assets.nodes.len())
+        .map(|release| {
+            // GraphQL 不返回源码压缩包链接，按 REST 接口的格式生成
+            let archive_url = |kind: &str| {
+                format!(
+                    "https://api.github.com/repos/{}/{}/{}",
+                    repo_info.full_name, kind, release.tag_name
+                )
+            };
+            let zipball_url = Some(archive_url("zipball"));
+            let tarball_url = Some(archive_url("tarball"));
+            let release = GithubRelease {
+                tag_name: release.tag_name,
+                name: release.name,
+                body: release.description,
+                published_at: release.published_at,
+                prerelease: release.is_prerelease,
+                draft: false,
+                assets: release
+                    .release_assets
+                    .nodes
+                    .into_iter()
+                    .map(|asset| GithubAsset {
+                        name: asset.name,
+                        download_url: asset.download_url,
+                        size: asset.size,
+                    })
+                    .collect(),
+                author: release.author.map(|author| GithubUser {
+                    login: author.login,
+                    avatar_url: author.avatar_url,
+                }),
+                reactions: None,
+                zipball_url,
+                tarball_url,
+            };
+            to_latest_release_info(owner, repo, to_release_info(release, false))
+        });
+
+    RepoOutcome::Found {
+        repo: Box::new(PrefetchedRepo {
+            repo_info,
+            latest_release,
+        }),
+        has_release,
+    }
+}
+
+// 发送一条 GraphQL 查询
+async fn request_graphql(repos: &[(String, String)], token: &str) -> Result<Vec<RepoOutcome>, AppError> {
+    let request = create_client()
+        .post(GRAPHQL_URL)
+        .header("User-Agent", "gh-info-rs")
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&build_query(repos));
+
+    let response = send_github_api_request(request).await?;
+    if !response.status().is_success() {
+        return Err(AppError::ApiError(format!(
+            "GitHub GraphQL API 返回状态码: {}",
+            response.status()
+        )));
+    }
+
+    let response: GraphqlResponse = response.json().await?;
+    Ok(map_response(repos, response))
+}
+
+/// 通过 GraphQL 预取批量查询中仓库的基本信息和最新 release
+///
+/// 只查询缓存中缺少所需字段的仓库；结果写入缓存（不存在的仓库写入负缓存），
+/// 返回以 `owner/repo` 为键的预取数据。未启用、未配置 token 或不需要这些字段时返回空 Map，
+/// 调用方对 Map 中缺少的仓库和字段回退到 REST。
+pub async fn prefetch_batch(repo_strs: &[String], fields: &[String]) -> HashMap<String, PrefetchedRepo> {
+    if !batch_use_graphql() {
+        return HashMap::new();
+    }
+    let Some(token) = get_github_token() else {
+        log::debug!("未设置 GITHUB_TOKEN，批量查询不使用 GraphQL");
+        return HashMap::new();
+    };
+
+    let want_repo_info = fields.is_empty() || fields.iter().any(|f| f == "repo_info");
+    let want_latest_release = fields.is_empty() || fields.iter().any(|f| f == "latest_release");
+    if !want_repo_info && !want_latest_release {
+        return HashMap::new();
+    }
+
+    let cache = get_cache_manager().await;
+    let backend = get_cache_backend().await;
+
+    // 跳过格式错误、重复以及所需字段都已缓存的仓库
+    let mut repos: Vec<(String, String)> = Vec::new();
+    for (owner, repo) in repo_strs.iter().filter_map(|r| parse_repo(r)) {
+        if repos.contains(&(owner.clone(), repo.clone())) {
+            continue;
+        }
+        let need_repo_info = want_repo_info
+            && backend.get_repo_info(&owner, &repo).await.is_none()
+            && !cache.is_repo_info_not_found(&owner, &repo).await;
+        let need_latest_release = want_latest_release
+            && backend.get_latest_release(&owner, &repo).await.is_none()
+            && !cache.is_latest_release_not_found(&owner, &repo).await;
+        if need_repo_info || need_latest_release {
+            repos.push((owner, repo));
+        }
+    }
+    if repos.is_empty() {
+        return HashMap::new();
+    }
+
+    log::info!("通过 GraphQL 预取 {} 个仓库", repos.len());
+    let chunks: Vec<&[(String, String)]> = repos.chunks(GRAPHQL_BATCH_SIZE).collect();
+    let results = join_all(chunks.iter().map(|chunk| request_graphql(chunk, &token))).await;
+
+    let mut prefetched = HashMap::new();
+    for (chunk, result) in chunks.into_iter().zip(results) {
+        let outcomes = match result {
+            Ok(outcomes) => outcomes,
+            Err(e) => {
+                log::warn!("GraphQL 批量查询失败，回退到 REST: {}", e);
+                continue;
+            }
+        };
+
+        for ((owner, repo), outcome) in chunk.iter().zip(outcomes) {
+            match outcome {
+                RepoOutcome::Found { repo: data, has_release } => {
+                    backend.set_repo_info(owner, repo, data.repo_info.clone()).await;
+                    match &data.latest_release {
+                        Some(latest_release) => {
+                            backend
+                                .set_latest_release(owner, repo, latest_release.clone())
+                                .await
+                        }
+                        None if !has_release => cache.set_latest_release_not_found(owner, repo).await,
+                        None => {}
+                    }
+                    // 不允许访问的私有仓库照常写入缓存，但不作为预取结果返回，由 REST 流程返回错误
+                    if is_repo_visible(&data.repo_info) {
+                        prefetched.insert(format!("{}/{}", owner, repo), *data);
+                    }
+                }
+                RepoOutcome::NotFound => {
+                    cache.set_repo_info_not_found(owner, repo).await;
+                    cache.set_latest_release_not_found(owner, repo).await;
+                }
+                RepoOutcome::Failed(message) => {
+                    log::warn!("GraphQL 查询 {}/{} 失败，回退到 REST: {}", owner, repo, message);
+                }
+            }
+        }
+    }
+
+    prefetched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repos(names: &[(&str, &str)]) -> Vec<(String, String)> {
+        names
+            .iter()
+            .map(|(owner, repo)| (owner.to_string(), repo.to_string()))
+            .collect()
+    }
+
+    fn repository_json(name: &str, latest_release: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "name": name,
+            "nameWithOwner": format!("owner/{}", name),
+            "url": format!("https://github.com/owner/{}", name),
+            "description": "desc",
+            "stargazerCount": 42,
+            "forkCount": 7,
+            "updatedAt": "2024-01-01T00:00:00Z",
+            "latestRelease": latest_release
+        })
+    }
+
+    fn release_json(total_assets: usize) -> serde_json::Value {
+        serde_json::json!({
+            "tagName": "v1.0.0",
+            "name": "Release 1.0.0",
+            "description": "changelog",
+            "publishedAt": "2024-01-02T00:00:00Z",
+            "isPrerelease": false,
+            "author": { "login": "octocat", "avatarUrl": "https://avatars.example/octocat" },
+            "releaseAssets": {
+                "totalCount": total_assets,
+                "nodes": [
+                    { "name": "app.zip", "downloadUrl": "https://github.com/owner/a/releases/download/v1.0.0/app.zip" }
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn test_build_query_uses_aliases_and_variables() {
+        let body = build_query(&repos(&[("rust-lang", "rust"), ("own\"er", "re\\po")]));
+        let query = body["query"].as_str().unwrap();
+        assert!(query.contains("r0: repository(owner: $o0, name: $n0)"));
+        assert!(query.contains("r1: repository(owner: $o1, name: $n1)"));
+        assert!(query.contains("$o1: String!, $n1: String!"));
+        // 仓库名只出现在变量中，不会破坏查询语法
+        assert!(!query.contains("own\"er"));
+        assert_eq!(body["variables"]["o0"], "rust-lang");
+        assert_eq!(body["variables"]["n1"], "re\\po");
+    }
+
+    #[test]
+    fn test_map_response_found() {
+        let response: GraphqlResponse = serde_json::from_value(serde_json::json!({
+            "data": { "r0": repository_json("a", release_json(1)) }
+        }))
+        .unwrap();
+
+        let outcomes = map_response(&repos(&[("owner", "a")]), response);
+        let RepoOutcome::Found { repo, has_release } = &outcomes[0] else {
+            panic!("unexpected outcome: {:?}", outcomes[0]);
+        };
+        assert!(has_release);
+        assert_eq!(repo.repo_info.repo, "owner/a");
+        assert_eq!(repo.repo_info.full_name, "owner/a");
+        assert_eq!(repo.repo_info.stargazers_count, 42);
+        assert_eq!(repo.repo_info.forks_count, 7);
+        let latest = repo.latest_release.as_ref().unwrap();
+        assert_eq!(latest.latest_version, "v1.0.0");
+        assert_eq!(latest.changelog.as_deref(), Some("changelog"));
+        assert_eq!(latest.author_login.as_deref(), Some("octocat"));
+        assert_eq!(latest.attachments.len(), 1);
+        assert_eq!(
+            latest.zipball_url.as_deref(),
+            Some("https://api.github.com/repos/owner/a/zipball/v1.0.0")
+        );
+    }
+
+    #[test]
+    fn test_map_response_partial_errors() {
+        let response: GraphqlResponse = serde_json::from_value(serde_json::json!({
+            "data": {
+                "r0": repository_json("a", serde_json::Value::Null),
+                "r1": null,
+                "r2": null
+            },
+            "errors": [
+                {
+                    "type": "NOT_FOUND",
+                    "path": ["r1"],
+                    "message": "Could not resolve to a Repository with the name 'owner/missing'."
+                },
+                {
+                    "type": "FORBIDDEN",
+                    "path": ["r2"],
+                    "message": "Resource not accessible by integration"
+                }
+            ]
+        }))
+        .unwrap();
+
+        let outcomes = map_response(&repos(&[("owner", "a"), ("owner", "missing"), ("owner", "private")]), response);
+        assert!(matches!(
+            &outcomes[0],
+            RepoOutcome::Found { repo, has_release: false } if repo.latest_release.is_none()
+        ));
+        assert!(matches!(outcomes[1], RepoOutcome::NotFound));
+        assert!(matches!(
+            &outcomes[2],
+            RepoOutcome::Failed(message) if message == "Resource not accessible by integration"
+        ));
+    }
+
+    #[test]
+    fn test_map_response_top_level_error() {
+        let response: GraphqlResponse = serde_json::from_value(serde_json::json!({
+            "data": null,
+            "errors": [{ "type": "RATE_LIMITED", "message": "API rate limit exceeded" }]
+        }))
+        .unwrap();
+
+        let outcomes = map_response(&repos(&[("owner", "a"), ("owner", "b")]), response);
+        assert!(outcomes
+            .iter()
+            .all(|o| matches!(o, RepoOutcome::Failed(message) if message == "API rate limit exceeded")));
+    }
+
+    #[test]
+    fn test_release_with_too_many_assets_falls_back_to_rest() {
+        let response: GraphqlResponse = serde_json::from_value(serde_json::json!({
+            "data": { "r0": repository_json("a", release_json(GRAPHQL_MAX_ASSETS + 1)) }
+        }))
+        .unwrap();
+
+        let outcomes = map_response(&repos(&[("owner", "a")]), response);
+        assert!(matches!(
+            &outcomes[0],
+            RepoOutcome::Found { repo, has_release: true } if repo.latest_release.is_none()
+        ));
+    }
+}
@@ -1,48 +1,545 @@
-use crate::cache::get_cache_manager;
+use crate::admin::get_admin_config;
+use crate::cache::{
+    create_cache_file, get_cache_manager, CacheManager, gzip_sibling_path, partial_file_path, repo_from_key, write_gzip_sibling,
+    DownloadFlight, DownloadFlightGuard, FileCacheMetadata, FileSlot, Validators,
+};
+use crate::badge::{self, parse_color, render_badge};
+use crate::cache_backend::{get_cache_backend, CacheBackend};
+use crate::channel::{get_channel_config, ReleaseChannel};
+use crate::client::get_github_client_config;
+use crate::download::{
+    accepts_gzip, content_disposition, content_range_total, filename_from_content_disposition, get_download_config,
+    github_repo_from_url, glob_match, guard_download_stream, has_no_cache_directive, is_precompressible,
+    upstream_retry_after,
+    DownloadConfig, DownloadFilenameMode,
+};
+use crate::conditional::{conditional_responses_enabled, if_none_match_matches, CachedVersion};
 use crate::error::AppError;
+use crate::graphql::{prefetch_batch, PrefetchedRepo};
+use crate::metrics::{cache_metrics, CacheKind};
+#[cfg(feature = "s3")]
+use crate::mirror::{byte_stream as mirror_byte_stream, get_s3_mirror};
+use crate::notes::NotesFormat;
 use crate::models::{
-    BatchRequest, BatchResponse, BatchResponseMap, GithubRelease, GithubRepo,
-    HealthResponse, LatestReleaseInfo, ReleaseInfo, RepoBatchResult, RepoInfo,
+    AssetListResponse, AssetsQuery, BadgeQuery, BatchLatestRequest, BatchLatestResponse, BatchRequest, BatchResponse, BatchResponseMap, CacheFilesQuery, CacheReposQuery, CacheStatsResponse, CachedFileSummary, CachedRepoSummary, CachedResponse, ChannelQuery, ChecksumsInfo,
+    CompareInfo, ContentsQuery, FieldStatus, FlatAsset, FlatReleaseInfo, FormatQuery, GenerateNotesQuery, GeneratedNotes, GithubCompare, GithubRelease,
+    GithubRepo, HealthResponse, LatestReleaseInfo, MilestoneInfo, MilestoneQuery, NotesQuery, ReleaseAssets, ReleaseInfo, ReleaseQuery, RepoBatchResult,
+    RefreshQuery, ReleaseDateQuery, RepoInfo, ResolveAssetQuery, ResolvedAsset, SchemaQuery,
+    StatsResponse, VersionQuery, WatchEvent, LatestVersionResult, moved_to,
 };
-use crate::rate_limit::get_rate_limit_manager;
+use crate::rate_limit::{
+    client_ip, get_github_concurrency_limit, get_github_token_bucket, get_rate_limit_manager, RateLimitConfig,
+    GITHUB_TOKEN_MAX_WAIT,
+};
+use crate::telemetry::record_github_status;
+use crate::watch::{release_event_stream, WatchConfig};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use chrono::{DateTime, Utc};
 use actix_web::{get, post, web, HttpResponse, Responder, HttpRequest};
+use base64::Engine;
 use futures::future::join_all;
 use futures::join;
 use futures::StreamExt;
 use log;
 use reqwest::Client;
+use indexmap::IndexMap;
 use std::collections::HashMap;
 use std::env;
 use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
 use sha2::{Sha256, Digest};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
+use tokio::sync::OwnedSemaphorePermit;
 
 // 获取 GitHub token（可选，如果设置了环境变量则使用）
-fn get_github_token() -> Option<String> {
+pub(crate) fn get_github_token() -> Option<String> {
     dotenv::dotenv().ok();
     env::var("GITHUB_TOKEN").ok()
 }
 
-// 创建 GitHub API 请求客户端
-fn create_client() -> Client {
-    Client::new()
+// GitHub REST API 版本的默认值（X-GitHub-Api-Version）
+pub const DEFAULT_GITHUB_API_VERSION: &str = "2022-11-28";
+
+/// 请求 GitHub 时使用的 REST API 版本（`GITHUB_API_VERSION`，设置为空时不发送版本头）
+pub fn github_api_version() -> Option<&'static str> {
+    static VERSION: OnceLock<Option<String>> = OnceLock::new();
+    VERSION
+        .get_or_init(|| {
+            dotenv::dotenv().ok();
+            let version = env::var("GITHUB_API_VERSION")
+                .map(|v| v.trim().to_string())
+                .unwrap_or_else(|_| DEFAULT_GITHUB_API_VERSION.to_string());
+            (!version.is_empty()).then_some(version)
+        })
+        .as_deref()
+}
+
+// 获取 GitHub 请求客户端（所有请求共用同一个连接池，克隆的开销很小）
+pub(crate) fn create_client() -> Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(build_client).clone()
+}
+
+// 创建共享的 GitHub 请求客户端（所有请求都带上 X-GitHub-Api-Version 头，连接池大小见 GithubClientConfig）
+fn build_client() -> Client {
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Some(version) = github_api_version().and_then(|v| reqwest::header::HeaderValue::from_str(v).ok()) {
+        headers.insert("X-GitHub-Api-Version", version);
+    }
+    let config = get_github_client_config();
+    let max_concurrent_downloads = RateLimitConfig::from_env().max_concurrent_downloads;
+    let builder = Client::builder()
+        .default_headers(headers)
+        .pool_max_idle_per_host(config.pool_max_idle_for(max_concurrent_downloads))
+        .pool_idle_timeout(config.pool_idle_timeout);
+    config
+        .apply_http_version(builder)
+        .build()
+        .expect("创建 HTTP 客户端失败")
+}
+
+// GitHub 返回 202（数据仍在后台计算）时的最大重试次数（GITHUB_202_MAX_RETRIES）
+fn github_202_max_retries() -> u32 {
+    static MAX_RETRIES: OnceLock<u32> = OnceLock::new();
+    *MAX_RETRIES.get_or_init(|| {
+        dotenv::dotenv().ok();
+        env::var("GITHUB_202_MAX_RETRIES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3)
+    })
+}
+
+// 是否允许返回私有仓库的数据（PRIVATE_REPOS_ENABLED）
+fn private_repos_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        dotenv::dotenv().ok();
+        env::var("PRIVATE_REPOS_ENABLED")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false)
+    })
+}
+
+// 仓库数据是否可以返回给客户端（未启用 PRIVATE_REPOS_ENABLED 时私有仓库不可见）
+pub(crate) fn is_repo_visible(repo_info: &RepoInfo) -> bool {
+    !repo_info.private || private_repos_enabled()
+}
+
+// 私有仓库不可见时返回 403
+fn check_repo_visibility(repo_info: RepoInfo, private_repos_enabled: bool) -> Result<RepoInfo, AppError> {
+    if repo_info.private && !private_repos_enabled {
+        return Err(AppError::Forbidden(format!(
+            "{} 是私有仓库，服务未启用 PRIVATE_REPOS_ENABLED",
+            repo_info.repo
+        )));
+    }
+    Ok(repo_info)
+}
+
+// 返回仓库的 release 等数据之前确认仓库不是（不可见的）私有仓库
+// 未配置 token 时 GitHub 不会返回私有仓库的数据，无需额外查询仓库信息
+// 已缓存的原始仓库 JSON 同样带有 private 字段，命中时不再请求仓库信息
+async fn ensure_repo_visible(owner: &str, repo: &str) -> Result<(), AppError> {
+    if private_repos_enabled() || get_github_token().is_none() {
+        return Ok(());
+    }
+    if let Some(raw) = get_cache_backend().await.get_raw_repo(owner, repo).await {
+        return check_raw_repo_visibility(owner, repo, raw, false).map(|_| ());
+    }
+    fetch_repo_info(owner, repo).await.map(|_| ())
+}
+
+// 下载请求附加的 GitHub token。/download 可以请求任意允许的地址，直接附加 token 会绕过私有仓库检查：
+// 能从地址解析出仓库时先确认仓库可见（私有仓库不可见时返回 403），无法判断仓库时只在启用 PRIVATE_REPOS_ENABLED 时附加。
+// 查询仓库信息失败（如 GitHub 故障）时不附加 token，公开内容仍然可以下载
+async fn download_token(url: &str) -> Result<Option<String>, AppError> {
+    let Some(token) = get_github_token() else {
+        return Ok(None);
+    };
+    if private_repos_enabled() {
+        return Ok(Some(token));
+    }
+    let Some((owner, repo)) = github_repo_from_url(url) else {
+        return Ok(None);
+    };
+    match ensure_repo_visible(&owner, &repo).await {
+        Ok(()) => Ok(Some(token)),
+        Err(e @ AppError::Forbidden(_)) => Err(e),
+        Err(e) => {
+            log::warn!("无法确认 {}/{} 是否可见，下载时不附加 token: {}", owner, repo, e);
+            Ok(None)
+        }
+    }
+}
+
+// GitHub REST API 地址
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+#[cfg(test)]
+tokio::task_local! {
+    // 测试中将当前任务发出的 GitHub API 请求指向本地模拟服务器
+    static GITHUB_API_URL_OVERRIDE: String;
+}
+
+// 获取 GitHub REST API 地址（不带结尾的 /）
+fn github_api_url() -> String {
+    #[cfg(test)]
+    if let Ok(url) = GITHUB_API_URL_OVERRIDE.try_with(|url| url.clone()) {
+        return url;
+    }
+    GITHUB_API_URL.to_string()
+}
+
+// 202 重试的初始等待时间，之后每次翻倍
+const GITHUB_202_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// GitHub API 响应，持有并发许可直到响应体被读取（或响应被丢弃），
+/// 保证慢速传输的响应体同样计入 `GITHUB_MAX_CONCURRENT_REQUESTS`
+pub(crate) struct GithubResponse {
+    response: reqwest::Response,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl GithubResponse {
+    pub(crate) fn status(&self) -> reqwest::StatusCode {
+        self.response.status()
+    }
+
+    pub(crate) fn headers(&self) -> &reqwest::header::HeaderMap {
+        self.response.headers()
+    }
+
+    /// 读取并解析 JSON 响应体，读取结束后释放许可
+    pub(crate) async fn json<T: serde::de::DeserializeOwned>(self) -> Result<T, reqwest::Error> {
+        self.response.json().await
+    }
+}
+
+// 发送 GitHub API 请求，202 时按退避重试
+pub(crate) async fn send_github_api_request(request: reqwest::RequestBuilder) -> Result<GithubResponse, AppError> {
+    send_with_202_retry(request, github_202_max_retries(), GITHUB_202_INITIAL_BACKOFF).await
+}
+
+// 部分 GitHub 端点（如统计、贡献者）在冷缓存时返回 202 和空响应体，需要客户端稍后重试；
+// 重试次数用尽后仍为 202 时返回 503，提示调用方稍后重试。
+// 每次实际发出请求前都要从全局令牌桶取令牌，避免耗尽共享的 GitHub 额度
+async fn send_with_202_retry(
+    request: reqwest::RequestBuilder,
+    max_retries: u32,
+    initial_backoff: Duration,
+) -> Result<GithubResponse, AppError> {
+    let mut backoff = initial_backoff;
+    for attempt in 0..=max_retries {
+        let attempt_request = request
+            .try_clone()
+            .ok_or_else(|| AppError::Internal("无法重试 GitHub 请求".to_string()))?;
+        get_github_token_bucket().acquire(GITHUB_TOKEN_MAX_WAIT).await?;
+        // 持有并发许可直到读取完响应体，限制同时发往 GitHub 的请求数（包括批量查询的扇出）
+        let permit = get_github_concurrency_limit().acquire().await;
+        cache_metrics().record_github_api_call();
+        let response = attempt_request.send().await?;
+        record_github_status(response.status().as_u16());
+        if response.status() != reqwest::StatusCode::ACCEPTED {
+            return Ok(GithubResponse { response, _permit: permit });
+        }
+        // 202 的响应体为空，等待重试期间不占用许可
+        drop(permit);
+        if attempt < max_retries {
+            log::debug!(
+                "GitHub 返回 202（数据计算中），{} 毫秒后重试 ({}/{}): {}",
+                backoff.as_millis(),
+                attempt + 1,
+                max_retries,
+                response.url()
+            );
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    Err(AppError::ServiceUnavailable {
+        message: "GitHub 仍在计算数据，请稍后重试".to_string(),
+        retry_after_secs: backoff.as_secs().max(1),
+    })
+}
+
+// 从 GitHub 分页响应的 Link 头中取出下一页的地址（rel="next"），没有下一页时返回 None
+fn next_page_url(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+// 条件请求（If-Modified-Since / If-None-Match）的结果
+enum Conditional<T> {
+    // 数据有变化（或未发送条件请求），附带 GitHub 返回的 Last-Modified 和 ETag
+    Modified { value: T, validators: Validators },
+    // GitHub 返回 304，缓存中的数据仍然有效
+    NotModified,
+}
+
+// 发送 GitHub API 请求，提供 validators 时附加 If-Modified-Since 头，
+// use_etag 为 true（ENABLE_CONDITIONAL_RESPONSES）时还附加 If-None-Match 头并记录 GitHub 返回的 ETag；
+// 304 不计入 GitHub 的速率限制，适合重新验证已过期的缓存
+async fn send_conditional_request<T: serde::de::DeserializeOwned>(
+    mut request: reqwest::RequestBuilder,
+    validators: Option<&Validators>,
+    use_etag: bool,
+) -> Result<Conditional<T>, AppError> {
+    if let Some(validators) = validators {
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header("If-Modified-Since", last_modified);
+        }
+        if let Some(etag) = validators.etag.as_ref().filter(|_| use_etag) {
+            request = request.header("If-None-Match", etag);
+        }
+    }
+
+    let response = send_github_api_request(request).await?;
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED && validators.is_some() {
+        return Ok(Conditional::NotModified);
+    }
+    if !response.status().is_success() {
+        if response.status().as_u16() == 404 {
+            return Err(AppError::NotFound);
+        }
+        return Err(AppError::UpstreamStatus(response.status()));
+    }
+
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    };
+    let validators = Validators {
+        last_modified: header("last-modified"),
+        etag: header("etag").filter(|_| use_etag),
+    };
+    let value = response.json().await?;
+    Ok(Conditional::Modified { value, validators })
+}
+
+// 生成 JSON 响应，pretty 为 true 时返回缩进格式（便于调试），默认返回紧凑格式
+fn json_response<T: serde::Serialize>(value: &T, pretty: bool) -> Result<HttpResponse, AppError> {
+    let body = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+    .map_err(|e| AppError::Internal(format!("JSON 序列化失败: {}", e)))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(mime::APPLICATION_JSON)
+        .body(body))
+}
+
+// 为强制刷新的响应添加 X-Cache: REFRESH 头
+fn mark_refreshed(mut response: HttpResponse) -> HttpResponse {
+    response.headers_mut().insert(
+        HeaderName::from_static("x-cache"),
+        HeaderValue::from_static("REFRESH"),
+    );
+    response
+}
+
+// 缓存条目的（已缓存秒数, 剩余有效秒数），禁用缓存或条目不存在时返回 None
+async fn cache_entry_age(key: &str) -> Option<(u64, u64)> {
+    if !get_cache_manager().await.config().enabled {
+        return None;
+    }
+    let backend = get_cache_backend().await;
+    let cached_at = backend.cached_at(key).await?;
+    let expires_at = backend.expires_at(key).await?;
+    let now = Utc::now().timestamp().max(0) as u64;
+    Some((now.saturating_sub(cached_at), expires_at.saturating_sub(now)))
+}
+
+// 生成缓存数据的 JSON 响应并添加缓存相关的头（key 为 None 表示数据不经过缓存）
+// format.include_meta 为 true 时将数据包装为 { data, cache_age_secs, expires_in_secs }
+async fn cached_json_response<T: serde::Serialize>(
+    value: &T,
+    format: &FormatQuery,
+    key: Option<&str>,
+) -> Result<HttpResponse, AppError> {
+    let response = if format.include_meta {
+        let age = match key {
+            Some(key) => cache_entry_age(key).await,
+            None => None,
+        };
+        let wrapped = CachedResponse {
+            data: value,
+            cache_age_secs: age.map(|(age, _)| age),
+            expires_in_secs: age.map(|(_, expires_in)| expires_in),
+        };
+        json_response(&wrapped, format.pretty)?
+    } else {
+        json_response(value, format.pretty)?
+    };
+    let mut response = match key {
+        Some(key) => with_cache_control(response, key).await,
+        None => response,
+    };
+    // 缓存数据的响应由条件响应中间件根据数据版本计算 ETag；带 include_meta 的响应体随时间变化，仍按响应体计算
+    if let Some(key) = key.filter(|_| conditional_responses_enabled() && !format.include_meta) {
+        if let Some(cached_at) = get_cache_backend().await.cached_at(key).await {
+            response
+                .extensions_mut()
+                .insert(CachedVersion(format!("{}:{}", key, cached_at)));
+        }
+    }
+    Ok(response)
+}
+
+// Cache-Control 的缓存范围：私有仓库的数据只允许客户端缓存（private），不能被 CDN 等共享缓存保存
+// 启用 PRIVATE_REPOS_ENABLED 且缓存中没有仓库信息时无法确认是否为私有仓库，同样按 private 处理
+async fn cache_scope(key: &str) -> &'static str {
+    let Some((owner, repo)) = repo_from_key(key) else {
+        return "public";
+    };
+    match get_cache_backend().await.get_repo_info(owner, repo).await {
+        Some(repo_info) if repo_info.private => "private",
+        Some(_) => "public",
+        None if private_repos_enabled() && get_github_token().is_some() => "private",
+        None => "public",
+    }
+}
+
+// 根据缓存条目的剩余有效期添加 Cache-Control 和 X-Cache-Expires-At 头，便于 CDN 和浏览器缓存响应
+// 缓存未命中时条目刚刚写入，剩余有效期即完整的 TTL；禁用缓存时返回 no-store
+// 条目存在时还会添加 X-Cache-Age 头（数据从 GitHub 获取后经过的秒数），便于客户端判断是否需要强制刷新
+// GitHub 故障时兜底返回的过期数据带 X-Cache-Stale: true，且不允许下游缓存（max-age=0），
+// 只通过 stale-if-error 允许在剩余的兜底时间内继续使用
+async fn with_cache_control(mut response: HttpResponse, key: &str) -> HttpResponse {
+    let cache = get_cache_manager().await;
+    let config = cache.config();
+    if let Some((age, _)) = cache_entry_age(key).await {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static("x-cache-age"), HeaderValue::from(age));
+    }
+    let stale_for = cache.outage_grace_remaining(key).await;
+    if stale_for.is_some() {
+        response.headers_mut().insert(
+            HeaderName::from_static("x-cache-stale"),
+            HeaderValue::from_static("true"),
+        );
+    }
+    if !config.api_cache_control {
+        return response;
+    }
+    let headers = response.headers_mut();
+    if !config.enabled {
+        headers.insert(
+            actix_web::http::header::CACHE_CONTROL,
+            HeaderValue::from_static("no-store"),
+        );
+        return response;
+    }
+    let scope = cache_scope(key).await;
+    if let Some(stale_for) = stale_for {
+        if let Ok(value) =
+            HeaderValue::from_str(&format!("{}, max-age=0, stale-if-error={}", scope, stale_for))
+        {
+            headers.insert(actix_web::http::header::CACHE_CONTROL, value);
+        }
+        return response;
+    }
+
+    let now = Utc::now().timestamp().max(0) as u64;
+    let expires_at = get_cache_backend()
+        .await
+        .expires_at(key)
+        .await
+        .filter(|expires_at| *expires_at > now)
+        .unwrap_or(now + config.ttl_seconds);
+    if let Ok(value) = HeaderValue::from_str(&format!("{}, max-age={}", scope, expires_at - now)) {
+        headers.insert(actix_web::http::header::CACHE_CONTROL, value);
+    }
+    headers.insert(
+        HeaderName::from_static("x-cache-expires-at"),
+        HeaderValue::from(expires_at),
+    );
+    response
 }
 
 // 获取仓库基本信息
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
 pub async fn fetch_repo_info(owner: &str, repo: &str) -> Result<RepoInfo, AppError> {
     let cache = get_cache_manager().await;
+    let backend = get_cache_backend().await;
 
     // 先尝试从缓存获取
-    if let Some(cached_info) = cache.get_repo_info(owner, repo).await {
+    let cached_info = backend.get_repo_info(owner, repo).await;
+    cache_metrics().record_lookup(CacheKind::RepoInfo, cached_info.is_some());
+    if let Some(cached_info) = cached_info {
         log::debug!("从缓存获取仓库信息: {}/{}", owner, repo);
-        return Ok(cached_info);
+        return check_repo_visibility(cached_info, private_repos_enabled());
+    }
+
+    // 近期已确认仓库不存在时直接返回 404（负缓存）
+    if cache.is_repo_info_not_found(owner, repo).await {
+        log::debug!("负缓存命中，仓库不存在: {}/{}", owner, repo);
+        cache_metrics().record_negative_hit();
+        return Err(AppError::NotFound);
     }
 
-    // 缓存未命中，从 API 获取
+    // 缓存未命中，从 API 获取（并发的相同请求会被合并为一次）
+    let (owner_owned, repo_owned) = (owner.to_string(), repo.to_string());
+    let result = cache
+        .coalesce_repo_info(owner, repo, async move {
+            request_repo_info(&owner_owned, &repo_owned).await
+        })
+        .await;
+    let repo_info = match result {
+        // GitHub 故障时退回使用保留期内的过期数据
+        Err(e) if e.is_transient() => match cache.outage_repo_info(owner, repo).await {
+            Some(stale) => {
+                log::warn!("获取仓库信息失败（{}），使用已过期的缓存: {}/{}", e, owner, repo);
+                stale
+            }
+            None => return Err(e),
+        },
+        result => result?,
+    };
+    check_repo_visibility(repo_info, private_repos_enabled())
+}
+
+// 跳过缓存读取，强制从 GitHub 获取仓库信息（结果仍会写入缓存）
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn refresh_repo_info(owner: &str, repo: &str) -> Result<RepoInfo, AppError> {
+    let cache = get_cache_manager().await;
+    let (owner_owned, repo_owned) = (owner.to_string(), repo.to_string());
+    let repo_info = cache
+        .coalesce_repo_info(owner, repo, async move {
+            request_repo_info(&owner_owned, &repo_owned).await
+        })
+        .await?;
+    check_repo_visibility(repo_info, private_repos_enabled())
+}
+
+// 从 GitHub API 获取仓库信息并存入缓存
+#[cfg_attr(
+    feature = "otel",
+    tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo), github.status = tracing::field::Empty))
+)]
+async fn request_repo_info(owner: &str, repo: &str) -> Result<RepoInfo, AppError> {
+    let cache = get_cache_manager().await;
+    let backend = get_cache_backend().await;
     log::debug!("从 GitHub API 获取仓库信息: {}/{}", owner, repo);
     let client = create_client();
-    let api_url = format!("https://api.github.com/repos/{}/{}", owner, repo);
+    let api_url = format!("{}/repos/{}/{}", github_api_url(), owner, repo);
 
     let mut request = client
         .get(&api_url)
@@ -54,22 +551,50 @@ pub async fn fetch_repo_info(owner: &str, repo: &str) -> Result<RepoInfo, AppErr
         request = request.header("Authorization", format!("Bearer {}", token));
     }
 
-    let response = request.send().await?;
-
-    if !response.status().is_success() {
-        if response.status().as_u16() == 404 {
+    // 缓存中有带 Last-Modified 或 ETag 的旧数据时发送条件请求
+    let stale = cache.revalidatable_repo_info(owner, repo).await;
+    let validators = stale.as_ref().map(|(_, validators)| validators);
+    let result = send_conditional_request(request, validators, conditional_responses_enabled()).await;
+    let (repo_info, validators) = match result {
+        Ok(Conditional::Modified { value, validators }) => {
+            (to_repo_info(owner, repo, value), validators)
+        }
+        Ok(Conditional::NotModified) => {
+            log::debug!("仓库信息未变化（304），继续使用缓存: {}/{}", owner, repo);
+            stale.expect("只有发送条件请求时才会返回 304")
+        }
+        Err(AppError::NotFound) => {
+            cache.set_repo_info_not_found(owner, repo).await;
             return Err(AppError::NotFound);
         }
-        return Err(AppError::ApiError(format!(
-            "GitHub API 返回状态码: {}",
-            response.status()
-        )));
+        Err(e) => return Err(e),
+    };
+
+    // 存入缓存（私有仓库同样缓存，是否可见在返回时检查）
+    backend.set_repo_info(owner, repo, repo_info.clone()).await;
+    if validators != Validators::default() {
+        cache.set_repo_info_validators(owner, repo, validators).await;
     }
+    // 仓库已改名或转移时，同时以新名称缓存，之后按新名称的请求不必再访问 GitHub
+    if let Some((new_owner, new_repo)) = repo_info.moved_to.as_deref().and_then(parse_repo) {
+        log::info!("仓库已改名或转移: {}/{} -> {}/{}", owner, repo, new_owner, new_repo);
+        let canonical = RepoInfo {
+            repo: format!("{}/{}", new_owner, new_repo),
+            moved_to: None,
+            ..repo_info.clone()
+        };
+        backend.set_repo_info(&new_owner, &new_repo, canonical).await;
+    }
+    log::debug!("成功获取并缓存仓库信息: {}/{}", owner, repo);
 
-    let github_repo: GithubRepo = response.json().await?;
+    Ok(repo_info)
+}
 
-    let repo_info = RepoInfo {
+// 将 GitHub API 返回的仓库转换为 RepoInfo
+fn to_repo_info(owner: &str, repo: &str, github_repo: GithubRepo) -> RepoInfo {
+    RepoInfo {
         repo: format!("{}/{}", owner, repo),
+        moved_to: moved_to(owner, repo, &github_repo.full_name),
         name: github_repo.name,
         full_name: github_repo.full_name,
         html_url: github_repo.html_url,
@@ -77,153 +602,835 @@ pub async fn fetch_repo_info(owner: &str, repo: &str) -> Result<RepoInfo, AppErr
         stargazers_count: github_repo.stargazers_count,
         forks_count: github_repo.forks_count,
         updated_at: github_repo.updated_at,
-    };
-
-    // 存入缓存
-    cache.set_repo_info(owner, repo, repo_info.clone()).await;
-    log::debug!("成功获取并缓存仓库信息: {}/{}", owner, repo);
-
-    Ok(repo_info)
+        private: github_repo.private,
+    }
 }
 
-// 获取所有 releases
-pub async fn fetch_releases(owner: &str, repo: &str) -> Result<Vec<ReleaseInfo>, AppError> {
+// 获取 GitHub 返回的原始仓库 JSON（带缓存），用于获取 RepoInfo 中没有的字段
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn fetch_raw_repo(owner: &str, repo: &str) -> Result<serde_json::Value, AppError> {
     let cache = get_cache_manager().await;
+    let backend = get_cache_backend().await;
 
-    // 先尝试从缓存获取
-    if let Some(cached_releases) = cache.get_releases(owner, repo).await {
-        log::debug!("从缓存获取 releases: {}/{} (共 {} 个)", owner, repo, cached_releases.len());
-        return Ok(cached_releases);
+    let cached_raw = backend.get_raw_repo(owner, repo).await;
+    cache_metrics().record_lookup(CacheKind::RawRepo, cached_raw.is_some());
+    if let Some(cached_raw) = cached_raw {
+        log::debug!("从缓存获取原始仓库信息: {}/{}", owner, repo);
+        return check_raw_repo_visibility(owner, repo, cached_raw, private_repos_enabled());
     }
 
-    // 缓存未命中，从 API 获取
-    log::debug!("从 GitHub API 获取 releases: {}/{}", owner, repo);
-    let client = create_client();
-    let api_url = format!("https://api.github.com/repos/{}/{}/releases", owner, repo);
+    // 与仓库信息共用负缓存：近期已确认仓库不存在时直接返回 404
+    if cache.is_repo_info_not_found(owner, repo).await {
+        log::debug!("负缓存命中，仓库不存在: {}/{}", owner, repo);
+        cache_metrics().record_negative_hit();
+        return Err(AppError::NotFound);
+    }
 
-    let mut request = client
+    let raw = request_raw_repo(owner, repo).await?;
+    check_raw_repo_visibility(owner, repo, raw, private_repos_enabled())
+}
+
+// 跳过缓存读取，强制从 GitHub 获取原始仓库 JSON（结果仍会写入缓存）
+pub async fn refresh_raw_repo(owner: &str, repo: &str) -> Result<serde_json::Value, AppError> {
+    let raw = request_raw_repo(owner, repo).await?;
+    check_raw_repo_visibility(owner, repo, raw, private_repos_enabled())
+}
+
+// 从 GitHub API 获取原始仓库 JSON 并存入缓存（只请求仓库端点，不代理任意路径）
+async fn request_raw_repo(owner: &str, repo: &str) -> Result<serde_json::Value, AppError> {
+    let cache = get_cache_manager().await;
+    let backend = get_cache_backend().await;
+    log::debug!("从 GitHub API 获取原始仓库信息: {}/{}", owner, repo);
+    let api_url = format!("{}/repos/{}/{}", github_api_url(), owner, repo);
+
+    let mut request = create_client()
         .get(&api_url)
         .header("User-Agent", "gh-info-rs")
         .header("Accept", "application/vnd.github.v3+json");
-
     if let Some(token) = get_github_token() {
         request = request.header("Authorization", format!("Bearer {}", token));
     }
 
-    let response = request.send().await?;
-
-    if !response.status().is_success() {
-        if response.status().as_u16() == 404 {
+    let raw = match send_conditional_request::<serde_json::Value>(request, None, false).await {
+        Ok(Conditional::Modified { value, .. }) => value,
+        Ok(Conditional::NotModified) => {
+            return Err(AppError::ApiError("GitHub API 返回状态码: 304 Not Modified".to_string()))
+        }
+        Err(AppError::NotFound) => {
+            cache.set_repo_info_not_found(owner, repo).await;
             return Err(AppError::NotFound);
         }
-        return Err(AppError::ApiError(format!(
-            "GitHub API 返回状态码: {}",
-            response.status()
+        Err(e) => return Err(e),
+    };
+
+    backend.set_raw_repo(owner, repo, raw.clone()).await;
+    log::debug!("成功获取并缓存原始仓库信息: {}/{}", owner, repo);
+    Ok(raw)
+}
+
+// 原始仓库 JSON 中 private 为 true 且未启用 PRIVATE_REPOS_ENABLED 时返回 403
+fn check_raw_repo_visibility(
+    owner: &str,
+    repo: &str,
+    raw: serde_json::Value,
+    private_repos_enabled: bool,
+) -> Result<serde_json::Value, AppError> {
+    if raw["private"].as_bool() == Some(true) && !private_repos_enabled {
+        return Err(AppError::Forbidden(format!(
+            "{}/{} 是私有仓库，服务未启用 PRIVATE_REPOS_ENABLED",
+            owner, repo
         )));
     }
+    Ok(raw)
+}
 
-    let releases: Vec<GithubRelease> = response.json().await?;
-
-    let release_infos: Vec<ReleaseInfo> = releases
-        .into_iter()
-        .map(|r| ReleaseInfo {
-            tag_name: r.tag_name,
-            name: r.name,
-            changelog: r.body,
-            published_at: r.published_at,
-            prerelease: r.prerelease,
-            attachments: r
-                .assets
-                .into_iter()
-                .map(|a| a.download_url)
-                .collect(),
-        })
-        .collect();
+// 获取仓库中指定路径的内容（带缓存），返回 GitHub contents API 的原始 JSON：文件为对象，目录为数组
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo), path = %path)))]
+pub async fn fetch_contents(owner: &str, repo: &str, path: &str, git_ref: Option<&str>) -> Result<serde_json::Value, AppError> {
+    ensure_repo_visible(owner, repo).await?;
+    let backend = get_cache_backend().await;
 
-    // 存入缓存
-    cache.set_releases(owner, repo, release_infos.clone()).await;
-    log::debug!("成功获取并缓存 releases: {}/{} (共 {} 个)", owner, repo, release_infos.len());
+    let cached_contents = backend.get_contents(owner, repo, path, git_ref).await;
+    cache_metrics().record_lookup(CacheKind::Contents, cached_contents.is_some());
+    if let Some(cached_contents) = cached_contents {
+        log::debug!("从缓存获取文件内容: {}/{}/{}", owner, repo, path);
+        return Ok(cached_contents);
+    }
 
-    Ok(release_infos)
+    request_contents(owner, repo, path, git_ref).await
 }
 
-// 获取最新 release
-pub async fn fetch_latest_release(owner: &str, repo: &str) -> Result<LatestReleaseInfo, AppError> {
-    let cache = get_cache_manager().await;
+// 跳过缓存读取，强制从 GitHub 获取文件内容（结果仍会写入缓存）
+pub async fn refresh_contents(owner: &str, repo: &str, path: &str, git_ref: Option<&str>) -> Result<serde_json::Value, AppError> {
+    ensure_repo_visible(owner, repo).await?;
+    request_contents(owner, repo, path, git_ref).await
+}
 
-    // 先尝试从缓存获取
-    if let Some(cached_release) = cache.get_latest_release(owner, repo).await {
-        log::debug!("从缓存获取最新 release: {}/{} (版本: {})", owner, repo, cached_release.latest_version);
-        return Ok(cached_release);
+// 构造 contents API 的地址，路径的每一段单独编码；
+// 拒绝 `.` 和 `..`，避免路径被规范化后指向 contents 以外的 GitHub API
+fn contents_api_url(owner: &str, repo: &str, path: &str, git_ref: Option<&str>) -> Result<reqwest::Url, AppError> {
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    if segments.iter().any(|segment| *segment == "." || *segment == "..") {
+        return Err(AppError::BadRequest("路径不能包含 . 或 .. 段".to_string()));
+    }
+    let mut url = reqwest::Url::parse(&format!("{}/repos", github_api_url())).expect("GitHub API 地址有效");
+    url.path_segments_mut()
+        .expect("GitHub API 地址可以追加路径")
+        .extend([owner, repo, "contents"])
+        .extend(segments);
+    if let Some(git_ref) = git_ref {
+        url.query_pairs_mut().append_pair("ref", git_ref);
     }
+    Ok(url)
+}
 
-    // 缓存未命中，从 API 获取
-    log::debug!("从 GitHub API 获取最新 release: {}/{}", owner, repo);
-    let client = create_client();
-    let api_url = format!(
-        "https://api.github.com/repos/{}/{}/releases/latest",
-        owner, repo
-    );
+// 从 GitHub contents API 获取文件或目录并存入缓存
+async fn request_contents(owner: &str, repo: &str, path: &str, git_ref: Option<&str>) -> Result<serde_json::Value, AppError> {
+    let backend = get_cache_backend().await;
+    log::debug!("从 GitHub API 获取文件内容: {}/{}/{} (ref: {:?})", owner, repo, path, git_ref);
+    let api_url = contents_api_url(owner, repo, path, git_ref)?;
 
-    let mut request = client
-        .get(&api_url)
+    let mut request = create_client()
+        .get(api_url)
         .header("User-Agent", "gh-info-rs")
         .header("Accept", "application/vnd.github.v3+json");
-
     if let Some(token) = get_github_token() {
         request = request.header("Authorization", format!("Bearer {}", token));
     }
 
-    let response = request.send().await?;
-
-    if !response.status().is_success() {
-        if response.status().as_u16() == 404 {
-            return Err(AppError::NotFound);
+    let contents = match send_conditional_request::<serde_json::Value>(request, None, false).await? {
+        Conditional::Modified { value, .. } => value,
+        Conditional::NotModified => {
+            return Err(AppError::ApiError("GitHub API 返回状态码: 304 Not Modified".to_string()))
         }
-        return Err(AppError::ApiError(format!(
-            "GitHub API 返回状态码: {}",
-            response.status()
-        )));
+    };
+
+    backend.set_contents(owner, repo, path, git_ref, contents.clone()).await;
+    log::debug!("成功获取并缓存文件内容: {}/{}/{}", owner, repo, path);
+    Ok(contents)
+}
+
+// 解码 contents API 返回的文件，返回（文件名, 内容）；目录、符号链接和子模块返回 None，直接输出原始 JSON
+// 超过 1MB 的文件 GitHub 不返回内容（encoding 为 none），需要改用 blobs API
+fn decode_file_contents(path: &str, contents: &serde_json::Value) -> Result<Option<(String, Vec<u8>)>, AppError> {
+    if contents["type"].as_str() != Some("file") {
+        return Ok(None);
+    }
+    let name = contents["name"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| path.rsplit('/').next().unwrap_or(path).to_string());
+    if contents["encoding"].as_str() != Some("base64") {
+        return Err(AppError::FileTooLarge {
+            path: path.to_string(),
+            size: contents["size"].as_u64().unwrap_or(0),
+        });
     }
+    // GitHub 每 60 个字符插入一个换行
+    let encoded: String = contents["content"]
+        .as_str()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| AppError::ApiError(format!("GitHub 返回的文件内容无法解码: {}", e)))?;
+    Ok(Some((name, bytes)))
+}
 
-    let release: GithubRelease = response.json().await?;
+// 获取 Release 反应统计时需要使用的 Accept 头
+const REACTIONS_ACCEPT: &str = "application/vnd.github.squirrel-girl-preview+json";
 
-    let latest_release = LatestReleaseInfo {
-        repo: format!("{}/{}", owner, repo),
-        latest_version: release.tag_name,
-        changelog: release.body,
+// 将 GitHub 返回的反应统计转换为表情计数 Map（去掉 url 和 total_count）
+fn reaction_counts(reactions: HashMap<String, serde_json::Value>) -> HashMap<String, u32> {
+    reactions
+        .into_iter()
+        .filter(|(key, _)| key != "url" && key != "total_count")
+        .filter_map(|(key, value)| value.as_u64().map(|count| (key, count as u32)))
+        .collect()
+}
+
+// changelog 的最大字节数（MAX_CHANGELOG_BYTES），未设置或为 0 时不截断
+fn max_changelog_bytes() -> Option<usize> {
+    static MAX_CHANGELOG_BYTES: OnceLock<Option<usize>> = OnceLock::new();
+    *MAX_CHANGELOG_BYTES.get_or_init(|| {
+        dotenv::dotenv().ok();
+        env::var("MAX_CHANGELOG_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+    })
+}
+
+// changelog 被截断时追加的标记
+const CHANGELOG_TRUNCATED_MARKER: &str = "…(truncated)";
+
+// 将 changelog 截断到 max_bytes 字节以内（在 UTF-8 字符边界处截断）并追加截断标记，返回是否发生了截断
+fn truncate_changelog(mut changelog: String, max_bytes: usize) -> (String, bool) {
+    if changelog.len() <= max_bytes {
+        return (changelog, false);
+    }
+    let mut end = max_bytes;
+    while !changelog.is_char_boundary(end) {
+        end -= 1;
+    }
+    changelog.truncate(end);
+    changelog.push_str(CHANGELOG_TRUNCATED_MARKER);
+    (changelog, true)
+}
+
+// 将 GitHub Release 转换为 ReleaseInfo
+pub(crate) fn to_release_info(release: GithubRelease, include_reactions: bool) -> ReleaseInfo {
+    let (author_login, author_avatar_url) = match release.author {
+        Some(author) => (Some(author.login), Some(author.avatar_url)),
+        None => (None, None),
+    };
+
+    let (changelog, changelog_truncated) = match (release.body, max_changelog_bytes()) {
+        (Some(body), Some(max_bytes)) => {
+            let (body, truncated) = truncate_changelog(body, max_bytes);
+            (Some(body), truncated)
+        }
+        (body, _) => (body, false),
+    };
+
+    ReleaseInfo {
+        tag_name: release.tag_name,
+        name: release.name,
+        changelog,
+        changelog_truncated,
         published_at: release.published_at,
         prerelease: release.prerelease,
+        draft: release.draft,
+        attachment_sizes: release.assets.iter().map(|a| a.size).collect(),
         attachments: release
             .assets
             .into_iter()
             .map(|a| a.download_url)
             .collect(),
-    };
+        author_login,
+        author_avatar_url,
+        reactions: if include_reactions {
+            release.reactions.map(reaction_counts)
+        } else {
+            None
+        },
+        zipball_url: release.zipball_url,
+        tarball_url: release.tarball_url,
+    }
+}
 
-    // 存入缓存
-    cache
-        .set_latest_release(owner, repo, latest_release.clone())
-        .await;
-    log::debug!("成功获取并缓存最新 release: {}/{} (版本: {})", owner, repo, latest_release.latest_version);
+// 将 ReleaseInfo 转换为 LatestReleaseInfo
+pub(crate) fn to_latest_release_info(owner: &str, repo: &str, release: ReleaseInfo) -> LatestReleaseInfo {
+    LatestReleaseInfo {
+        repo: format!("{}/{}", owner, repo),
+        latest_version: release.tag_name,
+        changelog: release.changelog,
+        changelog_truncated: release.changelog_truncated,
+        published_at: release.published_at,
+        prerelease: release.prerelease,
+        attachments: release.attachments,
+        attachment_sizes: release.attachment_sizes,
+        author_login: release.author_login,
+        author_avatar_url: release.author_avatar_url,
+        reactions: release.reactions,
+        zipball_url: release.zipball_url,
+        tarball_url: release.tarball_url,
+    }
+}
+
+// 去掉版本号开头的单个 `v`/`V`（如 `v1.2.3` -> `1.2.3`）
+//
+// 只有去掉后剩余部分是有效的版本号（`数字(.数字)*`，可带 `-预发布` 或 `+构建` 后缀）时才去掉，
+// 避免误处理 `vendor-2.0` 这类非版本 tag。
+pub fn strip_version_prefix(tag: &str) -> &str {
+    match tag.strip_prefix(['v', 'V']) {
+        Some(rest) if is_version_number(rest) => rest,
+        _ => tag,
+    }
+}
+
+// 判断字符串是否为版本号：`数字(.数字)*`，后面可以跟 `-` 或 `+` 开头的非空后缀
+fn is_version_number(s: &str) -> bool {
+    let core_end = s.find(['-', '+']).unwrap_or(s.len());
+    let (core, suffix) = s.split_at(core_end);
+    let core_valid = core
+        .split('.')
+        .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    core_valid && suffix.len() != 1
+}
+
+// 按需去掉 Tauri latest.json 中 `version` 字段开头的 `v`
+fn strip_tauri_version(mut json: serde_json::Value, strip_v: bool) -> serde_json::Value {
+    if strip_v {
+        if let Some(version) = json.get_mut("version") {
+            if let Some(stripped) = version.as_str().map(|v| strip_version_prefix(v).to_string()) {
+                *version = serde_json::Value::String(stripped);
+            }
+        }
+    }
+    json
+}
+
+// 从 GitHub API 请求 release 数据（不经过缓存）
+#[cfg_attr(
+    feature = "otel",
+    tracing::instrument(skip_all, fields(url = %api_url, github.status = tracing::field::Empty))
+)]
+async fn request_github_release_api<T: serde::de::DeserializeOwned>(
+    api_url: &str,
+    include_reactions: bool,
+) -> Result<T, AppError> {
+    match request_github_release_api_conditional(api_url, include_reactions, None).await? {
+        Conditional::Modified { value, .. } => Ok(value),
+        Conditional::NotModified => Err(AppError::ApiError(
+            "GitHub API 返回状态码: 304 Not Modified".to_string(),
+        )),
+    }
+}
+
+// 从 GitHub API 请求 release 数据，提供 validators 时发送条件请求
+async fn request_github_release_api_conditional<T: serde::de::DeserializeOwned>(
+    api_url: &str,
+    include_reactions: bool,
+    validators: Option<&Validators>,
+) -> Result<Conditional<T>, AppError> {
+    let client = create_client();
+    let accept = if include_reactions {
+        REACTIONS_ACCEPT
+    } else {
+        "application/vnd.github.v3+json"
+    };
+
+    let mut request = client
+        .get(api_url)
+        .header("User-Agent", "gh-info-rs")
+        .header("Accept", accept);
+
+    if let Some(token) = get_github_token() {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    send_conditional_request(request, validators, conditional_responses_enabled()).await
+}
+
+// 获取所有 releases
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn fetch_releases(owner: &str, repo: &str) -> Result<Vec<ReleaseInfo>, AppError> {
+    ensure_repo_visible(owner, repo).await?;
+    let cache = get_cache_manager().await;
+    let backend = get_cache_backend().await;
+
+    // 先尝试从缓存获取
+    let cached_releases = backend.get_releases(owner, repo).await;
+    cache_metrics().record_lookup(CacheKind::Releases, cached_releases.is_some());
+    if let Some(cached_releases) = cached_releases {
+        log::debug!("从缓存获取 releases: {}/{} (共 {} 个)", owner, repo, cached_releases.len());
+        return Ok(cached_releases);
+    }
+
+    // 近期已确认仓库不存在时直接返回 404（负缓存）
+    if cache.is_releases_not_found(owner, repo).await {
+        log::debug!("负缓存命中，releases 不存在: {}/{}", owner, repo);
+        cache_metrics().record_negative_hit();
+        return Err(AppError::NotFound);
+    }
+
+    // 缓存未命中，从 API 获取（并发的相同请求会被合并为一次）
+    let (owner_owned, repo_owned) = (owner.to_string(), repo.to_string());
+    let result = cache
+        .coalesce_releases(owner, repo, async move {
+            request_releases(&owner_owned, &repo_owned).await
+        })
+        .await;
+    match result {
+        // GitHub 故障时退回使用保留期内的过期数据
+        Err(e) if e.is_transient() => match cache.outage_releases(owner, repo).await {
+            Some(stale) => {
+                log::warn!("获取 releases 失败（{}），使用已过期的缓存: {}/{}", e, owner, repo);
+                Ok(stale)
+            }
+            None => Err(e),
+        },
+        result => result,
+    }
+}
+
+// 跳过缓存读取，强制从 GitHub 获取所有 releases（结果仍会写入缓存）
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn refresh_releases(owner: &str, repo: &str) -> Result<Vec<ReleaseInfo>, AppError> {
+    ensure_repo_visible(owner, repo).await?;
+    let cache = get_cache_manager().await;
+    let (owner_owned, repo_owned) = (owner.to_string(), repo.to_string());
+    cache
+        .coalesce_releases(owner, repo, async move {
+            request_releases(&owner_owned, &repo_owned).await
+        })
+        .await
+}
+
+// 从 GitHub API 获取所有 releases 并存入缓存
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+async fn request_releases(owner: &str, repo: &str) -> Result<Vec<ReleaseInfo>, AppError> {
+    let cache = get_cache_manager().await;
+    let backend = get_cache_backend().await;
+    log::debug!("从 GitHub API 获取 releases: {}/{}", owner, repo);
+    let api_url = format!("{}/repos/{}/{}/releases", github_api_url(), owner, repo);
+
+    // 缓存中有带 Last-Modified 或 ETag 的旧数据时发送条件请求
+    let stale = cache.revalidatable_releases(owner, repo).await;
+    let validators = stale.as_ref().map(|(_, validators)| validators);
+    let result = request_github_release_api_conditional::<Vec<GithubRelease>>(
+        &api_url,
+        false,
+        validators,
+    )
+    .await;
+    let (release_infos, validators) = match result {
+        Ok(Conditional::Modified { value, validators }) => {
+            let release_infos: Vec<ReleaseInfo> = value
+                .into_iter()
+                .map(|r| to_release_info(r, false))
+                .collect();
+            (release_infos, validators)
+        }
+        Ok(Conditional::NotModified) => {
+            log::debug!("releases 未变化（304），继续使用缓存: {}/{}", owner, repo);
+            stale.expect("只有发送条件请求时才会返回 304")
+        }
+        Err(AppError::NotFound) => {
+            cache.set_releases_not_found(owner, repo).await;
+            return Err(AppError::NotFound);
+        }
+        Err(e) => return Err(e),
+    };
+
+    // 存入缓存
+    backend.set_releases(owner, repo, release_infos.clone()).await;
+    if validators != Validators::default() {
+        cache.set_releases_validators(owner, repo, validators).await;
+    }
+    log::debug!("成功获取并缓存 releases: {}/{} (共 {} 个)", owner, repo, release_infos.len());
+
+    Ok(release_infos)
+}
+
+// 获取所有 releases（包含反应统计，反应计数变化频繁，因此不使用缓存）
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn fetch_releases_with_reactions(
+    owner: &str,
+    repo: &str,
+) -> Result<Vec<ReleaseInfo>, AppError> {
+    ensure_repo_visible(owner, repo).await?;
+    log::debug!("从 GitHub API 获取 releases（包含反应统计）: {}/{}", owner, repo);
+    let api_url = format!("{}/repos/{}/{}/releases", github_api_url(), owner, repo);
+    let releases: Vec<GithubRelease> = request_github_release_api(&api_url, true).await?;
+
+    Ok(releases
+        .into_iter()
+        .map(|r| to_release_info(r, true))
+        .collect())
+}
+
+// 获取最新 release
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn fetch_latest_release(owner: &str, repo: &str) -> Result<LatestReleaseInfo, AppError> {
+    ensure_repo_visible(owner, repo).await?;
+    let cache = get_cache_manager().await;
+    let backend = get_cache_backend().await;
+
+    // 先尝试从缓存获取
+    let cached_release = backend.get_latest_release(owner, repo).await;
+    cache_metrics().record_lookup(CacheKind::LatestRelease, cached_release.is_some());
+    if let Some(cached_release) = cached_release {
+        log::debug!("从缓存获取最新 release: {}/{} (版本: {})", owner, repo, cached_release.latest_version);
+        return Ok(cached_release);
+    }
+
+    // 近期已确认仓库不存在或没有 release 时直接返回 404（负缓存）
+    if cache.is_latest_release_not_found(owner, repo).await {
+        log::debug!("负缓存命中，最新 release 不存在: {}/{}", owner, repo);
+        cache_metrics().record_negative_hit();
+        return Err(classify_missing_release(owner, repo).await);
+    }
+
+    // 缓存未命中，从 API 获取（并发的相同请求会被合并为一次）
+    let (owner_owned, repo_owned) = (owner.to_string(), repo.to_string());
+    let result = cache
+        .coalesce_latest_release(owner, repo, async move {
+            request_latest_release(&owner_owned, &repo_owned).await
+        })
+        .await;
+    match result {
+        Err(AppError::NotFound) => Err(classify_missing_release(owner, repo).await),
+        // GitHub 故障时退回使用保留期内的过期数据
+        Err(e) if e.is_transient() => match cache.outage_latest_release(owner, repo).await {
+            Some(stale) => {
+                log::warn!("获取最新 release 失败（{}），使用已过期的缓存: {}/{}", e, owner, repo);
+                Ok(stale)
+            }
+            None => Err(e),
+        },
+        result => result,
+    }
+}
+
+// GitHub 对仓库不存在和仓库没有 release 都返回 404，通过（缓存的）仓库信息区分两者：
+// 仓库存在时返回 NoReleases，仓库不存在或无法确认时返回 NotFound
+async fn classify_missing_release(owner: &str, repo: &str) -> AppError {
+    match fetch_repo_info(owner, repo).await {
+        Ok(_) => AppError::NoReleases,
+        Err(_) => AppError::NotFound,
+    }
+}
+
+// 跳过缓存读取，强制从 GitHub 获取最新 release（结果仍会写入缓存）
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn refresh_latest_release(owner: &str, repo: &str) -> Result<LatestReleaseInfo, AppError> {
+    ensure_repo_visible(owner, repo).await?;
+    let cache = get_cache_manager().await;
+    let (owner_owned, repo_owned) = (owner.to_string(), repo.to_string());
+    let result = cache
+        .coalesce_latest_release(owner, repo, async move {
+            request_latest_release(&owner_owned, &repo_owned).await
+        })
+        .await;
+    match result {
+        Err(AppError::NotFound) => Err(classify_missing_release(owner, repo).await),
+        result => result,
+    }
+}
+
+// 从 GitHub API 获取最新 release 并存入缓存
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+async fn request_latest_release(owner: &str, repo: &str) -> Result<LatestReleaseInfo, AppError> {
+    let cache = get_cache_manager().await;
+    let backend = get_cache_backend().await;
+    log::debug!("从 GitHub API 获取最新 release: {}/{}", owner, repo);
+    let api_url = format!(
+        "{}/repos/{}/{}/releases/latest",
+        github_api_url(),
+        owner, repo
+    );
+    let release: GithubRelease = match request_github_release_api(&api_url, false).await {
+        Err(AppError::NotFound) => {
+            cache.set_latest_release_not_found(owner, repo).await;
+            return Err(AppError::NotFound);
+        }
+        result => result?,
+    };
+
+    let latest_release = to_latest_release_info(owner, repo, to_release_info(release, false));
+
+    // 存入缓存
+    backend
+        .set_latest_release(owner, repo, latest_release.clone())
+        .await;
+    log::debug!("成功获取并缓存最新 release: {}/{} (版本: {})", owner, repo, latest_release.latest_version);
 
     Ok(latest_release)
 }
 
+// 获取最新 release（包含反应统计，不使用缓存）
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn fetch_latest_release_with_reactions(
+    owner: &str,
+    repo: &str,
+) -> Result<LatestReleaseInfo, AppError> {
+    ensure_repo_visible(owner, repo).await?;
+    log::debug!("从 GitHub API 获取最新 release（包含反应统计）: {}/{}", owner, repo);
+    let api_url = format!(
+        "{}/repos/{}/{}/releases/latest",
+        github_api_url(),
+        owner, repo
+    );
+    let release: GithubRelease = match request_github_release_api(&api_url, true).await {
+        Err(AppError::NotFound) => return Err(classify_missing_release(owner, repo).await),
+        result => result?,
+    };
+
+    Ok(to_latest_release_info(owner, repo, to_release_info(release, true)))
+}
+
+// 按发布时间过滤 releases（边界包含在内），指定了时间范围时排除没有发布时间的 release
+fn filter_releases_by_date(
+    releases: Vec<ReleaseInfo>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Vec<ReleaseInfo> {
+    if since.is_none() && until.is_none() {
+        return releases;
+    }
+    releases
+        .into_iter()
+        .filter(|r| match r.published_at {
+            Some(published_at) => {
+                since.is_none_or(|since| published_at >= since)
+                    && until.is_none_or(|until| published_at <= until)
+            }
+            None => false,
+        })
+        .collect()
+}
+
+// 从 releases 列表中选出最新的一个（包括 pre-release）
+fn select_latest_release(
+    owner: &str,
+    repo: &str,
+    releases: Vec<ReleaseInfo>,
+) -> Result<LatestReleaseInfo, AppError> {
+    // 按发布时间排序，取最新的一个（没有发布时间的草稿 release 排在最后）；
+    // 能获取到 releases 列表说明仓库存在，列表为空时返回 NoReleases
+    let latest = releases
+        .into_iter()
+        .max_by_key(|r| r.published_at)
+        .ok_or(AppError::NoReleases)?;
+
+    Ok(to_latest_release_info(owner, repo, latest))
+}
+
+// 从 releases 列表中选出最新的正式版（跳过 pre-release 和草稿）
+// 列表为空时返回 NoReleases，有 release 但都不是正式版时返回 NoStableRelease
+fn select_stable_release(
+    owner: &str,
+    repo: &str,
+    releases: Vec<ReleaseInfo>,
+) -> Result<LatestReleaseInfo, AppError> {
+    if releases.is_empty() {
+        return Err(AppError::NoReleases);
+    }
+    // 旧缓存数据没有 draft 字段，没有发布时间的 release 同样视为草稿
+    let latest = releases
+        .into_iter()
+        .filter(|r| !r.prerelease && !r.draft && r.published_at.is_some())
+        .max_by_key(|r| r.published_at)
+        .ok_or(AppError::NoStableRelease)?;
+
+    Ok(to_latest_release_info(owner, repo, latest))
+}
+
+// 获取最新正式版 release（基于缓存的 releases 列表）
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn fetch_latest_stable_release(owner: &str, repo: &str) -> Result<LatestReleaseInfo, AppError> {
+    let releases = fetch_releases(owner, repo).await?;
+    select_stable_release(owner, repo, releases)
+}
+
+// 获取最新正式版 release（包含反应统计，不使用缓存）
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn fetch_latest_stable_release_with_reactions(
+    owner: &str,
+    repo: &str,
+) -> Result<LatestReleaseInfo, AppError> {
+    let releases = fetch_releases_with_reactions(owner, repo).await?;
+    select_stable_release(owner, repo, releases)
+}
+
 // 获取最新 release（包括 pre-release）
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
 pub async fn fetch_latest_release_pre(owner: &str, repo: &str) -> Result<LatestReleaseInfo, AppError> {
+    let releases = fetch_releases(owner, repo).await?;
+    select_latest_release(owner, repo, releases)
+}
+
+// 获取最新 release（包括 pre-release，包含反应统计，不使用缓存）
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn fetch_latest_release_pre_with_reactions(
+    owner: &str,
+    repo: &str,
+) -> Result<LatestReleaseInfo, AppError> {
+    let releases = fetch_releases_with_reactions(owner, repo).await?;
+    select_latest_release(owner, repo, releases)
+}
+
+// 获取两个版本（tag/分支/提交）之间的差异
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn fetch_compare(
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+) -> Result<CompareInfo, AppError> {
+    ensure_repo_visible(owner, repo).await?;
     let cache = get_cache_manager().await;
+    let backend = get_cache_backend().await;
 
-    // 先尝试从缓存获取所有releases
-    let releases = if let Some(cached_releases) = cache.get_releases(owner, repo).await {
-        log::debug!("从缓存获取 releases: {}/{} (共 {} 个)", owner, repo, cached_releases.len());
-        cached_releases
-    } else {
-        // 缓存未命中，从 API 获取
-        log::debug!("从 GitHub API 获取 releases: {}/{}", owner, repo);
-        let client = create_client();
-        let api_url = format!("https://api.github.com/repos/{}/{}/releases", owner, repo);
+    // 先尝试从缓存获取
+    let cached_compare = backend.get_compare(owner, repo, base, head).await;
+    cache_metrics().record_lookup(CacheKind::Compare, cached_compare.is_some());
+    if let Some(cached_compare) = cached_compare {
+        log::debug!("从缓存获取版本对比: {}/{} ({}...{})", owner, repo, base, head);
+        return Ok(cached_compare);
+    }
+
+    // 缓存未命中，从 API 获取（并发的相同请求会被合并为一次）
+    let (owner_owned, repo_owned) = (owner.to_string(), repo.to_string());
+    let (base_owned, head_owned) = (base.to_string(), head.to_string());
+    cache
+        .coalesce_compare(owner, repo, base, head, async move {
+            request_compare(&owner_owned, &repo_owned, &base_owned, &head_owned).await
+        })
+        .await
+}
+
+// 从 GitHub API 获取版本对比信息并存入缓存
+#[cfg_attr(
+    feature = "otel",
+    tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo), github.status = tracing::field::Empty))
+)]
+async fn request_compare(
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+) -> Result<CompareInfo, AppError> {
+    let backend = get_cache_backend().await;
+    log::debug!("从 GitHub API 获取版本对比: {}/{} ({}...{})", owner, repo, base, head);
+    let client = create_client();
+    let api_url = format!(
+        "{}/repos/{}/{}/compare/{}...{}",
+        github_api_url(),
+        owner, repo, base, head
+    );
+
+    let mut request = client
+        .get(&api_url)
+        .header("User-Agent", "gh-info-rs")
+        .header("Accept", "application/vnd.github.v3+json");
+
+    if let Some(token) = get_github_token() {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = send_github_api_request(request).await?;
+
+    if !response.status().is_success() {
+        // 仓库不存在或 base/head 引用不存在时 GitHub 返回 404
+        if response.status().as_u16() == 404 {
+            return Err(AppError::NotFound);
+        }
+        return Err(AppError::UpstreamStatus(response.status()));
+    }
+
+    let github_compare: GithubCompare = response.json().await?;
+
+    let compare_info = CompareInfo {
+        repo: format!("{}/{}", owner, repo),
+        base: base.to_string(),
+        head: head.to_string(),
+        status: github_compare.status,
+        ahead_by: github_compare.ahead_by,
+        behind_by: github_compare.behind_by,
+        total_commits: github_compare.total_commits,
+        commits: github_compare
+            .commits
+            .into_iter()
+            .map(|c| c.commit.message)
+            .collect(),
+    };
+
+    // 存入缓存
+    backend
+        .set_compare(owner, repo, base, head, compare_info.clone())
+        .await;
+    log::debug!("成功获取并缓存版本对比: {}/{} ({}...{})", owner, repo, base, head);
+
+    Ok(compare_info)
+}
+
+// 获取仓库的里程碑列表（state 为 open、closed 或 all），没有里程碑时返回空列表
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn fetch_milestones(
+    owner: &str,
+    repo: &str,
+    state: &str,
+) -> Result<Vec<MilestoneInfo>, AppError> {
+    ensure_repo_visible(owner, repo).await?;
+    let backend = get_cache_backend().await;
+
+    // 先尝试从缓存获取
+    let cached_milestones = backend.get_milestones(owner, repo, state).await;
+    cache_metrics().record_lookup(CacheKind::Milestones, cached_milestones.is_some());
+    if let Some(cached_milestones) = cached_milestones {
+        log::debug!("从缓存获取里程碑: {}/{} ({})", owner, repo, state);
+        return Ok(cached_milestones);
+    }
+
+    // 缓存未命中，从 API 获取（并发的相同请求会被合并为一次）
+    let (owner_owned, repo_owned, state_owned) = (owner.to_string(), repo.to_string(), state.to_string());
+    get_cache_manager()
+        .await
+        .coalesce_milestones(owner, repo, state, async move {
+            request_milestones(&owner_owned, &repo_owned, &state_owned).await
+        })
+        .await
+}
+
+// 里程碑列表最多获取的页数（每页 100 个），超出的里程碑不返回
+const MILESTONES_MAX_PAGES: usize = 10;
+
+// 从 GitHub API 获取里程碑列表并存入缓存，按 Link 头的 rel="next" 翻页，最多获取 MILESTONES_MAX_PAGES 页
+#[cfg_attr(
+    feature = "otel",
+    tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo), github.status = tracing::field::Empty))
+)]
+async fn request_milestones(
+    owner: &str,
+    repo: &str,
+    state: &str,
+) -> Result<Vec<MilestoneInfo>, AppError> {
+    let backend = get_cache_backend().await;
+    log::debug!("从 GitHub API 获取里程碑: {}/{} ({})", owner, repo, state);
+    let client = create_client();
+    let mut api_url = format!(
+        "{}/repos/{}/{}/milestones?state={}&per_page=100",
+        github_api_url(),
+        owner, repo, state
+    );
 
+    let mut milestones: Vec<MilestoneInfo> = Vec::new();
+    for page in 1..=MILESTONES_MAX_PAGES {
         let mut request = client
             .get(&api_url)
             .header("User-Agent", "gh-info-rs")
@@ -233,64 +1440,110 @@ pub async fn fetch_latest_release_pre(owner: &str, repo: &str) -> Result<LatestR
             request = request.header("Authorization", format!("Bearer {}", token));
         }
 
-        let response = request.send().await?;
+        let response = send_github_api_request(request).await?;
 
         if !response.status().is_success() {
             if response.status().as_u16() == 404 {
                 return Err(AppError::NotFound);
             }
-            return Err(AppError::ApiError(format!(
-                "GitHub API 返回状态码: {}",
-                response.status()
-            )));
+            return Err(AppError::UpstreamStatus(response.status()));
         }
 
-        let github_releases: Vec<GithubRelease> = response.json().await?;
+        let next_url = next_page_url(response.headers());
+        let page_milestones: Vec<MilestoneInfo> = response.json().await?;
+        milestones.extend(page_milestones);
+        match next_url {
+            Some(next_url) if page < MILESTONES_MAX_PAGES => api_url = next_url,
+            Some(_) => log::warn!(
+                "里程碑超过 {} 页，只返回前 {} 个: {}/{} ({})",
+                MILESTONES_MAX_PAGES,
+                milestones.len(),
+                owner,
+                repo,
+                state
+            ),
+            None => break,
+        }
+    }
 
-        let release_infos: Vec<ReleaseInfo> = github_releases
-            .into_iter()
-            .map(|r| ReleaseInfo {
-                tag_name: r.tag_name,
-                name: r.name,
-                changelog: r.body,
-                published_at: r.published_at,
-                prerelease: r.prerelease,
-                attachments: r
-                    .assets
-                    .into_iter()
-                    .map(|a| a.download_url)
-                    .collect(),
-            })
-            .collect();
+    // 存入缓存（空列表同样缓存，避免没有里程碑的仓库每次都请求 GitHub）
+    backend
+        .set_milestones(owner, repo, state, milestones.clone())
+        .await;
+    log::debug!("成功获取并缓存里程碑: {}/{} ({}, {} 个)", owner, repo, state, milestones.len());
 
-        // 存入缓存
-        cache.set_releases(owner, repo, release_infos.clone()).await;
-        log::debug!("成功获取并缓存 releases: {}/{} (共 {} 个)", owner, repo, release_infos.len());
+    Ok(milestones)
+}
 
-        release_infos
+// 通过 GitHub API 生成两个 tag 之间的 release notes（需要 GITHUB_TOKEN）
+#[cfg_attr(
+    feature = "otel",
+    tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo), github.status = tracing::field::Empty))
+)]
+pub async fn fetch_generated_notes(
+    owner: &str,
+    repo: &str,
+    tag: &str,
+    previous_tag: Option<&str>,
+) -> Result<GeneratedNotes, AppError> {
+    // 生成接口不支持匿名访问，未配置 token 时直接返回明确的错误
+    let Some(token) = get_github_token() else {
+        return Err(AppError::Forbidden(
+            "服务端未配置 GITHUB_TOKEN，无法生成 release notes".to_string(),
+        ));
     };
+    ensure_repo_visible(owner, repo).await?;
 
-    // 找到最新的release（包括pre-release）
-    if releases.is_empty() {
-        return Err(AppError::NotFound);
+    let backend = get_cache_backend().await;
+    let cached_notes = backend.get_generated_notes(owner, repo, tag, previous_tag).await;
+    cache_metrics().record_lookup(CacheKind::GeneratedNotes, cached_notes.is_some());
+    if let Some(cached_notes) = cached_notes {
+        log::debug!("从缓存获取生成的 release notes: {}/{} ({})", owner, repo, tag);
+        return Ok(cached_notes);
     }
 
-    // 按发布时间排序，最新的在前
-    let latest = releases
-        .into_iter()
-        .max_by_key(|r| r.published_at.clone())
-        .unwrap();
+    log::debug!("从 GitHub API 生成 release notes: {}/{} ({})", owner, repo, tag);
+    let client = create_client();
+    let api_url = format!(
+        "{}/repos/{}/{}/releases/generate-notes",
+        github_api_url(),
+        owner, repo
+    );
+    let mut body = serde_json::json!({ "tag_name": tag });
+    if let Some(previous_tag) = previous_tag {
+        body["previous_tag_name"] = serde_json::Value::String(previous_tag.to_string());
+    }
 
-    let latest_release = LatestReleaseInfo {
-        repo: format!("{}/{}", owner, repo),
-        latest_version: latest.tag_name,
-        changelog: latest.changelog,
-        published_at: latest.published_at,
-        prerelease: latest.prerelease,
-        attachments: latest.attachments,
-    };
+    let request = client
+        .post(&api_url)
+        .header("User-Agent", "gh-info-rs")
+        .header("Accept", "application/vnd.github.v3+json")
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&body);
 
-    Ok(latest_release)
+    let response = send_github_api_request(request).await?;
+
+    if !response.status().is_success() {
+        return Err(match response.status().as_u16() {
+            404 => AppError::NotFound,
+            // tag 或 previous_tag 无效时 GitHub 返回 422
+            422 => AppError::BadRequest(format!("无法为 tag {} 生成 release notes", tag)),
+            // 生成接口要求 token 对仓库有写权限
+            403 => AppError::Forbidden(
+                "GITHUB_TOKEN 没有该仓库的写权限，无法生成 release notes".to_string(),
+            ),
+            _ => AppError::UpstreamStatus(response.status()),
+        });
+    }
+
+    let notes: GeneratedNotes = response.json().await?;
+
+    backend
+        .set_generated_notes(owner, repo, tag, previous_tag, notes.clone())
+        .await;
+    log::debug!("成功生成并缓存 release notes: {}/{} ({})", owner, repo, tag);
+
+    Ok(notes)
 }
 
 // 从 release 的 attachments 中查找 latest.json 文件 URL
@@ -300,11 +1553,15 @@ fn find_latest_json_url(attachments: &[String]) -> Option<&String> {
         .find(|url| {
             url.ends_with("latest.json") || 
             url.contains("/latest.json") ||
-            url.split('/').last().map(|s| s == "latest.json").unwrap_or(false)
+            url.split('/').next_back().map(|s| s == "latest.json").unwrap_or(false)
         })
 }
 
 // 获取 latest.json 文件内容
+#[cfg_attr(
+    feature = "otel",
+    tracing::instrument(skip_all, fields(url = %url, github.status = tracing::field::Empty))
+)]
 async fn fetch_latest_json(url: &str) -> Result<serde_json::Value, AppError> {
     let client = create_client();
     
@@ -318,6 +1575,7 @@ async fn fetch_latest_json(url: &str) -> Result<serde_json::Value, AppError> {
     }
 
     let response = request.send().await?;
+    record_github_status(response.status().as_u16());
 
     if !response.status().is_success() {
         return Err(AppError::ApiError(format!(
@@ -331,6 +1589,7 @@ async fn fetch_latest_json(url: &str) -> Result<serde_json::Value, AppError> {
 }
 
 // 获取最新 release 的 latest.json 文件内容
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
 pub async fn fetch_latest_release_tauri_json(owner: &str, repo: &str) -> Result<serde_json::Value, AppError> {
     let latest_release = fetch_latest_release(owner, repo).await?;
     
@@ -342,6 +1601,7 @@ pub async fn fetch_latest_release_tauri_json(owner: &str, repo: &str) -> Result<
 }
 
 // 获取最新 release（包括 pre-release）的 latest.json 文件内容
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
 pub async fn fetch_latest_release_pre_tauri_json(owner: &str, repo: &str) -> Result<serde_json::Value, AppError> {
     let latest_release = fetch_latest_release_pre(owner, repo).await?;
     
@@ -352,44 +1612,203 @@ pub async fn fetch_latest_release_pre_tauri_json(owner: &str, repo: &str) -> Res
     fetch_latest_json(latest_json_url).await
 }
 
-// API 端点：GET / - 健康检查和基本信息
-#[utoipa::path(
-    get,
-    path = "/",
-    tag = "health",
-    responses(
-        (status = 200, description = "服务健康", body = HealthResponse)
-    )
-)]
-#[get("/")]
-pub async fn health_check() -> impl Responder {
-    use crate::models::HealthResponse;
-    HttpResponse::Ok().json(HealthResponse {
-        status: "ok".to_string(),
-        service: "GitHub API 信息收集服务".to_string(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
-    })
-}
+// 校验和文件的签名或证书（如 goreleaser 生成的 checksums.txt.sig、checksums.txt.pem），不是校验和列表本身
+const CHECKSUMS_SIGNATURE_SUFFIXES: &[&str] = &[".sig", ".pem", ".asc", ".sigstore", ".sigstore.json"];
 
-// API 端点：GET /health - 健康检查端点
+// 从 release 的 attachments 中查找校验和文件 URL（如 SHA256SUMS、checksums.txt）
+// 跳过签名和证书文件，优先选择 `SHA256SUMS` 或 `.txt` 文件
+fn find_checksums_url(attachments: &[String]) -> Option<&String> {
+    let candidates: Vec<(&String, String)> = attachments
+        .iter()
+        .map(|url| (url, url.split('/').next_back().unwrap_or("").to_lowercase()))
+        .filter(|(_, filename)| {
+            (filename.contains("sha256sums") || filename.contains("checksums"))
+                && !CHECKSUMS_SIGNATURE_SUFFIXES.iter().any(|suffix| filename.ends_with(suffix))
+        })
+        .collect();
+    candidates
+        .iter()
+        .find(|(_, filename)| filename == "sha256sums" || filename.ends_with(".txt"))
+        .or_else(|| candidates.first())
+        .map(|(url, _)| *url)
+}
+
+// 解析 `<hash>  <filename>` 格式的校验和文件（兼容两个空格、单个空格和二进制模式的 `*` 前缀）
+fn parse_checksums(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let (hash, filename) = line.split_once(char::is_whitespace)?;
+            let filename = filename.trim_start();
+            let filename = filename.strip_prefix('*').unwrap_or(filename);
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) || filename.is_empty() {
+                return None;
+            }
+            Some((filename.to_string(), hash.to_lowercase()))
+        })
+        .collect()
+}
+
+// 下载校验和文件内容
+#[cfg_attr(
+    feature = "otel",
+    tracing::instrument(skip_all, fields(url = %url, github.status = tracing::field::Empty))
+)]
+async fn fetch_checksums_file(url: &str) -> Result<String, AppError> {
+    let client = create_client();
+
+    let mut request = client
+        .get(url)
+        .header("User-Agent", "gh-info-rs")
+        .header("Accept", "*/*");
+
+    if let Some(token) = get_github_token() {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = request.send().await?;
+    record_github_status(response.status().as_u16());
+
+    if !response.status().is_success() {
+        return Err(AppError::ApiError(format!(
+            "下载校验和文件失败，状态码: {}",
+            response.status()
+        )));
+    }
+
+    Ok(response.text().await?)
+}
+
+// 获取最新 release 的校验和（文件名 -> SHA-256）
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn fetch_latest_release_checksums(owner: &str, repo: &str) -> Result<ChecksumsInfo, AppError> {
+    let latest_release = fetch_latest_release(owner, repo).await?;
+    let backend = get_cache_backend().await;
+
+    // 缓存的校验和只有与当前最新版本一致时才有效
+    let cached = backend
+        .get_checksums(owner, repo)
+        .await
+        .filter(|cached| cached.version == latest_release.latest_version);
+    cache_metrics().record_lookup(CacheKind::Checksums, cached.is_some());
+    if let Some(cached) = cached {
+        log::debug!("从缓存获取校验和: {}/{} (版本: {})", owner, repo, cached.version);
+        return Ok(cached);
+    }
+
+    let checksums_url = find_checksums_url(&latest_release.attachments)
+        .ok_or(AppError::NotFound)?;
+    log::debug!("找到校验和文件 URL: {}", checksums_url);
+
+    let content = fetch_checksums_file(checksums_url).await?;
+    let checksums = parse_checksums(&content);
+    if checksums.is_empty() {
+        log::debug!("校验和文件中没有可识别的条目: {}", checksums_url);
+        return Err(AppError::NotFound);
+    }
+
+    let checksums_info = ChecksumsInfo {
+        repo: format!("{}/{}", owner, repo),
+        version: latest_release.latest_version,
+        checksums_url: checksums_url.clone(),
+        checksums,
+    };
+
+    backend.set_checksums(owner, repo, checksums_info.clone()).await;
+    log::debug!("成功获取并缓存校验和: {}/{} (共 {} 个文件)", owner, repo, checksums_info.checksums.len());
+
+    Ok(checksums_info)
+}
+
+// API 端点：GET / - 健康检查和基本信息
 #[utoipa::path(
     get,
-    path = "/health",
+    path = "/",
     tag = "health",
     responses(
         (status = 200, description = "服务健康", body = HealthResponse)
     )
 )]
-#[get("/health")]
-pub async fn health() -> impl Responder {
-    use crate::models::HealthResponse;
-    HttpResponse::Ok().json(HealthResponse {
+#[get("/")]
+pub async fn health_check() -> impl Responder {
+    HttpResponse::Ok().json(health_response())
+}
+
+fn health_response() -> HealthResponse {
+    HealthResponse {
         status: "ok".to_string(),
         service: "GitHub API 信息收集服务".to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
+        authenticated: get_github_token().is_some_and(|t| !t.is_empty()),
+    }
+}
+
+// /health 的响应体（及其 ETag）只取决于服务版本和启动时的 token 配置，首次请求时序列化一次并复用，避免高频存活探针每次分配和序列化
+fn health_body() -> &'static (web::Bytes, String) {
+    static BODY: OnceLock<(web::Bytes, String)> = OnceLock::new();
+    BODY.get_or_init(|| {
+        let body = serde_json::to_vec(&health_response()).expect("序列化健康检查响应失败");
+        let etag = format!("\"health-{}\"", env!("CARGO_PKG_VERSION"));
+        (web::Bytes::from(body), etag)
     })
 }
 
+// API 端点：GET /health - 健康检查端点
+// 响应带固定的 ETag，探针发送匹配的 If-None-Match 时返回不带响应体的 304
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "服务健康", body = HealthResponse),
+        (status = 304, description = "服务健康，且 If-None-Match 与 ETag 匹配")
+    )
+)]
+#[get("/health")]
+pub async fn health(req: HttpRequest) -> HttpResponse {
+    let (body, etag) = health_body();
+    let not_modified = req
+        .headers()
+        .get(actix_web::http::header::IF_NONE_MATCH)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|header| if_none_match_matches(header, etag));
+    if not_modified {
+        return HttpResponse::NotModified()
+            .insert_header((actix_web::http::header::ETAG, etag.as_str()))
+            .finish();
+    }
+    HttpResponse::Ok()
+        .content_type(actix_web::http::header::ContentType::json())
+        .insert_header((actix_web::http::header::ETAG, etag.as_str()))
+        .body(body.clone())
+}
+
+// API 端点：GET /stats - 服务运行统计
+#[utoipa::path(
+    get,
+    path = "/stats",
+    tag = "health",
+    params(
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "成功获取运行统计", body = StatsResponse)
+    )
+)]
+#[get("/stats")]
+pub async fn get_stats(format: web::Query<FormatQuery>) -> Result<HttpResponse, AppError> {
+    let rate_limit_manager = get_rate_limit_manager().await;
+    let stats = StatsResponse {
+        downloads: rate_limit_manager.download_queue_stats(),
+        github_tokens_remaining: get_github_token_bucket().remaining(),
+    };
+    json_response(&stats, format.pretty)
+}
+
 // API 端点：GET /repos/{owner}/{repo}
 #[utoipa::path(
     get,
@@ -397,19 +1816,128 @@ pub async fn health() -> impl Responder {
     tag = "repos",
     params(
         ("owner" = String, Path, description = "仓库所有者"),
-        ("repo" = String, Path, description = "仓库名称")
+        ("repo" = String, Path, description = "仓库名称"),
+        ("refresh" = Option<bool>, Query, description = "是否跳过缓存强制从 GitHub 获取（默认 false，受更严格的限流）"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）"),
+        ("include_meta" = Option<bool>, Query, description = "是否将数据包装为 { data, cache_age_secs, expires_in_secs }（默认 false）")
     ),
     responses(
         (status = 200, description = "成功获取仓库信息", body = RepoInfo),
+        (status = 403, description = "私有仓库且未启用 PRIVATE_REPOS_ENABLED"),
         (status = 404, description = "仓库不存在")
     )
 )]
 #[get("/repos/{owner}/{repo}")]
-pub async fn get_repo_info(path: web::Path<(String, String)>) -> Result<impl Responder, AppError> {
+pub async fn get_repo_info(
+    path: web::Path<(String, String)>,
+    refresh: web::Query<RefreshQuery>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
     let (owner, repo) = path.into_inner();
-    log::info!("请求: GET /repos/{}/{}", owner, repo);
+    log::info!("请求: GET /repos/{}/{} (refresh: {})", owner, repo, refresh.refresh);
+    let cache_key = CacheManager::repo_info_key(&owner, &repo);
+    if refresh.refresh {
+        let repo_info = refresh_repo_info(&owner, &repo).await?;
+        let response = cached_json_response(&repo_info, &format, Some(&cache_key)).await?;
+        return Ok(mark_refreshed(response));
+    }
     let repo_info = fetch_repo_info(&owner, &repo).await?;
-    Ok(HttpResponse::Ok().json(repo_info))
+    cached_json_response(&repo_info, &format, Some(&cache_key)).await
+}
+
+// API 端点：GET /repos/{owner}/{repo}/raw
+#[utoipa::path(
+    get,
+    path = "/repos/{owner}/{repo}/raw",
+    tag = "repos",
+    params(
+        ("owner" = String, Path, description = "仓库所有者"),
+        ("repo" = String, Path, description = "仓库名称"),
+        ("refresh" = Option<bool>, Query, description = "是否跳过缓存强制从 GitHub 获取（默认 false，受更严格的限流）"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）"),
+        ("include_meta" = Option<bool>, Query, description = "是否将数据包装为 { data, cache_age_secs, expires_in_secs }（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "GitHub `GET /repos/{owner}/{repo}` 返回的原始 JSON，结构与 GitHub API 完全一致", content_type = "application/json"),
+        (status = 403, description = "私有仓库且未启用 PRIVATE_REPOS_ENABLED"),
+        (status = 404, description = "仓库不存在")
+    )
+)]
+#[get("/repos/{owner}/{repo}/raw")]
+pub async fn get_raw_repo(
+    path: web::Path<(String, String)>,
+    refresh: web::Query<RefreshQuery>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
+    let (owner, repo) = path.into_inner();
+    log::info!("请求: GET /repos/{}/{}/raw (refresh: {})", owner, repo, refresh.refresh);
+    let cache_key = CacheManager::raw_repo_key(&owner, &repo);
+    if refresh.refresh {
+        let raw = refresh_raw_repo(&owner, &repo).await?;
+        let response = cached_json_response(&raw, &format, Some(&cache_key)).await?;
+        return Ok(mark_refreshed(response));
+    }
+    let raw = fetch_raw_repo(&owner, &repo).await?;
+    cached_json_response(&raw, &format, Some(&cache_key)).await
+}
+
+// API 端点：GET /repos/{owner}/{repo}/contents/{path} - 获取仓库中指定路径的文件
+#[utoipa::path(
+    get,
+    path = "/repos/{owner}/{repo}/contents/{path}",
+    tag = "repos",
+    params(
+        ("owner" = String, Path, description = "仓库所有者"),
+        ("repo" = String, Path, description = "仓库名称"),
+        ("path" = String, Path, description = "文件或目录在仓库中的路径，可以包含 `/`"),
+        ("ref" = Option<String>, Query, description = "分支、tag 或 commit SHA（默认使用仓库的默认分支）"),
+        ("refresh" = Option<bool>, Query, description = "是否跳过缓存强制从 GitHub 获取（默认 false，受更严格的限流）"),
+        ("pretty" = Option<bool>, Query, description = "目录时是否返回缩进格式的 JSON（默认 false）"),
+        ("include_meta" = Option<bool>, Query, description = "目录时是否将数据包装为 { data, cache_age_secs, expires_in_secs }（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "文件的原始内容（Content-Type 按扩展名推断）；目录、符号链接和子模块返回 GitHub contents API 的原始 JSON"),
+        (status = 400, description = "路径包含 . 或 .. 段"),
+        (status = 403, description = "私有仓库且未启用 PRIVATE_REPOS_ENABLED"),
+        (status = 404, description = "仓库、ref 或路径不存在"),
+        (status = 422, description = "文件超过 GitHub contents API 的 1MB 上限")
+    )
+)]
+#[get("/repos/{owner}/{repo}/contents/{path:.*}")]
+pub async fn get_repo_contents(
+    path: web::Path<(String, String, String)>,
+    query: web::Query<ContentsQuery>,
+    refresh: web::Query<RefreshQuery>,
+    format: web::Query<FormatQuery>,
+) -> Result<HttpResponse, AppError> {
+    let (owner, repo, file_path) = path.into_inner();
+    let git_ref = query.git_ref.as_deref().filter(|r| !r.is_empty());
+    log::info!(
+        "请求: GET /repos/{}/{}/contents/{} (ref: {:?}, refresh: {})",
+        owner, repo, file_path, git_ref, refresh.refresh
+    );
+    let cache_key = CacheManager::contents_key(&owner, &repo, &file_path, git_ref);
+    let contents = if refresh.refresh {
+        refresh_contents(&owner, &repo, &file_path, git_ref).await?
+    } else {
+        fetch_contents(&owner, &repo, &file_path, git_ref).await?
+    };
+
+    let response = match decode_file_contents(&file_path, &contents)? {
+        Some((name, bytes)) => {
+            let content_type = get_download_config().resolve_content_type(None, &name);
+            let response = HttpResponse::Ok()
+                .insert_header((
+                    actix_web::http::header::CONTENT_DISPOSITION,
+                    content_disposition(&content_type, &name),
+                ))
+                .content_type(content_type)
+                .body(bytes);
+            with_cache_control(response, &cache_key).await
+        }
+        None => cached_json_response(&contents, &format, Some(&cache_key)).await?,
+    };
+    Ok(if refresh.refresh { mark_refreshed(response) } else { response })
 }
 
 // API 端点：GET /repos/{owner}/{repo}/releases
@@ -419,19 +1947,74 @@ pub async fn get_repo_info(path: web::Path<(String, String)>) -> Result<impl Res
     tag = "repos",
     params(
         ("owner" = String, Path, description = "仓库所有者"),
-        ("repo" = String, Path, description = "仓库名称")
+        ("repo" = String, Path, description = "仓库名称"),
+        ("include_reactions" = Option<bool>, Query, description = "是否包含反应统计（默认 false）"),
+        ("include_source" = Option<bool>, Query, description = "是否将源码压缩包（zip、tar.gz）链接追加到 attachments（默认 false）"),
+        ("since" = Option<String>, Query, description = "只返回在该时间之后发布的 release（RFC3339，例如 2024-01-01T00:00:00Z）"),
+        ("until" = Option<String>, Query, description = "只返回在该时间之前发布的 release（RFC3339）"),
+        ("refresh" = Option<bool>, Query, description = "是否跳过缓存强制从 GitHub 获取（默认 false，受更严格的限流）"),
+        ("schema" = Option<String>, Query, description = "响应格式：default（默认）或 flat（附件为 {name, url, size} 对象，changelog 只返回预览）"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）"),
+        ("include_meta" = Option<bool>, Query, description = "是否将数据包装为 { data, cache_age_secs, expires_in_secs }（默认 false）")
     ),
     responses(
-        (status = 200, description = "成功获取所有 releases", body = Vec<ReleaseInfo>),
+        (status = 200, description = "成功获取所有 releases（schema=flat 时为 FlatReleaseInfo 数组），X-Release-Count 头为返回的 release 数量", body = Vec<ReleaseInfo>),
+        (status = 400, description = "since 或 until 不是有效的 RFC3339 时间，或 schema 无法识别"),
+        (status = 403, description = "私有仓库且未启用 PRIVATE_REPOS_ENABLED"),
         (status = 404, description = "仓库不存在")
     )
 )]
 #[get("/repos/{owner}/{repo}/releases")]
-pub async fn get_releases(path: web::Path<(String, String)>) -> Result<impl Responder, AppError> {
+pub async fn get_releases(
+    path: web::Path<(String, String)>,
+    query: web::Query<ReleaseQuery>,
+    date_query: web::Query<ReleaseDateQuery>,
+    refresh: web::Query<RefreshQuery>,
+    schema: web::Query<SchemaQuery>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
     let (owner, repo) = path.into_inner();
-    log::info!("请求: GET /repos/{}/{}/releases", owner, repo);
-    let releases = fetch_releases(&owner, &repo).await?;
-    Ok(HttpResponse::Ok().json(releases))
+    log::info!("请求: GET /repos/{}/{}/releases (refresh: {})", owner, repo, refresh.refresh);
+    let (since, until) = date_query.parse().map_err(|param| {
+        AppError::BadRequest(format!("{} 参数必须是 RFC3339 格式的时间，例如 2024-01-01T00:00:00Z", param))
+    })?;
+    let flat = schema.is_flat().map_err(|schema| {
+        AppError::BadRequest(format!("无法识别的 schema: {}，可选值为 default 或 flat", schema))
+    })?;
+
+    // 包含反应统计的请求本身不使用缓存，无需区分是否刷新
+    let refreshed = refresh.refresh && !query.include_reactions;
+    let releases = if query.include_reactions {
+        fetch_releases_with_reactions(&owner, &repo).await?
+    } else if refreshed {
+        refresh_releases(&owner, &repo).await?
+    } else {
+        fetch_releases(&owner, &repo).await?
+    };
+
+    // 在完整列表（缓存结果）上按发布时间过滤
+    let mut releases = filter_releases_by_date(releases, since, until);
+    releases
+        .iter_mut()
+        .for_each(|release| release.apply_include_source(query.include_source));
+    let release_count = releases.len();
+    // 包含反应统计的响应不经过缓存，不添加缓存相关的头
+    let releases_key = CacheManager::releases_key(&owner, &repo);
+    let cache_key = (!query.include_reactions).then_some(releases_key.as_str());
+    let mut response = if flat {
+        let releases: Vec<FlatReleaseInfo> = releases.into_iter().map(FlatReleaseInfo::from).collect();
+        cached_json_response(&releases, &format, cache_key).await?
+    } else {
+        // 附件大小只通过 schema=flat 的 size 字段返回，默认格式保持不变
+        releases.iter_mut().for_each(|release| release.attachment_sizes.clear());
+        cached_json_response(&releases, &format, cache_key).await?
+    };
+    // 返回的 release 数量（仓库存在但没有 release 时为 0，与仓库不存在的 404 区分）
+    response.headers_mut().insert(
+        HeaderName::from_static("x-release-count"),
+        HeaderValue::from(release_count),
+    );
+    Ok(if refreshed { mark_refreshed(response) } else { response })
 }
 
 // API 端点：GET /repos/{owner}/{repo}/releases/latest
@@ -441,21 +2024,84 @@ pub async fn get_releases(path: web::Path<(String, String)>) -> Result<impl Resp
     tag = "repos",
     params(
         ("owner" = String, Path, description = "仓库所有者"),
-        ("repo" = String, Path, description = "仓库名称")
+        ("repo" = String, Path, description = "仓库名称"),
+        ("include_reactions" = Option<bool>, Query, description = "是否包含反应统计（默认 false）"),
+        ("include_source" = Option<bool>, Query, description = "是否将源码压缩包（zip、tar.gz）链接追加到 attachments（默认 false）"),
+        ("refresh" = Option<bool>, Query, description = "是否跳过缓存强制从 GitHub 获取（默认 false，受更严格的限流）"),
+        ("strip_v" = Option<bool>, Query, description = "是否去掉 latest_version 开头的 v（默认 false）"),
+        ("channel" = Option<String>, Query, description = "发布渠道：stable、beta、nightly 或 any；指定时基于完整的 releases 列表按渠道选择，未指定时使用 GitHub 的 latest release"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）"),
+        ("include_meta" = Option<bool>, Query, description = "是否将数据包装为 { data, cache_age_secs, expires_in_secs }（默认 false）")
     ),
     responses(
         (status = 200, description = "成功获取最新 release", body = LatestReleaseInfo),
-        (status = 404, description = "仓库不存在或没有 releases")
+        (status = 400, description = "无法识别的 channel"),
+        (status = 404, description = "仓库不存在；仓库存在但没有 releases 时响应体中 code 为 no_releases；指定的渠道没有 release 时 code 为 no_channel_release")
     )
 )]
 #[get("/repos/{owner}/{repo}/releases/latest")]
 pub async fn get_latest_release(
     path: web::Path<(String, String)>,
+    query: web::Query<ReleaseQuery>,
+    refresh: web::Query<RefreshQuery>,
+    version: web::Query<VersionQuery>,
+    channel: web::Query<ChannelQuery>,
+    format: web::Query<FormatQuery>,
 ) -> Result<impl Responder, AppError> {
     let (owner, repo) = path.into_inner();
-    log::info!("请求: GET /repos/{}/{}/releases/latest", owner, repo);
-    let release = fetch_latest_release(&owner, &repo).await?;
-    Ok(HttpResponse::Ok().json(release))
+    log::info!("请求: GET /repos/{}/{}/releases/latest (refresh: {})", owner, repo, refresh.refresh);
+    let channel = channel
+        .channel
+        .as_deref()
+        .map(ReleaseChannel::parse)
+        .transpose()
+        .map_err(|channel| {
+            AppError::BadRequest(format!(
+                "无法识别的 channel: {}，可选值为 stable、beta、nightly 或 any",
+                channel
+            ))
+        })?;
+
+    // 包含反应统计的请求本身不使用缓存，无需区分是否刷新
+    let (mut release, cache_key) = match channel {
+        // 指定渠道时基于完整的 releases 列表按渠道选择
+        Some(channel) => {
+            let releases = if query.include_reactions {
+                fetch_releases_with_reactions(&owner, &repo).await?
+            } else if refresh.refresh {
+                refresh_releases(&owner, &repo).await?
+            } else {
+                fetch_releases(&owner, &repo).await?
+            };
+            let release = get_channel_config().select(releases, channel)?;
+            (
+                to_latest_release_info(&owner, &repo, release),
+                CacheManager::releases_key(&owner, &repo),
+            )
+        }
+        None => {
+            let release = if query.include_reactions {
+                fetch_latest_release_with_reactions(&owner, &repo).await?
+            } else if refresh.refresh {
+                refresh_latest_release(&owner, &repo).await?
+            } else {
+                fetch_latest_release(&owner, &repo).await?
+            };
+            (release, CacheManager::latest_release_key(&owner, &repo))
+        }
+    };
+    if version.strip_v {
+        release.latest_version = strip_version_prefix(&release.latest_version).to_string();
+    }
+    release.apply_include_source(query.include_source);
+
+    // 包含反应统计的响应不经过缓存，不添加缓存相关的头
+    let cache_key = (!query.include_reactions).then_some(cache_key.as_str());
+    let response = cached_json_response(&release, &format, cache_key).await?;
+    if refresh.refresh && !query.include_reactions {
+        return Ok(mark_refreshed(response));
+    }
+    Ok(response)
 }
 
 // API 端点：GET /repos/{owner}/{repo}/releases/latest/pre
@@ -465,21 +2111,83 @@ pub async fn get_latest_release(
     tag = "repos",
     params(
         ("owner" = String, Path, description = "仓库所有者"),
-        ("repo" = String, Path, description = "仓库名称")
+        ("repo" = String, Path, description = "仓库名称"),
+        ("include_reactions" = Option<bool>, Query, description = "是否包含反应统计（默认 false）"),
+        ("include_source" = Option<bool>, Query, description = "是否将源码压缩包（zip、tar.gz）链接追加到 attachments（默认 false）"),
+        ("strip_v" = Option<bool>, Query, description = "是否去掉 latest_version 开头的 v（默认 false）"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）"),
+        ("include_meta" = Option<bool>, Query, description = "是否将数据包装为 { data, cache_age_secs, expires_in_secs }（默认 false）")
     ),
     responses(
         (status = 200, description = "成功获取最新 release（包括 pre-release）", body = LatestReleaseInfo),
-        (status = 404, description = "仓库不存在或没有 releases")
+        (status = 404, description = "仓库不存在；仓库存在但没有 releases 时响应体中 code 为 no_releases")
     )
 )]
 #[get("/repos/{owner}/{repo}/releases/latest/pre")]
 pub async fn get_latest_release_pre(
     path: web::Path<(String, String)>,
+    query: web::Query<ReleaseQuery>,
+    version: web::Query<VersionQuery>,
+    format: web::Query<FormatQuery>,
 ) -> Result<impl Responder, AppError> {
     let (owner, repo) = path.into_inner();
     log::info!("请求: GET /repos/{}/{}/releases/latest/pre", owner, repo);
-    let release = fetch_latest_release_pre(&owner, &repo).await?;
-    Ok(HttpResponse::Ok().json(release))
+    let mut release = if query.include_reactions {
+        fetch_latest_release_pre_with_reactions(&owner, &repo).await?
+    } else {
+        fetch_latest_release_pre(&owner, &repo).await?
+    };
+    if version.strip_v {
+        release.latest_version = strip_version_prefix(&release.latest_version).to_string();
+    }
+    release.apply_include_source(query.include_source);
+    // 最新 pre-release 由缓存的 releases 列表计算得出，包含反应统计的响应不经过缓存
+    let releases_key = CacheManager::releases_key(&owner, &repo);
+    let cache_key = (!query.include_reactions).then_some(releases_key.as_str());
+    cached_json_response(&release, &format, cache_key).await
+}
+
+// API 端点：GET /repos/{owner}/{repo}/releases/latest/stable
+#[utoipa::path(
+    get,
+    path = "/repos/{owner}/{repo}/releases/latest/stable",
+    tag = "repos",
+    params(
+        ("owner" = String, Path, description = "仓库所有者"),
+        ("repo" = String, Path, description = "仓库名称"),
+        ("include_reactions" = Option<bool>, Query, description = "是否包含反应统计（默认 false）"),
+        ("include_source" = Option<bool>, Query, description = "是否将源码压缩包（zip、tar.gz）链接追加到 attachments（默认 false）"),
+        ("strip_v" = Option<bool>, Query, description = "是否去掉 latest_version 开头的 v（默认 false）"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）"),
+        ("include_meta" = Option<bool>, Query, description = "是否将数据包装为 { data, cache_age_secs, expires_in_secs }（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "成功获取最新正式版 release（跳过 pre-release 和草稿）", body = LatestReleaseInfo),
+        (status = 404, description = "仓库不存在；没有 releases 时 code 为 no_releases，只有 pre-release 或草稿时 code 为 no_stable_release")
+    )
+)]
+#[get("/repos/{owner}/{repo}/releases/latest/stable")]
+pub async fn get_latest_release_stable(
+    path: web::Path<(String, String)>,
+    query: web::Query<ReleaseQuery>,
+    version: web::Query<VersionQuery>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
+    let (owner, repo) = path.into_inner();
+    log::info!("请求: GET /repos/{}/{}/releases/latest/stable", owner, repo);
+    let mut release = if query.include_reactions {
+        fetch_latest_stable_release_with_reactions(&owner, &repo).await?
+    } else {
+        fetch_latest_stable_release(&owner, &repo).await?
+    };
+    if version.strip_v {
+        release.latest_version = strip_version_prefix(&release.latest_version).to_string();
+    }
+    release.apply_include_source(query.include_source);
+    // 最新正式版由缓存的 releases 列表计算得出，包含反应统计的响应不经过缓存
+    let releases_key = CacheManager::releases_key(&owner, &repo);
+    let cache_key = (!query.include_reactions).then_some(releases_key.as_str());
+    cached_json_response(&release, &format, cache_key).await
 }
 
 // API 端点：GET /repos/{owner}/{repo}/releases/latest/tauri
@@ -489,7 +2197,8 @@ pub async fn get_latest_release_pre(
     tag = "repos",
     params(
         ("owner" = String, Path, description = "仓库所有者"),
-        ("repo" = String, Path, description = "仓库名称")
+        ("repo" = String, Path, description = "仓库名称"),
+        ("strip_v" = Option<bool>, Query, description = "是否去掉 version 字段开头的 v（默认 false）")
     ),
     responses(
         (status = 200, description = "成功获取 latest.json 文件内容", body = serde_json::Value),
@@ -500,14 +2209,15 @@ pub async fn get_latest_release_pre(
 #[get("/repos/{owner}/{repo}/releases/latest/tauri")]
 pub async fn get_latest_release_tauri(
     path: web::Path<(String, String)>,
+    version: web::Query<VersionQuery>,
 ) -> Result<impl Responder, AppError> {
     let (owner, repo) = path.into_inner();
     log::info!("请求: GET /repos/{}/{}/releases/latest/tauri", owner, repo);
     
     // 根据 Tauri 更新器规范，当没有更新时返回 204 No Content
     match fetch_latest_release_tauri_json(&owner, &repo).await {
-        Ok(json_content) => Ok(HttpResponse::Ok().json(json_content)),
-        Err(AppError::NotFound) => {
+        Ok(json_content) => Ok(HttpResponse::Ok().json(strip_tauri_version(json_content, version.strip_v))),
+        Err(AppError::NotFound | AppError::NoReleases) => {
             // 没有 release 或没有 latest.json 文件时返回 204
             log::debug!("没有可用的更新，返回 204 No Content");
             Ok(HttpResponse::NoContent().finish())
@@ -523,7 +2233,8 @@ pub async fn get_latest_release_tauri(
     tag = "repos",
     params(
         ("owner" = String, Path, description = "仓库所有者"),
-        ("repo" = String, Path, description = "仓库名称")
+        ("repo" = String, Path, description = "仓库名称"),
+        ("strip_v" = Option<bool>, Query, description = "是否去掉 version 字段开头的 v（默认 false）")
     ),
     responses(
         (status = 200, description = "成功获取 latest.json 文件内容（包括 pre-release）", body = serde_json::Value),
@@ -534,14 +2245,15 @@ pub async fn get_latest_release_tauri(
 #[get("/repos/{owner}/{repo}/releases/latest/pre/tauri")]
 pub async fn get_latest_release_pre_tauri(
     path: web::Path<(String, String)>,
+    version: web::Query<VersionQuery>,
 ) -> Result<impl Responder, AppError> {
     let (owner, repo) = path.into_inner();
     log::info!("请求: GET /repos/{}/{}/releases/latest/pre/tauri", owner, repo);
     
     // 根据 Tauri 更新器规范，当没有更新时返回 204 No Content
     match fetch_latest_release_pre_tauri_json(&owner, &repo).await {
-        Ok(json_content) => Ok(HttpResponse::Ok().json(json_content)),
-        Err(AppError::NotFound) => {
+        Ok(json_content) => Ok(HttpResponse::Ok().json(strip_tauri_version(json_content, version.strip_v))),
+        Err(AppError::NotFound | AppError::NoReleases) => {
             // 没有 release 或没有 latest.json 文件时返回 204
             log::debug!("没有可用的更新，返回 204 No Content");
             Ok(HttpResponse::NoContent().finish())
@@ -550,116 +2262,1678 @@ pub async fn get_latest_release_pre_tauri(
     }
 }
 
-// 解析仓库字符串 "owner/repo" 为 (owner, repo)
-fn parse_repo(repo_str: &str) -> Option<(String, String)> {
-    let parts: Vec<&str> = repo_str.split('/').collect();
-    if parts.len() == 2 && !parts[0].is_empty() && !parts[1].is_empty() {
-        Some((parts[0].to_string(), parts[1].to_string()))
-    } else {
-        None
-    }
+// API 端点：GET /repos/{owner}/{repo}/releases/latest/checksums
+#[utoipa::path(
+    get,
+    path = "/repos/{owner}/{repo}/releases/latest/checksums",
+    tag = "repos",
+    params(
+        ("owner" = String, Path, description = "仓库所有者"),
+        ("repo" = String, Path, description = "仓库名称"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）"),
+        ("include_meta" = Option<bool>, Query, description = "是否将数据包装为 { data, cache_age_secs, expires_in_secs }（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "成功获取最新 release 的校验和", body = ChecksumsInfo),
+        (status = 404, description = "仓库不存在、没有 releases 或最新 release 中没有校验和文件")
+    )
+)]
+#[get("/repos/{owner}/{repo}/releases/latest/checksums")]
+pub async fn get_latest_release_checksums(
+    path: web::Path<(String, String)>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
+    let (owner, repo) = path.into_inner();
+    log::info!("请求: GET /repos/{}/{}/releases/latest/checksums", owner, repo);
+    let checksums = fetch_latest_release_checksums(&owner, &repo).await?;
+    let cache_key = CacheManager::checksums_key(&owner, &repo);
+    cached_json_response(&checksums, &format, Some(&cache_key)).await
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_parse_repo_valid() {
-        assert_eq!(
-            parse_repo("owner/repo"),
-            Some(("owner".to_string(), "repo".to_string()))
-        );
-        assert_eq!(
-            parse_repo("octocat/Hello-World"),
-            Some(("octocat".to_string(), "Hello-World".to_string()))
-        );
-    }
+// 在附件中查找第一个文件名与模式匹配的附件，返回（序号, 文件名, URL）
+fn find_matching_asset<'a>(attachments: &'a [String], pattern: &str) -> Option<(usize, &'a str, &'a String)> {
+    attachments.iter().enumerate().find_map(|(index, url)| {
+        let filename = url.split('/').next_back().unwrap_or("");
+        glob_match(pattern, filename).then_some((index, filename, url))
+    })
+}
 
-    #[test]
-    fn test_parse_repo_invalid() {
-        assert_eq!(parse_repo("invalid"), None);
-        assert_eq!(parse_repo("owner/repo/extra"), None);
-        assert_eq!(parse_repo(""), None);
-        assert_eq!(parse_repo("owner/"), None);
-        assert_eq!(parse_repo("/repo"), None);
-    }
+// 在附件中查找文件名与 name 完全一致的附件 URL。
+// 附件 URL 中的文件名经过百分号编码（如空格为 %20），name 来自已解码的请求路径，比较前先解码
+fn find_asset_by_name<'a>(attachments: &'a [String], name: &str) -> Option<&'a String> {
+    attachments.iter().find(|url| {
+        let segment = url.split('/').next_back().unwrap_or_default();
+        percent_encoding::percent_decode_str(segment).decode_utf8_lossy() == name
+    })
 }
 
-// 处理单个仓库的批量请求
-async fn process_single_repo(repo_str: &str, fields: &[String]) -> RepoBatchResult {
-    let (owner, repo) = match parse_repo(repo_str) {
-        Some(parsed) => parsed,
-        None => {
-            return RepoBatchResult {
-                repo: repo_str.to_string(),
-                success: false,
-                error: Some("仓库格式错误，应为 'owner/repo'".to_string()),
-                repo_info: None,
-                releases: None,
-                latest_release: None,
-            };
+// 解析最新 release 中与模式匹配的附件：下载地址、大小以及校验和（如果有校验和文件）
+#[cfg_attr(feature = "otel", tracing::instrument(skip_all, fields(repo = %format_args!("{}/{}", owner, repo))))]
+pub async fn resolve_latest_release_asset(
+    owner: &str,
+    repo: &str,
+    pattern: &str,
+) -> Result<ResolvedAsset, AppError> {
+    let latest_release = fetch_latest_release(owner, repo).await?;
+    let (index, name, url) = find_matching_asset(&latest_release.attachments, pattern)
+        .ok_or(AppError::NotFound)?;
+    // 大小来自 release 附件数据，旧缓存数据中没有大小时为 None
+    let size = latest_release.attachment_sizes.get(index).copied();
+
+    let checksums = fetch_latest_release_checksums(owner, repo).await;
+
+    // 校验和是可选的：没有校验和文件或获取失败时只返回下载地址和大小
+    let sha256 = match checksums {
+        Ok(checksums) if checksums.version == latest_release.latest_version => {
+            checksums.checksums.get(name).cloned()
+        }
+        Ok(_) | Err(AppError::NotFound | AppError::NoReleases) => None,
+        Err(e) => {
+            log::warn!("获取校验和失败: {}/{} ({})", owner, repo, e);
+            None
         }
     };
 
-    // 如果没有指定fields，默认获取全部
-    let should_get_repo_info = fields.is_empty() || fields.contains(&"repo_info".to_string());
-    let should_get_releases = fields.is_empty() || fields.contains(&"releases".to_string());
-    let should_get_latest_release =
-        fields.is_empty() || fields.contains(&"latest_release".to_string());
+    Ok(ResolvedAsset {
+        name: name.to_string(),
+        url: url.clone(),
+        size,
+        sha256,
+    })
+}
 
-    // 并发获取所有请求的数据
-    let repo_info_future = if should_get_repo_info {
-        Some(fetch_repo_info(&owner, &repo))
-    } else {
-        None
-    };
+// API 端点：GET /repos/{owner}/{repo}/releases/latest/resolve?asset=<模式>
+#[utoipa::path(
+    get,
+    path = "/repos/{owner}/{repo}/releases/latest/resolve",
+    tag = "repos",
+    params(
+        ("owner" = String, Path, description = "仓库所有者"),
+        ("repo" = String, Path, description = "仓库名称"),
+        ("asset" = String, Query, description = "附件文件名的通配符模式，支持 `*` 和 `?`，例如 `*linux-x86_64.tar.gz`"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "成功解析匹配的附件", body = ResolvedAsset),
+        (status = 400, description = "缺少 asset 参数"),
+        (status = 404, description = "仓库不存在、没有 releases 或没有与模式匹配的附件")
+    )
+)]
+#[get("/repos/{owner}/{repo}/releases/latest/resolve")]
+pub async fn resolve_latest_asset(
+    path: web::Path<(String, String)>,
+    query: web::Query<ResolveAssetQuery>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
+    let (owner, repo) = path.into_inner();
+    let pattern = query.asset.trim();
+    if pattern.is_empty() {
+        return Err(AppError::BadRequest("缺少 asset 参数".to_string()));
+    }
 
-    let releases_future = if should_get_releases {
-        Some(fetch_releases(&owner, &repo))
-    } else {
-        None
-    };
+    log::info!("请求: GET /repos/{}/{}/releases/latest/resolve?asset={}", owner, repo, pattern);
+    let asset = resolve_latest_release_asset(&owner, &repo, pattern).await?;
+    json_response(&asset, format.pretty)
+}
 
-    let latest_release_future = if should_get_latest_release {
-        Some(fetch_latest_release(&owner, &repo))
-    } else {
-        None
-    };
+// 附件列表每页的默认和最大 release 数量
+const ASSETS_DEFAULT_PER_PAGE: usize = 30;
+const ASSETS_MAX_PER_PAGE: usize = 100;
 
-    // 并发执行所有请求
-    let (repo_info_result, releases_result, latest_release_result) = join!(
-        async {
-            match repo_info_future {
-                Some(f) => f.await.ok(),
-                None => None,
+// 在所有 release（跳过草稿）中收集文件名与模式匹配的附件，按 tag 分组后分页
+fn collect_release_assets(
+    releases: Vec<ReleaseInfo>,
+    pattern: &str,
+    page: usize,
+    per_page: usize,
+) -> AssetListResponse {
+    let matched: Vec<ReleaseAssets> = releases
+        .into_iter()
+        .filter(|r| !r.draft)
+        .filter_map(|release| {
+            let assets: Vec<FlatAsset> = release
+                .attachments
+                .iter()
+                .enumerate()
+                .filter_map(|(i, url)| {
+                    let name = url.rsplit('/').next().unwrap_or_default();
+                    glob_match(pattern, name).then(|| FlatAsset {
+                        name: name.to_string(),
+                        url: url.clone(),
+                        size: release.attachment_sizes.get(i).copied(),
+                    })
+                })
+                .collect();
+            (!assets.is_empty()).then_some(ReleaseAssets {
+                tag_name: release.tag_name,
+                published_at: release.published_at,
+                prerelease: release.prerelease,
+                assets,
+            })
+        })
+        .collect();
+
+    let total_releases = matched.len();
+    let total_assets = matched.iter().map(|r| r.assets.len()).sum();
+    let releases = matched
+        .into_iter()
+        .skip((page - 1).saturating_mul(per_page))
+        .take(per_page)
+        .collect();
+
+    AssetListResponse {
+        releases,
+        page,
+        per_page,
+        total_releases,
+        total_assets,
+    }
+}
+
+// API 端点：GET /repos/{owner}/{repo}/assets?pattern=<模式>
+#[utoipa::path(
+    get,
+    path = "/repos/{owner}/{repo}/assets",
+    tag = "repos",
+    params(
+        ("owner" = String, Path, description = "仓库所有者"),
+        ("repo" = String, Path, description = "仓库名称"),
+        ("pattern" = String, Query, description = "附件文件名的通配符模式，支持 `*` 和 `?`，例如 `*.dmg`"),
+        ("page" = Option<usize>, Query, description = "页码，从 1 开始（默认 1）"),
+        ("per_page" = Option<usize>, Query, description = "每页的 release 数量（默认 30，最大 100）"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）"),
+        ("include_meta" = Option<bool>, Query, description = "是否将数据包装为 { data, cache_age_secs, expires_in_secs }（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "成功获取匹配的附件（按 tag 分组，没有匹配时 releases 为空数组）", body = AssetListResponse),
+        (status = 400, description = "缺少 pattern 参数，或 page、per_page 无效"),
+        (status = 403, description = "私有仓库且未启用 PRIVATE_REPOS_ENABLED"),
+        (status = 404, description = "仓库不存在")
+    )
+)]
+#[get("/repos/{owner}/{repo}/assets")]
+pub async fn get_release_assets(
+    path: web::Path<(String, String)>,
+    query: web::Query<AssetsQuery>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
+    let (owner, repo) = path.into_inner();
+    let pattern = query.pattern.trim();
+    if pattern.is_empty() {
+        return Err(AppError::BadRequest("缺少 pattern 参数".to_string()));
+    }
+    let page = query.page.unwrap_or(1);
+    if page == 0 {
+        return Err(AppError::BadRequest("page 参数必须大于 0".to_string()));
+    }
+    let per_page = query.per_page.unwrap_or(ASSETS_DEFAULT_PER_PAGE);
+    if per_page == 0 || per_page > ASSETS_MAX_PER_PAGE {
+        return Err(AppError::BadRequest(format!(
+            "per_page 参数必须在 1 到 {} 之间",
+            ASSETS_MAX_PER_PAGE
+        )));
+    }
+
+    log::info!("请求: GET /repos/{}/{}/assets?pattern={}", owner, repo, pattern);
+    let releases = fetch_releases(&owner, &repo).await?;
+    let assets = collect_release_assets(releases, pattern, page, per_page);
+    let cache_key = CacheManager::releases_key(&owner, &repo);
+    cached_json_response(&assets, &format, Some(&cache_key)).await
+}
+
+// API 端点：GET /repos/{owner}/{repo}/releases/latest/notes
+#[utoipa::path(
+    get,
+    path = "/repos/{owner}/{repo}/releases/latest/notes",
+    tag = "repos",
+    params(
+        ("owner" = String, Path, description = "仓库所有者"),
+        ("repo" = String, Path, description = "仓库名称"),
+        ("format" = Option<String>, Query, description = "输出格式：`markdown`（默认）、`html` 或 `text`")
+    ),
+    responses(
+        (status = 200, description = "成功获取最新 release 的更新说明", content_type = "text/markdown"),
+        (status = 400, description = "无法识别的 format 参数"),
+        (status = 404, description = "仓库不存在、没有 releases 或最新 release 没有更新说明")
+    )
+)]
+#[get("/repos/{owner}/{repo}/releases/latest/notes")]
+pub async fn get_latest_release_notes(
+    path: web::Path<(String, String)>,
+    query: web::Query<NotesQuery>,
+) -> Result<impl Responder, AppError> {
+    let (owner, repo) = path.into_inner();
+    let format = NotesFormat::parse(query.format.as_deref()).map_err(|format| {
+        AppError::BadRequest(format!("不支持的 format: {}（可选 markdown、html、text）", format))
+    })?;
+
+    log::info!("请求: GET /repos/{}/{}/releases/latest/notes", owner, repo);
+    let release = fetch_latest_release(&owner, &repo).await?;
+    let changelog = release
+        .changelog
+        .filter(|changelog| !changelog.trim().is_empty())
+        .ok_or(AppError::NotFound)?;
+
+    let response = HttpResponse::Ok()
+        .content_type(format.content_type())
+        .body(format.render(&changelog));
+    Ok(with_cache_control(response, &CacheManager::latest_release_key(&owner, &repo)).await)
+}
+
+// API 端点：GET /repos/{owner}/{repo}/releases/latest/badge.svg
+#[utoipa::path(
+    get,
+    path = "/repos/{owner}/{repo}/releases/latest/badge.svg",
+    tag = "repos",
+    params(
+        ("owner" = String, Path, description = "仓库所有者"),
+        ("repo" = String, Path, description = "仓库名称"),
+        ("color" = Option<String>, Query, description = "右侧底色：颜色名称（brightgreen、green、yellowgreen、yellow、orange、red、blue、lightgrey、grey）或十六进制颜色（如 `4c1`），默认 blue")
+    ),
+    responses(
+        (status = 200, description = "最新版本的 SVG 徽章，没有 release 时显示 none", content_type = "image/svg+xml"),
+        (status = 400, description = "无法识别的 color 参数"),
+        (status = 403, description = "私有仓库且未启用 PRIVATE_REPOS_ENABLED"),
+        (status = 404, description = "仓库不存在")
+    )
+)]
+#[get("/repos/{owner}/{repo}/releases/latest/badge.svg")]
+pub async fn get_latest_release_badge(
+    path: web::Path<(String, String)>,
+    query: web::Query<BadgeQuery>,
+) -> Result<impl Responder, AppError> {
+    let (owner, repo) = path.into_inner();
+    let color = query
+        .color
+        .as_deref()
+        .map(parse_color)
+        .transpose()
+        .map_err(|color| AppError::BadRequest(format!("无法识别的 color: {}", color)))?;
+
+    log::info!("请求: GET /repos/{}/{}/releases/latest/badge.svg", owner, repo);
+    let (value, color) = match fetch_latest_release(&owner, &repo).await {
+        Ok(release) => (
+            release.latest_version,
+            color.unwrap_or_else(|| badge::DEFAULT_COLOR.to_string()),
+        ),
+        // 仓库存在但没有 release 时同样渲染徽章，避免 README 中出现图片加载失败
+        Err(AppError::NoReleases) => (badge::BADGE_NONE.to_string(), badge::NONE_COLOR.to_string()),
+        Err(e) => return Err(e),
+    };
+
+    let scope = cache_scope(&CacheManager::latest_release_key(&owner, &repo)).await;
+    Ok(HttpResponse::Ok()
+        .content_type("image/svg+xml")
+        .insert_header((
+            actix_web::http::header::CACHE_CONTROL,
+            format!("{}, max-age={}", scope, badge::BADGE_MAX_AGE_SECS),
+        ))
+        .body(render_badge(badge::BADGE_LABEL, &value, &color)))
+}
+
+// API 端点：GET /repos/{owner}/{repo}/compare/{base}...{head}
+#[utoipa::path(
+    get,
+    path = "/repos/{owner}/{repo}/compare/{refs}",
+    tag = "repos",
+    params(
+        ("owner" = String, Path, description = "仓库所有者"),
+        ("repo" = String, Path, description = "仓库名称"),
+        ("refs" = String, Path, description = "`{base}...{head}`，base 和 head 为 tag、分支或提交，可以包含 `/`（如 `release/1.0...main`）；不含 `/` 的引用也可以写成 `{base}/{head}`"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）"),
+        ("include_meta" = Option<bool>, Query, description = "是否将数据包装为 { data, cache_age_secs, expires_in_secs }（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "成功获取版本对比信息", body = CompareInfo),
+        (status = 400, description = "无法从路径中解析出 base 和 head"),
+        (status = 404, description = "仓库或版本引用不存在")
+    )
+)]
+#[get("/repos/{owner}/{repo}/compare/{refs:.*}")]
+pub async fn get_compare(
+    path: web::Path<(String, String, String)>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
+    let (owner, repo, refs) = path.into_inner();
+    log::info!("请求: GET /repos/{}/{}/compare/{}", owner, repo, refs);
+    let (base, head) = parse_compare_refs(&refs).ok_or_else(|| {
+        AppError::BadRequest(format!(
+            "无法解析版本对比引用: {}，请使用 {{base}}...{{head}} 格式",
+            refs
+        ))
+    })?;
+    let compare = fetch_compare(&owner, &repo, &base, &head).await?;
+    let cache_key = CacheManager::compare_key(&owner, &repo, &base, &head);
+    cached_json_response(&compare, &format, Some(&cache_key)).await
+}
+
+// API 端点：GET /repos/{owner}/{repo}/milestones
+#[utoipa::path(
+    get,
+    path = "/repos/{owner}/{repo}/milestones",
+    tag = "repos",
+    params(
+        ("owner" = String, Path, description = "仓库所有者"),
+        ("repo" = String, Path, description = "仓库名称"),
+        ("state" = Option<String>, Query, description = "里程碑状态：open（默认）、closed 或 all"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）"),
+        ("include_meta" = Option<bool>, Query, description = "是否将数据包装为 { data, cache_age_secs, expires_in_secs }（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "成功获取里程碑列表（没有里程碑时为空数组）", body = Vec<MilestoneInfo>),
+        (status = 400, description = "无法识别的 state"),
+        (status = 404, description = "仓库不存在")
+    )
+)]
+#[get("/repos/{owner}/{repo}/milestones")]
+pub async fn get_milestones(
+    path: web::Path<(String, String)>,
+    query: web::Query<MilestoneQuery>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
+    let (owner, repo) = path.into_inner();
+    let state = query
+        .state()
+        .map_err(|state| AppError::BadRequest(format!("无法识别的 state: {}（可选 open、closed、all）", state)))?;
+    log::info!("请求: GET /repos/{}/{}/milestones (state: {})", owner, repo, state);
+    let milestones = fetch_milestones(&owner, &repo, state).await?;
+    let cache_key = CacheManager::milestones_key(&owner, &repo, state);
+    cached_json_response(&milestones, &format, Some(&cache_key)).await
+}
+
+// API 端点：GET /repos/{owner}/{repo}/releases/notes
+#[utoipa::path(
+    get,
+    path = "/repos/{owner}/{repo}/releases/notes",
+    tag = "repos",
+    params(
+        ("owner" = String, Path, description = "仓库所有者"),
+        ("repo" = String, Path, description = "仓库名称"),
+        ("tag" = String, Query, description = "要生成说明的 tag（可以尚未创建）"),
+        ("previous_tag" = Option<String>, Query, description = "起始 tag，未指定时由 GitHub 自动选择上一个 release"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）"),
+        ("include_meta" = Option<bool>, Query, description = "是否将数据包装为 { data, cache_age_secs, expires_in_secs }（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "成功生成 release notes", body = GeneratedNotes),
+        (status = 400, description = "缺少 tag 参数或 tag 无效"),
+        (status = 403, description = "服务端未配置 GITHUB_TOKEN 或 token 没有仓库的写权限"),
+        (status = 404, description = "仓库不存在")
+    )
+)]
+#[get("/repos/{owner}/{repo}/releases/notes")]
+pub async fn get_generated_release_notes(
+    path: web::Path<(String, String)>,
+    query: web::Query<GenerateNotesQuery>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
+    let (owner, repo) = path.into_inner();
+    let query = query.into_inner();
+    if query.tag.trim().is_empty() {
+        return Err(AppError::BadRequest("缺少 tag 参数".to_string()));
+    }
+    let previous_tag = query.previous_tag.as_deref().filter(|t| !t.trim().is_empty());
+
+    log::info!("请求: GET /repos/{}/{}/releases/notes?tag={}", owner, repo, query.tag);
+    let notes = fetch_generated_notes(&owner, &repo, &query.tag, previous_tag).await?;
+    let cache_key = CacheManager::generated_notes_key(&owner, &repo, &query.tag, previous_tag);
+    cached_json_response(&notes, &format, Some(&cache_key)).await
+}
+
+// 解析仓库字符串 "owner/repo" 为 (owner, repo)
+pub(crate) fn parse_repo(repo_str: &str) -> Option<(String, String)> {
+    let parts: Vec<&str> = repo_str.split('/').collect();
+    if parts.len() == 2 && !parts[0].is_empty() && !parts[1].is_empty() {
+        Some((parts[0].to_string(), parts[1].to_string()))
+    } else {
+        None
+    }
+}
+
+// 解析版本对比路径 "{base}...{head}" 为 (base, head)
+//
+// 引用名可以包含 `/`（如 `release/1.0`），因此优先按 GitHub 的 `...` 分隔；
+// 没有 `...` 时兼容旧的 "{base}/{head}" 写法，但只在恰好有一个 `/` 时才能无歧义地拆分。
+fn parse_compare_refs(refs: &str) -> Option<(String, String)> {
+    let (base, head) = match refs.split_once("...") {
+        Some(pair) => pair,
+        None => {
+            let (base, head) = refs.split_once('/')?;
+            if head.contains('/') {
+                return None;
+            }
+            (base, head)
+        }
+    };
+    if base.is_empty() || head.is_empty() {
+        return None;
+    }
+    Some((base.to_string(), head.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release_published_at(tag: &str, published_at: Option<&str>) -> ReleaseInfo {
+        ReleaseInfo {
+            tag_name: tag.to_string(),
+            name: None,
+            changelog: None,
+            changelog_truncated: false,
+            published_at: published_at.map(|p| p.parse().unwrap()),
+            prerelease: false,
+            draft: false,
+            attachments: vec![],
+            attachment_sizes: vec![],
+            author_login: None,
+            author_avatar_url: None,
+            reactions: None,
+            zipball_url: None,
+            tarball_url: None,
+        }
+    }
+
+    // 启动一个按顺序返回给定响应（状态行和响应头, 响应体）的本地 HTTP 服务，返回其地址
+    async fn spawn_status_sequence_server(responses: Vec<(&'static str, &'static str)>) -> String {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for (head, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    head,
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_send_download_request_retries_after_rate_limit() {
+        let config = DownloadConfig::default();
+
+        // 429 之后按 Retry-After 等待并重试一次
+        let url = spawn_status_sequence_server(vec![
+            ("429 Too Many Requests\r\nRetry-After: 0", ""),
+            ("200 OK", "asset"),
+        ])
+        .await;
+        let response = send_download_request(create_client().get(&url), &config).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "asset");
+
+        // 要求等待的时间超过上限时直接返回 429，不重试
+        let url = spawn_status_sequence_server(vec![("403 Forbidden\r\nRetry-After: 120", "")]).await;
+        let result = send_download_request(create_client().get(&url), &config).await;
+        assert!(matches!(result, Err(AppError::UpstreamRateLimited { retry_after_secs: 120 })));
+
+        // 重试后仍被限流
+        let url = spawn_status_sequence_server(vec![
+            ("429 Too Many Requests\r\nRetry-After: 0", ""),
+            ("429 Too Many Requests\r\nRetry-After: 30", ""),
+        ])
+        .await;
+        let result = send_download_request(create_client().get(&url), &config).await;
+        assert!(matches!(result, Err(AppError::UpstreamRateLimited { retry_after_secs: 30 })));
+
+        // 不带 Retry-After 的 403 原样返回，由调用方按普通错误处理
+        let url = spawn_status_sequence_server(vec![("403 Forbidden", "")]).await;
+        let response = send_download_request(create_client().get(&url), &config).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_send_with_202_retry_retries_until_ready() {
+        let url = spawn_status_sequence_server(vec![("202 Accepted", ""), ("200 OK", "{\"ok\":true}")]).await;
+        let request = create_client().get(&url);
+
+        let response = send_with_202_retry(request, 3, Duration::from_millis(10)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["ok"], true);
+    }
+
+    #[tokio::test]
+    async fn test_batch_requests_hold_github_permit_until_body_is_read() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use tokio::io::AsyncReadExt;
+
+        // 模拟 GitHub：先返回响应头，等待一段时间后才发送响应体，记录同时处于传输响应体阶段的请求数
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (server_in_flight, server_max) = (in_flight.clone(), max_in_flight.clone());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let (in_flight, max_in_flight) = (server_in_flight.clone(), server_max.clone());
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 2048];
+                    let _ = socket.read(&mut buf).await;
+                    let body = r#"{"name":"r","full_name":"o/r","html_url":"https://github.com/o/r","description":null,"stargazers_count":1,"forks_count":0,"updated_at":"2024-01-01T00:00:00Z","private":false}"#;
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    socket.write_all(head.as_bytes()).await.unwrap();
+                    socket.flush().await.unwrap();
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(current, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    let _ = socket.write_all(body.as_bytes()).await;
+                });
+            }
+        });
+
+        // 仓库数超过 GITHUB_MAX_CONCURRENT_REQUESTS 的默认值 20
+        let repos: Vec<String> = (0..30).map(|i| format!("permit-owner/permit-repo-{}", i)).collect();
+        let fields = vec!["repo_info".to_string()];
+        let results = GITHUB_API_URL_OVERRIDE
+            .scope(
+                format!("http://{}", addr),
+                join_all(repos.iter().map(|repo| process_single_repo(repo, &fields, None))),
+            )
+            .await;
+
+        assert!(results.iter().all(|result| result.success), "{:?}", results.iter().map(|r| &r.error).collect::<Vec<_>>());
+        let max_in_flight = max_in_flight.load(Ordering::SeqCst);
+        assert!(max_in_flight > 0);
+        assert!(max_in_flight <= 20, "同时传输响应体的请求数: {}", max_in_flight);
+    }
+
+    #[test]
+    fn test_next_page_url() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(next_page_url(&headers), None);
+        headers.insert(
+            reqwest::header::LINK,
+            "<https://api.github.com/repositories/1/milestones?page=2>; rel=\"next\", <https://api.github.com/repositories/1/milestones?page=5>; rel=\"last\""
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            next_page_url(&headers).as_deref(),
+            Some("https://api.github.com/repositories/1/milestones?page=2")
+        );
+        // 最后一页只有 prev 和 first
+        headers.insert(
+            reqwest::header::LINK,
+            "<https://api.github.com/repositories/1/milestones?page=4>; rel=\"prev\", <https://api.github.com/repositories/1/milestones?page=1>; rel=\"first\""
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(next_page_url(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_milestones_follows_pages_and_coalesces() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use tokio::io::AsyncReadExt;
+
+        // 第一页带指向第二页的 Link 头，第二页没有下一页；响应前稍作等待，让并发请求在进行中合并
+        let requests = Arc::new(AtomicUsize::new(0));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server_requests = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                server_requests.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 2048];
+                    let n = socket.read(&mut buf).await.unwrap();
+                    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                    let milestone = |title: &str| {
+                        format!(
+                            r#"{{"title":"{}","description":null,"state":"open","due_on":null,"open_issues":1,"closed_issues":0,"html_url":"https://github.com/o/r/milestone/1"}}"#,
+                            title
+                        )
+                    };
+                    let (link, body) = if request.contains("page=2") {
+                        (String::new(), format!("[{}]", milestone("v2")))
+                    } else {
+                        (
+                            format!("Link: <http://{}/repositories/1/milestones?state=open&per_page=100&page=2>; rel=\"next\"\r\n", addr),
+                            format!("[{}]", milestone("v1")),
+                        )
+                    };
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        link,
+                        body.len(),
+                        body
+                    );
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+
+        let results = GITHUB_API_URL_OVERRIDE
+            .scope(
+                format!("http://{}", addr),
+                join_all((0..3).map(|_| fetch_milestones("milestones-owner", "paged-repo", "open"))),
+            )
+            .await;
+        for result in results {
+            let titles: Vec<String> = result.unwrap().into_iter().map(|m| m.title).collect();
+            assert_eq!(titles, ["v1", "v2"]);
+        }
+        // 三个并发请求合并为一次获取，每页只请求一次
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_send_with_202_retry_returns_503_when_still_computing() {
+        let url = spawn_status_sequence_server(vec![("202 Accepted", ""), ("202 Accepted", "")]).await;
+        let request = create_client().get(&url);
+
+        let result = send_with_202_retry(request, 1, Duration::from_millis(10)).await;
+        assert!(matches!(result, Err(AppError::ServiceUnavailable { .. })));
+    }
+
+    // 模拟 GitHub 的条件请求：带 If-Modified-Since 或 If-None-Match 时返回 304，否则返回数据、Last-Modified 和 ETag
+    async fn spawn_last_modified_server(requests: usize) -> String {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for _ in 0..requests {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 2048];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let response = if request.contains("if-modified-since: mon, 01 jan 2024 00:00:00 gmt")
+                    || request.contains("if-none-match: \"v1\"")
+                {
+                    "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n".to_string()
+                } else {
+                    let body = "{\"ok\":true}";
+                    format!(
+                        "HTTP/1.1 200 OK\r\nLast-Modified: Mon, 01 Jan 2024 00:00:00 GMT\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_conditional_request_not_modified() {
+        let url = spawn_last_modified_server(2).await;
+
+        // 首次请求返回数据，并记录 Last-Modified
+        let result = send_conditional_request::<serde_json::Value>(create_client().get(&url), None, false)
+            .await
+            .unwrap();
+        let validators = match result {
+            Conditional::Modified { value, validators } => {
+                assert_eq!(value["ok"], true);
+                validators
+            }
+            Conditional::NotModified => panic!("expected Modified"),
+        };
+        assert_eq!(validators.last_modified.as_deref(), Some("Mon, 01 Jan 2024 00:00:00 GMT"));
+        // 未启用条件响应时不记录 ETag
+        assert_eq!(validators.etag, None);
+
+        // 带上 If-Modified-Since 重新验证时返回 304
+        let result = send_conditional_request::<serde_json::Value>(
+            create_client().get(&url),
+            Some(&validators),
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(result, Conditional::NotModified));
+    }
+
+    #[tokio::test]
+    async fn test_conditional_request_if_none_match() {
+        let url = spawn_last_modified_server(2).await;
+
+        // 启用条件响应时记录 GitHub 返回的 ETag
+        let result = send_conditional_request::<serde_json::Value>(create_client().get(&url), None, true)
+            .await
+            .unwrap();
+        let Conditional::Modified { validators, .. } = result else {
+            panic!("expected Modified");
+        };
+        assert_eq!(validators.etag.as_deref(), Some("\"v1\""));
+
+        // 只有 ETag 时通过 If-None-Match 重新验证
+        let validators = Validators { last_modified: None, ..validators };
+        let result = send_conditional_request::<serde_json::Value>(
+            create_client().get(&url),
+            Some(&validators),
+            true,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(result, Conditional::NotModified));
+    }
+
+    #[test]
+    fn test_contents_api_url() {
+        let url = contents_api_url("owner", "repo", "docs/my file#1.md", Some("release/v1")).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://api.github.com/repos/owner/repo/contents/docs/my%20file%231.md?ref=release%2Fv1"
+        );
+        // 空路径为仓库根目录
+        let url = contents_api_url("owner", "repo", "", None).unwrap();
+        assert_eq!(url.as_str(), "https://api.github.com/repos/owner/repo/contents");
+        assert!(matches!(
+            contents_api_url("owner", "repo", "../../orgs/secret", None),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_file_contents() {
+        let file = serde_json::json!({
+            "type": "file",
+            "name": "version.json",
+            "size": 19,
+            "encoding": "base64",
+            "content": "eyJ2ZXJzaW9uIjoi\nMS4yLjMifQ==\n"
+        });
+        let (name, bytes) = decode_file_contents("config/version.json", &file).unwrap().unwrap();
+        assert_eq!(name, "version.json");
+        assert_eq!(bytes, br#"{"version":"1.2.3"}"#);
+
+        // 目录返回原始 JSON
+        let dir = serde_json::json!([{ "type": "file", "name": "a.txt" }]);
+        assert!(decode_file_contents("config", &dir).unwrap().is_none());
+
+        // 超过 1MB 的文件没有内容
+        let large = serde_json::json!({
+            "type": "file",
+            "name": "big.bin",
+            "size": 5_000_000,
+            "encoding": "none",
+            "content": ""
+        });
+        assert!(matches!(
+            decode_file_contents("big.bin", &large),
+            Err(AppError::FileTooLarge { size: 5_000_000, .. })
+        ));
+    }
+
+    #[test]
+    fn test_raw_repo_visibility() {
+        let raw = serde_json::json!({ "full_name": "owner/secret", "private": true, "homepage": null });
+        match check_raw_repo_visibility("owner", "secret", raw.clone(), false) {
+            Err(AppError::Forbidden(message)) => assert!(message.contains("owner/secret")),
+            other => panic!("expected Forbidden, got {:?}", other),
+        }
+        // 启用后原样返回
+        assert_eq!(check_raw_repo_visibility("owner", "secret", raw.clone(), true).unwrap(), raw);
+
+        let public = serde_json::json!({ "full_name": "owner/public", "private": false });
+        assert!(check_raw_repo_visibility("owner", "public", public, false).is_ok());
+    }
+
+    #[test]
+    fn test_private_repo_visibility() {
+        // 模拟 GitHub 对私有仓库返回的响应（token 有访问权限）
+        let json = r#"{
+            "name": "secret",
+            "full_name": "owner/secret",
+            "html_url": "https://github.com/owner/secret",
+            "description": null,
+            "stargazers_count": 0,
+            "forks_count": 0,
+            "updated_at": "2024-01-01T00:00:00Z",
+            "private": true
+        }"#;
+        let github_repo: GithubRepo = serde_json::from_str(json).unwrap();
+        let repo_info = to_repo_info("owner", "secret", github_repo);
+        assert!(repo_info.private);
+
+        // 未启用 PRIVATE_REPOS_ENABLED 时返回 403
+        match check_repo_visibility(repo_info.clone(), false) {
+            Err(AppError::Forbidden(message)) => assert!(message.contains("owner/secret")),
+            other => panic!("expected Forbidden, got {:?}", other),
+        }
+        // 启用后正常返回
+        let repo_info = check_repo_visibility(repo_info, true).unwrap();
+        assert_eq!(repo_info.full_name, "owner/secret");
+
+        // 公开仓库（响应中没有 private 字段时视为公开）不受开关影响
+        let mut public: serde_json::Value = serde_json::from_str(json).unwrap();
+        public.as_object_mut().unwrap().remove("private");
+        let public: GithubRepo = serde_json::from_value(public).unwrap();
+        assert!(check_repo_visibility(to_repo_info("owner", "secret", public), false).is_ok());
+    }
+
+    #[test]
+    fn test_select_latest_release_without_releases() {
+        assert!(matches!(
+            select_latest_release("owner", "repo", vec![]),
+            Err(AppError::NoReleases)
+        ));
+        let latest = select_latest_release(
+            "owner",
+            "repo",
+            vec![
+                release_published_at("v1", Some("2024-01-01T00:00:00Z")),
+                release_published_at("v2", Some("2024-02-01T00:00:00Z")),
+            ],
+        )
+        .unwrap();
+        assert_eq!(latest.latest_version, "v2");
+    }
+
+    #[test]
+    fn test_select_stable_release() {
+        assert!(matches!(
+            select_stable_release("owner", "repo", vec![]),
+            Err(AppError::NoReleases)
+        ));
+
+        let mut beta = release_published_at("v2.0.0-beta", Some("2024-03-01T00:00:00Z"));
+        beta.prerelease = true;
+        let mut draft = release_published_at("v3.0.0", None);
+        draft.draft = true;
+        assert!(matches!(
+            select_stable_release("owner", "repo", vec![beta.clone(), draft.clone()]),
+            Err(AppError::NoStableRelease)
+        ));
+
+        let releases = vec![
+            beta,
+            draft,
+            release_published_at("v1.0.0", Some("2024-01-01T00:00:00Z")),
+            release_published_at("v1.1.0", Some("2024-02-01T00:00:00Z")),
+        ];
+        let stable = select_stable_release("owner", "repo", releases).unwrap();
+        assert_eq!(stable.latest_version, "v1.1.0");
+    }
+
+    #[test]
+    fn test_truncate_changelog() {
+        let (changelog, truncated) = truncate_changelog("short".to_string(), 10);
+        assert_eq!(changelog, "short");
+        assert!(!truncated);
+
+        let (changelog, truncated) = truncate_changelog("0123456789abc".to_string(), 10);
+        assert_eq!(changelog, format!("0123456789{}", CHANGELOG_TRUNCATED_MARKER));
+        assert!(truncated);
+    }
+
+    #[test]
+    fn test_truncate_changelog_on_char_boundary() {
+        // "更新日志" 每个字符占 3 个字节，4 字节处位于第二个字符中间
+        let (changelog, truncated) = truncate_changelog("更新日志".to_string(), 4);
+        assert_eq!(changelog, format!("更{}", CHANGELOG_TRUNCATED_MARKER));
+        assert!(truncated);
+    }
+
+    #[test]
+    fn test_filter_releases_by_date() {
+        let releases = vec![
+            release_published_at("v1", Some("2024-01-01T00:00:00Z")),
+            release_published_at("v2", Some("2024-02-01T00:00:00Z")),
+            release_published_at("v3", Some("2024-03-01T00:00:00Z")),
+            release_published_at("draft", None),
+        ];
+
+        // 未指定时间范围时保留全部（包括没有发布时间的 release）
+        assert_eq!(filter_releases_by_date(releases.clone(), None, None).len(), 4);
+
+        let since = Some("2024-02-01T00:00:00Z".parse().unwrap());
+        let tags: Vec<_> = filter_releases_by_date(releases.clone(), since, None)
+            .into_iter()
+            .map(|r| r.tag_name)
+            .collect();
+        assert_eq!(tags, vec!["v2", "v3"]);
+
+        let until = Some("2024-02-15T00:00:00Z".parse().unwrap());
+        let tags: Vec<_> = filter_releases_by_date(releases, since, until)
+            .into_iter()
+            .map(|r| r.tag_name)
+            .collect();
+        assert_eq!(tags, vec!["v2"]);
+    }
+
+    #[test]
+    fn test_collect_release_assets() {
+        let release = |tag: &str, files: &[&str], draft: bool| {
+            let mut release = release_published_at(tag, Some("2024-01-01T00:00:00Z"));
+            release.draft = draft;
+            release.attachments = files
+                .iter()
+                .map(|f| format!("https://github.com/o/r/releases/download/{}/{}", tag, f))
+                .collect();
+            release.attachment_sizes = (1..=files.len() as u64).collect();
+            release
+        };
+        let releases = vec![
+            release("v3", &["app.dmg", "app.exe"], false),
+            release("v2", &["app.exe"], false),
+            release("v1", &["app-x64.dmg", "app-arm64.dmg"], false),
+            release("draft", &["app.dmg"], true),
+        ];
+
+        let all = collect_release_assets(releases.clone(), "*.dmg", 1, 30);
+        let tags: Vec<_> = all.releases.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(tags, vec!["v3", "v1"]);
+        assert_eq!((all.total_releases, all.total_assets), (2, 3));
+        assert_eq!(all.releases[0].assets[0].name, "app.dmg");
+        assert_eq!(all.releases[0].assets[0].size, Some(1));
+        assert_eq!(all.releases[1].assets[1].size, Some(2));
+
+        // 分页按 release 分组计数，总数不受分页影响
+        let second = collect_release_assets(releases.clone(), "*.dmg", 2, 1);
+        assert_eq!(second.releases.len(), 1);
+        assert_eq!(second.releases[0].tag_name, "v1");
+        assert_eq!((second.total_releases, second.total_assets), (2, 3));
+        assert!(collect_release_assets(releases.clone(), "*.dmg", 3, 1).releases.is_empty());
+
+        // 没有匹配时返回空结果
+        let none = collect_release_assets(releases, "*.deb", 1, 30);
+        assert!(none.releases.is_empty());
+        assert_eq!((none.total_releases, none.total_assets), (0, 0));
+    }
+
+    #[test]
+    fn test_release_date_query_parse() {
+        let query = ReleaseDateQuery {
+            since: Some("2024-01-01T00:00:00+08:00".to_string()),
+            until: None,
+        };
+        let (since, until) = query.parse().unwrap();
+        assert_eq!(since, Some("2023-12-31T16:00:00Z".parse().unwrap()));
+        assert!(until.is_none());
+
+        let query = ReleaseDateQuery {
+            since: None,
+            until: Some("last month".to_string()),
+        };
+        assert_eq!(query.parse().unwrap_err(), "until");
+    }
+
+    #[test]
+    fn test_find_checksums_url() {
+        let attachments = vec![
+            "https://github.com/o/r/releases/download/v1/app.tar.gz".to_string(),
+            "https://github.com/o/r/releases/download/v1/SHA256SUMS".to_string(),
+        ];
+        assert_eq!(find_checksums_url(&attachments), Some(&attachments[1]));
+
+        let attachments = vec!["https://github.com/o/r/releases/download/v1/app_1.0_checksums.txt".to_string()];
+        assert!(find_checksums_url(&attachments).is_some());
+
+        let attachments = vec!["https://github.com/o/r/releases/download/v1/app.zip".to_string()];
+        assert!(find_checksums_url(&attachments).is_none());
+
+        // goreleaser 的签名和证书排在校验和文件之前时跳过
+        let attachments = vec![
+            "https://github.com/o/r/releases/download/v1/checksums.txt.sig".to_string(),
+            "https://github.com/o/r/releases/download/v1/checksums.txt.pem".to_string(),
+            "https://github.com/o/r/releases/download/v1/checksums.txt".to_string(),
+        ];
+        assert_eq!(find_checksums_url(&attachments), Some(&attachments[2]));
+
+        // 优先选择 .txt 或 SHA256SUMS
+        let attachments = vec![
+            "https://github.com/o/r/releases/download/v1/checksums.json".to_string(),
+            "https://github.com/o/r/releases/download/v1/SHA256SUMS".to_string(),
+        ];
+        assert_eq!(find_checksums_url(&attachments), Some(&attachments[1]));
+
+        let attachments = vec!["https://github.com/o/r/releases/download/v1/SHA256SUMS.asc".to_string()];
+        assert!(find_checksums_url(&attachments).is_none());
+    }
+
+    #[test]
+    fn test_parse_checksums() {
+        let hash_a = "a".repeat(64);
+        let hash_b = "B".repeat(64);
+        let content = format!(
+            "{}  app-linux.tar.gz\n{} app-windows.zip\n\n# comment\nnot-a-hash file.txt\n{} *app-macos.dmg\n",
+            hash_a, hash_b, hash_a
+        );
+        let checksums = parse_checksums(&content);
+
+        assert_eq!(checksums.len(), 3);
+        assert_eq!(checksums.get("app-linux.tar.gz"), Some(&hash_a));
+        assert_eq!(checksums.get("app-windows.zip"), Some(&"b".repeat(64)));
+        assert_eq!(checksums.get("app-macos.dmg"), Some(&hash_a));
+    }
+
+    #[test]
+    fn test_parse_repo_valid() {
+        assert_eq!(
+            parse_repo("owner/repo"),
+            Some(("owner".to_string(), "repo".to_string()))
+        );
+        assert_eq!(
+            parse_repo("octocat/Hello-World"),
+            Some(("octocat".to_string(), "Hello-World".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_compare_refs() {
+        let pair = |base: &str, head: &str| Some((base.to_string(), head.to_string()));
+        assert_eq!(parse_compare_refs("v1.0.0...v1.2.0"), pair("v1.0.0", "v1.2.0"));
+        assert_eq!(parse_compare_refs("v1.0.0/v1.2.0"), pair("v1.0.0", "v1.2.0"));
+        assert_eq!(parse_compare_refs("release/1.0...main"), pair("release/1.0", "main"));
+        assert_eq!(
+            parse_compare_refs("release/1.0...feature/x"),
+            pair("release/1.0", "feature/x")
+        );
+        // 没有 `...` 时多个 `/` 无法确定分界
+        assert_eq!(parse_compare_refs("release/1.0/main"), None);
+        assert_eq!(parse_compare_refs("v1.0.0"), None);
+        assert_eq!(parse_compare_refs("v1.0.0..."), None);
+        assert_eq!(parse_compare_refs("/v1.2.0"), None);
+    }
+
+    #[test]
+    fn test_to_release_info_reactions() {
+        let json = r#"{
+            "tag_name": "v1.0.0",
+            "name": null,
+            "body": null,
+            "published_at": "2024-01-01T00:00:00Z",
+            "prerelease": false,
+            "assets": [],
+            "author": { "login": "octocat", "avatar_url": "https://example.com/a.png" },
+            "reactions": { "url": "https://example.com", "total_count": 3, "+1": 2, "heart": 1 }
+        }"#;
+
+        let release: GithubRelease = serde_json::from_str(json).unwrap();
+        let info = to_release_info(release, true);
+        assert_eq!(info.author_login.as_deref(), Some("octocat"));
+        let reactions = info.reactions.unwrap();
+        assert_eq!(reactions.len(), 2);
+        assert_eq!(reactions["+1"], 2);
+        assert_eq!(reactions["heart"], 1);
+
+        // 未请求反应统计时不返回 reactions
+        let release: GithubRelease = serde_json::from_str(json).unwrap();
+        let info = to_release_info(release, false);
+        assert_eq!(info.author_login.as_deref(), Some("octocat"));
+        assert!(info.reactions.is_none());
+    }
+
+    #[test]
+    fn test_parse_repo_invalid() {
+        assert_eq!(parse_repo("invalid"), None);
+        assert_eq!(parse_repo("owner/repo/extra"), None);
+        assert_eq!(parse_repo(""), None);
+        assert_eq!(parse_repo("owner/"), None);
+        assert_eq!(parse_repo("/repo"), None);
+    }
+
+    #[test]
+    fn test_to_repo_info_detects_renamed_repo() {
+        let github_repo: GithubRepo = serde_json::from_value(serde_json::json!({
+            "name": "new-name",
+            "full_name": "new-owner/new-name",
+            "html_url": "https://github.com/new-owner/new-name",
+            "description": null,
+            "stargazers_count": 1,
+            "forks_count": 0,
+            "updated_at": "2024-01-01T00:00:00Z"
+        }))
+        .unwrap();
+        let repo_info = to_repo_info("old-owner", "old-name", github_repo);
+        assert_eq!(repo_info.repo, "old-owner/old-name");
+        assert_eq!(repo_info.moved_to.as_deref(), Some("new-owner/new-name"));
+        let json = serde_json::to_value(&repo_info).unwrap();
+        assert_eq!(json["moved_to"], "new-owner/new-name");
+    }
+
+    #[test]
+    fn test_strip_version_prefix() {
+        assert_eq!(strip_version_prefix("v1.0.0"), "1.0.0");
+        assert_eq!(strip_version_prefix("V2"), "2");
+        assert_eq!(strip_version_prefix("v1.2.3-beta.1"), "1.2.3-beta.1");
+        assert_eq!(strip_version_prefix("v1.0.0+build.5"), "1.0.0+build.5");
+        assert_eq!(strip_version_prefix("1.0.0"), "1.0.0");
+        // 非版本号 tag 保持不变
+        assert_eq!(strip_version_prefix("vendor-1.0"), "vendor-1.0");
+        assert_eq!(strip_version_prefix("v"), "v");
+        assert_eq!(strip_version_prefix("v1..0"), "v1..0");
+        assert_eq!(strip_version_prefix("v1.0-"), "v1.0-");
+        assert_eq!(strip_version_prefix("vv1.0"), "vv1.0");
+    }
+
+    #[test]
+    fn test_strip_tauri_version() {
+        let json = serde_json::json!({ "version": "v1.2.0", "notes": "n" });
+        assert_eq!(strip_tauri_version(json.clone(), false)["version"], "v1.2.0");
+        assert_eq!(strip_tauri_version(json, true)["version"], "1.2.0");
+        let json = serde_json::json!({ "version": "vendor-1.0" });
+        assert_eq!(strip_tauri_version(json, true)["version"], "vendor-1.0");
+    }
+
+    #[test]
+    fn test_find_matching_asset() {
+        let attachments = vec![
+            "https://github.com/o/r/releases/download/v1/app-windows.zip".to_string(),
+            "https://github.com/o/r/releases/download/v1/app-linux.tar.gz".to_string(),
+            "https://github.com/o/r/releases/download/v1/SHA256SUMS".to_string(),
+        ];
+        assert_eq!(
+            find_matching_asset(&attachments, "*linux*"),
+            Some((1, "app-linux.tar.gz", &attachments[1]))
+        );
+        // 多个附件匹配时返回第一个
+        assert_eq!(find_matching_asset(&attachments, "app-*").map(|(_, name, _)| name), Some("app-windows.zip"));
+        assert_eq!(find_matching_asset(&attachments, "*.dmg"), None);
+    }
+
+    #[actix_web::test]
+    async fn test_resolve_latest_release_asset_uses_release_sizes() {
+        let (owner, repo) = ("resolve-owner", "sizes");
+        let mut release = release_published_at("v1.0.0", None);
+        release.attachments = vec![
+            "https://github.com/o/r/releases/download/v1.0.0/app-windows.zip".to_string(),
+            "https://github.com/o/r/releases/download/v1.0.0/app-linux.tar.gz".to_string(),
+        ];
+        release.attachment_sizes = vec![1024, 2048];
+        let backend = get_cache_backend().await;
+        backend
+            .set_latest_release(owner, repo, to_latest_release_info(owner, repo, release))
+            .await;
+        backend
+            .set_checksums(
+                owner,
+                repo,
+                ChecksumsInfo {
+                    repo: format!("{}/{}", owner, repo),
+                    version: "v1.0.0".to_string(),
+                    checksums_url: "https://github.com/o/r/releases/download/v1.0.0/SHA256SUMS".to_string(),
+                    checksums: HashMap::from([("app-linux.tar.gz".to_string(), "abc".to_string())]),
+                },
+            )
+            .await;
+
+        // 大小取自缓存的 release 数据，不发送额外请求
+        let asset = resolve_latest_release_asset(owner, repo, "*linux*").await.unwrap();
+        assert_eq!(asset.name, "app-linux.tar.gz");
+        assert_eq!(asset.size, Some(2048));
+        assert_eq!(asset.sha256.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_find_asset_by_name() {
+        let attachments = vec![
+            "https://github.com/o/r/releases/download/v1/app-linux.tar.gz".to_string(),
+            "https://github.com/o/r/releases/download/v1/app-linux.tar.gz.sha256".to_string(),
+        ];
+        assert_eq!(find_asset_by_name(&attachments, "app-linux.tar.gz"), Some(&attachments[0]));
+        assert_eq!(find_asset_by_name(&attachments, "app-linux.tar.gz.sha256"), Some(&attachments[1]));
+        // 只做完全匹配，不支持通配符
+        assert_eq!(find_asset_by_name(&attachments, "app-*"), None);
+        assert_eq!(find_asset_by_name(&attachments, "app-linux"), None);
+
+        // URL 中编码的文件名按解码后的名称匹配
+        let attachments = vec![
+            "https://github.com/o/r/releases/download/v1/My%20App%2B1.0.zip".to_string(),
+        ];
+        assert_eq!(find_asset_by_name(&attachments, "My App+1.0.zip"), Some(&attachments[0]));
+        assert_eq!(find_asset_by_name(&attachments, "My%20App%2B1.0.zip"), None);
+    }
+
+    #[actix_web::test]
+    async fn test_serve_cached_file_prefers_gzip_sibling() {
+        use std::sync::Arc;
+        use tokio::sync::Semaphore;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let file_path = dir.join("notes.txt");
+        std::fs::write(&file_path, "hello ".repeat(50)).unwrap();
+        let gzip_path = write_gzip_sibling(&file_path).unwrap();
+        let metadata = FileCacheMetadata {
+            url: "https://example.com/notes.txt".to_string(),
+            file_path: file_path.clone(),
+            original_filename: "notes.txt".to_string(),
+            content_type: Some("text/plain".to_string()),
+            expires_at: u64::MAX,
+            last_accessed_at: 0,
+            etag: None,
+            cached_at: 0,
+            gzip_path: Some(gzip_path.clone()),
+            immutable: false,
+            size: None,
+        };
+        let semaphore = Arc::new(Semaphore::new(2));
+        let serve = |accept_gzip: bool| {
+            let metadata = metadata.clone();
+            let permit = semaphore.clone().try_acquire_owned().unwrap();
+            async move {
+                let response = serve_cached_file(metadata, permit, "127.0.0.1".to_string(), accept_gzip)
+                    .await
+                    .unwrap();
+                let encoding = response
+                    .headers()
+                    .get("content-encoding")
+                    .map(|v| v.to_str().unwrap().to_string());
+                let vary = response.headers().get("vary").cloned();
+                let body = actix_web::body::to_bytes(response.into_body()).await.ok().unwrap();
+                (encoding, vary, body)
+            }
+        };
+
+        let (encoding, vary, body) = serve(true).await;
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+        assert!(vary.is_some());
+        assert_eq!(body, std::fs::read(&gzip_path).unwrap());
+
+        let (encoding, vary, body) = serve(false).await;
+        assert_eq!(encoding, None);
+        assert!(vary.is_some());
+        assert_eq!(body, std::fs::read(&file_path).unwrap());
+    }
+
+    // 测试用的缓存管理器：缓存文件在后台任务中登记，需要 'static 生命周期
+    async fn leaked_cache_manager(enabled: bool) -> &'static CacheManager {
+        use crate::cache::create_test_cache_config;
+
+        Box::leak(Box::new(CacheManager::new(create_test_cache_config(enabled, 3600)).await))
+    }
+
+    // 以负责方身份开始下载 url，写入任务结束（无论成功与否）时释放守卫
+    fn lead_download(cache: &CacheManager, url: &str) -> DownloadFlightGuard {
+        match cache.begin_download(url) {
+            DownloadFlight::Leader(guard) => guard,
+            DownloadFlight::Follower(_) => panic!("{} 已在下载中", url),
+        }
+    }
+
+    // 等待 url 的后台缓存写入结束
+    async fn wait_for_download(cache: &CacheManager, url: &str) {
+        if let DownloadFlight::Follower(waiter) = cache.begin_download(url) {
+            waiter.wait().await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_stream_and_cache_discards_incomplete_file() {
+        use std::sync::Arc;
+        use tokio::sync::Semaphore;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let cache = leaked_cache_manager(true).await;
+        let semaphore = Arc::new(Semaphore::new(2));
+        let run = |name: &str, fail: bool| {
+            let url = format!("https://example.com/{}", name);
+            let target = CacheTarget {
+                cache,
+                flight: Some(lead_download(cache, &url)),
+                url,
+                filename: name.to_string(),
+                cache_filename: name.to_string(),
+                cache_file_path: dir.join(name),
+                content_type: mime::APPLICATION_OCTET_STREAM,
+                etag: None,
+                expected_len: None,
+                #[cfg(feature = "s3")]
+                mirror_to_s3: false,
+            };
+            let permit = semaphore.clone().try_acquire_owned().unwrap();
+            let mut chunks = vec![Ok(actix_web::web::Bytes::from_static(b"hello"))];
+            if fail {
+                chunks.push(Err(AppError::ApiError("连接中断".to_string())));
+            }
+            async move {
+                let response = stream_and_cache(futures::stream::iter(chunks), target, permit, "127.0.0.1".to_string())
+                    .await
+                    .unwrap();
+                let _ = actix_web::body::to_bytes(response.into_body()).await;
+            }
+        };
+
+        run("complete.bin", false).await;
+        run("broken.bin", true).await;
+
+        // 等待后台写入任务结束
+        wait_for_download(cache, "https://example.com/complete.bin").await;
+        wait_for_download(cache, "https://example.com/broken.bin").await;
+        assert!(!dir.join("broken.bin").exists());
+        assert!(cache.get_file_cache("https://example.com/broken.bin").await.is_none());
+        assert_eq!(std::fs::read(dir.join("complete.bin")).unwrap(), b"hello");
+        // 临时文件已被重命名或删除
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_same_filename_from_different_urls() {
+        use std::sync::Arc;
+        use tokio::sync::Semaphore;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        let cache = leaked_cache_manager(true).await;
+        let semaphore = Arc::new(Semaphore::new(2));
+        let urls = [
+            "https://github.com/alice/tool/releases/download/v1.0.0/app.zip",
+            "https://github.com/bob/other/releases/download/v2.0.0/app.zip",
+        ];
+
+        let mut cache_files = Vec::new();
+        for url in urls {
+            let filename = filename_from_url(url);
+            assert_eq!(filename, "app.zip");
+            let cache_filename = cache_filename_for_url(url, &filename);
+            let target = CacheTarget {
+                cache,
+                url: url.to_string(),
+                filename,
+                cache_file_path: dir.join(&cache_filename),
+                cache_filename: cache_filename.clone(),
+                content_type: mime::APPLICATION_OCTET_STREAM,
+                etag: None,
+                expected_len: None,
+                #[cfg(feature = "s3")]
+                mirror_to_s3: false,
+                flight: Some(lead_download(cache, url)),
+            };
+            let permit = semaphore.clone().try_acquire_owned().unwrap();
+            let body = futures::stream::iter(vec![Ok(actix_web::web::Bytes::from(url))]);
+            let response = stream_and_cache(body, target, permit, "127.0.0.1".to_string())
+                .await
+                .unwrap();
+            assert_eq!(
+                response.headers().get("content-disposition").unwrap(),
+                "attachment; filename=\"app.zip\""
+            );
+            let _ = actix_web::body::to_bytes(response.into_body()).await;
+            cache_files.push(cache_filename);
+        }
+        assert_ne!(cache_files[0], cache_files[1]);
+        assert!(cache_files.iter().all(|name| name.ends_with(".zip")));
+
+        // 等待后台写入任务结束，两个 URL 各自对应独立的缓存文件
+        for url in urls {
+            wait_for_download(cache, url).await;
+        }
+        for (url, cache_filename) in urls.iter().zip(&cache_files) {
+            assert_eq!(std::fs::read(dir.join(cache_filename)).unwrap(), url.as_bytes());
+            let metadata = cache.get_file_cache(url).await.unwrap();
+            assert_eq!(metadata.file_path, dir.join(cache_filename));
+            assert_eq!(metadata.original_filename, "app.zip");
+
+            let permit = semaphore.clone().try_acquire_owned().unwrap();
+            let response = serve_cached_file(metadata, permit, "127.0.0.1".to_string(), false)
+                .await
+                .unwrap();
+            assert_eq!(
+                response.headers().get("content-disposition").unwrap(),
+                "attachment; filename=\"app.zip\""
+            );
+            let body = actix_web::body::to_bytes(response.into_body()).await.ok().unwrap();
+            assert_eq!(body, url.as_bytes());
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_stream_download_serves_cached_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_path_buf();
+        // release 附件是不可变内容，缓存命中时不会访问上游
+        let url = "https://github.com/owner/repo/releases/download/v1.0.0/tool.bin";
+        let file_path = dir.join(cache_filename_for_url(url, "tool.bin"));
+        std::fs::write(&file_path, b"cached tool").unwrap();
+        let cache = get_cache_manager().await;
+        cache
+            .set_file_cache(url, file_path, "tool.bin".to_string(), None, None, None)
+            .await;
+
+        let req = actix_web::test::TestRequest::get()
+            .uri("/repos/owner/repo/releases/latest/assets/tool.bin")
+            .to_http_request();
+        let response = stream_download(cache, url, "127.0.0.1".to_string(), &req).await.unwrap();
+        assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-disposition").unwrap(),
+            "attachment; filename=\"tool.bin\""
+        );
+        let body = actix_web::body::to_bytes(response.into_body()).await.ok().unwrap();
+        assert_eq!(body, "cached tool");
+
+        // max_age 参数与 /download 相同，在访问缓存之前校验
+        let req = actix_web::test::TestRequest::get()
+            .uri("/download?max_age=soon")
+            .to_http_request();
+        assert!(matches!(
+            stream_download(cache, url, "127.0.0.1".to_string(), &req).await,
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_stream_download_without_cache_creates_no_file() {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/owner/repo/tool.bin", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 2048];
+            let _ = socket.read(&mut buf).await;
+            let body = "proxied tool";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        // CACHE_ENABLED=false
+        let cache = leaked_cache_manager(false).await;
+        let req = actix_web::test::TestRequest::get().uri("/download").to_http_request();
+        let response = stream_download(cache, &url, "127.0.0.1".to_string(), &req).await.unwrap();
+        assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+        let body = actix_web::body::to_bytes(response.into_body()).await.ok().unwrap();
+        assert_eq!(body, "proxied tool");
+
+        // 响应已经完整返回，没有缓存文件或写了一半的临时文件
+        let cache_file_path = cache.get_file_cache_dir().join(cache_filename_for_url(&url, "tool.bin"));
+        assert!(!cache_file_path.exists());
+        assert!(!partial_file_path(&cache_file_path).exists());
+        assert!(cache.get_file_cache(&url).await.is_none());
+    }
+
+    #[test]
+    fn test_ensure_content_type_allowed() {
+        let url = "https://github.com/owner/repo/releases/download/v1.0.0/app.zip";
+        let zip: mime::Mime = "application/zip".parse().unwrap();
+
+        // 未设置时允许所有类型
+        let unrestricted = DownloadConfig::default();
+        assert!(ensure_content_type_allowed(url, &mime::TEXT_HTML, &unrestricted).is_ok());
+
+        let restricted = DownloadConfig {
+            allowed_content_types: vec!["application/zip".to_string(), "application/x-*".to_string()],
+            ..Default::default()
+        };
+        assert!(ensure_content_type_allowed(url, &zip, &restricted).is_ok());
+        assert!(ensure_content_type_allowed(url, &"application/x-apple-diskimage".parse().unwrap(), &restricted).is_ok());
+        assert!(matches!(
+            ensure_content_type_allowed(url, &mime::TEXT_HTML_UTF_8, &restricted),
+            Err(AppError::UnsupportedMediaType(content_type)) if content_type == "text/html"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_ensure_release_asset() {
+        use crate::cache::{create_test_cache_config, CacheManager};
+
+        let backend = CacheManager::new(create_test_cache_config(true, 3600)).await;
+        let asset = "https://github.com/owner/repo/releases/download/v1.0.0/app.zip";
+        let mut release = release_published_at("v1.0.0", None);
+        release.attachments = vec![asset.to_string()];
+        backend
+            .set_latest_release("owner", "repo", to_latest_release_info("owner", "repo", release))
+            .await;
+
+        let arbitrary = "https://raw.githubusercontent.com/owner/repo/main/secret.txt";
+        let disabled = DownloadConfig::default();
+        let enabled = DownloadConfig {
+            require_release_asset: true,
+            ..Default::default()
+        };
+
+        // 未启用时行为不变
+        assert!(ensure_release_asset(arbitrary, &disabled, &backend).await.is_ok());
+        assert!(ensure_release_asset(asset, &enabled, &backend).await.is_ok());
+        assert!(matches!(
+            ensure_release_asset(arbitrary, &enabled, &backend).await,
+            Err(AppError::Forbidden(_))
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_process_single_repo_reports_field_status() {
+        // 仓库信息存在但没有任何 release：releases 为空列表，最新 release 不存在
+        let (owner, repo) = ("field-status-owner", "no-releases");
+        get_cache_backend()
+            .await
+            .set_repo_info(
+                owner,
+                repo,
+                RepoInfo {
+                    repo: format!("{}/{}", owner, repo),
+                    name: repo.to_string(),
+                    full_name: format!("{}/{}", owner, repo),
+                    html_url: format!("https://github.com/{}/{}", owner, repo),
+                    description: None,
+                    stargazers_count: 0,
+                    forks_count: 0,
+                    updated_at: "2024-01-01T00:00:00Z".parse().unwrap(),
+                    private: false,
+                    moved_to: None,
+                },
+            )
+            .await;
+        get_cache_backend().await.set_releases(owner, repo, vec![]).await;
+        get_cache_manager().await.set_latest_release_not_found(owner, repo).await;
+
+        let result = process_single_repo(&format!("{}/{}", owner, repo), &[], None).await;
+        assert!(!result.success);
+        assert!(result.repo_info.is_some());
+        assert_eq!(result.releases.as_deref().map(<[_]>::len), Some(0));
+        assert!(result.latest_release.is_none());
+        let statuses: Vec<_> = result.field_status.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        assert_eq!(
+            statuses,
+            [
+                ("repo_info", FieldStatus::Ok),
+                ("releases", FieldStatus::Ok),
+                ("latest_release", FieldStatus::Error),
+            ]
+        );
+        assert_eq!(result.error.as_deref(), Some("最新 release 获取失败"));
+
+        // 只请求部分字段时只报告这些字段
+        let fields = vec!["repo_info".to_string()];
+        let result = process_single_repo(&format!("{}/{}", owner, repo), &fields, None).await;
+        assert!(result.success);
+        assert_eq!(result.field_status.len(), 1);
+        assert_eq!(result.field_status["repo_info"], FieldStatus::Ok);
+    }
+}
+
+// 处理单个仓库的批量请求，prefetched 为通过 GraphQL 预取的数据
+async fn process_single_repo(
+    repo_str: &str,
+    fields: &[String],
+    prefetched: Option<&PrefetchedRepo>,
+) -> RepoBatchResult {
+    let (owner, repo) = match parse_repo(repo_str) {
+        Some(parsed) => parsed,
+        None => {
+            return RepoBatchResult {
+                repo: repo_str.to_string(),
+                success: false,
+                error: Some("仓库格式错误，应为 'owner/repo'".to_string()),
+                field_status: IndexMap::new(),
+                repo_info: None,
+                releases: None,
+                latest_release: None,
+            };
+        }
+    };
+
+    // 如果没有指定fields，默认获取全部
+    let should_get_repo_info = fields.is_empty() || fields.contains(&"repo_info".to_string());
+    let should_get_releases = fields.is_empty() || fields.contains(&"releases".to_string());
+    let should_get_latest_release =
+        fields.is_empty() || fields.contains(&"latest_release".to_string());
+
+    // 并发获取所有请求的数据，GraphQL 已预取的字段直接使用
+    let (repo_info_result, mut releases_result, mut latest_release_result) = join!(
+        async {
+            if !should_get_repo_info {
+                return None;
+            }
+            match prefetched {
+                Some(data) => Some(data.repo_info.clone()),
+                None => fetch_repo_info(&owner, &repo).await.ok(),
             }
         },
         async {
-            match releases_future {
-                Some(f) => f.await.ok(),
-                None => None,
+            if !should_get_releases {
+                return None;
             }
+            fetch_releases(&owner, &repo).await.ok()
         },
         async {
-            match latest_release_future {
-                Some(f) => f.await.ok(),
-                None => None,
+            if !should_get_latest_release {
+                return None;
+            }
+            match prefetched.and_then(|data| data.latest_release.clone()) {
+                Some(latest_release) => Some(latest_release),
+                None => fetch_latest_release(&owner, &repo).await.ok(),
             }
         }
     );
 
-    // 检查是否有任何错误并生成错误消息
+    // 分别记录每个请求字段的结果，并为失败的字段生成错误消息
+    let mut field_status = IndexMap::new();
     let mut error_parts = Vec::new();
-
-    if should_get_repo_info && repo_info_result.is_none() {
-        error_parts.push("仓库信息获取失败");
-    }
-    if should_get_releases && releases_result.is_none() {
-        error_parts.push("releases 获取失败");
-    }
-    if should_get_latest_release && latest_release_result.is_none() {
-        error_parts.push("最新 release 获取失败");
+    for (field, requested, succeeded, message) in [
+        ("repo_info", should_get_repo_info, repo_info_result.is_some(), "仓库信息获取失败"),
+        ("releases", should_get_releases, releases_result.is_some(), "releases 获取失败"),
+        ("latest_release", should_get_latest_release, latest_release_result.is_some(), "最新 release 获取失败"),
+    ] {
+        if !requested {
+            continue;
+        }
+        let status = if succeeded {
+            FieldStatus::Ok
+        } else {
+            error_parts.push(message);
+            FieldStatus::Error
+        };
+        field_status.insert(field.to_string(), status);
     }
 
     let has_error = !error_parts.is_empty();
@@ -669,94 +3943,449 @@ async fn process_single_repo(repo_str: &str, fields: &[String]) -> RepoBatchResu
         None
     };
 
+    // 批量查询不支持 include_source 和 schema=flat，源码压缩包链接和附件大小不出现在结果中
+    releases_result.iter_mut().flatten().for_each(|release| {
+        release.apply_include_source(false);
+        release.attachment_sizes.clear();
+    });
+    if let Some(release) = latest_release_result.as_mut() {
+        release.apply_include_source(false);
+    }
+
     RepoBatchResult {
         repo: repo_str.to_string(),
         success: !has_error,
         error: error_message,
+        field_status,
         repo_info: repo_info_result,
         releases: releases_result,
         latest_release: latest_release_result,
     }
 }
 
-// API 端点：POST /repos/batch - 批量获取多个仓库的信息（返回数组格式）
+// API 端点：POST /repos/batch - 批量获取多个仓库的信息（返回数组格式）
+#[utoipa::path(
+    post,
+    path = "/repos/batch",
+    tag = "repos",
+    request_body = BatchRequest,
+    params(
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "批量获取成功", body = BatchResponse),
+        (status = 400, description = "请求参数错误"),
+        (status = 503, description = "同时处理的批量查询达到 MAX_CONCURRENT_BATCHES 上限（响应带 Retry-After 头）")
+    )
+)]
+#[post("/repos/batch")]
+pub async fn batch_get_repos(
+    body: web::Json<BatchRequest>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
+    let repos = &body.repos;
+    let fields = &body.fields;
+
+    if repos.is_empty() {
+        return Err(AppError::BadRequest("repos 列表不能为空".to_string()));
+    }
+
+    log::info!("请求: POST /repos/batch (共 {} 个仓库)", repos.len());
+
+    // 全局并发批量查询数达到上限时直接返回 503，许可在处理完成后释放
+    let _batch_permit = get_rate_limit_manager().await.try_acquire_batch_permit()?;
+
+    // 启用 GraphQL 时先一次性预取基本信息和最新 release，其余字段并发走 REST
+    let prefetched = prefetch_batch(repos, fields).await;
+    let futures: Vec<_> = repos
+        .iter()
+        .map(|repo| process_single_repo(repo, fields, prefetched.get(repo)))
+        .collect();
+
+    let results = join_all(futures).await;
+
+    let success_count = results.iter().filter(|r| r.success).count();
+    log::info!("批量请求完成: 成功 {}/{}", success_count, repos.len());
+
+    json_response(&BatchResponse { results }, format.pretty)
+}
+
+// API 端点：POST /repos/batch/map - 批量获取多个仓库的信息（返回 Map 格式，方便客户端处理）
+#[utoipa::path(
+    post,
+    path = "/repos/batch/map",
+    tag = "repos",
+    request_body = BatchRequest,
+    params(
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "批量获取成功", body = BatchResponseMap),
+        (status = 400, description = "请求参数错误"),
+        (status = 503, description = "同时处理的批量查询达到 MAX_CONCURRENT_BATCHES 上限（响应带 Retry-After 头）")
+    )
+)]
+#[post("/repos/batch/map")]
+pub async fn batch_get_repos_map(
+    body: web::Json<BatchRequest>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
+    let repos = &body.repos;
+    let fields = &body.fields;
+
+    if repos.is_empty() {
+        return Err(AppError::BadRequest("repos 列表不能为空".to_string()));
+    }
+
+    log::info!("请求: POST /repos/batch/map (共 {} 个仓库)", repos.len());
+
+    // 全局并发批量查询数达到上限时直接返回 503，许可在处理完成后释放
+    let _batch_permit = get_rate_limit_manager().await.try_acquire_batch_permit()?;
+
+    // 启用 GraphQL 时先一次性预取基本信息和最新 release，其余字段并发走 REST
+    let prefetched = prefetch_batch(repos, fields).await;
+    let futures: Vec<_> = repos
+        .iter()
+        .map(|repo| process_single_repo(repo, fields, prefetched.get(repo)))
+        .collect();
+
+    let results = join_all(futures).await;
+
+    // 将结果转换为 Map，使用 repo 作为 key，按请求顺序输出
+    let response = BatchResponseMap::from(results);
+
+    let success_count = response.results_map.values().filter(|r| r.success).count();
+    log::info!("批量请求完成: 成功 {}/{}", success_count, repos.len());
+
+    json_response(&response, format.pretty)
+}
+
+// 获取单个仓库的最新版本号（用于 POST /repos/batch/latest），仓库没有 release 时返回 None
+async fn fetch_latest_version(
+    repo_str: &str,
+    prefetched: Option<&PrefetchedRepo>,
+) -> Option<LatestVersionResult> {
+    let Some((owner, repo)) = parse_repo(repo_str) else {
+        return Some(LatestVersionResult::Error {
+            error: "仓库格式错误，应为 'owner/repo'".to_string(),
+        });
+    };
+
+    let result = match prefetched.and_then(|data| data.latest_release.clone()) {
+        Some(latest_release) => Ok(latest_release),
+        None => fetch_latest_release(&owner, &repo).await,
+    };
+    match result {
+        Ok(release) => Some(LatestVersionResult::Version {
+            version: release.latest_version,
+            published_at: release.published_at,
+        }),
+        Err(AppError::NoReleases) => None,
+        Err(e) => Some(LatestVersionResult::Error {
+            error: e.to_string(),
+        }),
+    }
+}
+
+// API 端点：POST /repos/batch/latest - 批量获取多个仓库的最新版本号（只获取最新 release，适合检查更新）
+#[utoipa::path(
+    post,
+    path = "/repos/batch/latest",
+    tag = "repos",
+    request_body = BatchLatestRequest,
+    params(
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON（默认 false）")
+    ),
+    responses(
+        (status = 200, description = "批量获取成功，没有 release 的仓库为 null", body = BatchLatestResponse),
+        (status = 400, description = "请求参数错误"),
+        (status = 503, description = "同时处理的批量查询达到 MAX_CONCURRENT_BATCHES 上限（响应带 Retry-After 头）")
+    )
+)]
+#[post("/repos/batch/latest")]
+pub async fn batch_get_latest_versions(
+    body: web::Json<BatchLatestRequest>,
+    format: web::Query<FormatQuery>,
+) -> Result<impl Responder, AppError> {
+    let repos = &body.repos;
+
+    if repos.is_empty() {
+        return Err(AppError::BadRequest("repos 列表不能为空".to_string()));
+    }
+
+    log::info!("请求: POST /repos/batch/latest (共 {} 个仓库)", repos.len());
+
+    // 全局并发批量查询数达到上限时直接返回 503，许可在处理完成后释放
+    let _batch_permit = get_rate_limit_manager().await.try_acquire_batch_permit()?;
+
+    // 启用 GraphQL 时只预取最新 release，重复的仓库只查询一次
+    let prefetched = prefetch_batch(repos, &["latest_release".to_string()]).await;
+    let mut unique: Vec<&String> = Vec::new();
+    for repo in repos {
+        if !unique.contains(&repo) {
+            unique.push(repo);
+        }
+    }
+    let results = join_all(
+        unique
+            .iter()
+            .map(|repo| fetch_latest_version(repo, prefetched.get(*repo))),
+    )
+    .await;
+
+    let response = BatchLatestResponse(unique.into_iter().cloned().zip(results).collect());
+    let found_count = response
+        .0
+        .values()
+        .filter(|r| matches!(r, Some(LatestVersionResult::Version { .. })))
+        .count();
+    log::info!("批量最新版本查询完成: 找到 {}/{}", found_count, response.0.len());
+
+    json_response(&response, format.pretty)
+}
+
+// API 端点：GET /cache/repos - 列出缓存中的仓库及其新鲜度（需要管理令牌）
+#[utoipa::path(
+    get,
+    path = "/cache/repos",
+    tag = "cache",
+    params(
+        ("expiring_within_secs" = Option<u64>, Query, description = "只返回在该秒数内过期的仓库"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON")
+    ),
+    responses(
+        (status = 200, description = "成功获取缓存仓库列表", body = Vec<CachedRepoSummary>),
+        (status = 401, description = "管理令牌无效或缺失")
+    )
+)]
+#[get("/cache/repos")]
+pub async fn list_cached_repos(
+    req: HttpRequest,
+    query: web::Query<CacheReposQuery>,
+    format: web::Query<FormatQuery>,
+) -> Result<HttpResponse, AppError> {
+    get_admin_config().verify(&req)?;
+
+    log::info!(
+        "请求: GET /cache/repos (expiring_within_secs: {:?})",
+        query.expiring_within_secs
+    );
+
+    let cache = get_cache_manager().await;
+    // 未启用 PRIVATE_REPOS_ENABLED 时私有仓库对外不可见，缓存列表中同样隐藏
+    let repos = cache
+        .list_cached_repos(query.expiring_within_secs, private_repos_enabled())
+        .await;
+    json_response(&repos, format.pretty)
+}
+
+// API 端点：GET /cache/files - 列出缓存的下载文件（需要管理令牌）
 #[utoipa::path(
-    post,
-    path = "/repos/batch",
-    tag = "repos",
-    request_body = BatchRequest,
+    get,
+    path = "/cache/files",
+    tag = "cache",
+    params(
+        ("sort" = Option<String>, Query, description = "排序方式：accessed（默认，最近访问的在前）、size（大的在前）或 expires（先过期的在前）"),
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON")
+    ),
     responses(
-        (status = 200, description = "批量获取成功", body = BatchResponse),
-        (status = 400, description = "请求参数错误")
+        (status = 200, description = "成功获取缓存文件列表", body = Vec<CachedFileSummary>),
+        (status = 400, description = "无法识别的 sort"),
+        (status = 401, description = "管理令牌无效或缺失")
     )
 )]
-#[post("/repos/batch")]
-pub async fn batch_get_repos(body: web::Json<BatchRequest>) -> Result<impl Responder, AppError> {
-    let repos = &body.repos;
-    let fields = &body.fields;
-
-    if repos.is_empty() {
-        return Err(AppError::BadRequest("repos 列表不能为空".to_string()));
-    }
-
-    log::info!("请求: POST /repos/batch (共 {} 个仓库)", repos.len());
+#[get("/cache/files")]
+pub async fn list_cached_files(
+    req: HttpRequest,
+    query: web::Query<CacheFilesQuery>,
+    format: web::Query<FormatQuery>,
+) -> Result<HttpResponse, AppError> {
+    get_admin_config().verify(&req)?;
 
-    // 并发处理所有仓库
-    let futures: Vec<_> = repos
-        .iter()
-        .map(|repo| process_single_repo(repo, fields))
-        .collect();
+    let sort = query
+        .sort()
+        .map_err(|sort| AppError::BadRequest(format!("无法识别的 sort: {}（可选 accessed、size、expires）", sort)))?;
+    log::info!("请求: GET /cache/files (sort: {:?})", sort);
 
-    let results = join_all(futures).await;
+    let cache = get_cache_manager().await;
+    let files = cache.list_cached_files(sort).await;
+    json_response(&files, format.pretty)
+}
 
-    let success_count = results.iter().filter(|r| r.success).count();
-    log::info!("批量请求完成: 成功 {}/{}", success_count, repos.len());
+// API 端点：GET /cache/stats - 缓存效果统计（需要管理令牌）
+#[utoipa::path(
+    get,
+    path = "/cache/stats",
+    tag = "cache",
+    params(
+        ("pretty" = Option<bool>, Query, description = "是否返回缩进格式的 JSON")
+    ),
+    responses(
+        (status = 200, description = "成功获取自启动以来的缓存效果统计", body = CacheStatsResponse),
+        (status = 401, description = "管理令牌无效或缺失")
+    )
+)]
+#[get("/cache/stats")]
+pub async fn get_cache_stats(
+    req: HttpRequest,
+    format: web::Query<FormatQuery>,
+) -> Result<HttpResponse, AppError> {
+    get_admin_config().verify(&req)?;
 
-    Ok(HttpResponse::Ok().json(BatchResponse { results }))
+    log::info!("请求: GET /cache/stats");
+    let cache = get_cache_manager().await;
+    let stats = cache_metrics().snapshot(cache.coalesced_requests());
+    json_response(&stats, format.pretty)
 }
 
-// API 端点：POST /repos/batch/map - 批量获取多个仓库的信息（返回 Map 格式，方便客户端处理）
+// API 端点：GET /repos/watch/sse - 通过 Server-Sent Events 监听多个仓库的新版本
 #[utoipa::path(
-    post,
-    path = "/repos/batch/map",
+    get,
+    path = "/repos/watch/sse",
     tag = "repos",
-    request_body = BatchRequest,
+    params(
+        ("repos" = String, Query, description = "要监听的仓库列表，逗号分隔，例如 owner1/repo1,owner2/repo2")
+    ),
     responses(
-        (status = 200, description = "批量获取成功", body = BatchResponseMap),
-        (status = 400, description = "请求参数错误")
+        (status = 200, description = "SSE 事件流，版本变化时发送 release 事件", body = WatchEvent, content_type = "text/event-stream"),
+        (status = 400, description = "repos 参数缺失、格式错误或数量超过上限")
     )
 )]
-#[post("/repos/batch/map")]
-pub async fn batch_get_repos_map(
-    body: web::Json<BatchRequest>,
+#[get("/repos/watch/sse")]
+pub async fn watch_releases_sse(
+    query: web::Query<HashMap<String, String>>,
 ) -> Result<impl Responder, AppError> {
-    let repos = &body.repos;
-    let fields = &body.fields;
+    let config = WatchConfig::from_env();
+
+    let repos_param = query.get("repos").ok_or_else(|| {
+        AppError::BadRequest("缺少 repos 参数".to_string())
+    })?;
+
+    let mut repos: Vec<(String, String)> = Vec::new();
+    for repo_str in repos_param.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let parsed = parse_repo(repo_str).ok_or_else(|| {
+            AppError::BadRequest(format!("仓库格式错误，应为 'owner/repo': {}", repo_str))
+        })?;
+        if !repos.contains(&parsed) {
+            repos.push(parsed);
+        }
+    }
 
     if repos.is_empty() {
         return Err(AppError::BadRequest("repos 列表不能为空".to_string()));
     }
+    if repos.len() > config.max_repos {
+        return Err(AppError::BadRequest(format!(
+            "每个连接最多监听 {} 个仓库",
+            config.max_repos
+        )));
+    }
 
-    log::info!("请求: POST /repos/batch/map (共 {} 个仓库)", repos.len());
+    log::info!("请求: GET /repos/watch/sse (共 {} 个仓库)", repos.len());
 
-    // 并发处理所有仓库
-    let futures: Vec<_> = repos
-        .iter()
-        .map(|repo| process_single_repo(repo, fields))
-        .collect();
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .append_header(("Cache-Control", "no-cache"))
+        .streaming(release_event_stream(repos, &config)))
+}
 
-    let results = join_all(futures).await;
+// 以流的形式返回缓存文件（避免一次性加载大文件到内存）
+async fn serve_cached_file(
+    metadata: FileCacheMetadata,
+    permit: OwnedSemaphorePermit,
+    client_ip: String,
+    accept_gzip: bool,
+) -> Result<HttpResponse, AppError> {
+    use actix_web::web::Bytes;
+    use futures::stream::TryStreamExt;
 
-    // 将结果转换为 HashMap，使用 repo 作为 key
-    let results_map: HashMap<String, RepoBatchResult> = results
-        .into_iter()
-        .map(|result| (result.repo.clone(), result))
-        .collect();
+    let content_type = get_download_config().resolve_content_type(
+        metadata.content_type.as_deref(),
+        &metadata.original_filename,
+    );
+    ensure_content_type_allowed(&metadata.url, &content_type, get_download_config())?;
 
-    let success_count = results_map.values().filter(|r| r.success).count();
-    log::info!("批量请求完成: 成功 {}/{}", success_count, repos.len());
+    // 客户端接受 gzip 且存在预压缩副本时直接返回副本，否则返回原始文件
+    let gzip_path = metadata
+        .gzip_path
+        .as_ref()
+        .filter(|path| accept_gzip && path.exists());
+    let file = fs::File::open(gzip_path.unwrap_or(&metadata.file_path)).await
+        .map_err(|e| AppError::ApiError(format!("打开缓存文件失败: {}", e)))?;
+
+    let stream = tokio_util::io::ReaderStream::new(file);
+    let bytes_stream = stream.map_ok(Bytes::from)
+        .map(|r| r.map_err(|e| AppError::ApiError(format!("读取文件错误: {}", e))));
+
+    // 将 permit 绑定到流上，确保在整个流完成之前都不会释放（客户端空闲超时时提前释放）
+    let stream_with_permit = guard_download_stream(
+        bytes_stream,
+        permit,
+        get_download_config().client_idle_timeout(),
+        client_ip,
+    );
+
+    let cache_filename = metadata
+        .file_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(&metadata.original_filename);
+    let filename = get_download_config().served_filename(&metadata.original_filename, cache_filename);
+
+    let mut response = HttpResponse::Ok();
+    response.append_header(("Content-Disposition", content_disposition(&content_type, filename)));
+    if metadata.gzip_path.is_some() {
+        response.append_header(("Vary", "Accept-Encoding"));
+    }
+    if gzip_path.is_some() {
+        response.append_header(("Content-Encoding", "gzip"));
+    }
+    Ok(response.content_type(content_type).streaming(stream_with_permit))
+}
+
+// API 端点：GET /repos/{owner}/{repo}/releases/latest/assets/{name}
+#[utoipa::path(
+    get,
+    path = "/repos/{owner}/{repo}/releases/latest/assets/{name}",
+    tag = "download",
+    params(
+        ("owner" = String, Path, description = "仓库所有者"),
+        ("repo" = String, Path, description = "仓库名称"),
+        ("name" = String, Path, description = "附件文件名（完全匹配），例如 `app-linux-x86_64.tar.gz`"),
+        ("max_age" = Option<u64>, Query, description = "缓存文件的最大可接受时长（秒），超过时重新下载")
+    ),
+    responses(
+        (status = 200, description = "文件下载成功", content_type = "application/octet-stream"),
+        (status = 206, description = "与 /download 相同，启用 CACHE_FULL_ON_RANGE 或转发 Range 时返回部分内容", content_type = "application/octet-stream"),
+        (status = 404, description = "仓库不存在；仓库存在但没有 releases 时响应体中 code 为 no_releases；最新 release 中没有该附件时 code 为 asset_not_found"),
+        (status = 415, description = "设置了 DOWNLOAD_ALLOWED_CONTENT_TYPES 时文件的内容类型不在允许列表中"),
+        (status = 429, description = "下载主机限流且要求等待的时间超过 DOWNLOAD_RETRY_AFTER_MAX_SECS（响应带 Retry-After 头）"),
+        (status = 503, description = "等待下载许可超时（响应带 Retry-After 头）")
+    )
+)]
+#[get("/repos/{owner}/{repo}/releases/latest/assets/{name}")]
+#[cfg_attr(
+    feature = "otel",
+    tracing::instrument(
+        skip_all,
+        fields(repo = %format_args!("{}/{}", path.0, path.1), asset = %path.2, github.status = tracing::field::Empty)
+    )
+)]
+pub async fn download_latest_release_asset(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+) -> Result<impl Responder, AppError> {
+    let (owner, repo, name) = path.into_inner();
+    let client_ip = client_ip(&req);
+    log::info!(
+        "请求: GET /repos/{}/{}/releases/latest/assets/{} (IP: {})",
+        owner, repo, name, client_ip
+    );
 
-    Ok(HttpResponse::Ok().json(BatchResponseMap { results_map }))
+    let latest_release = fetch_latest_release(&owner, &repo).await?;
+    let url = find_asset_by_name(&latest_release.attachments, &name)
+        .ok_or_else(|| AppError::AssetNotFound(name.clone()))?;
+    stream_download(get_cache_manager().await, url, client_ip, &req).await
 }
 
 // 下载附件文件（支持缓存）
@@ -765,14 +4394,27 @@ pub async fn batch_get_repos_map(
     path = "/download",
     tag = "download",
     params(
-        ("url" = String, Query, description = "要下载的文件 URL")
+        ("url" = String, Query, description = "要下载的文件 URL"),
+        ("max_age" = Option<u64>, Query, description = "缓存文件的最大可接受时长（秒），超过时重新下载")
     ),
     responses(
         (status = 200, description = "文件下载成功", content_type = "application/octet-stream"),
-        (status = 400, description = "缺少 url 参数")
+        (status = 206, description = "启用 CACHE_FULL_ON_RANGE 时未缓存的不可变文件的 Range 请求，或 DOWNLOAD_FORWARD_HEADERS 包含 Range 时返回部分内容", content_type = "application/octet-stream"),
+        (status = 400, description = "缺少 url 参数"),
+        (status = 403, description = "启用 DOWNLOAD_REQUIRE_RELEASE_ASSET 时 url 不是已知 release 的附件"),
+        (status = 415, description = "设置了 DOWNLOAD_ALLOWED_CONTENT_TYPES 时文件的内容类型不在允许列表中"),
+        (status = 429, description = "下载主机限流且要求等待的时间超过 DOWNLOAD_RETRY_AFTER_MAX_SECS（响应带 Retry-After 头）"),
+        (status = 503, description = "等待下载许可超时（响应带 Retry-After 头）")
     )
 )]
 #[get("/download")]
+#[cfg_attr(
+    feature = "otel",
+    tracing::instrument(
+        skip_all,
+        fields(url = query.get("url").map(String::as_str).unwrap_or_default(), github.status = tracing::field::Empty)
+    )
+)]
 pub async fn download_attachment(
     req: HttpRequest,
     query: web::Query<HashMap<String, String>>,
@@ -780,78 +4422,145 @@ pub async fn download_attachment(
     let url = query.get("url").ok_or_else(|| {
         AppError::BadRequest("缺少 url 参数".to_string())
     })?;
+    get_download_config().validate_download_url(url)?;
 
-    // 获取客户端 IP 地址（用于限流）
-    let client_ip = req
-        .connection_info()
-        .peer_addr()
-        .map(|s| s.to_string())
-        .or_else(|| {
-            // 尝试从 X-Forwarded-For 或 X-Real-IP 获取（如果使用反向代理）
-            req.headers()
-                .get("X-Forwarded-For")
-                .and_then(|h| h.to_str().ok())
-                .and_then(|s| s.split(',').next())
-                .map(|s| s.trim().to_string())
-                .or_else(|| {
-                    req.headers()
-                        .get("X-Real-IP")
-                        .and_then(|h| h.to_str().ok())
-                        .map(|s| s.to_string())
-                })
-        })
-        .unwrap_or_else(|| "unknown".to_string());
+    ensure_release_asset(url, get_download_config(), get_cache_backend().await).await?;
+
+    // 获取客户端 IP 地址（用于日志）
+    let client_ip = client_ip(&req);
 
     log::info!("请求下载文件: {} (IP: {})", url, client_ip);
 
-    // 获取限流管理器并获取并发下载许可
-    let rate_limit_manager = get_rate_limit_manager().await;
+    stream_download(get_cache_manager().await, url, client_ip, &req).await
+}
 
-    // 获取并发下载许可（这会在下载完成后自动释放）
-    let permit = rate_limit_manager.acquire_download_permit().await;
+// 下载文件并返回给客户端：检查缓存、获取并发下载许可，缓存未命中时从 S3 镜像或上游流式下载并写入缓存
+//
+// /download 和按名称代理附件的端点都只负责确定 url，缓存、限流和流式传输的行为完全相同。
+// url 需要由调用方校验；req 用于读取 max_age 参数和 Cache-Control、Accept-Encoding、Range 等请求头。
+// 未启用缓存（CACHE_ENABLED=false）时只做代理，不读写缓存文件
+async fn stream_download(
+    cache: &'static CacheManager,
+    url: &str,
+    client_ip: String,
+    req: &HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    // 缓存文件超过该时长（秒）时视为过期，即使仍在 TTL 内也重新下载
+    let max_age = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+        .map_err(|e| AppError::BadRequest(e.to_string()))?
+        .get("max_age")
+        .map(|v| {
+            v.parse::<u64>()
+                .map_err(|_| AppError::BadRequest("max_age 参数必须是非负整数".to_string()))
+        })
+        .transpose()?;
 
-    let cache = get_cache_manager().await;
+    // 客户端发送 Cache-Control: no-cache 时跳过缓存读取（下载结果仍会写入缓存）
+    let no_cache = req
+        .headers()
+        .get(actix_web::http::header::CACHE_CONTROL)
+        .and_then(|h| h.to_str().ok())
+        .map(has_no_cache_directive)
+        .unwrap_or(false);
+
+    // 客户端接受 gzip 时优先返回预压缩的缓存副本
+    let accept_gzip = req
+        .headers()
+        .get(actix_web::http::header::ACCEPT_ENCODING)
+        .and_then(|h| h.to_str().ok())
+        .map(accepts_gzip)
+        .unwrap_or(false);
+
+    // 转发 DOWNLOAD_FORWARD_HEADERS 中列出的请求头（如 Referer）时，上游可能返回因请求头而异的内容，
+    // 而缓存只按 URL 区分，因此这类请求既不读取也不写入缓存
+    let forwarded = get_download_config().forwarded_headers(req.headers());
+    let bypass_cache = !forwarded.is_empty();
 
-    // 先检查缓存
-    if let Some(metadata) = cache.get_file_cache(url).await {
-        log::debug!("从缓存获取文件: {}", url);
-
-        let content_type = metadata.content_type
-            .as_ref()
-            .and_then(|ct| ct.parse::<mime::Mime>().ok())
-            .unwrap_or_else(|| mime::APPLICATION_OCTET_STREAM);
-
-        let filename = metadata.original_filename.clone();
-        let file_path = metadata.file_path.clone();
-
-        // 使用流式读取缓存文件（避免一次性加载大文件到内存）
-        use actix_web::web::Bytes;
-        use futures::stream::TryStreamExt;
-
-        let file = fs::File::open(&file_path).await
-            .map_err(|e| AppError::ApiError(format!("打开缓存文件失败: {}", e)))?;
-
-        let stream = tokio_util::io::ReaderStream::new(file);
-        let bytes_stream = stream.map_ok(|b| Bytes::from(b))
-            .map(|r| r.map_err(|e| AppError::ApiError(format!("读取文件错误: {}", e))));
-
-        // 将 permit 绑定到流上，确保在整个流完成之前都不会释放
-        // 使用 map 将 permit 移动到闭包中，permit 会在流完成时自动释放
-        // 注意：permit 需要在整个流期间保持，所以将其移动到闭包的捕获中
-        let permit_for_stream = permit;
-        let stream_with_permit = bytes_stream.map(move |result| {
-            // permit_for_stream 在闭包中保持，直到流完成
-            let _keep_permit = &permit_for_stream;
-            result
+    // 获取限流管理器，并发下载许可在确定需要传输数据时才获取（这会在下载完成后自动释放），排队超时时返回 503
+    let rate_limit_manager = get_rate_limit_manager().await;
+
+    // 先检查缓存；相同 URL 正在下载时等待其完成后重新检查缓存，避免重复下载和并发写入。
+    // 等待期间不占用下载许可，否则同一热门文件的大量请求会占满许可，而实际只有一个下载在进行
+    let mut revalidating_metadata = None;
+    let flight = loop {
+        let cached_metadata = if no_cache {
+            log::debug!("请求要求跳过缓存（no-cache），重新下载: {}", url);
+            None
+        } else if bypass_cache {
+            log::debug!("请求转发了请求头，跳过缓存: {}", url);
+            None
+        } else {
+            cache.get_file_cache(url).await
+        };
+        let cached_metadata = cached_metadata.filter(|metadata| match max_age {
+            Some(max_age) if metadata.is_older_than(max_age) => {
+                log::debug!("缓存文件超过 max_age={} 秒，重新下载: {}", max_age, url);
+                false
+            }
+            _ => true,
         });
+        if let Some(metadata) = cached_metadata {
+            if get_download_config().is_immutable_url(url) {
+                log::debug!("从缓存获取文件: {}", url);
+                let permit = rate_limit_manager.acquire_download_permit().await?;
+                return serve_cached_file(metadata, permit, client_ip, accept_gzip).await;
+            }
 
-        return Ok(HttpResponse::Ok()
-            .content_type(content_type.clone())
-            .append_header((
-                "Content-Disposition",
-                format!("attachment; filename=\"{}\"", filename)
-            ))
-            .streaming(stream_with_permit));
+            // 可变内容（如 raw.githubusercontent.com）需要使用 ETag 向上游重新验证
+            if metadata.etag.is_some() {
+                log::debug!("缓存文件需要重新验证: {}", url);
+                revalidating_metadata = Some(metadata);
+            } else {
+                log::debug!("缓存文件没有 ETag，无法重新验证，重新下载: {}", url);
+            }
+        }
+
+        // 未启用缓存或跳过缓存时没有可以等待的缓存结果，每个请求都单独代理
+        if !cache.is_enabled() || bypass_cache {
+            break None;
+        }
+        match cache.begin_download(url) {
+            DownloadFlight::Leader(guard) => break Some(guard),
+            // 跳过缓存的请求不会使用其他请求的下载结果，直接单独下载
+            DownloadFlight::Follower(_) if no_cache => break None,
+            DownloadFlight::Follower(waiter) => {
+                log::debug!("相同文件正在下载，等待完成后从缓存获取: {}", url);
+                waiter.wait().await;
+                revalidating_metadata = None;
+            }
+        }
+    };
+    let permit = rate_limit_manager.acquire_download_permit().await?;
+
+    // 缓存文件名基于 URL 的 hash（S3 镜像以此为键保存 URL 到内容哈希的索引）
+    let filename = filename_from_url(url);
+    let cache_filename = cache_filename_for_url(url, &filename);
+    let cache_file_path = cache.get_file_cache_dir().join(&cache_filename);
+
+    // 不可变内容在本地缓存未命中时优先从 S3 镜像获取（需要 s3 特性，客户端要求跳过缓存时除外）
+    let immutable = get_download_config().is_immutable_url(url);
+    #[cfg(feature = "s3")]
+    let s3_mirror = if immutable && cache.is_enabled() { get_s3_mirror().await } else { None };
+    #[cfg(feature = "s3")]
+    if let Some(mirror) = s3_mirror.filter(|_| !no_cache && !bypass_cache && max_age.is_none()) {
+        if let Some(object) = mirror.get(&cache_filename).await {
+            log::debug!("从 S3 镜像获取文件: {}", url);
+            let content_type = get_download_config().resolve_content_type(object.content_type.as_deref(), &filename);
+            ensure_content_type_allowed(url, &content_type, get_download_config())?;
+            let target = CacheTarget {
+                cache,
+                url: url.to_string(),
+                filename,
+                cache_filename,
+                cache_file_path,
+                content_type,
+                etag: None,
+                expected_len: None,
+                mirror_to_s3: false,
+                flight,
+            };
+            return stream_and_cache(mirror_byte_stream(object.body), target, permit, client_ip).await;
+        }
     }
 
     // 缓存未命中，从 GitHub 流式下载
@@ -863,12 +4572,43 @@ pub async fn download_attachment(
         .header("User-Agent", "gh-info-rs")
         .header("Accept", "*/*");
 
-    // 如果设置了 token，则添加认证头
-    if let Some(token) = get_github_token() {
+    // 如果设置了 token 且目标仓库可见，则添加认证头
+    if let Some(token) = download_token(url).await? {
         request = request.header("Authorization", format!("Bearer {}", token));
     }
 
-    let response = request.send().await?;
+    // 重新验证缓存时发送条件请求
+    if let Some(etag) = revalidating_metadata.as_ref().and_then(|m| m.etag.as_ref()) {
+        request = request.header("If-None-Match", etag);
+    }
+
+    // 转发 DOWNLOAD_FORWARD_HEADERS 中列出的请求头
+    let forwards_range = forwarded.iter().any(|(name, _)| name == "range");
+    for (name, value) in forwarded {
+        request = request.header(name, value);
+    }
+
+    // 启用 CACHE_FULL_ON_RANGE 时，未缓存的不可变文件的 Range 请求转发给上游，完整文件随后在后台下载并缓存
+    let cache_full_on_range = immutable && flight.is_some() && get_download_config().cache_full_on_range;
+    let range = req
+        .headers()
+        .get(actix_web::http::header::RANGE)
+        .and_then(|h| h.to_str().ok())
+        .filter(|_| cache_full_on_range && !forwards_range);
+    if let Some(range) = range {
+        request = request.header("Range", range);
+    }
+
+    let response = send_download_request(request, get_download_config()).await?;
+    record_github_status(response.status().as_u16());
+
+    // 304：上游内容未变化，直接使用缓存文件
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        if let Some(metadata) = revalidating_metadata {
+            log::debug!("缓存文件未变化（304），从缓存获取: {}", url);
+            return serve_cached_file(metadata, permit, client_ip, accept_gzip).await;
+        }
+    }
 
     if !response.status().is_success() {
         return Err(AppError::ApiError(format!(
@@ -877,107 +4617,568 @@ pub async fn download_attachment(
         )));
     }
 
-    // 先获取 Content-Type（在移动 response 之前）
-    let content_type = response.headers()
-        .get("content-type")
+    // 记录 ETag，用于之后重新验证
+    let etag = response.headers()
+        .get("etag")
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_string());
+
+    // DOWNLOAD_FILENAME=upstream 时优先使用上游 Content-Disposition 中的文件名（记录到缓存元数据中）
+    let filename = match get_download_config().filename_mode {
+        DownloadFilenameMode::Upstream => response.headers()
+            .get("content-disposition")
+            .and_then(|h| h.to_str().ok())
+            .and_then(filename_from_content_disposition)
+            .unwrap_or(filename),
+        DownloadFilenameMode::Original | DownloadFilenameMode::Hashed => filename,
+    };
+
+    // 先获取 Content-Type（在移动 response 之前），上游未给出具体类型时根据文件名推测
+    let content_type = get_download_config().resolve_content_type(
+        response.headers()
+            .get("content-type")
+            .and_then(|h| h.to_str().ok()),
+        &filename,
+    );
+    // 在开始向客户端发送响应体和写入缓存文件之前检查，被拒绝的内容不会留下缓存文件
+    ensure_content_type_allowed(url, &content_type, get_download_config())?;
+
+    let target = CacheTarget {
+        cache,
+        url: url.to_string(),
+        filename,
+        cache_filename,
+        cache_file_path,
+        content_type,
+        etag,
+        expected_len: response.content_length(),
+        #[cfg(feature = "s3")]
+        mirror_to_s3: s3_mirror.is_some(),
+        flight,
+    };
+
+    // 上游返回 206：只有请求的范围，启用 CACHE_FULL_ON_RANGE 时完整文件在后台下载
+    if response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+        return Ok(serve_range_and_cache_full(response, target, permit, client_ip, cache_full_on_range));
+    }
+
+    // 小文件一次性读入内存，写入缓存后返回完整的响应体
+    if cache.is_enabled() && !bypass_cache && get_download_config().should_buffer(target.expected_len) {
+        return buffer_and_cache(response, target, permit, client_ip).await;
+    }
+
+    // 获取响应流并转换为字节流
+    let bytes_stream = response
+        .bytes_stream()
+        .map(|result| result.map_err(|e| AppError::ApiError(format!("流式下载错误: {}", e))));
+
+    // 未启用缓存或跳过缓存时直接转发上游响应，不创建缓存文件也不启动写入任务
+    if !cache.is_enabled() || bypass_cache {
+        return Ok(proxy_download(bytes_stream, target, permit, client_ip));
+    }
+
+    stream_and_cache(bytes_stream, target, permit, client_ip).await
+}
+
+// 将上游的 206 响应返回给客户端；cache_full 为 true 且文件大小不超过 CACHE_FULL_ON_RANGE_MAX_BYTES 时在后台完整下载并缓存
+// （通过 DOWNLOAD_FORWARD_HEADERS 转发的 Range 请求只返回请求的范围，不缓存）
+fn serve_range_and_cache_full(
+    response: reqwest::Response,
+    target: CacheTarget,
+    permit: OwnedSemaphorePermit,
+    client_ip: String,
+    cache_full: bool,
+) -> HttpResponse {
+    let content_range = response
+        .headers()
+        .get("content-range")
         .and_then(|h| h.to_str().ok())
-        .and_then(|ct| ct.parse::<mime::Mime>().ok())
-        .unwrap_or_else(|| mime::APPLICATION_OCTET_STREAM);
+        .map(|s| s.to_string());
+    let content_type = target.content_type.clone();
+    let disposition = content_disposition(&content_type, target.served_filename());
+
+    let max_bytes = get_download_config().cache_full_on_range_max_bytes;
+    match content_range.as_deref().and_then(content_range_total) {
+        _ if !cache_full => {}
+        Some(total) if max_bytes == 0 || total <= max_bytes => {
+            log::debug!("返回请求的范围，同时在后台完整下载文件（{} 字节）: {}", total, target.url);
+            tokio::spawn(cache_full_download(target));
+        }
+        total => {
+            log::debug!(
+                "文件大小未知或超过 CACHE_FULL_ON_RANGE_MAX_BYTES（{:?} 字节），只返回请求的范围: {}",
+                total, target.url
+            );
+        }
+    }
+
+    let bytes_stream = response
+        .bytes_stream()
+        .map(|result| result.map_err(|e| AppError::ApiError(format!("流式下载错误: {}", e))));
+
+    let mut builder = HttpResponse::PartialContent();
+    builder
+        .append_header(("Content-Disposition", disposition))
+        .append_header(("Accept-Ranges", "bytes"));
+    if let Some(content_range) = content_range {
+        builder.append_header(("Content-Range", content_range));
+    }
+    builder.content_type(content_type).streaming(guard_download_stream(
+        bytes_stream,
+        permit,
+        get_download_config().client_idle_timeout(),
+        client_ip,
+    ))
+}
+
+// 将字节流转发给客户端，不写入缓存（CACHE_ENABLED=false，或文件缓存没有可用的位置）
+fn proxy_download<S>(
+    bytes_stream: S,
+    target: CacheTarget,
+    permit: OwnedSemaphorePermit,
+    client_ip: String,
+) -> HttpResponse
+where
+    S: futures::Stream<Item = Result<actix_web::web::Bytes, AppError>> + 'static,
+{
+    let disposition = content_disposition(&target.content_type, target.served_filename());
+    HttpResponse::Ok()
+        .append_header(("Content-Disposition", disposition))
+        .content_type(target.content_type)
+        .streaming(guard_download_stream(
+            bytes_stream,
+            permit,
+            get_download_config().client_idle_timeout(),
+            client_ip,
+        ))
+}
+
+// 后台完整下载文件并写入缓存（单独获取下载许可，受并发下载数限制）
+async fn cache_full_download(mut target: CacheTarget) {
+    let permit = match get_rate_limit_manager().await.acquire_download_permit().await {
+        Ok(permit) => permit,
+        Err(_) => {
+            log::warn!("等待下载许可超时，放弃后台缓存: {}", target.url);
+            return;
+        }
+    };
+
+    let mut request = create_client()
+        .get(&target.url)
+        .header("User-Agent", "gh-info-rs")
+        .header("Accept", "*/*");
+    match download_token(&target.url).await {
+        Ok(Some(token)) => request = request.header("Authorization", format!("Bearer {}", token)),
+        Ok(None) => {}
+        Err(e) => {
+            log::warn!("放弃后台缓存: {}: {}", target.url, e);
+            return;
+        }
+    }
+
+    let response = match send_download_request(request, get_download_config()).await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            log::warn!("后台下载失败，GitHub 返回状态码 {}: {}", response.status(), target.url);
+            return;
+        }
+        Err(e) => {
+            log::warn!("后台下载失败: {} ({})", target.url, e);
+            return;
+        }
+    };
+    target.expected_len = response.content_length();
+
+    let Some(slot) = target.cache.reserve_file_slot(&target.cache_file_path).await else {
+        log::warn!("文件缓存没有可用的位置，放弃后台缓存: {}", target.url);
+        return;
+    };
+    let url = target.url.clone();
+    let tx = match spawn_cache_writer(target, slot).await {
+        Ok(tx) => tx,
+        Err(e) => {
+            log::warn!("后台下载失败: {} ({})", url, e);
+            return;
+        }
+    };
+    let bytes_stream = response
+        .bytes_stream()
+        .map(|result| result.map_err(|e| AppError::ApiError(format!("流式下载错误: {}", e))));
+    tee_to_cache(bytes_stream, tx)
+        .for_each(|_| futures::future::ready(()))
+        .await;
+    drop(permit);
+}
+
+// 发送下载请求。下载主机（github.com、objects.githubusercontent.com）触发限流时返回 429 或带 Retry-After 的 403：
+// 要求等待的时间不超过 DOWNLOAD_RETRY_AFTER_MAX_SECS 时等待后重试一次，否则（或重试后仍被限流）返回 429
+// 日志中记录原始请求地址：重定向后的 response.url() 是带签名参数的临时下载地址，不应写入日志
+async fn send_download_request(
+    request: reqwest::RequestBuilder,
+    config: &DownloadConfig,
+) -> Result<reqwest::Response, AppError> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let url = request.url().clone();
+    let retry_request = request.try_clone();
+    let response = client.execute(request).await?;
+    let Some(retry_after_secs) = rate_limited_retry_after(&response) else {
+        return Ok(response);
+    };
+
+    let (Some(wait), Some(retry_request)) = (config.retry_wait(retry_after_secs), retry_request) else {
+        log::warn!("下载主机限流（{}），需要等待 {} 秒: {}", response.status(), retry_after_secs, url);
+        return Err(AppError::UpstreamRateLimited { retry_after_secs });
+    };
+    log::warn!(
+        "下载主机限流（{}），{} 秒后重试: {}",
+        response.status(),
+        retry_after_secs,
+        url
+    );
+    tokio::time::sleep(wait).await;
+
+    let response = client.execute(retry_request).await?;
+    match rate_limited_retry_after(&response) {
+        Some(retry_after_secs) => {
+            log::warn!("重试后下载主机仍然限流，需要等待 {} 秒: {}", retry_after_secs, url);
+            Err(AppError::UpstreamRateLimited { retry_after_secs })
+        }
+        None => Ok(response),
+    }
+}
+
+// 响应是否为限流（429，或带 Retry-After 的 403），是则返回要求等待的秒数
+fn rate_limited_retry_after(response: &reqwest::Response) -> Option<u64> {
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|h| h.to_str().ok());
+    upstream_retry_after(response.status().as_u16(), retry_after)
+}
 
-    // 从 URL 提取文件名
-    let filename = url
-        .split('/')
-        .last()
+// 从 URL 提取文件名
+fn filename_from_url(url: &str) -> String {
+    url.split('/')
+        .next_back()
         .unwrap_or("file")
         .split('?')
         .next()
         .unwrap_or("file")
-        .to_string();
+        .to_string()
+}
 
-    // 生成缓存文件名（基于 URL 的 hash）
+// 生成缓存文件名（基于 URL 的 hash，尽量保留原始扩展名）
+fn cache_filename_for_url(url: &str, filename: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(url.as_bytes());
     let file_hash = hex::encode(hasher.finalize());
 
-    // 尝试从文件名获取扩展名
-    let filename_path = PathBuf::from(&filename);
-    let extension = filename_path
+    let extension = std::path::Path::new(filename)
         .extension()
         .and_then(|ext| ext.to_str())
         .unwrap_or("bin");
 
-    let cache_filename = format!("{}.{}", file_hash, extension);
-    let cache_file_path = cache.get_file_cache_dir().join(&cache_filename);
-    let filename_clone = filename.clone();
-    let url_clone = url.to_string();
-    let content_type_str = content_type.to_string();
+    format!("{}.{}", file_hash, extension)
+}
 
-    // 创建缓存文件（用于写入）
-    let cache_file = fs::File::create(&cache_file_path).await
-        .map_err(|e| AppError::ApiError(format!("创建缓存文件失败: {}", e)))?;
+// 启用 DOWNLOAD_REQUIRE_RELEASE_ASSET 时，只允许下载缓存中已知 release 的附件，避免下载代理被用于任意内容
+async fn ensure_release_asset(
+    url: &str,
+    config: &DownloadConfig,
+    backend: &dyn CacheBackend,
+) -> Result<(), AppError> {
+    if !config.require_release_asset || backend.is_known_release_asset(url).await {
+        return Ok(());
+    }
+    log::warn!("拒绝下载未知 release 附件: {}", url);
+    Err(AppError::Forbidden(
+        "只允许下载已知 release 的附件，请先查询该仓库的 releases".to_string(),
+    ))
+}
 
-    // 获取响应流并转换为字节流
-    let bytes_stream = response.bytes_stream();
+// 设置了 DOWNLOAD_ALLOWED_CONTENT_TYPES 时，拒绝下载其他类型的内容（在向客户端发送响应体和写入缓存之前检查）
+fn ensure_content_type_allowed(
+    url: &str,
+    content_type: &mime::Mime,
+    config: &DownloadConfig,
+) -> Result<(), AppError> {
+    if config.is_content_type_allowed(content_type) {
+        return Ok(());
+    }
+    log::warn!("拒绝下载不允许的内容类型 {}: {}", content_type.essence_str(), url);
+    Err(AppError::UnsupportedMediaType(content_type.essence_str().to_string()))
+}
 
-    // 创建一个流，同时写入缓存和发送给客户端
-    // 使用 channel 来分离写入任务，避免阻塞流
-    use tokio::sync::mpsc;
-    use actix_web::web::Bytes;
+// 下载内容写入本地缓存（以及 S3 镜像）所需的信息
+struct CacheTarget {
+    cache: &'static CacheManager, // 登记缓存文件的缓存管理器
+    url: String,
+    filename: String,
+    cache_filename: String,
+    cache_file_path: PathBuf,
+    content_type: mime::Mime,
+    etag: Option<String>,
+    expected_len: Option<u64>, // 上游声明的文件大小，用于确认缓存文件完整
+    #[cfg(feature = "s3")]
+    mirror_to_s3: bool,
+    flight: Option<DownloadFlightGuard>, // 写入缓存后释放，等待相同下载的请求随后从缓存获取
+}
+
+impl CacheTarget {
+    // 下载响应 Content-Disposition 中使用的文件名（DOWNLOAD_FILENAME）
+    fn served_filename(&self) -> &str {
+        get_download_config().served_filename(&self.filename, &self.cache_filename)
+    }
+}
+
+// 发送给缓存写入任务的消息
+enum CacheChunk {
+    Data(actix_web::web::Bytes),
+    // 上游流正常结束，缓存文件已完整
+    End,
+    // 上游流出错，缓存文件不完整
+    Abort,
+}
+
+// 启用 CACHE_PRECOMPRESS 时为文本类缓存文件生成 gzip 副本，返回副本路径；
+// 不生成副本时删除旧副本，避免重新下载后返回过期的压缩内容
+async fn precompress_cache_file(target: &CacheTarget) -> Option<PathBuf> {
+    let path = target.cache_file_path.clone();
+    let precompress = get_download_config().precompress && is_precompressible(&target.content_type);
+    if !precompress {
+        let _ = fs::remove_file(gzip_sibling_path(&path)).await;
+        return None;
+    }
+
+    match tokio::task::spawn_blocking(move || write_gzip_sibling(&path)).await {
+        Ok(Ok(gzip_path)) => Some(gzip_path),
+        Ok(Err(e)) => {
+            log::warn!("预压缩缓存文件失败: {} ({})", target.url, e);
+            None
+        }
+        Err(e) => {
+            log::warn!("预压缩任务异常退出: {} ({})", target.url, e);
+            None
+        }
+    }
+}
+
+// 一次性读取完整的小文件并返回给客户端，同时在后台写入缓存（不经过流式写入任务）
+async fn buffer_and_cache(
+    response: reqwest::Response,
+    target: CacheTarget,
+    permit: OwnedSemaphorePermit,
+    client_ip: String,
+) -> Result<HttpResponse, AppError> {
+    let bytes = response.bytes().await?;
+    let len = bytes.len() as u64;
+    if target.expected_len.is_some_and(|expected| expected != len) {
+        return Err(AppError::ApiError(format!(
+            "下载的文件不完整（收到 {} 字节）: {}",
+            len, target.url
+        )));
+    }
+
+    let content_type = target.content_type.clone();
+    let disposition = content_disposition(&content_type, target.served_filename());
+    match target.cache.reserve_file_slot(&target.cache_file_path).await {
+        Some(slot) => {
+            tokio::spawn(write_cache_file(target, bytes.clone(), slot));
+        }
+        None => log::warn!("文件缓存没有可用的位置，本次下载不写入缓存: {}", target.url),
+    }
+
+    // 仍然经过 guard_download_stream，保证下载许可和下载统计与流式下载一致
+    let stream = futures::stream::once(futures::future::ready(Ok(bytes)));
+    Ok(HttpResponse::Ok()
+        .append_header(("Content-Disposition", disposition))
+        .content_type(content_type)
+        .body(actix_web::body::SizedStream::new(
+            len,
+            guard_download_stream(
+                stream,
+                permit,
+                get_download_config().client_idle_timeout(),
+                client_ip,
+            ),
+        )))
+}
+
+// 将完整的文件内容写入缓存（先写入临时文件再重命名），失败时只记录警告；写入结束后释放预留的位置
+async fn write_cache_file(target: CacheTarget, bytes: actix_web::web::Bytes, _slot: FileSlot) {
+    let partial_path = partial_file_path(&target.cache_file_path);
+    let result = async {
+        let mut file = create_cache_file(&partial_path).await?;
+        file.write_all(&bytes).await?;
+        file.flush().await?;
+        drop(file);
+        fs::rename(&partial_path, &target.cache_file_path).await
+    }
+    .await;
+    if let Err(e) = result {
+        log::warn!("写入缓存文件失败: {} ({})", target.url, e);
+        let _ = fs::remove_file(&partial_path).await;
+        return;
+    }
+
+    log::info!("文件已下载并缓存: {}", target.url);
+    finish_cache_file(target).await;
+}
+
+// 将字节流发送给客户端，同时写入本地缓存文件；写入完成后按需上传到 S3 镜像
+async fn stream_and_cache<S>(
+    bytes_stream: S,
+    target: CacheTarget,
+    permit: OwnedSemaphorePermit,
+    client_ip: String,
+) -> Result<HttpResponse, AppError>
+where
+    S: futures::Stream<Item = Result<actix_web::web::Bytes, AppError>> + 'static,
+{
+    // 写入前预留文件缓存的位置（缓存已满时先淘汰最久未访问的文件），没有可用的位置时只转发不缓存
+    let Some(slot) = target.cache.reserve_file_slot(&target.cache_file_path).await else {
+        log::warn!("文件缓存没有可用的位置，本次下载不写入缓存: {}", target.url);
+        return Ok(proxy_download(bytes_stream, target, permit, client_ip));
+    };
+    let content_type = target.content_type.clone();
+    let disposition = content_disposition(&content_type, target.served_filename());
+    let tx = spawn_cache_writer(target, slot).await?;
+    let stream = tee_to_cache(bytes_stream, tx);
+
+    Ok(HttpResponse::Ok()
+        .append_header(("Content-Disposition", disposition))
+        .content_type(content_type)
+        .streaming(guard_download_stream(
+            stream,
+            permit,
+            get_download_config().client_idle_timeout(),
+            client_ip,
+        )))
+}
+
+// 启动写入缓存文件的后台任务，返回向其发送数据的 channel；任务结束时释放预留的位置
+async fn spawn_cache_writer(
+    target: CacheTarget,
+    slot: FileSlot,
+) -> Result<tokio::sync::mpsc::Sender<CacheChunk>, AppError> {
+    // 先写入临时文件，下载完成后再重命名为缓存文件，避免其他请求读到写了一半的文件
+    let partial_path = partial_file_path(&target.cache_file_path);
+    let cache_file = create_cache_file(&partial_path).await
+        .map_err(|e| AppError::ApiError(format!("创建缓存文件失败: {}", e)))?;
 
-    let (tx, mut rx) = mpsc::channel::<Bytes>(100);
-    let tx_for_stream = tx.clone(); // mpsc::Sender 实现了 Clone
-    let cache_file_path_clone = cache_file_path.clone();
-    let url_for_cache = url_clone.clone();
-    let filename_for_cache = filename_clone.clone();
-    let content_type_for_cache = content_type_str.clone();
+    // 使用 channel 来分离写入任务；channel 满时等待写入任务消费（背压），不会丢弃数据
+    let (tx, mut rx) =
+        tokio::sync::mpsc::channel::<CacheChunk>(get_download_config().cache_write_buffer);
 
     // 启动后台任务写入缓存文件
     tokio::spawn(async move {
+        let _slot = slot;
         let mut file = cache_file;
-        while let Some(bytes) = rx.recv().await {
-            if let Err(e) = file.write_all(&bytes).await {
-                log::warn!("写入缓存文件失败: {}", e);
-                break;
+        let mut written: u64 = 0;
+        let mut finished = false;
+        while let Some(chunk) = rx.recv().await {
+            match chunk {
+                CacheChunk::Data(bytes) => {
+                    if let Err(e) = file.write_all(&bytes).await {
+                        log::warn!(
+                            "写入缓存文件失败（已写入 {} 字节）: {} ({})",
+                            written, target.url, e
+                        );
+                        break;
+                    }
+                    written += bytes.len() as u64;
+                }
+                CacheChunk::End => {
+                    finished = true;
+                    break;
+                }
+                CacheChunk::Abort => break,
             }
         }
 
         // 文件写入完成，刷新并更新缓存元数据
         if let Err(e) = file.flush().await {
-            log::warn!("刷新缓存文件失败: {}", e);
+            log::warn!("刷新缓存文件失败（已写入 {} 字节）: {} ({})", written, target.url, e);
+            finished = false;
         }
 
-        let cache = get_cache_manager().await;
-        cache.set_file_cache(
-            &url_for_cache,
-            cache_file_path_clone,
-            filename_for_cache,
-            Some(content_type_for_cache),
-        ).await;
-        log::info!("文件已流式下载并缓存: {}", url_for_cache);
+        // 上游出错、客户端提前断开或写入失败时缓存文件不完整，不能在之后的请求中返回
+        let complete = finished && target.expected_len.is_none_or(|len| len == written);
+        drop(file);
+        if !complete {
+            log::warn!("缓存文件不完整（已写入 {} 字节），丢弃: {}", written, target.url);
+            let _ = fs::remove_file(&partial_path).await;
+            return;
+        }
+        if let Err(e) = fs::rename(&partial_path, &target.cache_file_path).await {
+            log::warn!("重命名缓存文件失败: {} ({})", target.url, e);
+            let _ = fs::remove_file(&partial_path).await;
+            return;
+        }
+
+        log::info!("文件已流式下载并缓存: {}", target.url);
+        finish_cache_file(target).await;
     });
 
-    // 创建一个流，将数据同时发送给客户端和缓存写入任务
-    // 将 permit 绑定到流上，确保在整个流完成之前都不会释放
-    // 注意：permit 需要在整个流期间保持，所以将其移动到闭包的捕获中
-    let permit_for_stream = permit;
-    let stream = bytes_stream.map(move |result| {
-        // permit_for_stream 在闭包中保持，直到流完成
-        let _keep_permit = &permit_for_stream;
-        match result {
-            Ok(bytes) => {
-                // 发送到缓存写入任务（非阻塞，如果 channel 满了就丢弃）
-                let _ = tx_for_stream.try_send(bytes.clone());
-                Ok(bytes)
-            }
-            Err(e) => Err(AppError::ApiError(format!("流式下载错误: {}", e))),
+    Ok(tx)
+}
+
+// 缓存文件写入完成后：生成预压缩副本、更新缓存元数据，并按需上传到 S3 镜像
+async fn finish_cache_file(target: CacheTarget) {
+    let gzip_path = precompress_cache_file(&target).await;
+    target.cache.set_file_cache(
+        &target.url,
+        target.cache_file_path.clone(),
+        target.filename,
+        Some(target.content_type.to_string()),
+        target.etag,
+        gzip_path,
+    ).await;
+    drop(target.flight);
+
+    #[cfg(feature = "s3")]
+    if let Some(mirror) = get_s3_mirror().await.filter(|_| target.mirror_to_s3) {
+        let content_type = target.content_type.to_string();
+        if let Err(e) = mirror
+            .upload(&target.cache_filename, &target.cache_file_path, Some(&content_type))
+            .await
+        {
+            log::warn!("镜像文件到 S3 失败: {} ({})", target.url, e);
         }
-    });
+    }
+}
 
-    Ok(HttpResponse::Ok()
-        .content_type(content_type.clone())
-        .append_header((
-            "Content-Disposition",
-            format!("attachment; filename=\"{}\"", filename)
-        ))
-        .streaming(stream))
+// 将字节流中的数据同时发送给缓存写入任务
+fn tee_to_cache<S>(
+    bytes_stream: S,
+    tx: tokio::sync::mpsc::Sender<CacheChunk>,
+) -> impl futures::Stream<Item = Result<actix_web::web::Bytes, AppError>>
+where
+    S: futures::Stream<Item = Result<actix_web::web::Bytes, AppError>>,
+{
+    // 上游流结束时通知写入任务数据已完整；写入任务已退出（写入失败）时 send 立即返回错误，不会阻塞下载
+    bytes_stream
+        .map(Some)
+        .chain(futures::stream::once(async { None }))
+        .then(move |item| {
+            let tx = tx.clone();
+            async move {
+                match item {
+                    Some(Ok(bytes)) => {
+                        let _ = tx.send(CacheChunk::Data(bytes.clone())).await;
+                        Some(Ok(bytes))
+                    }
+                    Some(Err(e)) => {
+                        let _ = tx.send(CacheChunk::Abort).await;
+                        Some(Err(e))
+                    }
+                    None => {
+                        let _ = tx.send(CacheChunk::End).await;
+                        None
+                    }
+                }
+            }
+        })
+        .filter_map(futures::future::ready)
 }
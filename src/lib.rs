@@ -1,12 +1,33 @@
+pub mod admin;
+pub mod allow;
+pub mod badge;
 pub mod cache;
+pub mod cache_backend;
+pub mod channel;
+pub mod client;
+pub mod conditional;
+pub mod config;
+pub mod download;
+pub mod endpoints;
 pub mod error;
+pub mod graphql;
 pub mod handlers;
+pub mod metrics;
+#[cfg(feature = "s3")]
+pub mod mirror;
 pub mod models;
+pub mod notes;
 pub mod rate_limit;
+pub mod response_headers;
+pub mod single_flight;
+pub mod telemetry;
+pub mod timeout;
+pub mod trailing_slash;
+pub mod watch;
 
 use utoipa::OpenApi;
 use crate::models::{
-    HealthResponse, RepoInfo, ReleaseInfo, LatestReleaseInfo, BatchRequest, RepoBatchResult, BatchResponse, BatchResponseMap
+    HealthResponse, RepoInfo, ReleaseInfo, FlatReleaseInfo, FlatAsset, LatestReleaseInfo, CompareInfo, MilestoneInfo, ChecksumsInfo, GeneratedNotes, ResolvedAsset, ReleaseAssets, AssetListResponse, BatchRequest, FieldStatus, RepoBatchResult, BatchResponse, BatchResponseMap, BatchLatestRequest, LatestVersionResult, BatchLatestResponse, WatchEvent, CachedRepoSummary, CachedFileSummary, CacheHitStats, CacheStatsResponse, DownloadQueueStats, StatsResponse
 };
 
 #[derive(OpenApi)]
@@ -14,30 +35,69 @@ use crate::models::{
     paths(
         handlers::health_check,
         handlers::health,
+        handlers::get_stats,
         handlers::get_repo_info,
+        handlers::get_raw_repo,
+        handlers::get_repo_contents,
         handlers::get_releases,
         handlers::get_latest_release,
         handlers::get_latest_release_pre,
+        handlers::get_latest_release_stable,
         handlers::get_latest_release_tauri,
         handlers::get_latest_release_pre_tauri,
+        handlers::get_latest_release_checksums,
+        handlers::resolve_latest_asset,
+        handlers::get_release_assets,
+        handlers::get_latest_release_notes,
+        handlers::get_latest_release_badge,
+        handlers::get_generated_release_notes,
+        handlers::get_compare,
+        handlers::get_milestones,
         handlers::batch_get_repos,
         handlers::batch_get_repos_map,
+        handlers::batch_get_latest_versions,
+        handlers::watch_releases_sse,
         handlers::download_attachment,
+        handlers::download_latest_release_asset,
+        handlers::list_cached_repos,
+        handlers::list_cached_files,
+        handlers::get_cache_stats,
     ),
     components(schemas(
         HealthResponse,
         RepoInfo,
         ReleaseInfo,
+        FlatReleaseInfo,
+        FlatAsset,
         LatestReleaseInfo,
+        CompareInfo,
+        MilestoneInfo,
+        ChecksumsInfo,
+        GeneratedNotes,
+        ResolvedAsset,
+        ReleaseAssets,
+        AssetListResponse,
         BatchRequest,
+        FieldStatus,
         RepoBatchResult,
         BatchResponse,
         BatchResponseMap,
+        BatchLatestRequest,
+        LatestVersionResult,
+        BatchLatestResponse,
+        WatchEvent,
+        CachedRepoSummary,
+        CachedFileSummary,
+        CacheHitStats,
+        CacheStatsResponse,
+        DownloadQueueStats,
+        StatsResponse,
     )),
     tags(
         (name = "health", description = "健康检查端点"),
         (name = "repos", description = "仓库信息相关端点"),
         (name = "download", description = "文件下载端点"),
+        (name = "cache", description = "缓存管理端点（需要管理令牌）"),
     ),
 )]
 pub struct ApiDoc;
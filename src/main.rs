@@ -1,14 +1,18 @@
 use actix_cors::Cors;
-use actix_web::{App, HttpServer};
+use actix_web::middleware::from_fn;
+use actix_web::{web, App, HttpServer};
+use gh_info_rs::allow::{allow_header_middleware, unmatched_route};
+use gh_info_rs::conditional::conditional_response_middleware;
 use gh_info_rs::cache::get_cache_manager;
-use gh_info_rs::handlers::{
-    batch_get_repos, batch_get_repos_map, download_attachment, get_latest_release,
-    get_latest_release_pre, get_latest_release_pre_tauri, get_latest_release_tauri, get_releases,
-    get_repo_info, health, health_check,
-};
-use gh_info_rs::rate_limit::get_rate_limit_manager;
-use gh_info_rs::ApiDoc;
-use utoipa::OpenApi;
+use gh_info_rs::config::validate_config;
+use gh_info_rs::download::get_download_config;
+use gh_info_rs::endpoints::{configure_services, enabled_openapi, get_endpoints_config};
+use gh_info_rs::handlers::github_api_version;
+use gh_info_rs::rate_limit::{assign_connection_id, get_rate_limit_manager, rate_limit_middleware};
+use gh_info_rs::response_headers::{get_response_headers_config, response_headers_middleware};
+use gh_info_rs::telemetry;
+use gh_info_rs::timeout::request_timeout_middleware;
+use gh_info_rs::trailing_slash::get_trailing_slash_mode;
 use utoipa_swagger_ui::SwaggerUi;
 
 #[actix_web::main]
@@ -20,27 +24,65 @@ async fn main() -> std::io::Result<()> {
         .or_else(|_| std::env::var("RUST_LOG"))
         .unwrap_or_else(|_| "info".to_string());
 
-    // 创建自定义环境变量配置，优先使用 LOG_LEVEL，如果没有则使用 RUST_LOG
-    let env = env_logger::Env::default().filter_or("RUST_LOG", &log_level);
-    env_logger::Builder::from_env(env).init();
+    // 启用 otel 特性并设置了 OTEL_EXPORTER_OTLP_ENDPOINT 时使用 tracing 输出日志并导出追踪数据
+    // 守卫需要保留到 main 结束，退出时刷新尚未导出的 span
+    let telemetry_guard = telemetry::init(&log_level);
+    if telemetry_guard.is_some() {
+        log::info!("已启用 OpenTelemetry 追踪导出");
+    } else {
+        // 创建自定义环境变量配置，优先使用 LOG_LEVEL，如果没有则使用 RUST_LOG
+        let env = env_logger::Env::default().filter_or("RUST_LOG", &log_level);
+        env_logger::Builder::from_env(env).init();
+    }
+
+    // 检查配置，存在致命错误时以非零退出码退出，便于编排系统发现问题
+    let config_report = validate_config();
+    config_report.log();
+    if !config_report.is_ok() {
+        eprintln!("❌ 配置错误，服务无法启动:");
+        for error in &config_report.errors {
+            eprintln!("   - {}", error);
+        }
+        std::process::exit(1);
+    }
 
     // 从环境变量获取绑定地址，默认为 0.0.0.0:8080（Docker 友好）
     let bind_addr = std::env::var("BIND_ADDRESS").unwrap_or_else(|_| "0.0.0.0:8080".to_string());
 
     println!("🚀 GitHub API 信息收集服务启动中...");
     println!("📡 服务地址: http://{}", bind_addr);
+    // validate_config 已加载 .env，这里直接读取环境变量
+    if std::env::var("GITHUB_TOKEN").map_or(true, |t| t.trim().is_empty()) {
+        println!("⚠️  未配置 GITHUB_TOKEN：GitHub API 限制为每个出口 IP 每小时 60 次请求，稍有负载就会返回 403");
+    }
     println!("📚 可用端点:");
     println!("   GET  /                                    - 健康检查和基本信息");
     println!("   GET  /health                              - 健康检查端点");
+    println!("   GET  /stats                               - 运行统计（下载队列等待时间等）");
     println!("   GET  /repos/{{owner}}/{{repo}}              - 获取仓库基本信息");
+    println!("   GET  /repos/{{owner}}/{{repo}}/raw          - 获取 GitHub 返回的原始仓库 JSON");
+    println!("   GET  /repos/{{owner}}/{{repo}}/contents/{{path}}?ref=xxx - 获取仓库中指定路径的文件");
     println!("   GET  /repos/{{owner}}/{{repo}}/releases     - 获取所有 releases");
     println!("   GET  /repos/{{owner}}/{{repo}}/releases/latest - 获取最新 release");
     println!("   GET  /repos/{{owner}}/{{repo}}/releases/latest/pre - 获取最新 release（包括 pre-release）");
+    println!("   GET  /repos/{{owner}}/{{repo}}/releases/latest/stable - 获取最新正式版 release（跳过 pre-release 和草稿）");
     println!("   GET  /repos/{{owner}}/{{repo}}/releases/latest/tauri - 获取最新 release 的 latest.json 文件内容");
     println!("   GET  /repos/{{owner}}/{{repo}}/releases/latest/pre/tauri - 获取最新 release（包括 pre-release）的 latest.json 文件内容");
+    println!("   GET  /repos/{{owner}}/{{repo}}/releases/latest/checksums - 获取最新 release 的校验和");
+    println!("   GET  /repos/{{owner}}/{{repo}}/releases/latest/notes - 获取最新 release 的更新说明");
+    println!("   GET  /repos/{{owner}}/{{repo}}/releases/latest/badge.svg - 最新版本的 SVG 徽章");
+    println!("   GET  /repos/{{owner}}/{{repo}}/assets?pattern=xxx - 列出所有 release 中与模式匹配的附件");
+    println!("   GET  /repos/{{owner}}/{{repo}}/releases/notes?tag=xxx - 生成指定 tag 的 release notes（需要 GITHUB_TOKEN）");
+    println!("   GET  /repos/{{owner}}/{{repo}}/compare/{{base}}...{{head}} - 获取两个版本之间的差异");
+    println!("   GET  /repos/{{owner}}/{{repo}}/milestones?state=open - 获取仓库的里程碑列表");
     println!("   POST /repos/batch                          - 批量获取多个仓库信息（数组格式）");
     println!("   POST /repos/batch/map                      - 批量获取多个仓库信息（Map 格式）");
+    println!("   POST /repos/batch/latest                   - 批量获取多个仓库的最新版本号");
+    println!("   GET  /repos/watch/sse?repos={{repos}}       - 通过 SSE 监听多个仓库的新版本");
     println!("   GET  /download?url={{url}}                 - 下载附件文件（支持缓存）");
+    println!("   GET  /cache/repos                          - 列出缓存中的仓库（需要管理令牌）");
+    println!("   GET  /cache/files                          - 列出缓存的下载文件（需要管理令牌）");
+    println!("   GET  /cache/stats                          - 缓存效果统计（需要管理令牌）");
     println!("   GET  /swagger-ui/*                         - API 文档页面");
     println!();
 
@@ -54,6 +96,38 @@ async fn main() -> std::io::Result<()> {
     get_rate_limit_manager().await;
     log::info!("限流管理器初始化完成");
 
+    match github_api_version() {
+        Some(version) => log::info!("GitHub API 版本: {}", version),
+        None => log::info!("GitHub API 版本: 未指定（使用 GitHub 的默认版本）"),
+    }
+
+    // 加载固定响应头配置（无效的条目在启动时输出警告）
+    let response_headers = get_response_headers_config();
+    log::info!("固定响应头: {} 个", response_headers.headers.len());
+
+    // 加载下载配置（DOWNLOAD_FORWARD_HEADERS 中无效或禁止转发的名称、CONTENT_TYPE_OVERRIDES 中无效的条目在启动时输出警告）
+    let download_config = get_download_config();
+    if !download_config.forward_headers.is_empty() {
+        log::info!("下载时转发的请求头: {}", download_config.forward_headers.join(", "));
+    }
+    if !download_config.content_type_overrides.is_empty() {
+        let mut extensions: Vec<&str> =
+            download_config.content_type_overrides.keys().map(String::as_str).collect();
+        extensions.sort_unstable();
+        log::info!("覆盖 Content-Type 的扩展名: {}", extensions.join(", "));
+    }
+
+    let trailing_slash = get_trailing_slash_mode();
+    log::info!("路径末尾斜杠处理: {:?}", trailing_slash);
+
+    // 加载端点开关配置（无法识别的端点名称在启动时输出警告）
+    let endpoints = get_endpoints_config();
+    if !endpoints.disabled.is_empty() {
+        let mut disabled: Vec<&str> = endpoints.disabled.iter().map(String::as_str).collect();
+        disabled.sort_unstable();
+        log::info!("已禁用端点: {}", disabled.join(", "));
+    }
+
     // 配置 CORS
     // 如果设置了 CORS_ALLOWED_ORIGINS 环境变量，则只允许指定的域（逗号分隔）
     // 如果未设置，则允许所有来源
@@ -94,24 +168,37 @@ async fn main() -> std::io::Result<()> {
                 .max_age(3600)
         };
 
-        App::new()
+        let app = App::new()
+            // 最内层：只限制处理函数生成响应的时间，不包括限流检查和响应体传输
+            .wrap(from_fn(request_timeout_middleware))
+            // 在处理函数生成的 JSON 响应上计算 ETag，匹配 If-None-Match 时改为 304（ENABLE_CONDITIONAL_RESPONSES）
+            .wrap(from_fn(conditional_response_middleware))
+            .wrap(from_fn(rate_limit_middleware))
             .wrap(cors)
+            // 在 CORS 之外补充 Allow 头（包括 CORS 预检响应）
+            .wrap(from_fn(allow_header_middleware))
+            // 为所有响应（包括错误和预检响应）添加固定响应头
+            .wrap(from_fn(response_headers_middleware))
+            // 在所有中间件之前规范化路径，限流和 Allow 头都基于规范化后的路径
+            .wrap(trailing_slash.middleware())
             .service(
                 SwaggerUi::new("/swagger-ui/{_:.*}")
-                    .url("/api-doc/openapi.json", ApiDoc::openapi()),
+                    .url("/api-doc/openapi.json", enabled_openapi()),
             )
-            .service(health_check)
-            .service(health)
-            .service(get_repo_info)
-            .service(get_releases)
-            .service(get_latest_release)
-            .service(get_latest_release_pre)
-            .service(get_latest_release_tauri)
-            .service(get_latest_release_pre_tauri)
-            .service(batch_get_repos)
-            .service(batch_get_repos_map)
-            .service(download_attachment)
+            // 去掉末尾斜杠后 /swagger-ui/ 变为 /swagger-ui，不再匹配上面的路由，重定向到首页
+            .service(web::redirect("/swagger-ui", "/swagger-ui/index.html"))
+            .configure(configure_services)
+            // 没有匹配任何路由时返回 JSON 格式的 404/405
+            .default_service(web::to(unmatched_route));
+
+        // 最外层中间件：为每个请求创建根 span（并提取上游传递的 trace 上下文）
+        #[cfg(feature = "otel")]
+        let app = app.wrap(telemetry::request_tracing());
+
+        app
     })
+    // 为每个连接分配标识，无法获取客户端 IP 时用于限流
+    .on_connect(assign_connection_id)
     .bind(&bind_addr)?
     .run()
     .await
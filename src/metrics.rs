@@ -0,0 +1,185 @@
+//! 缓存效果统计
+//!
+//! 记录自启动以来各类数据的缓存命中情况、负缓存命中次数和发往 GitHub API 的请求数，
+//! 通过 `GET /cache/stats` 查看，用于评估缓存和请求合并节省的 GitHub 请求以及调整 TTL。
+
+use crate::models::{CacheHitStats, CacheStatsResponse};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 使用数据缓存的数据类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheKind {
+    RepoInfo,
+    Releases,
+    LatestRelease,
+    Compare,
+    Checksums,
+    GeneratedNotes,
+    RawRepo,
+    Contents,
+    Milestones,
+}
+
+impl CacheKind {
+    /// 所有数据类型（顺序与统计输出一致）
+    pub const ALL: [CacheKind; 9] = [
+        CacheKind::RepoInfo,
+        CacheKind::Releases,
+        CacheKind::LatestRelease,
+        CacheKind::Compare,
+        CacheKind::Checksums,
+        CacheKind::GeneratedNotes,
+        CacheKind::RawRepo,
+        CacheKind::Contents,
+        CacheKind::Milestones,
+    ];
+
+    /// 统计输出中使用的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            CacheKind::RepoInfo => "repo_info",
+            CacheKind::Releases => "releases",
+            CacheKind::LatestRelease => "latest_release",
+            CacheKind::Compare => "compare",
+            CacheKind::Checksums => "checksums",
+            CacheKind::GeneratedNotes => "generated_notes",
+            CacheKind::RawRepo => "raw_repo",
+            CacheKind::Contents => "contents",
+            CacheKind::Milestones => "milestones",
+        }
+    }
+}
+
+// 单个数据类型的命中/未命中计数
+struct HitCounter {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl HitCounter {
+    const fn new() -> Self {
+        Self {
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+}
+
+/// 缓存效果计数器
+pub struct CacheMetrics {
+    lookups: [HitCounter; CacheKind::ALL.len()],
+    negative_hits: AtomicU64,
+    github_api_calls: AtomicU64,
+    cache_file_corruptions: AtomicU64,
+}
+
+impl Default for CacheMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CacheMetrics {
+    pub const fn new() -> Self {
+        Self {
+            lookups: [const { HitCounter::new() }; CacheKind::ALL.len()],
+            negative_hits: AtomicU64::new(0),
+            github_api_calls: AtomicU64::new(0),
+            cache_file_corruptions: AtomicU64::new(0),
+        }
+    }
+
+    /// 记录一次缓存查询的结果
+    pub fn record_lookup(&self, kind: CacheKind, hit: bool) {
+        let counter = &self.lookups[kind as usize];
+        if hit {
+            counter.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            counter.misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 记录一次负缓存命中（近期已确认数据不存在，省去一次 GitHub 请求）
+    pub fn record_negative_hit(&self) {
+        self.negative_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录一次实际发出的 GitHub API 请求（包括 202 重试）
+    pub fn record_github_api_call(&self) {
+        self.github_api_calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 记录一次无法完整解析的缓存文件（启动时加载 `CACHE_FILE` 失败）
+    pub fn record_cache_file_corruption(&self) {
+        self.cache_file_corruptions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 生成统计快照，`coalesced_requests` 为请求合并（single-flight）省去的请求数
+    pub fn snapshot(&self, coalesced_requests: u64) -> CacheStatsResponse {
+        let caches = CacheKind::ALL
+            .iter()
+            .map(|kind| {
+                let counter = &self.lookups[*kind as usize];
+                let hits = counter.hits.load(Ordering::Relaxed);
+                let misses = counter.misses.load(Ordering::Relaxed);
+                let total = hits + misses;
+                let hit_ratio = (total > 0).then(|| hits as f64 / total as f64);
+                (
+                    kind.name().to_string(),
+                    CacheHitStats {
+                        hits,
+                        misses,
+                        hit_ratio,
+                    },
+                )
+            })
+            .collect();
+
+        CacheStatsResponse {
+            caches,
+            coalesced_requests,
+            negative_cache_hits: self.negative_hits.load(Ordering::Relaxed),
+            github_api_calls: self.github_api_calls.load(Ordering::Relaxed),
+            cache_file_corruptions: self.cache_file_corruptions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+static CACHE_METRICS: CacheMetrics = CacheMetrics::new();
+
+/// 获取全局缓存效果计数器
+pub fn cache_metrics() -> &'static CacheMetrics {
+    &CACHE_METRICS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let metrics = CacheMetrics::new();
+        metrics.record_lookup(CacheKind::RepoInfo, true);
+        metrics.record_lookup(CacheKind::RepoInfo, true);
+        metrics.record_lookup(CacheKind::RepoInfo, true);
+        metrics.record_lookup(CacheKind::RepoInfo, false);
+        metrics.record_lookup(CacheKind::Compare, false);
+        metrics.record_negative_hit();
+        metrics.record_github_api_call();
+        metrics.record_github_api_call();
+        metrics.record_cache_file_corruption();
+
+        let stats = metrics.snapshot(5);
+        assert_eq!(stats.caches.len(), CacheKind::ALL.len());
+        let repo_info = &stats.caches["repo_info"];
+        assert_eq!((repo_info.hits, repo_info.misses), (3, 1));
+        assert_eq!(repo_info.hit_ratio, Some(0.75));
+        assert_eq!(stats.caches["compare"].hit_ratio, Some(0.0));
+        // 没有查询过的类型命中率为 null
+        assert_eq!(stats.caches["releases"].hit_ratio, None);
+        assert_eq!(stats.coalesced_requests, 5);
+        assert_eq!(stats.negative_cache_hits, 1);
+        assert_eq!(stats.github_api_calls, 2);
+        assert_eq!(stats.cache_file_corruptions, 1);
+    }
+}
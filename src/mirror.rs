@@ -0,0 +1,267 @@
+use crate::error::AppError;
+use actix_web::web::Bytes;
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::operation::get_object::GetObjectOutput;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use futures::stream::{self, Stream};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+use tokio::sync::OnceCell;
+
+/// S3 兼容存储镜像配置
+#[derive(Clone, Debug)]
+pub struct S3Config {
+    /// S3 兼容服务的地址（如 `https://s3.amazonaws.com`、MinIO 或 R2 的地址）
+    pub endpoint: String,
+    /// 存储桶名称
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+    /// 区域（大多数 S3 兼容服务可以使用默认值）
+    pub region: String,
+    /// 对象键前缀
+    pub key_prefix: String,
+}
+
+impl S3Config {
+    /// 从环境变量加载配置，`S3_ENDPOINT`/`S3_BUCKET`/`S3_ACCESS_KEY`/`S3_SECRET_KEY` 任意一个未设置时返回 None（不启用镜像）
+    pub fn from_env() -> Option<Self> {
+        dotenv::dotenv().ok();
+
+        let required = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        Some(Self {
+            endpoint: required("S3_ENDPOINT")?,
+            bucket: required("S3_BUCKET")?,
+            access_key: required("S3_ACCESS_KEY")?,
+            secret_key: required("S3_SECRET_KEY")?,
+            region: required("S3_REGION").unwrap_or_else(|| "us-east-1".to_string()),
+            key_prefix: std::env::var("S3_KEY_PREFIX").unwrap_or_default(),
+        })
+    }
+}
+
+/// 从 S3 读取到的镜像对象
+pub struct MirroredObject {
+    pub content_type: Option<String>,
+    pub body: ByteStream,
+}
+
+/// release 附件的 S3 镜像
+///
+/// 文件内容按内容的 SHA-256 存储（`{前缀}sha256/{hash}`），不同 URL 的相同文件只保存一份。
+/// 下载前只知道 URL，因此每个 URL 另有一个索引对象（`{前缀}urls/{本地缓存文件名}`），
+/// 内容为对应的内容对象键；查找时先读索引，再读内容对象。
+pub struct S3Mirror {
+    client: Client,
+    bucket: String,
+    key_prefix: String,
+}
+
+impl S3Mirror {
+    pub fn new(config: S3Config) -> Self {
+        let credentials = Credentials::new(
+            config.access_key,
+            config.secret_key,
+            None,
+            None,
+            "gh-info-rs",
+        );
+        let s3_config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .endpoint_url(config.endpoint)
+            .region(Region::new(config.region))
+            .credentials_provider(credentials)
+            // 大多数 S3 兼容服务（如 MinIO）只支持路径风格的地址
+            .force_path_style(true)
+            .build();
+
+        Self {
+            client: Client::from_conf(s3_config),
+            bucket: config.bucket,
+            key_prefix: config.key_prefix,
+        }
+    }
+
+    // 内容对象键
+    fn content_key(&self, content_hash: &str) -> String {
+        format!("{}sha256/{}", self.key_prefix, content_hash)
+    }
+
+    // URL 索引对象键（本地缓存文件名基于 URL 的 SHA-256）
+    fn index_key(&self, cache_filename: &str) -> String {
+        format!("{}urls/{}", self.key_prefix, cache_filename)
+    }
+
+    async fn get_object(&self, key: &str) -> Option<GetObjectOutput> {
+        match self.client.get_object().bucket(&self.bucket).key(key).send().await {
+            Ok(output) => Some(output),
+            Err(e) => {
+                log::debug!("S3 镜像未命中: {} ({})", key, e);
+                None
+            }
+        }
+    }
+
+    /// 根据本地缓存文件名（URL）从 S3 获取镜像对象，索引或内容不存在、请求失败时返回 None
+    pub async fn get(&self, cache_filename: &str) -> Option<MirroredObject> {
+        let index = self.get_object(&self.index_key(cache_filename)).await?;
+        let content_key = match index.body.collect().await {
+            Ok(body) => String::from_utf8(body.to_vec()).ok()?.trim().to_string(),
+            Err(e) => {
+                log::debug!("读取 S3 索引失败: {} ({})", cache_filename, e);
+                return None;
+            }
+        };
+        let output = self.get_object(&content_key).await?;
+        Some(MirroredObject {
+            content_type: output.content_type,
+            body: output.body,
+        })
+    }
+
+    /// 将本地缓存文件按内容哈希上传到 S3（内容已存在时跳过），并写入 URL 索引
+    pub async fn upload(
+        &self,
+        cache_filename: &str,
+        file_path: &Path,
+        content_type: Option<&str>,
+    ) -> Result<(), AppError> {
+        let content_key = self.content_key(&file_sha256(file_path).await?);
+
+        let exists = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(&content_key)
+            .send()
+            .await
+            .is_ok();
+        if !exists {
+            let body = ByteStream::from_path(file_path)
+                .await
+                .map_err(|e| AppError::Internal(format!("读取缓存文件失败: {}", e)))?;
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(&content_key)
+                .set_content_type(content_type.map(str::to_string))
+                .body(body)
+                .send()
+                .await
+                .map_err(|e| AppError::ApiError(format!("上传到 S3 失败: {}", e)))?;
+        }
+
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(self.index_key(cache_filename))
+            .content_type("text/plain")
+            .body(ByteStream::from(content_key.clone().into_bytes()))
+            .send()
+            .await
+            .map_err(|e| AppError::ApiError(format!("写入 S3 索引失败: {}", e)))?;
+
+        log::info!("文件已镜像到 S3: {}{}", content_key, if exists { "（内容已存在）" } else { "" });
+        Ok(())
+    }
+}
+
+// 计算文件内容的 SHA-256（十六进制）
+async fn file_sha256(path: &Path) -> Result<String, AppError> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| AppError::Internal(format!("读取缓存文件失败: {}", e)))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .await
+            .map_err(|e| AppError::Internal(format!("读取缓存文件失败: {}", e)))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// 将 S3 对象内容转换为字节流
+pub fn byte_stream(body: ByteStream) -> impl Stream<Item = Result<Bytes, AppError>> {
+    stream::unfold(Some(body), |body| async move {
+        let mut body = body?;
+        match body.try_next().await {
+            Ok(Some(bytes)) => Some((Ok(bytes), Some(body))),
+            Ok(None) => None,
+            Err(e) => Some((Err(AppError::ApiError(format!("读取 S3 对象失败: {}", e))), None)),
+        }
+    })
+}
+
+static S3_MIRROR: OnceCell<Option<S3Mirror>> = OnceCell::const_new();
+
+/// 获取全局 S3 镜像，未配置时返回 None
+pub async fn get_s3_mirror() -> Option<&'static S3Mirror> {
+    S3_MIRROR
+        .get_or_init(|| async {
+            let config = S3Config::from_env()?;
+            log::info!("已启用 S3 镜像: {} (bucket: {})", config.endpoint, config.bucket);
+            Some(S3Mirror::new(config))
+        })
+        .await
+        .as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> S3Config {
+        S3Config {
+            endpoint: "http://127.0.0.1:9000".to_string(),
+            bucket: "assets".to_string(),
+            access_key: "key".to_string(),
+            secret_key: "secret".to_string(),
+            region: "us-east-1".to_string(),
+            key_prefix: "gh-info/".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_object_keys_use_prefix() {
+        let mirror = S3Mirror::new(test_config());
+        assert_eq!(mirror.content_key("abc"), "gh-info/sha256/abc");
+        assert_eq!(mirror.index_key("def.zip"), "gh-info/urls/def.zip");
+    }
+
+    #[tokio::test]
+    async fn test_file_sha256() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let path = tmp.path().to_path_buf();
+        tokio::fs::write(&path, b"hello").await.unwrap();
+        assert_eq!(
+            file_sha256(&path).await.unwrap(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_byte_stream() {
+        use futures::StreamExt;
+
+        let chunks: Vec<_> = byte_stream(ByteStream::from_static(b"hello")).collect().await;
+        let data: Vec<u8> = chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.unwrap().to_vec())
+            .collect();
+        assert_eq!(data, b"hello");
+    }
+}
@@ -1,12 +1,32 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
 use utoipa::ToSchema;
 
+// 宽松解析可选的 RFC3339 时间戳：缺失或为 null（如草稿 release）时返回 None，
+// 格式错误时记录警告并返回 None，避免单个异常时间戳导致整个响应解析失败
+fn deserialize_optional_datetime<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Option<String> = Option::deserialize(deserializer)?;
+    Ok(value.and_then(|raw| match DateTime::parse_from_rfc3339(&raw) {
+        Ok(datetime) => Some(datetime.with_timezone(&Utc)),
+        Err(e) => {
+            log::warn!("无法解析时间戳 {:?}: {}", raw, e);
+            None
+        }
+    }))
+}
+
 // 健康检查响应结构
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct HealthResponse {
     pub status: String,
     pub service: String,
     pub version: String,
+    pub authenticated: bool, // 是否配置了 GITHUB_TOKEN，未配置时 GitHub API 限制为每小时 60 次请求
 }
 
 // GitHub API 返回的仓库基本信息
@@ -23,7 +43,9 @@ pub struct GithubRepo {
     #[serde(rename = "forks_count")]
     pub forks_count: u32,
     #[serde(rename = "updated_at")]
-    pub updated_at: String,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub private: bool,
 }
 
 // GitHub API 返回的 Release Asset
@@ -32,6 +54,16 @@ pub struct GithubAsset {
     pub name: String,
     #[serde(rename = "browser_download_url")]
     pub download_url: String,
+    #[serde(default)]
+    pub size: u64,
+}
+
+// GitHub API 返回的用户信息（Release 作者）
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GithubUser {
+    pub login: String,
+    #[serde(rename = "avatar_url")]
+    pub avatar_url: String,
 }
 
 // GitHub API 返回的 Release 数据
@@ -41,10 +73,48 @@ pub struct GithubRelease {
     pub tag_name: String,
     pub name: Option<String>,
     pub body: Option<String>,
-    #[serde(rename = "published_at")]
-    pub published_at: String,
+    #[serde(rename = "published_at", default, deserialize_with = "deserialize_optional_datetime")]
+    pub published_at: Option<DateTime<Utc>>,
     pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
     pub assets: Vec<GithubAsset>,
+    #[serde(default)]
+    pub author: Option<GithubUser>,
+    // 反应统计（需要 squirrel-girl-preview Accept 头），包含 url、total_count 以及各表情计数
+    #[serde(default)]
+    pub reactions: Option<HashMap<String, serde_json::Value>>,
+    // 源码压缩包链接（GitHub 自动生成，不在 assets 中）
+    #[serde(default)]
+    pub zipball_url: Option<String>,
+    #[serde(default)]
+    pub tarball_url: Option<String>,
+}
+
+// GitHub API 返回的 Compare 数据
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GithubCompare {
+    pub status: String,
+    #[serde(rename = "ahead_by")]
+    pub ahead_by: u32,
+    #[serde(rename = "behind_by")]
+    pub behind_by: u32,
+    #[serde(rename = "total_commits")]
+    pub total_commits: u32,
+    pub commits: Vec<GithubCompareCommit>,
+}
+
+// GitHub API Compare 数据中的单个提交
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GithubCompareCommit {
+    pub sha: String,
+    pub commit: GithubCommitDetail,
+}
+
+// GitHub API 提交详情（仅保留提交信息）
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GithubCommitDetail {
+    pub message: String,
 }
 
 // 整理后的仓库信息（用于 API 响应）
@@ -57,7 +127,19 @@ pub struct RepoInfo {
     pub description: Option<String>,
     pub stargazers_count: u32,
     pub forks_count: u32,
-    pub updated_at: String,
+    pub updated_at: DateTime<Utc>, // RFC3339 格式
+    #[serde(default)]
+    pub private: bool, // 是否为私有仓库
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moved_to: Option<String>, // 仓库已改名或转移时的新名称（owner/repo），GitHub 会自动重定向到新仓库
+}
+
+/// 判断 GitHub 返回的仓库是否与请求的仓库不同（仓库已改名或转移），返回新的 `owner/repo`
+///
+/// GitHub 的仓库名不区分大小写，只有大小写不同时不视为改名。
+pub fn moved_to(owner: &str, repo: &str, full_name: &str) -> Option<String> {
+    let requested = format!("{}/{}", owner, repo);
+    (!requested.eq_ignore_ascii_case(full_name)).then(|| full_name.to_string())
 }
 
 // 整理后的 Release 信息（用于 API 响应）
@@ -66,9 +148,114 @@ pub struct ReleaseInfo {
     pub tag_name: String,
     pub name: Option<String>,
     pub changelog: Option<String>,
-    pub published_at: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub changelog_truncated: bool, // changelog 超过 MAX_CHANGELOG_BYTES 时被截断
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>, // RFC3339 格式，草稿 release 可能为 null
     pub prerelease: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub draft: bool, // 草稿 release（仅 token 有仓库写权限时可见）
     pub attachments: Vec<String>, // 附件下载链接
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachment_sizes: Vec<u64>, // 附件大小（字节），与 attachments 一一对应，只通过 schema=flat 返回；旧缓存数据中为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_login: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_avatar_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reactions: Option<HashMap<String, u32>>, // 仅在 include_reactions=true 时返回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zipball_url: Option<String>, // 源码压缩包（zip）链接，不在 attachments 中
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tarball_url: Option<String>, // 源码压缩包（tar.gz）链接，不在 attachments 中
+}
+
+impl ReleaseInfo {
+    // include_source=true 时将源码压缩包链接（zip、tar.gz）追加到 attachments 末尾（没有对应的 attachment_sizes），
+    // 否则从响应中去掉这两个链接（它们只保存在缓存中，供 include_source 请求使用）
+    pub fn apply_include_source(&mut self, include_source: bool) {
+        if include_source {
+            let archives = self.zipball_url.iter().chain(&self.tarball_url).cloned();
+            self.attachments.extend(archives);
+        } else {
+            self.zipball_url = None;
+            self.tarball_url = None;
+        }
+    }
+}
+
+// 扁平格式中 changelog 预览的最大字符数
+pub const CHANGELOG_PREVIEW_CHARS: usize = 280;
+
+// 扁平格式的 release 附件
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FlatAsset {
+    pub name: String,      // 附件文件名
+    pub url: String,       // 附件下载链接
+    pub size: Option<u64>, // 附件大小（字节），未知时为 null
+}
+
+// 扁平格式的 Release 信息（schema=flat，便于渲染 release 列表）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FlatReleaseInfo {
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub changelog_preview: Option<String>, // changelog 的前 280 个字符，被截断时以 `…` 结尾
+    pub published_at: Option<DateTime<Utc>>,
+    pub prerelease: bool,
+    pub assets: Vec<FlatAsset>,
+    pub author_login: Option<String>,
+}
+
+impl From<ReleaseInfo> for FlatReleaseInfo {
+    fn from(release: ReleaseInfo) -> Self {
+        let assets = release
+            .attachments
+            .into_iter()
+            .enumerate()
+            .map(|(i, url)| FlatAsset {
+                name: url.rsplit('/').next().unwrap_or_default().to_string(),
+                size: release.attachment_sizes.get(i).copied(),
+                url,
+            })
+            .collect();
+
+        Self {
+            tag_name: release.tag_name,
+            name: release.name,
+            changelog_preview: release.changelog.map(|c| changelog_preview(&c)),
+            published_at: release.published_at,
+            prerelease: release.prerelease,
+            assets,
+            author_login: release.author_login,
+        }
+    }
+}
+
+// 截取 changelog 预览（按字符截断，避免切断多字节字符）
+fn changelog_preview(changelog: &str) -> String {
+    let changelog = changelog.trim();
+    match changelog.char_indices().nth(CHANGELOG_PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}…", changelog[..end].trim_end()),
+        None => changelog.to_string(),
+    }
+}
+
+// release 列表响应格式的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct SchemaQuery {
+    pub schema: Option<String>, // `default`（默认）或 `flat`
+}
+
+impl SchemaQuery {
+    /// 是否请求扁平格式，无法识别的格式返回 Err
+    pub fn is_flat(&self) -> Result<bool, String> {
+        match self.schema.as_deref() {
+            None | Some("default") => Ok(false),
+            Some("flat") => Ok(true),
+            Some(other) => Err(other.to_string()),
+        }
+    }
 }
 
 // 整理后的最新版本信息（用于 API 响应）
@@ -77,9 +264,345 @@ pub struct LatestReleaseInfo {
     pub repo: String,
     pub latest_version: String,
     pub changelog: Option<String>,
-    pub published_at: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub changelog_truncated: bool, // changelog 超过 MAX_CHANGELOG_BYTES 时被截断
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>, // RFC3339 格式，草稿 release 可能为 null
     pub prerelease: bool,
     pub attachments: Vec<String>, // 附件下载链接
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachment_sizes: Vec<u64>, // 附件大小（字节），与 attachments 一一对应，只保存在缓存中供 resolve 端点使用；旧缓存数据中为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_login: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_avatar_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reactions: Option<HashMap<String, u32>>, // 仅在 include_reactions=true 时返回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zipball_url: Option<String>, // 源码压缩包（zip）链接，不在 attachments 中
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tarball_url: Option<String>, // 源码压缩包（tar.gz）链接，不在 attachments 中
+}
+
+impl LatestReleaseInfo {
+    // include_source=true 时将源码压缩包链接（zip、tar.gz）追加到 attachments 末尾，否则从响应中去掉这两个链接。
+    // 附件大小不出现在响应中，这里一并去掉
+    pub fn apply_include_source(&mut self, include_source: bool) {
+        self.attachment_sizes.clear();
+        if include_source {
+            let archives = self.zipball_url.iter().chain(&self.tarball_url).cloned();
+            self.attachments.extend(archives);
+        } else {
+            self.zipball_url = None;
+            self.tarball_url = None;
+        }
+    }
+}
+
+// Release 相关端点的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct ReleaseQuery {
+    #[serde(default)]
+    pub include_reactions: bool, // 是否包含反应统计（需要额外的 GitHub 请求）
+    #[serde(default)]
+    pub include_source: bool, // 是否将源码压缩包（zipball、tarball）追加到 attachments
+}
+
+// 版本号格式的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct VersionQuery {
+    #[serde(default)]
+    pub strip_v: bool, // 是否去掉版本号开头的 `v`/`V`（仅当其余部分是有效版本号时）
+}
+
+// 发布渠道的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct ChannelQuery {
+    pub channel: Option<String>, // stable、beta、nightly 或 any，未指定时使用 GitHub 的 latest release
+}
+
+// JSON 端点通用的输出格式查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct FormatQuery {
+    #[serde(default)]
+    pub pretty: bool, // 是否返回缩进格式的 JSON（默认紧凑格式）
+    #[serde(default)]
+    pub include_meta: bool, // 是否将缓存数据包装为 { data, cache_age_secs, expires_in_secs }（仅对读取缓存数据的端点有效）
+}
+
+// include_meta=true 时的响应包装，cache_age_secs/expires_in_secs 在数据不经过缓存或缓存被禁用时为 null
+#[derive(Debug, Serialize)]
+pub struct CachedResponse<T> {
+    pub data: T,
+    pub cache_age_secs: Option<u64>,  // 数据从 GitHub 获取后经过的秒数
+    pub expires_in_secs: Option<u64>, // 缓存条目的剩余有效秒数
+}
+
+// 发布时间范围（起始时间, 结束时间），None 表示不限制
+pub type DateRange = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+// Release 列表按发布时间过滤的查询参数（RFC3339 格式）
+#[derive(Debug, Default, Deserialize)]
+pub struct ReleaseDateQuery {
+    pub since: Option<String>, // 只返回在该时间（含）之后发布的 release
+    pub until: Option<String>, // 只返回在该时间（含）之前发布的 release
+}
+
+impl ReleaseDateQuery {
+    // 解析 RFC3339 时间参数，格式错误时返回参数名
+    fn parse_param(name: &'static str, value: Option<&str>) -> Result<Option<DateTime<Utc>>, &'static str> {
+        value
+            .map(|v| {
+                DateTime::parse_from_rfc3339(v)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .map_err(|_| name)
+            })
+            .transpose()
+    }
+
+    /// 解析 since/until，返回格式错误的参数名
+    pub fn parse(&self) -> Result<DateRange, &'static str> {
+        Ok((
+            Self::parse_param("since", self.since.as_deref())?,
+            Self::parse_param("until", self.until.as_deref())?,
+        ))
+    }
+}
+
+// 跳过缓存强制刷新的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct RefreshQuery {
+    #[serde(default)]
+    pub refresh: bool, // 是否跳过缓存直接从 GitHub 获取（结果仍会写入缓存）
+}
+
+// 整理后的版本对比信息（用于 API 响应）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CompareInfo {
+    pub repo: String,
+    pub base: String,
+    pub head: String,
+    pub status: String,
+    pub ahead_by: u32,
+    pub behind_by: u32,
+    pub total_commits: u32,
+    pub commits: Vec<String>, // 提交信息列表
+}
+
+// 仓库的里程碑（GitHub milestones API 返回的数据，也直接用于 API 响应）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MilestoneInfo {
+    pub title: String,
+    pub description: Option<String>,
+    pub state: String, // `open` 或 `closed`
+    #[serde(default)]
+    pub due_on: Option<DateTime<Utc>>, // 截止时间（RFC3339），未设置时为 null
+    pub open_issues: u32,
+    pub closed_issues: u32,
+    pub html_url: String,
+}
+
+// 最新 release 的校验和信息（用于 API 响应）
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ChecksumsInfo {
+    pub repo: String,
+    pub version: String,                     // 校验和对应的 release 版本
+    pub checksums_url: String,               // 校验和文件的下载链接
+    pub checksums: HashMap<String, String>, // 文件名 -> SHA-256
+}
+
+// 最新 release 中与模式匹配的附件（用于安装脚本获取直接下载地址）
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ResolvedAsset {
+    pub name: String,           // 附件文件名
+    pub url: String,            // GitHub 直接下载链接
+    pub size: Option<u64>,      // 附件大小（字节），无法获取时为 null
+    pub sha256: Option<String>, // 校验和文件中该附件的 SHA-256，没有校验和文件时为 null
+}
+
+// GitHub 自动生成的 release notes（generate-notes API 返回的数据，也直接用于 API 响应）
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct GeneratedNotes {
+    pub name: String, // 建议的 release 标题
+    pub body: String, // Markdown 格式的 release notes
+}
+
+// 生成 release notes 的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct GenerateNotesQuery {
+    #[serde(default)]
+    pub tag: String,                  // 要生成说明的 tag（可以尚未创建）
+    pub previous_tag: Option<String>, // 起始 tag，未指定时由 GitHub 自动选择上一个 release
+}
+
+// 附件解析的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct ResolveAssetQuery {
+    #[serde(default)]
+    pub asset: String, // 附件文件名的通配符模式（支持 `*` 和 `?`）
+}
+
+// 附件列表的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct AssetsQuery {
+    #[serde(default)]
+    pub pattern: String,         // 附件文件名的通配符模式（支持 `*` 和 `?`）
+    pub page: Option<usize>,     // 页码（从 1 开始，默认 1）
+    pub per_page: Option<usize>, // 每页的 release 数量（默认 30，最大 100）
+}
+
+// 单个 release 中与模式匹配的附件
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReleaseAssets {
+    pub tag_name: String,
+    pub published_at: Option<DateTime<Utc>>,
+    pub prerelease: bool,
+    pub assets: Vec<FlatAsset>,
+}
+
+// 所有 release 中与模式匹配的附件（按 tag 分组、分页，用于 API 响应）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AssetListResponse {
+    pub releases: Vec<ReleaseAssets>, // 当前页的 release（按 GitHub 返回顺序，只包含有匹配附件的 release）
+    pub page: usize,
+    pub per_page: usize,
+    pub total_releases: usize, // 有匹配附件的 release 总数（所有页）
+    pub total_assets: usize,   // 匹配的附件总数（所有页）
+}
+
+// 版本徽章的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct BadgeQuery {
+    pub color: Option<String>, // 颜色名称（如 `green`）或十六进制颜色（如 `4c1`），默认 `blue`
+}
+
+// 仓库文件内容的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct ContentsQuery {
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>, // 分支、tag 或 commit SHA，未指定时使用仓库的默认分支
+}
+
+// 里程碑列表的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct MilestoneQuery {
+    pub state: Option<String>, // `open`（默认）、`closed` 或 `all`
+}
+
+impl MilestoneQuery {
+    /// 请求的里程碑状态，无法识别的状态返回 Err
+    pub fn state(&self) -> Result<&str, String> {
+        match self.state.as_deref() {
+            None | Some("open") => Ok("open"),
+            Some(state @ ("closed" | "all")) => Ok(state),
+            Some(other) => Err(other.to_string()),
+        }
+    }
+}
+
+// release notes 的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct NotesQuery {
+    pub format: Option<String>, // `markdown`（默认）、`html` 或 `text`
+}
+
+// Release 监听（SSE）事件
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WatchEvent {
+    pub repo: String,
+    pub release: LatestReleaseInfo,
+}
+
+// 缓存中的仓库概览（用于缓存管理接口）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CachedRepoSummary {
+    pub owner: String,
+    pub repo: String,
+    pub has_repo_info: bool,
+    pub has_releases: bool,
+    pub has_latest_release: bool,
+    pub oldest_expires_at: u64, // 该仓库所有缓存条目中最早的过期时间（Unix 时间戳，秒）
+}
+
+// 缓存的下载文件（用于缓存管理接口）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CachedFileSummary {
+    pub url: String,
+    pub original_filename: String,
+    pub size: Option<u64>, // 写入缓存时的文件大小（字节）
+    pub content_type: Option<String>,
+    pub expires_at: u64,       // 过期时间（Unix 时间戳，秒），immutable 为 true 时忽略
+    pub last_accessed_at: u64, // 最后访问时间（Unix 时间戳，秒），清理时最久未访问的文件先被淘汰
+    pub immutable: bool,
+}
+
+// 缓存文件列表的排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachedFileSort {
+    Accessed, // 最近访问的在前
+    Size,     // 大的在前
+    Expires,  // 先过期的在前
+}
+
+// 单个数据类型的缓存命中统计
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CacheHitStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_ratio: Option<f64>, // 命中率（0~1），尚未查询过时为 null
+}
+
+// 缓存效果统计（自启动以来）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CacheStatsResponse {
+    pub caches: std::collections::BTreeMap<String, CacheHitStats>, // 按数据类型统计的缓存命中情况
+    pub coalesced_requests: u64, // 被请求合并（single-flight）合并、未单独请求 GitHub 的请求数
+    pub negative_cache_hits: u64, // 负缓存命中次数（近期已确认不存在，省去的 GitHub 请求数）
+    pub github_api_calls: u64,   // 实际发往 GitHub API 的请求数（包括 202 重试）
+    pub cache_file_corruptions: u64, // 无法完整解析、已被移到一旁的缓存文件数
+}
+
+// 缓存仓库列表的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct CacheReposQuery {
+    pub expiring_within_secs: Option<u64>, // 只返回在该秒数内过期的仓库
+}
+
+// 缓存文件列表的查询参数
+#[derive(Debug, Default, Deserialize)]
+pub struct CacheFilesQuery {
+    pub sort: Option<String>, // `accessed`（默认）、`size` 或 `expires`
+}
+
+impl CacheFilesQuery {
+    /// 请求的排序方式，无法识别的值返回 Err
+    pub fn sort(&self) -> Result<CachedFileSort, String> {
+        match self.sort.as_deref() {
+            None | Some("accessed") => Ok(CachedFileSort::Accessed),
+            Some("size") => Ok(CachedFileSort::Size),
+            Some("expires") => Ok(CachedFileSort::Expires),
+            Some(other) => Err(other.to_string()),
+        }
+    }
+}
+
+// 下载队列统计
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DownloadQueueStats {
+    pub max_concurrent_downloads: usize,
+    pub available_permits: usize, // 当前空闲的下载许可数
+    pub waiting: usize,           // 正在等待下载许可的请求数
+    pub rejected_total: u64,      // 因排队超时被拒绝的请求总数
+    pub wait_samples: usize,      // 参与统计的最近样本数
+    pub avg_wait_ms: f64,         // 最近样本的平均等待时间（毫秒）
+    pub p95_wait_ms: f64,         // 最近样本的 P95 等待时间（毫秒）
+}
+
+// 服务运行统计
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StatsResponse {
+    pub downloads: DownloadQueueStats,
+    pub github_tokens_remaining: Option<u64>, // 发往 GitHub API 的剩余请求额度（令牌桶），未启用限制时为 null
 }
 
 // 批量请求的数据结构
@@ -90,13 +613,25 @@ pub struct BatchRequest {
     pub fields: Vec<String>, // 可选字段: "repo_info", "releases", "latest_release"，默认全部
 }
 
+// 批量查询中单个字段的获取结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldStatus {
+    Ok,
+    Error,
+}
+
 // 单个仓库的批量响应结果
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RepoBatchResult {
     pub repo: String,
-    pub success: bool,
+    pub success: bool, // 所有请求的字段是否都获取成功
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    // 每个请求的字段各自的获取结果（按 repo_info、releases、latest_release 的顺序），仓库格式错误时为空
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    #[schema(value_type = HashMap<String, FieldStatus>)]
+    pub field_status: IndexMap<String, FieldStatus>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub repo_info: Option<RepoInfo>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -111,18 +646,76 @@ pub struct BatchResponse {
     pub results: Vec<RepoBatchResult>,
 }
 
-// 批量响应数据结构（Map 格式，方便客户端按 repo 查找）
+// 批量响应数据结构（Map 格式，方便客户端按 repo 查找；key 按请求中的仓库顺序输出）
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BatchResponseMap {
     #[serde(rename = "results_map")]
-    pub results_map: std::collections::HashMap<String, RepoBatchResult>,
+    pub results_map: IndexMap<String, RepoBatchResult>,
+}
+
+impl From<Vec<RepoBatchResult>> for BatchResponseMap {
+    // 按结果顺序（即请求顺序）构建，重复的仓库保留第一次出现的位置
+    fn from(results: Vec<RepoBatchResult>) -> Self {
+        Self {
+            results_map: results
+                .into_iter()
+                .map(|result| (result.repo.clone(), result))
+                .collect(),
+        }
+    }
+}
+
+// POST /repos/batch/latest 的请求体
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct BatchLatestRequest {
+    pub repos: Vec<String>, // 格式: "owner/repo"
+}
+
+// POST /repos/batch/latest 中单个仓库的结果：最新版本号，或获取失败时的错误信息
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+#[serde(untagged)]
+pub enum LatestVersionResult {
+    Version {
+        version: String,
+        published_at: Option<DateTime<Utc>>, // RFC3339 格式
+    },
+    Error {
+        error: String,
+    },
 }
 
+// POST /repos/batch/latest 的响应：仓库名 -> 最新版本（没有 release 时为 null），key 按请求中的仓库顺序输出
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(transparent)]
+#[schema(value_type = HashMap<String, LatestVersionResult>)]
+pub struct BatchLatestResponse(pub IndexMap<String, Option<LatestVersionResult>>);
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json;
 
+    #[test]
+    fn test_batch_response_map_preserves_request_order() {
+        let result = |repo: &str| RepoBatchResult {
+            repo: repo.to_string(),
+            success: true,
+            error: None,
+            field_status: IndexMap::new(),
+            repo_info: None,
+            releases: None,
+            latest_release: None,
+        };
+        let repos = ["zeta/z", "alpha/a", "mid/m"];
+        let map = BatchResponseMap::from(repos.iter().map(|r| result(r)).collect::<Vec<_>>());
+
+        let keys: Vec<_> = map.results_map.keys().map(String::as_str).collect();
+        assert_eq!(keys, repos);
+        let json = serde_json::to_string(&map).unwrap();
+        let positions: Vec<_> = repos.iter().map(|r| json.find(r).unwrap()).collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+
     #[test]
     fn test_github_repo_deserialize() {
         let json = r#"{
@@ -161,9 +754,209 @@ mod tests {
         let release: GithubRelease = serde_json::from_str(json).unwrap();
         assert_eq!(release.tag_name, "v1.0.0");
         assert_eq!(release.name, Some("Release 1.0.0".to_string()));
-        assert_eq!(release.prerelease, false);
+        assert!(!release.prerelease);
         assert_eq!(release.assets.len(), 1);
         assert_eq!(release.assets[0].name, "file.zip");
+        assert!(release.author.is_none());
+        assert!(release.reactions.is_none());
+    }
+
+    #[test]
+    fn test_github_release_deserialize_with_author_and_reactions() {
+        let json = r#"{
+            "tag_name": "v1.0.0",
+            "name": null,
+            "body": null,
+            "published_at": "2024-01-01T00:00:00Z",
+            "prerelease": false,
+            "assets": [],
+            "author": {
+                "login": "octocat",
+                "avatar_url": "https://avatars.githubusercontent.com/u/583231"
+            },
+            "reactions": {
+                "url": "https://api.github.com/repos/o/r/releases/1/reactions",
+                "total_count": 3,
+                "+1": 2,
+                "heart": 1
+            }
+        }"#;
+
+        let release: GithubRelease = serde_json::from_str(json).unwrap();
+        assert_eq!(release.author.unwrap().login, "octocat");
+        assert_eq!(release.reactions.unwrap()["+1"], 2);
+    }
+
+    #[test]
+    fn test_include_source_archives() {
+        let json = r#"{
+            "tag_name": "v1.0.0",
+            "name": null,
+            "body": null,
+            "published_at": "2024-01-01T00:00:00Z",
+            "prerelease": false,
+            "assets": [
+                {
+                    "name": "file.zip",
+                    "browser_download_url": "https://example.com/file.zip"
+                }
+            ],
+            "zipball_url": "https://api.github.com/repos/o/r/zipball/v1.0.0",
+            "tarball_url": "https://api.github.com/repos/o/r/tarball/v1.0.0"
+        }"#;
+        let release: GithubRelease = serde_json::from_str(json).unwrap();
+        let release = crate::handlers::to_release_info(release, false);
+        // 默认不在 attachments 中，也不出现在响应中
+        let mut default = release.clone();
+        default.apply_include_source(false);
+        assert_eq!(default.attachments, vec!["https://example.com/file.zip"]);
+        let json = serde_json::to_value(&default).unwrap();
+        assert!(json.get("zipball_url").is_none());
+        assert!(json.get("tarball_url").is_none());
+
+        let mut release = release;
+        release.apply_include_source(true);
+        assert_eq!(
+            release.attachments,
+            vec![
+                "https://example.com/file.zip",
+                "https://api.github.com/repos/o/r/zipball/v1.0.0",
+                "https://api.github.com/repos/o/r/tarball/v1.0.0",
+            ]
+        );
+
+        // 旧缓存条目没有源码压缩包链接，不追加任何内容
+        let mut legacy: ReleaseInfo = serde_json::from_str(
+            r#"{"tag_name": "v1.0.0", "name": null, "changelog": null, "prerelease": false, "attachments": []}"#,
+        )
+        .unwrap();
+        legacy.apply_include_source(true);
+        assert!(legacy.attachments.is_empty());
+    }
+
+    #[test]
+    fn test_release_info_deserialize_legacy_entry() {
+        // 旧版本缓存中的条目没有作者和反应字段
+        let json = r#"{
+            "tag_name": "v1.0.0",
+            "name": null,
+            "changelog": null,
+            "published_at": "2024-01-01T00:00:00Z",
+            "prerelease": false,
+            "attachments": []
+        }"#;
+
+        let release: ReleaseInfo = serde_json::from_str(json).unwrap();
+        assert!(release.author_login.is_none());
+        assert!(release.reactions.is_none());
+
+        let json = serde_json::to_string(&release).unwrap();
+        assert!(!json.contains("reactions"));
+        assert!(!json.contains("author_login"));
+    }
+
+    #[test]
+    fn test_github_release_deserialize_timestamps() {
+        let json = r#"{
+            "tag_name": "v1.0.0",
+            "name": null,
+            "body": null,
+            "published_at": "2024-01-01T08:00:00+08:00",
+            "prerelease": false,
+            "assets": []
+        }"#;
+        let release: GithubRelease = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            release.published_at,
+            Some("2024-01-01T00:00:00Z".parse().unwrap())
+        );
+
+        // 草稿 release 的 published_at 为 null，格式错误的时间戳也不会导致解析失败
+        for published_at in ["null", r#""not-a-date""#] {
+            let json = format!(
+                r#"{{"tag_name": "v1.0.0", "name": null, "body": null, "published_at": {}, "prerelease": false, "assets": []}}"#,
+                published_at
+            );
+            let release: GithubRelease = serde_json::from_str(&json).unwrap();
+            assert!(release.published_at.is_none());
+        }
+    }
+
+    #[test]
+    fn test_release_info_serializes_rfc3339() {
+        let json = r#"{
+            "tag_name": "v1.0.0",
+            "name": null,
+            "changelog": null,
+            "published_at": "2024-01-01T00:00:00Z",
+            "prerelease": false,
+            "attachments": []
+        }"#;
+        let release: ReleaseInfo = serde_json::from_str(json).unwrap();
+        let json = serde_json::to_string(&release).unwrap();
+        assert!(json.contains(r#""published_at":"2024-01-01T00:00:00Z""#));
+    }
+
+    #[test]
+    fn test_github_compare_deserialize() {
+        let json = r#"{
+            "status": "ahead",
+            "ahead_by": 2,
+            "behind_by": 0,
+            "total_commits": 2,
+            "commits": [
+                { "sha": "abc", "commit": { "message": "feat: first" } },
+                { "sha": "def", "commit": { "message": "fix: second" } }
+            ]
+        }"#;
+
+        let compare: GithubCompare = serde_json::from_str(json).unwrap();
+        assert_eq!(compare.status, "ahead");
+        assert_eq!(compare.ahead_by, 2);
+        assert_eq!(compare.total_commits, 2);
+        assert_eq!(compare.commits[1].commit.message, "fix: second");
+    }
+
+    #[test]
+    fn test_batch_latest_response_serialize() {
+        let response = BatchLatestResponse(IndexMap::from([
+            (
+                "owner/app".to_string(),
+                Some(LatestVersionResult::Version {
+                    version: "v1.2.0".to_string(),
+                    published_at: Some("2024-01-01T00:00:00Z".parse().unwrap()),
+                }),
+            ),
+            ("owner/empty".to_string(), None),
+            (
+                "invalid".to_string(),
+                Some(LatestVersionResult::Error {
+                    error: "仓库格式错误".to_string(),
+                }),
+            ),
+        ]));
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "owner/app": { "version": "v1.2.0", "published_at": "2024-01-01T00:00:00Z" },
+                "owner/empty": null,
+                "invalid": { "error": "仓库格式错误" }
+            })
+        );
+        let parsed: BatchLatestResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.0["owner/app"], response.0["owner/app"]);
+    }
+
+    #[test]
+    fn test_moved_to() {
+        assert_eq!(moved_to("owner", "test", "owner/test"), None);
+        // 只有大小写不同时不视为改名
+        assert_eq!(moved_to("Owner", "TEST", "owner/test"), None);
+        assert_eq!(
+            moved_to("owner", "old", "new-owner/new"),
+            Some("new-owner/new".to_string())
+        );
     }
 
     #[test]
@@ -176,7 +969,9 @@ mod tests {
             description: Some("Test repo".to_string()),
             stargazers_count: 100,
             forks_count: 50,
-            updated_at: "2024-01-01T00:00:00Z".to_string(),
+            updated_at: "2024-01-01T00:00:00Z".parse().unwrap(),
+            private: false,
+            moved_to: None,
         };
 
         let json = serde_json::to_string(&repo_info).unwrap();
@@ -213,6 +1008,7 @@ mod tests {
             repo: "owner/test".to_string(),
             success: true,
             error: None,
+            field_status: IndexMap::new(),
             repo_info: Some(RepoInfo {
                 repo: "owner/test".to_string(),
                 name: "test".to_string(),
@@ -221,7 +1017,9 @@ mod tests {
                 description: None,
                 stargazers_count: 0,
                 forks_count: 0,
-                updated_at: "2024-01-01T00:00:00Z".to_string(),
+                updated_at: "2024-01-01T00:00:00Z".parse().unwrap(),
+                private: false,
+                moved_to: None,
             }),
             releases: None,
             latest_release: None,
@@ -239,6 +1037,7 @@ mod tests {
             repo: "owner/test".to_string(),
             success: false,
             error: Some("Not found".to_string()),
+            field_status: IndexMap::new(),
             repo_info: None,
             releases: None,
             latest_release: None,
@@ -248,5 +1047,106 @@ mod tests {
         assert!(json.contains("error"));
         assert!(json.contains("Not found"));
     }
-}
 
+    #[test]
+    fn test_flat_release_info_serialization() {
+        let release = ReleaseInfo {
+            tag_name: "v1.0.0".to_string(),
+            name: Some("Release 1.0.0".to_string()),
+            changelog: Some("变".repeat(CHANGELOG_PREVIEW_CHARS + 10)),
+            changelog_truncated: false,
+            published_at: Some("2024-01-01T00:00:00Z".parse().unwrap()),
+            prerelease: false,
+            draft: false,
+            attachments: vec![
+                "https://github.com/o/r/releases/download/v1.0.0/app.zip".to_string(),
+                "https://github.com/o/r/releases/download/v1.0.0/app.tar.gz".to_string(),
+            ],
+            attachment_sizes: vec![1024],
+            author_login: Some("octocat".to_string()),
+            author_avatar_url: Some("https://avatars.example/octocat".to_string()),
+            reactions: None,
+            zipball_url: None,
+            tarball_url: None,
+        };
+
+        let json = serde_json::to_value(FlatReleaseInfo::from(release)).unwrap();
+        let preview = format!("{}…", "变".repeat(CHANGELOG_PREVIEW_CHARS));
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "tag_name": "v1.0.0",
+                "name": "Release 1.0.0",
+                "changelog_preview": preview,
+                "published_at": "2024-01-01T00:00:00Z",
+                "prerelease": false,
+                "assets": [
+                    {
+                        "name": "app.zip",
+                        "url": "https://github.com/o/r/releases/download/v1.0.0/app.zip",
+                        "size": 1024
+                    },
+                    {
+                        "name": "app.tar.gz",
+                        "url": "https://github.com/o/r/releases/download/v1.0.0/app.tar.gz",
+                        "size": null
+                    }
+                ],
+                "author_login": "octocat"
+            })
+        );
+    }
+
+    #[test]
+    fn test_changelog_preview_short() {
+        assert_eq!(changelog_preview("  short notes \n"), "short notes");
+    }
+
+    #[test]
+    fn test_schema_query() {
+        let query = |schema: Option<&str>| SchemaQuery { schema: schema.map(str::to_string) };
+        assert_eq!(query(None).is_flat(), Ok(false));
+        assert_eq!(query(Some("default")).is_flat(), Ok(false));
+        assert_eq!(query(Some("flat")).is_flat(), Ok(true));
+        assert_eq!(query(Some("nested")).is_flat(), Err("nested".to_string()));
+    }
+
+    #[test]
+    fn test_cache_files_query() {
+        let query = |sort: Option<&str>| CacheFilesQuery { sort: sort.map(str::to_string) };
+        assert_eq!(query(None).sort(), Ok(CachedFileSort::Accessed));
+        assert_eq!(query(Some("size")).sort(), Ok(CachedFileSort::Size));
+        assert_eq!(query(Some("expires")).sort(), Ok(CachedFileSort::Expires));
+        assert_eq!(query(Some("name")).sort(), Err("name".to_string()));
+    }
+
+    #[test]
+    fn test_milestone_query() {
+        let query = |state: Option<&str>| MilestoneQuery { state: state.map(str::to_string) };
+        assert_eq!(query(None).state(), Ok("open"));
+        assert_eq!(query(Some("closed")).state(), Ok("closed"));
+        assert_eq!(query(Some("all")).state(), Ok("all"));
+        assert_eq!(query(Some("merged")).state(), Err("merged".to_string()));
+    }
+
+    #[test]
+    fn test_milestone_deserialize() {
+        let json = r#"[{
+            "number": 1,
+            "title": "v1.0",
+            "description": null,
+            "state": "open",
+            "due_on": "2024-06-30T07:00:00Z",
+            "open_issues": 3,
+            "closed_issues": 5,
+            "html_url": "https://github.com/owner/repo/milestone/1"
+        }]"#;
+
+        let milestones: Vec<MilestoneInfo> = serde_json::from_str(json).unwrap();
+        assert_eq!(milestones[0].title, "v1.0");
+        assert_eq!(milestones[0].description, None);
+        assert_eq!(milestones[0].due_on.unwrap().to_rfc3339(), "2024-06-30T07:00:00+00:00");
+        assert_eq!(milestones[0].closed_issues, 5);
+        assert!(serde_json::from_str::<Vec<MilestoneInfo>>("[]").unwrap().is_empty());
+    }
+}
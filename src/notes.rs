@@ -0,0 +1,119 @@
+use pulldown_cmark::{html, Event, Options, Parser, TagEnd};
+
+/// Release notes 的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotesFormat {
+    /// 原始 Markdown（`text/markdown`）
+    Markdown,
+    /// 渲染后的 HTML 片段（`text/html`）
+    Html,
+    /// 去掉 Markdown 标记的纯文本（`text/plain`）
+    Text,
+}
+
+impl NotesFormat {
+    /// 解析 `format` 查询参数，未指定时为 Markdown，无法识别时返回 Err
+    pub fn parse(format: Option<&str>) -> Result<Self, String> {
+        match format {
+            None | Some("markdown") | Some("md") => Ok(Self::Markdown),
+            Some("html") => Ok(Self::Html),
+            Some("text") => Ok(Self::Text),
+            Some(other) => Err(other.to_string()),
+        }
+    }
+
+    /// 响应的 Content-Type
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Html => "text/html; charset=utf-8",
+            Self::Text => "text/plain; charset=utf-8",
+        }
+    }
+
+    /// 将 Markdown 格式的 release notes 转换为当前格式
+    pub fn render(&self, markdown: &str) -> String {
+        match self {
+            Self::Markdown => markdown.to_string(),
+            Self::Html => markdown_to_html(markdown),
+            Self::Text => markdown_to_text(markdown),
+        }
+    }
+}
+
+// GitHub 风格 Markdown 使用的扩展语法
+fn parser_options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+// 渲染为 HTML；release notes 由仓库作者编写，其中的原始 HTML 会被转义而不是原样输出，避免注入脚本
+fn markdown_to_html(markdown: &str) -> String {
+    let events = Parser::new_ext(markdown, parser_options()).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        other => other,
+    });
+    let mut output = String::new();
+    html::push_html(&mut output, events);
+    output
+}
+
+// 提取文本事件生成纯文本，块级元素之间换行
+fn markdown_to_text(markdown: &str) -> String {
+    let mut text = String::new();
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push('\n'),
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::CodeBlock
+                | TagEnd::TableHead
+                | TagEnd::TableRow,
+            ) if !text.is_empty() && !text.ends_with('\n') => text.push('\n'),
+            Event::End(TagEnd::TableCell) => text.push('\t'),
+            _ => {}
+        }
+    }
+    text.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTES: &str = "## What's Changed\n\n* Fix **crash** on startup by @octocat\n* Add `--verbose` flag\n\nSee [changelog](https://example.com).";
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(NotesFormat::parse(None), Ok(NotesFormat::Markdown));
+        assert_eq!(NotesFormat::parse(Some("html")), Ok(NotesFormat::Html));
+        assert_eq!(NotesFormat::parse(Some("text")), Ok(NotesFormat::Text));
+        assert_eq!(NotesFormat::parse(Some("pdf")), Err("pdf".to_string()));
+    }
+
+    #[test]
+    fn test_render_text_strips_markdown() {
+        assert_eq!(
+            NotesFormat::Text.render(NOTES),
+            "What's Changed\nFix crash on startup by @octocat\nAdd --verbose flag\nSee changelog."
+        );
+    }
+
+    #[test]
+    fn test_render_html() {
+        let html = NotesFormat::Html.render(NOTES);
+        assert!(html.contains("<h2>What's Changed</h2>"));
+        assert!(html.contains("<strong>crash</strong>"));
+        assert!(html.contains("<code>--verbose</code>"));
+    }
+
+    #[test]
+    fn test_render_html_escapes_raw_html() {
+        let html = NotesFormat::Html.render("Hello <script>alert(1)</script>\n\n<img src=x onerror=alert(1)>");
+        assert!(!html.contains("<script>"));
+        assert!(!html.contains("<img"));
+        assert!(html.contains("&lt;script&gt;"));
+    }
+}
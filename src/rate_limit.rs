@@ -1,17 +1,47 @@
-use std::sync::Arc;
-use tokio::sync::Semaphore;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Extensions, ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpRequest, ResponseError};
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 /// 限流配置
 #[derive(Clone, Debug)]
 pub struct RateLimitConfig {
     /// 最大并发下载数
     pub max_concurrent_downloads: usize,
+    /// 等待下载许可的最长时间（秒），超过后返回 503，0 表示一直等待
+    pub max_queue_wait_secs: u64,
+    /// 限流时间窗口大小（秒）
+    pub window_secs: u64,
+    /// 每个 IP 在时间窗口内对 API（JSON）端点的最大请求数，0 表示不限制
+    pub api_max_requests_per_window: usize,
+    /// 每个 IP 在时间窗口内对下载端点的最大请求数，0 表示不限制
+    pub download_max_requests_per_window: usize,
+    /// 每个 IP 在时间窗口内强制刷新（`?refresh=true`）请求的最大次数，0 表示不限制
+    pub refresh_max_requests_per_window: usize,
+    /// 全局同时处理的批量查询（`/repos/batch`、`/repos/batch/map`）数，达到上限时直接返回 503，0 表示不限制
+    pub max_concurrent_batches: usize,
+    /// 不受请求频率限制的客户端网段（如内部监控、自己的前端）
+    pub exempt_cidrs: Vec<IpCidr>,
 }
 
 impl Default for RateLimitConfig {
     fn default() -> Self {
         Self {
             max_concurrent_downloads: 10,
+            max_queue_wait_secs: 0,
+            window_secs: 60,
+            api_max_requests_per_window: 600,
+            download_max_requests_per_window: 100,
+            refresh_max_requests_per_window: 10,
+            max_concurrent_batches: 4,
+            exempt_cidrs: Vec::new(),
         }
     }
 }
@@ -19,41 +49,307 @@ impl Default for RateLimitConfig {
 impl RateLimitConfig {
     /// 从环境变量加载配置
     pub fn from_env() -> Self {
+        let default = Self::default();
+
         let max_concurrent = std::env::var("MAX_CONCURRENT_DOWNLOADS")
             .ok()
             .and_then(|v| v.parse().ok())
-            .unwrap_or(10);
+            .unwrap_or(default.max_concurrent_downloads);
+
+        let max_queue_wait_secs = std::env::var("DOWNLOAD_MAX_QUEUE_WAIT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.max_queue_wait_secs);
+
+        let window_secs = std::env::var("RATE_LIMIT_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(default.window_secs);
+
+        let api_max_requests_per_window = std::env::var("API_MAX_REQUESTS_PER_WINDOW")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.api_max_requests_per_window);
+
+        let download_max_requests_per_window = std::env::var("DOWNLOAD_MAX_REQUESTS_PER_WINDOW")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.download_max_requests_per_window);
+
+        let refresh_max_requests_per_window = std::env::var("REFRESH_MAX_REQUESTS_PER_WINDOW")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.refresh_max_requests_per_window);
+
+        let max_concurrent_batches = std::env::var("MAX_CONCURRENT_BATCHES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.max_concurrent_batches);
+
+        let exempt_cidrs = std::env::var("RATE_LIMIT_EXEMPT_CIDRS")
+            .map(|cidrs| parse_cidr_list("RATE_LIMIT_EXEMPT_CIDRS", &cidrs))
+            .unwrap_or(default.exempt_cidrs);
 
         Self {
             max_concurrent_downloads: max_concurrent,
+            max_queue_wait_secs,
+            window_secs,
+            api_max_requests_per_window,
+            download_max_requests_per_window,
+            refresh_max_requests_per_window,
+            max_concurrent_batches,
+            exempt_cidrs,
         }
     }
+
+    /// 获取指定端点类别在时间窗口内的最大请求数
+    pub fn max_requests_per_window(&self, class: EndpointClass) -> usize {
+        match class {
+            EndpointClass::Api => self.api_max_requests_per_window,
+            EndpointClass::Download => self.download_max_requests_per_window,
+            EndpointClass::Refresh => self.refresh_max_requests_per_window,
+        }
+    }
+
+    /// 客户端是否不受请求频率限制（无法解析为 IP 的标识，如 `unknown`、`conn-<id>`，始终不豁免）
+    pub fn is_exempt(&self, client_ip: &str) -> bool {
+        client_ip
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| self.exempt_cidrs.iter().any(|cidr| cidr.contains(ip)))
+    }
+}
+
+/// 端点类别（不同类别使用独立的请求频率限制）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EndpointClass {
+    /// 仓库、release 等 JSON 端点
+    Api,
+    /// 文件下载端点
+    Download,
+    /// 跳过缓存的强制刷新请求（在 API 限流之外额外限流）
+    Refresh,
+}
+
+impl EndpointClass {
+    /// 根据请求路径判断端点类别，不需要限流的路径（健康检查、文档等）返回 None
+    pub fn from_path(path: &str) -> Option<Self> {
+        if is_download_path(path) {
+            Some(EndpointClass::Download)
+        } else if path.starts_with("/repos") {
+            Some(EndpointClass::Api)
+        } else {
+            None
+        }
+    }
+}
+
+/// 判断请求是否为强制刷新请求（查询参数 `refresh=true`）
+pub fn is_refresh_request(query_string: &str) -> bool {
+    web::Query::<RefreshQuery>::from_query(query_string)
+        .map(|q| q.refresh)
+        .unwrap_or(false)
+}
+
+// 超过该数量的记录时清理不再活跃的 IP
+const REQUEST_RECORDS_CLEANUP_THRESHOLD: usize = 10_000;
+
+// 批量查询达到并发上限时建议客户端重试的等待时间（秒）
+const BATCH_RETRY_AFTER_SECS: u64 = 1;
+
+// 用于计算等待时间统计的最近样本数
+const WAIT_SAMPLES_CAPACITY: usize = 1_000;
+
+// 等待下载许可的耗时统计（保留最近的样本）
+#[derive(Default)]
+struct WaitTimeStats {
+    samples: VecDeque<Duration>,
+}
+
+impl WaitTimeStats {
+    fn record(&mut self, wait: Duration) {
+        if self.samples.len() >= WAIT_SAMPLES_CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(wait);
+    }
+
+    // 平均等待时间
+    fn average(&self) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+
+    // 第 95 百分位等待时间
+    fn p95(&self) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+        let mut sorted: Vec<_> = self.samples.iter().copied().collect();
+        sorted.sort();
+        let index = (sorted.len() * 95).div_ceil(100).saturating_sub(1);
+        sorted[index]
+    }
+}
+
+// 排队等待下载许可的计数：创建时加一，被丢弃时减一。
+// 客户端断开时等待的 future 被直接丢弃，计数仍能正确减少
+struct WaitingGuard<'a>(&'a AtomicUsize);
+
+impl<'a> WaitingGuard<'a> {
+    fn new(waiting: &'a AtomicUsize) -> Self {
+        waiting.fetch_add(1, Ordering::SeqCst);
+        Self(waiting)
+    }
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 /// 限流管理器
 pub struct RateLimitManager {
-    #[allow(dead_code)]
     config: RateLimitConfig,
     /// 并发下载信号量
     semaphore: Arc<Semaphore>,
+    /// 请求记录（端点类别 + IP -> 时间窗口内的请求时间）
+    request_records: Mutex<HashMap<(EndpointClass, String), VecDeque<Instant>>>,
+    /// 等待下载许可的耗时统计
+    wait_stats: Mutex<WaitTimeStats>,
+    /// 正在等待下载许可的请求数
+    waiting: AtomicUsize,
+    /// 因等待超时被拒绝的下载请求总数
+    rejected_total: AtomicU64,
+    /// 批量查询并发信号量，不限制时为 None
+    batch_semaphore: Option<Arc<Semaphore>>,
 }
 
 impl RateLimitManager {
     pub fn new(config: RateLimitConfig) -> Self {
         let semaphore = Arc::new(Semaphore::new(config.max_concurrent_downloads));
+        let batch_semaphore = (config.max_concurrent_batches > 0)
+            .then(|| Arc::new(Semaphore::new(config.max_concurrent_batches)));
         Self {
             config,
             semaphore,
+            request_records: Mutex::new(HashMap::new()),
+            wait_stats: Mutex::new(WaitTimeStats::default()),
+            waiting: AtomicUsize::new(0),
+            rejected_total: AtomicU64::new(0),
+            batch_semaphore,
         }
     }
 
+    /// 检查指定 IP 对某类端点的请求频率，未超过限制时记录本次请求
+    pub fn check_rate_limit(&self, class: EndpointClass, client_ip: &str) -> Result<(), RateLimitError> {
+        let max_requests = self.config.max_requests_per_window(class);
+        if max_requests == 0 || self.config.is_exempt(client_ip) {
+            return Ok(());
+        }
+
+        let window = Duration::from_secs(self.config.window_secs);
+        let now = Instant::now();
+        let mut records = self.request_records.lock().unwrap();
+
+        // 记录过多时清理时间窗口外已无请求的 IP，避免内存无限增长
+        if records.len() > REQUEST_RECORDS_CLEANUP_THRESHOLD {
+            records.retain(|_, times| {
+                times
+                    .back()
+                    .map(|last| now.duration_since(*last) < window)
+                    .unwrap_or(false)
+            });
+        }
+
+        let times = records
+            .entry((class, client_ip.to_string()))
+            .or_default();
+        while let Some(first) = times.front() {
+            if now.duration_since(*first) >= window {
+                times.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if times.len() >= max_requests {
+            return Err(RateLimitError::TooManyRequests {
+                max_requests,
+                window_secs: self.config.window_secs,
+            });
+        }
+
+        times.push_back(now);
+        Ok(())
+    }
+
     /// 获取并发下载许可（这会在下载完成后自动释放）
-    pub async fn acquire_download_permit(&self) -> tokio::sync::OwnedSemaphorePermit {
-        self.semaphore
+    ///
+    /// 有空闲许可时立即返回；否则排队等待，配置了最长等待时间且超时时返回 `QueueTimeout`。
+    /// 每次获取的等待耗时都会计入统计。
+    pub async fn acquire_download_permit(&self) -> Result<OwnedSemaphorePermit, RateLimitError> {
+        if let Ok(permit) = self.semaphore.clone().try_acquire_owned() {
+            self.wait_stats.lock().unwrap().record(Duration::ZERO);
+            return Ok(permit);
+        }
+
+        let started = Instant::now();
+        let waiting = WaitingGuard::new(&self.waiting);
+        let acquire = self.semaphore.clone().acquire_owned();
+        let result = if self.config.max_queue_wait_secs == 0 {
+            Ok(acquire.await)
+        } else {
+            tokio::time::timeout(Duration::from_secs(self.config.max_queue_wait_secs), acquire).await
+        };
+        drop(waiting);
+
+        match result {
+            Ok(permit) => {
+                self.wait_stats.lock().unwrap().record(started.elapsed());
+                Ok(permit.expect("Semaphore 不应该被关闭"))
+            }
+            Err(_) => {
+                self.rejected_total.fetch_add(1, Ordering::SeqCst);
+                // 建议客户端在平均等待时间后重试（至少 1 秒）
+                let average = self.wait_stats.lock().unwrap().average();
+                Err(RateLimitError::QueueTimeout {
+                    retry_after_secs: average.as_secs_f64().ceil().max(1.0) as u64,
+                })
+            }
+        }
+    }
+
+    /// 获取批量查询许可（处理完成后释放），达到并发上限时不排队，直接返回 `TooManyBatches`；
+    /// 不限制时返回 None
+    pub fn try_acquire_batch_permit(&self) -> Result<Option<OwnedSemaphorePermit>, RateLimitError> {
+        let Some(semaphore) = &self.batch_semaphore else {
+            return Ok(None);
+        };
+        semaphore
             .clone()
-            .acquire_owned()
-            .await
-            .expect("Semaphore 不应该被关闭")
+            .try_acquire_owned()
+            .map(Some)
+            .map_err(|_| RateLimitError::TooManyBatches {
+                max_concurrent_batches: self.config.max_concurrent_batches,
+            })
+    }
+
+    /// 下载队列统计（并发许可使用情况和等待耗时）
+    pub fn download_queue_stats(&self) -> DownloadQueueStats {
+        let wait_stats = self.wait_stats.lock().unwrap();
+        DownloadQueueStats {
+            max_concurrent_downloads: self.config.max_concurrent_downloads,
+            available_permits: self.semaphore.available_permits(),
+            waiting: self.waiting.load(Ordering::SeqCst),
+            rejected_total: self.rejected_total.load(Ordering::SeqCst),
+            wait_samples: wait_stats.samples.len(),
+            avg_wait_ms: wait_stats.average().as_secs_f64() * 1000.0,
+            p95_wait_ms: wait_stats.p95().as_secs_f64() * 1000.0,
+        }
     }
 
     /// 获取当前配置的最大并发数（用于测试）
@@ -68,10 +364,18 @@ impl RateLimitManager {
 pub enum RateLimitError {
     #[error("并发下载数已达上限")]
     TooManyConcurrent,
+    #[error("请求过于频繁：在 {window_secs} 秒内最多允许 {max_requests} 次请求")]
+    TooManyRequests { max_requests: usize, window_secs: u64 },
+    #[error("下载排队超时，请稍后再试")]
+    QueueTimeout { retry_after_secs: u64 },
+    #[error("同时处理的批量查询已达上限（{max_concurrent_batches} 个），请稍后再试")]
+    TooManyBatches { max_concurrent_batches: usize },
 }
 
 // 需要导入 AppError
 use crate::error::AppError;
+use crate::download::is_download_path;
+use crate::models::{DownloadQueueStats, RefreshQuery};
 
 impl From<RateLimitError> for AppError {
     fn from(err: RateLimitError) -> Self {
@@ -79,6 +383,15 @@ impl From<RateLimitError> for AppError {
             RateLimitError::TooManyConcurrent => {
                 AppError::BadRequest("并发下载数已达上限，请稍后再试".to_string())
             }
+            RateLimitError::TooManyRequests { .. } => AppError::BadRequest(err.to_string()),
+            RateLimitError::QueueTimeout { retry_after_secs } => AppError::ServiceUnavailable {
+                message: err.to_string(),
+                retry_after_secs,
+            },
+            RateLimitError::TooManyBatches { .. } => AppError::ServiceUnavailable {
+                message: err.to_string(),
+                retry_after_secs: BATCH_RETRY_AFTER_SECS,
+            },
         }
     }
 }
@@ -97,17 +410,719 @@ pub async fn get_rate_limit_manager() -> &'static Arc<RateLimitManager> {
         .await
 }
 
+/// GitHub 出站请求的令牌桶
+///
+/// 所有客户端共享同一个 GitHub Token 的额度，因此按服务整体（而不是按客户端 IP）限制发往 GitHub API 的请求数。
+/// 桶容量为每小时的请求上限，令牌按该速率匀速补充。
+pub struct GithubTokenBucket {
+    /// 桶容量（每小时最多请求数），0 表示不限制
+    capacity: u32,
+    state: Mutex<TokenBucketState>,
+}
+
+struct TokenBucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl GithubTokenBucket {
+    pub fn new(max_requests_per_hour: u32) -> Self {
+        Self {
+            capacity: max_requests_per_hour,
+            state: Mutex::new(TokenBucketState {
+                tokens: max_requests_per_hour as f64,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// 是否启用了限制
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    // 按经过的时间补充令牌
+    fn refill(&self, state: &mut TokenBucketState, now: Instant) {
+        let elapsed = now.saturating_duration_since(state.last_refill).as_secs_f64();
+        let refill_per_sec = self.capacity as f64 / 3600.0;
+        state.tokens = (state.tokens + elapsed * refill_per_sec).min(self.capacity as f64);
+        state.last_refill = now;
+    }
+
+    /// 尝试取出一个令牌，桶为空时返回下一个令牌补充到位前需要等待的时间
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state, Instant::now());
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            return Ok(());
+        }
+        let missing = 1.0 - state.tokens;
+        Err(Duration::from_secs_f64(missing * 3600.0 / self.capacity as f64))
+    }
+
+    /// 取出一个令牌；需要等待的时间不超过 max_wait 时等待补充，否则返回 503
+    pub async fn acquire(&self, max_wait: Duration) -> Result<(), AppError> {
+        let deadline = Instant::now() + max_wait;
+        loop {
+            let wait = match self.try_acquire() {
+                Ok(()) => return Ok(()),
+                Err(wait) => wait,
+            };
+            if Instant::now() + wait > deadline {
+                return Err(AppError::ServiceUnavailable {
+                    message: "GitHub API 请求额度已用尽，请稍后再试".to_string(),
+                    retry_after_secs: wait.as_secs_f64().ceil().max(1.0) as u64,
+                });
+            }
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// 当前剩余的令牌数，未启用限制时返回 None
+    pub fn remaining(&self) -> Option<u64> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let mut state = self.state.lock().unwrap();
+        self.refill(&mut state, Instant::now());
+        Some(state.tokens.floor() as u64)
+    }
+}
+
+/// 桶为空时最多等待令牌补充的时间，超过后直接返回 503
+pub const GITHUB_TOKEN_MAX_WAIT: Duration = Duration::from_secs(2);
+
+static GITHUB_TOKEN_BUCKET: OnceLock<GithubTokenBucket> = OnceLock::new();
+
+/// 获取全局 GitHub 出站请求令牌桶（容量由 `GITHUB_MAX_REQUESTS_PER_HOUR` 配置，默认 4500）
+pub fn get_github_token_bucket() -> &'static GithubTokenBucket {
+    GITHUB_TOKEN_BUCKET.get_or_init(|| {
+        let max_requests_per_hour = std::env::var("GITHUB_MAX_REQUESTS_PER_HOUR")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(4500);
+        GithubTokenBucket::new(max_requests_per_hour)
+    })
+}
+
+/// 同时发往 GitHub API 的请求数上限
+///
+/// 所有 GitHub API 请求（包括批量查询中每个仓库的每个字段）发送前都要取得许可，
+/// 批量查询无论包含多少仓库和字段，同时进行的请求数都不会超过上限，超出的请求排队等待。
+pub struct GithubConcurrencyLimit {
+    /// 未启用限制时为 None
+    semaphore: Option<Arc<Semaphore>>,
+}
+
+impl GithubConcurrencyLimit {
+    /// max_concurrent 为 0 表示不限制
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            semaphore: (max_concurrent > 0).then(|| Arc::new(Semaphore::new(max_concurrent))),
+        }
+    }
+
+    /// 等待并取得一个许可，许可在返回值被丢弃时释放；未启用限制时立即返回 None
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let semaphore = self.semaphore.as_ref()?;
+        // 信号量不会被关闭
+        semaphore.clone().acquire_owned().await.ok()
+    }
+}
+
+static GITHUB_CONCURRENCY_LIMIT: OnceLock<GithubConcurrencyLimit> = OnceLock::new();
+
+/// 获取全局 GitHub 并发请求限制（由 `GITHUB_MAX_CONCURRENT_REQUESTS` 配置，默认 20）
+pub fn get_github_concurrency_limit() -> &'static GithubConcurrencyLimit {
+    GITHUB_CONCURRENCY_LIMIT.get_or_init(|| {
+        let max_concurrent = std::env::var("GITHUB_MAX_CONCURRENT_REQUESTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(20);
+        GithubConcurrencyLimit::new(max_concurrent)
+    })
+}
+
+/// 网段（CIDR），如 `10.0.0.0/8`、`::1/128`，用于可信代理和限流豁免
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// 解析 CIDR，省略前缀长度时表示单个地址
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len.parse::<u8>().ok()?)),
+            None => (s, None),
+        };
+        let network: IpAddr = addr.parse().ok()?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = prefix_len.unwrap_or(max_len);
+        if prefix_len > max_len {
+            return None;
+        }
+        Some(Self { network, prefix_len })
+    }
+
+    /// 判断地址是否属于该网段（IPv4 映射的 IPv6 地址按 IPv4 处理）
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            v4 => v4,
+        };
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                prefix_matches(u32::from(network).into(), u32::from(ip).into(), 32, self.prefix_len)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                prefix_matches(u128::from(network), u128::from(ip), 128, self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+// 解析逗号分隔的网段列表，跳过无效的条目并输出警告
+fn parse_cidr_list(name: &str, cidrs: &str) -> Vec<IpCidr> {
+    cidrs
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter_map(|cidr| {
+            let parsed = IpCidr::parse(cidr);
+            if parsed.is_none() {
+                log::warn!("忽略无效的 {} 条目: {}", name, cidr);
+            }
+            parsed
+        })
+        .collect()
+}
+
+// 比较两个地址（以 bits 位整数表示）的前 prefix_len 位是否相同
+fn prefix_matches(network: u128, ip: u128, bits: u8, prefix_len: u8) -> bool {
+    if prefix_len == 0 {
+        return true;
+    }
+    let shift = bits - prefix_len;
+    (network >> shift) == (ip >> shift)
+}
+
+// 解析转发请求头中的单个地址（兼容带端口的形式，如 `1.2.3.4:5678`、`[::1]:80`）
+fn parse_forwarded_ip(s: &str) -> Option<IpAddr> {
+    let s = s.trim();
+    s.parse::<IpAddr>()
+        .ok()
+        .or_else(|| s.parse::<std::net::SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+/// 客户端 IP 识别配置
+#[derive(Clone, Debug)]
+pub struct ClientIpConfig {
+    /// 是否信任反向代理传递的客户端 IP 请求头（服务直接暴露在公网时必须关闭，否则客户端可以伪造 IP 绕过限流）
+    pub trust_forwarded_headers: bool,
+    /// 反向代理传递客户端 IP 使用的请求头（如 `X-Forwarded-For`、`X-Real-IP`）
+    pub trusted_proxy_header: String,
+    /// 可信代理的网段；非空时只信任来自这些地址的转发请求头，并在解析时跳过这些地址
+    pub trusted_proxy_cidrs: Vec<IpCidr>,
+}
+
+impl Default for ClientIpConfig {
+    fn default() -> Self {
+        Self {
+            trust_forwarded_headers: false,
+            trusted_proxy_header: "X-Forwarded-For".to_string(),
+            trusted_proxy_cidrs: Vec::new(),
+        }
+    }
+}
+
+impl ClientIpConfig {
+    /// 从环境变量加载配置
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        // TRUST_PROXY 是旧的变量名，继续兼容
+        let trust_forwarded_headers = std::env::var("TRUST_FORWARDED_HEADERS")
+            .or_else(|_| std::env::var("TRUST_PROXY"))
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default.trust_forwarded_headers);
+
+        let trusted_proxy_header = std::env::var("TRUSTED_PROXY_HEADER")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or(default.trusted_proxy_header);
+
+        let trusted_proxy_cidrs = std::env::var("TRUSTED_PROXY_CIDRS")
+            .map(|cidrs| parse_cidr_list("TRUSTED_PROXY_CIDRS", &cidrs))
+            .unwrap_or(default.trusted_proxy_cidrs);
+
+        Self {
+            trust_forwarded_headers,
+            trusted_proxy_header,
+            trusted_proxy_cidrs,
+        }
+    }
+
+    // 判断地址是否为可信代理
+    fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxy_cidrs.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    /// 从转发请求头中解析客户端 IP
+    ///
+    /// 最左侧的地址由客户端自己填写，可以任意伪造，因此从右向左查找：跳过可信代理的地址，
+    /// 返回第一个不可信的地址。未配置可信网段时直接使用最右侧的地址（即直连代理看到的对端地址）。
+    /// 遇到无法解析的地址时停止，返回 None。
+    pub fn forwarded_client_ip(&self, header_value: &str) -> Option<IpAddr> {
+        let mut last_trusted = None;
+        for hop in header_value.rsplit(',') {
+            let ip = parse_forwarded_ip(hop)?;
+            if !self.is_trusted_proxy(ip) {
+                return Some(ip);
+            }
+            last_trusted = Some(ip);
+        }
+        // 所有地址都是可信代理时，使用最左侧的地址
+        last_trusted
+    }
+
+    /// 获取客户端 IP 地址
+    ///
+    /// 启用 `trust_forwarded_headers` 且对端地址是可信代理时，从代理请求头中解析客户端 IP，
+    /// 否则只使用连接的对端地址。两者都无法获取时使用连接标识（`conn-<id>`），
+    /// 避免所有此类客户端共享同一个限流额度。
+    pub fn client_ip(&self, req: &HttpRequest) -> String {
+        let peer_addr = req.peer_addr().map(|addr| addr.ip());
+
+        let peer_trusted = match peer_addr {
+            Some(ip) => self.trusted_proxy_cidrs.is_empty() || self.is_trusted_proxy(ip),
+            // 没有对端地址（如 Unix socket）时无法校验代理，只在未配置可信网段时信任请求头
+            None => self.trusted_proxy_cidrs.is_empty(),
+        };
+
+        let forwarded = if self.trust_forwarded_headers && peer_trusted {
+            req.headers()
+                .get(self.trusted_proxy_header.as_str())
+                .and_then(|h| h.to_str().ok())
+                .and_then(|value| self.forwarded_client_ip(value))
+        } else {
+            None
+        };
+
+        forwarded
+            .or(peer_addr)
+            .map(|ip| ip.to_string())
+            .or_else(|| req.conn_data::<ConnectionId>().map(|id| format!("conn-{}", id.0)))
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+static CLIENT_IP_CONFIG: OnceLock<ClientIpConfig> = OnceLock::new();
+
+/// 获取全局客户端 IP 识别配置
+pub fn get_client_ip_config() -> &'static ClientIpConfig {
+    CLIENT_IP_CONFIG.get_or_init(ClientIpConfig::from_env)
+}
+
+/// 连接标识，在无法获取客户端 IP 时代替 IP 用于限流
+#[derive(Clone, Copy, Debug)]
+pub struct ConnectionId(pub u64);
+
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 为每个新连接分配连接标识（用于 `HttpServer::on_connect`）
+pub fn assign_connection_id(_connection: &dyn Any, data: &mut Extensions) {
+    data.insert(ConnectionId(NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed)));
+}
+
+/// 获取客户端 IP 地址（用于限流）
+pub fn client_ip(req: &HttpRequest) -> String {
+    get_client_ip_config().client_ip(req)
+}
+
+/// 请求频率限制中间件：按端点类别（API / 下载）和客户端 IP 分别限流，
+/// 强制刷新请求还需要通过更严格的刷新限流
+pub async fn rate_limit_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    if let Some(class) = EndpointClass::from_path(req.path()) {
+        let client_ip = client_ip(req.request());
+        let manager = get_rate_limit_manager().await;
+        let mut result = manager.check_rate_limit(class, &client_ip);
+        if result.is_ok() && class == EndpointClass::Api && is_refresh_request(req.query_string()) {
+            result = manager.check_rate_limit(EndpointClass::Refresh, &client_ip);
+        }
+        if let Err(e) = result {
+            log::warn!("请求被限流: {} {} (IP: {})", req.method(), req.path(), client_ip);
+            // 直接返回 429 响应（而不是错误），确保外层的 CORS 等中间件仍能正常处理
+            let response = AppError::from(e).error_response();
+            return Ok(req.into_response(response).map_into_right_body());
+        }
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tokio::time::Duration;
 
+    #[tokio::test]
+    async fn test_github_concurrency_limit_bounds_batch_fan_out() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // 模拟 50 个仓库 × 3 个字段的批量查询，每个字段一次 GitHub 请求
+        let limit = Arc::new(GithubConcurrencyLimit::new(4));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let requests = (0..50 * 3).map(|_| {
+            let (limit, in_flight, max_in_flight) = (limit.clone(), in_flight.clone(), max_in_flight.clone());
+            async move {
+                let _permit = limit.acquire().await;
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(2)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+        futures::future::join_all(requests).await;
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 4);
+
+        // 0 表示不限制
+        assert!(GithubConcurrencyLimit::new(0).acquire().await.is_none());
+    }
+
+    #[test]
+    fn test_github_token_bucket_exhausts_and_reports_wait() {
+        let bucket = GithubTokenBucket::new(3600);
+        {
+            // 直接清空桶，避免测试依赖 3600 次取令牌
+            let mut state = bucket.state.lock().unwrap();
+            state.tokens = 2.0;
+        }
+        assert!(bucket.try_acquire().is_ok());
+        assert!(bucket.try_acquire().is_ok());
+        // 3600 次/小时即每秒补充 1 个令牌
+        let wait = bucket.try_acquire().unwrap_err();
+        assert!(wait <= Duration::from_secs(1));
+        assert!(wait > Duration::from_millis(900));
+        assert_eq!(bucket.remaining(), Some(0));
+    }
+
+    #[test]
+    fn test_github_token_bucket_refills_up_to_capacity() {
+        let bucket = GithubTokenBucket::new(3600);
+        {
+            let mut state = bucket.state.lock().unwrap();
+            state.tokens = 0.0;
+            state.last_refill = Instant::now() - Duration::from_secs(10);
+        }
+        assert_eq!(bucket.remaining(), Some(10));
+        {
+            let mut state = bucket.state.lock().unwrap();
+            state.last_refill = Instant::now() - Duration::from_secs(7200);
+        }
+        assert_eq!(bucket.remaining(), Some(3600));
+    }
+
+    #[test]
+    fn test_github_token_bucket_disabled() {
+        let bucket = GithubTokenBucket::new(0);
+        assert!(!bucket.is_enabled());
+        assert!(bucket.try_acquire().is_ok());
+        assert_eq!(bucket.remaining(), None);
+    }
+
+    #[tokio::test]
+    async fn test_github_token_bucket_returns_503_when_wait_too_long() {
+        let bucket = GithubTokenBucket::new(1);
+        assert!(bucket.acquire(Duration::ZERO).await.is_ok());
+        match bucket.acquire(Duration::from_millis(10)).await {
+            Err(AppError::ServiceUnavailable { retry_after_secs, .. }) => {
+                assert!(retry_after_secs > 3500 && retry_after_secs <= 3600);
+            }
+            other => panic!("期望 503，实际为 {:?}", other.map(|_| ())),
+        }
+    }
+
     #[tokio::test]
     async fn test_rate_limit_config_default() {
         let config = RateLimitConfig::default();
         assert_eq!(config.max_concurrent_downloads, 10);
     }
 
+    fn trusting_config(cidrs: &[&str]) -> ClientIpConfig {
+        ClientIpConfig {
+            trust_forwarded_headers: true,
+            trusted_proxy_cidrs: cidrs.iter().map(|c| IpCidr::parse(c).unwrap()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ip_cidr_parse_and_contains() {
+        let v4 = IpCidr::parse("10.0.0.0/8").unwrap();
+        assert!(v4.contains("10.1.2.3".parse().unwrap()));
+        assert!(!v4.contains("11.0.0.1".parse().unwrap()));
+        // IPv4 映射的 IPv6 地址按 IPv4 处理
+        assert!(v4.contains("::ffff:10.0.0.1".parse().unwrap()));
+
+        let single = IpCidr::parse("192.168.1.1").unwrap();
+        assert!(single.contains("192.168.1.1".parse().unwrap()));
+        assert!(!single.contains("192.168.1.2".parse().unwrap()));
+
+        let v6 = IpCidr::parse("fd00::/8").unwrap();
+        assert!(v6.contains("fd12::1".parse().unwrap()));
+        assert!(!v6.contains("fe80::1".parse().unwrap()));
+        assert!(!v6.contains("10.0.0.1".parse().unwrap()));
+
+        assert!(IpCidr::parse("0.0.0.0/0").unwrap().contains("8.8.8.8".parse().unwrap()));
+
+        assert!(IpCidr::parse("10.0.0.0/33").is_none());
+        assert!(IpCidr::parse("not-an-ip/8").is_none());
+        assert!(IpCidr::parse("10.0.0.0/x").is_none());
+    }
+
+    #[test]
+    fn test_forwarded_client_ip_uses_rightmost_untrusted_hop() {
+        // 未配置可信网段时使用最右侧的地址，客户端伪造的最左侧地址被忽略
+        let config = trusting_config(&[]);
+        assert_eq!(
+            config.forwarded_client_ip("6.6.6.6, 1.2.3.4"),
+            Some("1.2.3.4".parse().unwrap())
+        );
+
+        // 跳过可信代理的地址
+        let config = trusting_config(&["10.0.0.0/8"]);
+        assert_eq!(
+            config.forwarded_client_ip("6.6.6.6, 1.2.3.4, 10.0.0.2, 10.0.0.3"),
+            Some("1.2.3.4".parse().unwrap())
+        );
+        // 所有地址都是可信代理时使用最左侧的地址
+        assert_eq!(
+            config.forwarded_client_ip("10.0.0.5, 10.0.0.2"),
+            Some("10.0.0.5".parse().unwrap())
+        );
+        // 兼容带端口的地址
+        assert_eq!(
+            trusting_config(&["fd00::/8"]).forwarded_client_ip("1.2.3.4:5678, [fd00::1]:80"),
+            Some("1.2.3.4".parse().unwrap())
+        );
+        // 在找到不可信地址之前遇到无法解析的地址时放弃
+        assert_eq!(config.forwarded_client_ip("1.2.3.4, garbage, 10.0.0.2"), None);
+        assert_eq!(config.forwarded_client_ip(""), None);
+    }
+
+    #[test]
+    fn test_client_ip_ignores_forwarded_header_by_default() {
+        let req = actix_web::test::TestRequest::default()
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "1.2.3.4"))
+            .to_http_request();
+        assert_eq!(ClientIpConfig::default().client_ip(&req), "10.0.0.1");
+    }
+
+    #[test]
+    fn test_client_ip_uses_trusted_proxy_header() {
+        let req = actix_web::test::TestRequest::default()
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "6.6.6.6, 1.2.3.4"))
+            .to_http_request();
+        assert_eq!(trusting_config(&[]).client_ip(&req), "1.2.3.4");
+        assert_eq!(trusting_config(&["10.0.0.0/8"]).client_ip(&req), "1.2.3.4");
+
+        let config = ClientIpConfig {
+            trusted_proxy_header: "X-Real-IP".to_string(),
+            ..trusting_config(&[])
+        };
+        let req = actix_web::test::TestRequest::default()
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "1.2.3.4"))
+            .insert_header(("X-Real-IP", "5.6.7.8"))
+            .to_http_request();
+        assert_eq!(config.client_ip(&req), "5.6.7.8");
+
+        // 代理没有传递请求头时使用对端地址
+        let req = actix_web::test::TestRequest::default()
+            .peer_addr("10.0.0.1:1234".parse().unwrap())
+            .to_http_request();
+        assert_eq!(trusting_config(&[]).client_ip(&req), "10.0.0.1");
+    }
+
+    #[test]
+    fn test_client_ip_ignores_header_from_untrusted_peer() {
+        // 对端不在可信网段内（客户端直连）时忽略请求头
+        let req = actix_web::test::TestRequest::default()
+            .peer_addr("8.8.8.8:1234".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "1.2.3.4"))
+            .to_http_request();
+        assert_eq!(trusting_config(&["10.0.0.0/8"]).client_ip(&req), "8.8.8.8");
+    }
+
+    #[test]
+    fn test_client_ip_falls_back_to_connection_id() {
+        let req = actix_web::test::TestRequest::default().to_http_request();
+        assert_eq!(ClientIpConfig::default().client_ip(&req), "unknown");
+
+        let mut first = Extensions::new();
+        assign_connection_id(&(), &mut first);
+        let mut second = Extensions::new();
+        assign_connection_id(&(), &mut second);
+        let first = first.get::<ConnectionId>().unwrap().0;
+        let second = second.get::<ConnectionId>().unwrap().0;
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_endpoint_class_from_path() {
+        assert_eq!(EndpointClass::from_path("/download"), Some(EndpointClass::Download));
+        assert_eq!(
+            EndpointClass::from_path("/repos/owner/repo/releases/latest/assets/app.zip"),
+            Some(EndpointClass::Download)
+        );
+        assert_eq!(EndpointClass::from_path("/repos/owner/repo"), Some(EndpointClass::Api));
+        assert_eq!(EndpointClass::from_path("/repos/batch"), Some(EndpointClass::Api));
+        assert_eq!(EndpointClass::from_path("/health"), None);
+        assert_eq!(EndpointClass::from_path("/swagger-ui/"), None);
+    }
+
+    #[test]
+    fn test_check_rate_limit_per_endpoint_class() {
+        let config = RateLimitConfig {
+            api_max_requests_per_window: 3,
+            download_max_requests_per_window: 1,
+            ..Default::default()
+        };
+        let manager = RateLimitManager::new(config);
+
+        // 下载和 API 使用独立的额度
+        assert!(manager.check_rate_limit(EndpointClass::Download, "1.2.3.4").is_ok());
+        assert!(manager.check_rate_limit(EndpointClass::Download, "1.2.3.4").is_err());
+        for _ in 0..3 {
+            assert!(manager.check_rate_limit(EndpointClass::Api, "1.2.3.4").is_ok());
+        }
+        assert!(matches!(
+            manager.check_rate_limit(EndpointClass::Api, "1.2.3.4"),
+            Err(RateLimitError::TooManyRequests { max_requests: 3, .. })
+        ));
+
+        // 不同 IP 互不影响
+        assert!(manager.check_rate_limit(EndpointClass::Api, "5.6.7.8").is_ok());
+    }
+
+    #[test]
+    fn test_check_rate_limit_exempt_cidrs() {
+        let config = RateLimitConfig {
+            download_max_requests_per_window: 1,
+            exempt_cidrs: parse_cidr_list("RATE_LIMIT_EXEMPT_CIDRS", "10.0.0.0/8, invalid, ::1"),
+            ..Default::default()
+        };
+        assert_eq!(config.exempt_cidrs.len(), 2);
+        let manager = RateLimitManager::new(config);
+
+        // 豁免网段内的 IP 不受限制
+        for ip in ["10.1.2.3", "::1"] {
+            for _ in 0..5 {
+                assert!(manager.check_rate_limit(EndpointClass::Download, ip).is_ok());
+            }
+        }
+
+        // 网段外的 IP 照常限流
+        assert!(manager.check_rate_limit(EndpointClass::Download, "1.2.3.4").is_ok());
+        assert!(manager.check_rate_limit(EndpointClass::Download, "1.2.3.4").is_err());
+
+        // 无法解析为 IP 的客户端标识不豁免
+        assert!(!manager.config.is_exempt("unknown"));
+        assert!(!manager.config.is_exempt("conn-1"));
+    }
+
+    #[test]
+    fn test_is_refresh_request() {
+        assert!(is_refresh_request("refresh=true"));
+        assert!(is_refresh_request("pretty=true&refresh=true"));
+        assert!(!is_refresh_request("refresh=false"));
+        assert!(!is_refresh_request(""));
+        assert!(!is_refresh_request("refresh=yes"));
+    }
+
+    #[test]
+    fn test_check_rate_limit_refresh_class() {
+        let config = RateLimitConfig {
+            refresh_max_requests_per_window: 1,
+            ..Default::default()
+        };
+        let manager = RateLimitManager::new(config);
+
+        assert!(manager.check_rate_limit(EndpointClass::Refresh, "1.2.3.4").is_ok());
+        assert!(manager.check_rate_limit(EndpointClass::Refresh, "1.2.3.4").is_err());
+        // 普通 API 请求不受刷新额度影响
+        assert!(manager.check_rate_limit(EndpointClass::Api, "1.2.3.4").is_ok());
+    }
+
+    #[test]
+    fn test_check_rate_limit_unlimited() {
+        let config = RateLimitConfig {
+            api_max_requests_per_window: 0,
+            ..Default::default()
+        };
+        let manager = RateLimitManager::new(config);
+        for _ in 0..1000 {
+            assert!(manager.check_rate_limit(EndpointClass::Api, "1.2.3.4").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_too_many_requests_maps_to_429() {
+        let error: AppError = RateLimitError::TooManyRequests {
+            max_requests: 100,
+            window_secs: 60,
+        }
+        .into();
+        assert_eq!(
+            error.error_response().status(),
+            actix_web::http::StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[test]
+    fn test_try_acquire_batch_permit() {
+        let config = RateLimitConfig {
+            max_concurrent_batches: 1,
+            ..Default::default()
+        };
+        let manager = RateLimitManager::new(config);
+
+        let permit = manager.try_acquire_batch_permit().unwrap();
+        assert!(permit.is_some());
+        let result = manager.try_acquire_batch_permit();
+        assert!(matches!(result, Err(RateLimitError::TooManyBatches { .. })));
+
+        let resp = AppError::from(result.unwrap_err()).error_response();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers().get("Retry-After").unwrap(), "1");
+
+        // 释放后可以再次获取
+        drop(permit);
+        assert!(manager.try_acquire_batch_permit().unwrap().is_some());
+
+        // 0 表示不限制
+        let manager = RateLimitManager::new(RateLimitConfig {
+            max_concurrent_batches: 0,
+            ..Default::default()
+        });
+        let _permits: Vec<_> = (0..10).map(|_| manager.try_acquire_batch_permit().unwrap()).collect();
+    }
+
     #[tokio::test]
     async fn test_rate_limit_config_from_env() {
         std::env::set_var("MAX_CONCURRENT_DOWNLOADS", "5");
@@ -120,12 +1135,13 @@ mod tests {
     async fn test_rate_limit_manager_concurrent_limit() {
         let config = RateLimitConfig {
             max_concurrent_downloads: 2,
+            ..Default::default()
         };
         let manager = RateLimitManager::new(config);
 
         // 获取两个许可
-        let permit1 = manager.acquire_download_permit().await;
-        let permit2 = manager.acquire_download_permit().await;
+        let permit1 = manager.acquire_download_permit().await.unwrap();
+        let permit2 = manager.acquire_download_permit().await.unwrap();
 
         // 第三个许可应该被阻塞（但我们可以设置超时来测试）
         let permit3_future = manager.acquire_download_permit();
@@ -141,7 +1157,7 @@ mod tests {
         drop(permit2);
 
         // 现在应该可以获取第三个许可
-        let permit3 = manager.acquire_download_permit().await;
+        let permit3 = manager.acquire_download_permit().await.unwrap();
         drop(permit3);
     }
 
@@ -149,6 +1165,7 @@ mod tests {
     async fn test_rate_limit_manager_multiple_permits() {
         let config = RateLimitConfig {
             max_concurrent_downloads: 3,
+            ..Default::default()
         };
         let manager = RateLimitManager::new(config);
 
@@ -161,9 +1178,89 @@ mod tests {
 
         // 验证所有许可都已获取
         assert_eq!(permits.len(), 3);
+        assert!(permits.iter().all(|p| p.is_ok()));
 
         // 释放所有许可
         drop(permits);
     }
-}
 
+    #[tokio::test]
+    async fn test_acquire_download_permit_queue_timeout() {
+        let config = RateLimitConfig {
+            max_concurrent_downloads: 1,
+            max_queue_wait_secs: 1,
+            ..Default::default()
+        };
+        let manager = RateLimitManager::new(config);
+
+        let _permit = manager.acquire_download_permit().await.unwrap();
+        let result = manager.acquire_download_permit().await;
+        assert!(matches!(result, Err(RateLimitError::QueueTimeout { .. })));
+
+        let stats = manager.download_queue_stats();
+        assert_eq!(stats.rejected_total, 1);
+        assert_eq!(stats.waiting, 0);
+        assert_eq!(stats.available_permits, 0);
+
+        let error: AppError = result.unwrap_err().into();
+        let resp = error.error_response();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SERVICE_UNAVAILABLE);
+        assert!(resp.headers().contains_key("Retry-After"));
+    }
+
+    #[tokio::test]
+    async fn test_download_queue_stats_records_wait_time() {
+        let config = RateLimitConfig {
+            max_concurrent_downloads: 1,
+            ..Default::default()
+        };
+        let manager = Arc::new(RateLimitManager::new(config));
+
+        let permit = manager.acquire_download_permit().await.unwrap();
+        let waiter = {
+            let manager = manager.clone();
+            tokio::spawn(async move { manager.acquire_download_permit().await.is_ok() })
+        };
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(manager.download_queue_stats().waiting, 1);
+        drop(permit);
+        assert!(waiter.await.unwrap());
+
+        let stats = manager.download_queue_stats();
+        assert_eq!(stats.wait_samples, 2);
+        assert!(stats.p95_wait_ms >= 50.0);
+        assert!(stats.avg_wait_ms > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_wait_releases_waiting_count() {
+        let config = RateLimitConfig {
+            max_concurrent_downloads: 1,
+            ..Default::default()
+        };
+        let manager = Arc::new(RateLimitManager::new(config));
+
+        let _permit = manager.acquire_download_permit().await.unwrap();
+        let waiter = {
+            let manager = manager.clone();
+            tokio::spawn(async move { manager.acquire_download_permit().await.is_ok() })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(manager.download_queue_stats().waiting, 1);
+
+        // 客户端断开：排队中的 future 被丢弃
+        waiter.abort();
+        let _ = waiter.await;
+        assert_eq!(manager.download_queue_stats().waiting, 0);
+    }
+
+    #[test]
+    fn test_wait_time_stats_p95() {
+        let mut stats = WaitTimeStats::default();
+        for ms in 1..=100 {
+            stats.record(Duration::from_millis(ms));
+        }
+        assert_eq!(stats.p95(), Duration::from_millis(95));
+        assert_eq!(stats.average(), Duration::from_micros(50_500));
+    }
+}
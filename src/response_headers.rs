@@ -0,0 +1,213 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue, AUTHORIZATION, CACHE_CONTROL};
+use actix_web::middleware::Next;
+use std::sync::OnceLock;
+
+/// 为所有响应添加的固定响应头
+///
+/// 默认始终添加 `X-Content-Type-Options: nosniff`（服务会代理下载任意文件，避免浏览器按内容猜测类型），
+/// 其他响应头通过 `EXTRA_RESPONSE_HEADERS` 配置。
+#[derive(Clone, Debug)]
+pub struct ResponseHeadersConfig {
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl Default for ResponseHeadersConfig {
+    fn default() -> Self {
+        Self {
+            headers: vec![(
+                actix_web::http::header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            )],
+        }
+    }
+}
+
+impl ResponseHeadersConfig {
+    /// 从环境变量加载配置
+    ///
+    /// `EXTRA_RESPONSE_HEADERS` 的格式为 `名称:值`，多个响应头之间用换行或分号分隔；
+    /// 与默认响应头同名时覆盖默认值。名称或值无效的条目会被跳过并输出警告。
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+
+        let mut config = Self::default();
+        if let Ok(value) = std::env::var("EXTRA_RESPONSE_HEADERS") {
+            for (name, value) in parse_headers(&value) {
+                config.headers.retain(|(existing, _)| *existing != name);
+                config.headers.push((name, value));
+            }
+        }
+        config
+    }
+}
+
+// 解析 `名称:值` 列表，跳过无效的条目
+fn parse_headers(value: &str) -> Vec<(HeaderName, HeaderValue)> {
+    value
+        .split(['\n', ';'])
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let Some((name, value)) = entry.split_once(':') else {
+                log::warn!("忽略无效的响应头配置（缺少冒号）: {}", entry);
+                return None;
+            };
+            let name = match HeaderName::from_bytes(name.trim().as_bytes()) {
+                Ok(name) => name,
+                Err(_) => {
+                    log::warn!("忽略无效的响应头名称: {}", name.trim());
+                    return None;
+                }
+            };
+            match HeaderValue::from_str(value.trim()) {
+                Ok(value) => Some((name, value)),
+                Err(_) => {
+                    log::warn!("忽略无效的响应头值: {}: {}", name, value.trim());
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+static RESPONSE_HEADERS_CONFIG: OnceLock<ResponseHeadersConfig> = OnceLock::new();
+
+/// 获取全局响应头配置
+pub fn get_response_headers_config() -> &'static ResponseHeadersConfig {
+    RESPONSE_HEADERS_CONFIG.get_or_init(ResponseHeadersConfig::from_env)
+}
+
+// 将 `Cache-Control` 中的 public 改为 private，没有 public 时返回 None
+fn privatize_cache_control(value: &HeaderValue) -> Option<HeaderValue> {
+    let value = value.to_str().ok()?;
+    let mut changed = false;
+    let directives: Vec<&str> = value
+        .split(',')
+        .map(str::trim)
+        .map(|directive| {
+            if directive.eq_ignore_ascii_case("public") {
+                changed = true;
+                "private"
+            } else {
+                directive
+            }
+        })
+        .collect();
+    if !changed {
+        return None;
+    }
+    HeaderValue::from_str(&directives.join(", ")).ok()
+}
+
+/// 响应头中间件：为所有响应添加配置的响应头（处理函数已设置的同名响应头保持不变）
+///
+/// 带 `Authorization` 头的请求的响应不能被共享缓存保存，`Cache-Control: public` 会改为 `private`。
+pub async fn response_headers_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let authenticated = req.headers().contains_key(AUTHORIZATION);
+    let mut res = next.call(req).await?;
+    let headers = res.headers_mut();
+    if authenticated {
+        if let Some(value) = headers.get(CACHE_CONTROL).and_then(privatize_cache_control) {
+            headers.insert(CACHE_CONTROL, value);
+        }
+    }
+    for (name, value) in &get_response_headers_config().headers {
+        if !headers.contains_key(name) {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+
+    #[test]
+    fn test_parse_headers() {
+        let headers = parse_headers(
+            "Referrer-Policy: no-referrer; X-Service-Instance:node-1\nInvalid Name: x;missing-colon;X-Bad: a\u{7f}b",
+        );
+        let headers: Vec<(&str, &str)> = headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.to_str().unwrap()))
+            .collect();
+        assert_eq!(
+            headers,
+            vec![("referrer-policy", "no-referrer"), ("x-service-instance", "node-1")]
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_middleware_adds_default_headers() {
+        let app = init_service(
+            App::new()
+                .wrap(from_fn(response_headers_middleware))
+                .route("/", web::get().to(HttpResponse::Ok))
+                .route(
+                    "/custom",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .insert_header(("X-Content-Type-Options", "custom"))
+                            .finish()
+                    }),
+                ),
+        )
+        .await;
+
+        let resp = call_service(&app, TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(resp.headers().get("x-content-type-options").unwrap(), "nosniff");
+
+        // 404 等错误响应同样带上响应头
+        let resp = call_service(&app, TestRequest::get().uri("/missing").to_request()).await;
+        assert_eq!(resp.headers().get("x-content-type-options").unwrap(), "nosniff");
+
+        let resp = call_service(&app, TestRequest::get().uri("/custom").to_request()).await;
+        assert_eq!(resp.headers().get("x-content-type-options").unwrap(), "custom");
+    }
+
+    #[test]
+    fn test_privatize_cache_control() {
+        let privatize = |value| privatize_cache_control(&HeaderValue::from_static(value));
+        assert_eq!(privatize("public, max-age=60").unwrap(), "private, max-age=60");
+        assert_eq!(
+            privatize("public, max-age=0, stale-if-error=30").unwrap(),
+            "private, max-age=0, stale-if-error=30"
+        );
+        assert!(privatize("private, max-age=60").is_none());
+        assert!(privatize("no-store").is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_middleware_privatizes_authenticated_responses() {
+        let app = init_service(
+            App::new().wrap(from_fn(response_headers_middleware)).route(
+                "/",
+                web::get().to(|| async {
+                    HttpResponse::Ok()
+                        .insert_header(("Cache-Control", "public, max-age=60"))
+                        .finish()
+                }),
+            ),
+        )
+        .await;
+
+        let resp = call_service(&app, TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(resp.headers().get("cache-control").unwrap(), "public, max-age=60");
+
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header(("Authorization", "Bearer token"))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.headers().get("cache-control").unwrap(), "private, max-age=60");
+    }
+}
@@ -0,0 +1,144 @@
+use crate::error::AppError;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+// 在多个等待者之间共享的请求结果
+type SharedResult<T> = Result<T, Arc<AppError>>;
+
+// 进行中的请求表（缓存键 -> 共享的请求 future）
+type InFlightMap<T> = Arc<Mutex<HashMap<String, Shared<BoxFuture<'static, SharedResult<T>>>>>>;
+
+/// 请求合并（single-flight）
+///
+/// 同一个键在同一时间只会执行一次请求，其他并发的相同请求会等待并共享这次请求的结果，
+/// 用于避免缓存过期时大量并发请求同时访问 GitHub（惊群效应）。
+pub struct SingleFlight<T> {
+    in_flight: InFlightMap<T>,
+    coalesced: AtomicU64,
+}
+
+impl<T> Default for SingleFlight<T> {
+    fn default() -> Self {
+        Self {
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            coalesced: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> SingleFlight<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 执行请求；如果相同键的请求正在进行中，则等待其结果而不是重复执行
+    ///
+    /// 当已有进行中的请求时，传入的 `fetch` 不会被执行。
+    pub async fn run<Fut>(&self, key: String, fetch: Fut) -> Result<T, AppError>
+    where
+        Fut: Future<Output = Result<T, AppError>> + Send + 'static,
+    {
+        let shared = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if let Some(existing) = in_flight.get(&key) {
+                log::debug!("合并进行中的请求: {}", key);
+                self.coalesced.fetch_add(1, Ordering::Relaxed);
+                existing.clone()
+            } else {
+                let in_flight_map = self.in_flight.clone();
+                let key_for_cleanup = key.clone();
+                let shared = async move {
+                    let result = fetch.await.map_err(Arc::new);
+                    // 请求完成后立即移除，后续请求将重新走缓存或发起新的请求
+                    in_flight_map.lock().unwrap().remove(&key_for_cleanup);
+                    result
+                }
+                .boxed()
+                .shared();
+                in_flight.insert(key, shared.clone());
+                shared
+            }
+        };
+
+        shared.await.map_err(|e| e.duplicate())
+    }
+
+    /// 当前进行中的请求数量
+    pub fn in_flight_count(&self) -> usize {
+        self.in_flight.lock().unwrap().len()
+    }
+
+    /// 自创建以来被合并（等待已有请求结果、未单独执行）的请求数量
+    pub fn coalesced_count(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::time::Duration;
+
+    #[tokio::test]
+    async fn test_single_flight_coalesces_concurrent_requests() {
+        let flight: SingleFlight<u32> = SingleFlight::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let futures: Vec<_> = (0..5)
+            .map(|_| {
+                let calls = calls.clone();
+                flight.run("key".to_string(), async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok(42)
+                })
+            })
+            .collect();
+
+        let results = futures::future::join_all(futures).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(results.iter().all(|r| matches!(r, Ok(42))));
+        assert_eq!(flight.in_flight_count(), 0);
+        assert_eq!(flight.coalesced_count(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_single_flight_runs_again_after_completion() {
+        let flight: SingleFlight<u32> = SingleFlight::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..2 {
+            let calls = calls.clone();
+            let result = flight
+                .run("key".to_string(), async move {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(1)
+                })
+                .await;
+            assert!(result.is_ok());
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_single_flight_shares_errors() {
+        let flight: SingleFlight<u32> = SingleFlight::new();
+
+        let (first, second) = futures::join!(
+            flight.run("key".to_string(), async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Err(AppError::NotFound)
+            }),
+            flight.run("key".to_string(), async { Ok(1) })
+        );
+
+        assert!(matches!(first, Err(AppError::NotFound)));
+        assert!(matches!(second, Err(AppError::NotFound)));
+    }
+}
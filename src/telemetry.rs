@@ -0,0 +1,177 @@
+//! OpenTelemetry 追踪
+//!
+//! 启用 `otel` 特性并设置 `OTEL_EXPORTER_OTLP_ENDPOINT` 后，fetch、缓存查询和下载流会生成 tracing span，
+//! 并通过 OTLP（HTTP）导出；`log` 宏的输出会被桥接为 span 内的事件。
+//! 未启用特性时本模块的所有函数都是空操作。
+
+#[cfg(feature = "otel")]
+use opentelemetry::trace::TracerProvider as _;
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::trace::SdkTracerProvider;
+
+/// 追踪初始化后的守卫，被丢弃时刷新并关闭导出器
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    provider: SdkTracerProvider,
+}
+
+#[cfg(feature = "otel")]
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            eprintln!("关闭 OpenTelemetry 导出器失败: {}", e);
+        }
+    }
+}
+
+/// 判断是否配置了 OTLP 导出地址
+pub fn otlp_endpoint_configured() -> bool {
+    std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .map(|v| !v.trim().is_empty())
+        .unwrap_or(false)
+}
+
+/// 初始化 OpenTelemetry 追踪
+///
+/// 未启用 `otel` 特性或未设置 `OTEL_EXPORTER_OTLP_ENDPOINT` 时返回 None，调用方应继续使用 env_logger。
+/// 成功时会安装全局 tracing 订阅器（同时输出到控制台）并将 `log` 宏桥接到 tracing。
+#[cfg(feature = "otel")]
+pub fn init(log_level: &str) -> Option<TelemetryGuard> {
+    use opentelemetry::propagation::TextMapCompositePropagator;
+    use opentelemetry_sdk::propagation::{BaggagePropagator, TraceContextPropagator};
+    use opentelemetry_sdk::Resource;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::EnvFilter;
+
+    if !otlp_endpoint_configured() {
+        return None;
+    }
+
+    // 导出器从 OTEL_EXPORTER_OTLP_ENDPOINT 等标准环境变量读取配置
+    let exporter = match opentelemetry_otlp::SpanExporter::builder().with_http().build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("创建 OTLP 导出器失败，已禁用追踪: {}", e);
+            return None;
+        }
+    };
+
+    let service_name =
+        std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string());
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build();
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+
+    opentelemetry::global::set_tracer_provider(provider.clone());
+    // 从请求头（traceparent/baggage）中提取上游传递的追踪上下文
+    opentelemetry::global::set_text_map_propagator(TextMapCompositePropagator::new(vec![
+        Box::new(TraceContextPropagator::new()),
+        Box::new(BaggagePropagator::new()),
+    ]));
+
+    let subscriber = tracing_subscriber::registry()
+        .with(EnvFilter::new(log_level))
+        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_opentelemetry::layer().with_tracer(tracer));
+
+    if let Err(e) = tracing::subscriber::set_global_default(subscriber) {
+        eprintln!("安装 tracing 订阅器失败，已禁用追踪: {}", e);
+        return None;
+    }
+    if let Err(e) = tracing_log::LogTracer::init() {
+        eprintln!("桥接 log 到 tracing 失败: {}", e);
+    }
+
+    Some(TelemetryGuard { provider })
+}
+
+/// 初始化 OpenTelemetry 追踪（未启用 `otel` 特性，始终返回 None）
+#[cfg(not(feature = "otel"))]
+pub fn init(_log_level: &str) -> Option<TelemetryGuard> {
+    None
+}
+
+/// 在当前 span 上记录缓存是否命中（`cache.hit`）
+pub fn record_cache_hit(hit: bool) {
+    #[cfg(feature = "otel")]
+    tracing::Span::current().record("cache.hit", hit);
+    #[cfg(not(feature = "otel"))]
+    let _ = hit;
+}
+
+/// 在当前 span 上记录 GitHub 返回的状态码（`github.status`）
+pub fn record_github_status(status: u16) {
+    #[cfg(feature = "otel")]
+    tracing::Span::current().record("github.status", status);
+    #[cfg(not(feature = "otel"))]
+    let _ = status;
+}
+
+/// 下载流的 span
+///
+/// 下载流在请求处理函数返回后才被 actix 消费，因此需要单独持有 span，
+/// 直到流结束时再记录传输的字节数（`bytes`）并关闭 span。
+pub struct DownloadSpan {
+    #[cfg(feature = "otel")]
+    span: tracing::Span,
+}
+
+impl DownloadSpan {
+    /// 在当前 span 下创建下载流的子 span
+    pub fn new(client_ip: &str) -> Self {
+        #[cfg(feature = "otel")]
+        {
+            Self {
+                span: tracing::info_span!(
+                    "download_stream",
+                    client.ip = client_ip,
+                    bytes = tracing::field::Empty
+                ),
+            }
+        }
+        #[cfg(not(feature = "otel"))]
+        {
+            let _ = client_ip;
+            Self {}
+        }
+    }
+
+    /// 记录下载流传输的字节数
+    pub fn record_bytes(&self, bytes: u64) {
+        #[cfg(feature = "otel")]
+        self.span.record("bytes", bytes);
+        #[cfg(not(feature = "otel"))]
+        let _ = bytes;
+    }
+}
+
+/// 为 actix 应用创建按请求生成根 span 的中间件
+///
+/// 根 span 会从请求头中提取上游传递的 trace 上下文。
+#[cfg(feature = "otel")]
+pub fn request_tracing() -> tracing_actix_web::TracingLogger<tracing_actix_web::DefaultRootSpanBuilder> {
+    tracing_actix_web::TracingLogger::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_init_without_endpoint_is_noop() {
+        if otlp_endpoint_configured() {
+            return;
+        }
+        assert!(init("info").is_none());
+    }
+
+    #[test]
+    fn test_record_helpers_without_span() {
+        // 没有活动 span 时记录属性不应 panic
+        record_cache_hit(true);
+        record_github_status(200);
+        DownloadSpan::new("127.0.0.1").record_bytes(42);
+    }
+}
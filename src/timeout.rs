@@ -0,0 +1,151 @@
+use crate::download::is_download_path;
+use crate::error::AppError;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// 请求处理超时配置
+///
+/// 超时只覆盖处理函数生成响应（响应头）之前的时间，响应体的传输（下载流、SSE 事件流）不受限制。
+#[derive(Clone, Debug)]
+pub struct RequestTimeoutConfig {
+    /// 普通请求的处理超时，`None` 表示不限制
+    pub timeout: Option<Duration>,
+    /// 下载端点（`/download` 和按名称代理附件）的处理超时（包括排队等待下载许可和连接 GitHub 的时间），`None` 表示不限制
+    pub download_timeout: Option<Duration>,
+}
+
+impl Default for RequestTimeoutConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(60)),
+            download_timeout: None,
+        }
+    }
+}
+
+impl RequestTimeoutConfig {
+    /// 从环境变量加载配置（`REQUEST_TIMEOUT_SECS`、`DOWNLOAD_REQUEST_TIMEOUT_SECS`，`0` 表示不限制）
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+        let default = Self::default();
+
+        let timeout = |name: &str, default: Option<Duration>| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(|secs| (secs > 0).then(|| Duration::from_secs(secs)))
+                .unwrap_or(default)
+        };
+
+        Self {
+            timeout: timeout("REQUEST_TIMEOUT_SECS", default.timeout),
+            download_timeout: timeout("DOWNLOAD_REQUEST_TIMEOUT_SECS", default.download_timeout),
+        }
+    }
+
+    /// 指定路径适用的超时
+    pub fn timeout_for(&self, path: &str) -> Option<Duration> {
+        if is_download_path(path) {
+            self.download_timeout
+        } else {
+            self.timeout
+        }
+    }
+}
+
+static REQUEST_TIMEOUT_CONFIG: OnceLock<RequestTimeoutConfig> = OnceLock::new();
+
+/// 获取全局请求超时配置
+pub fn get_request_timeout_config() -> &'static RequestTimeoutConfig {
+    REQUEST_TIMEOUT_CONFIG.get_or_init(RequestTimeoutConfig::from_env)
+}
+
+/// 请求超时中间件：处理时间超过配置的超时后取消处理并返回 504
+pub async fn request_timeout_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let timeout = get_request_timeout_config().timeout_for(req.path());
+    with_timeout(timeout, req, next).await
+}
+
+// 在超时限制内调用后续服务，超时时丢弃处理中的 future（取消处理）并返回 504 错误
+//
+// 与限流中间件不同，这里无法直接构造响应：请求在路由匹配之前不能被克隆，
+// 而超时后原请求已随处理中的 future 一起丢弃。
+async fn with_timeout(
+    timeout: Option<Duration>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let Some(timeout) = timeout else {
+        return next.call(req).await;
+    };
+
+    let method = req.method().clone();
+    let path = req.path().to_string();
+    match tokio::time::timeout(timeout, next.call(req)).await {
+        Ok(res) => res,
+        Err(_) => {
+            log::warn!(
+                "请求处理超时（{} 秒）: {} {}",
+                timeout.as_secs(),
+                method,
+                path
+            );
+            Err(AppError::Timeout(timeout.as_secs()).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::test::{call_service, init_service, try_call_service, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+
+    #[test]
+    fn test_timeout_for() {
+        let config = RequestTimeoutConfig::default();
+        assert_eq!(
+            config.timeout_for("/repos/owner/repo"),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(config.timeout_for("/download"), None);
+    }
+
+    #[actix_web::test]
+    async fn test_with_timeout() {
+        let app = init_service(
+            App::new()
+                .wrap(from_fn(|req, next| {
+                    with_timeout(Some(Duration::from_millis(50)), req, next)
+                }))
+                .route("/fast", web::get().to(HttpResponse::Ok))
+                .route(
+                    "/slow",
+                    web::get().to(|| async {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        HttpResponse::Ok().finish()
+                    }),
+                ),
+        )
+        .await;
+
+        let resp = call_service(&app, TestRequest::get().uri("/fast").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let result = try_call_service(&app, TestRequest::get().uri("/slow").to_request()).await;
+        let Err(err) = result else {
+            panic!("expected timeout error");
+        };
+        assert_eq!(
+            err.error_response().status(),
+            actix_web::http::StatusCode::GATEWAY_TIMEOUT
+        );
+    }
+}
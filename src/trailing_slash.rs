@@ -0,0 +1,75 @@
+use actix_web::middleware::{Condition, NormalizePath, TrailingSlash};
+use std::sync::OnceLock;
+
+/// 路由匹配前对路径末尾斜杠的处理方式（`TRAILING_SLASH`）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrailingSlashMode {
+    /// 合并连续的斜杠并去掉末尾的斜杠，`/repos/owner/repo/` 与 `/repos/owner/repo` 等价（默认）
+    Trim,
+    /// 只合并连续的斜杠，保留末尾的斜杠
+    Merge,
+    /// 不做任何处理，路径必须与注册的路由完全一致
+    Keep,
+}
+
+impl TrailingSlashMode {
+    /// 解析 `TRAILING_SLASH` 的值（不区分大小写），无法识别时返回 None
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "trim" => Some(Self::Trim),
+            "merge" => Some(Self::Merge),
+            "keep" => Some(Self::Keep),
+            _ => None,
+        }
+    }
+
+    /// 从环境变量加载配置，未设置或无法识别时使用 `trim`
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+
+        std::env::var("TRAILING_SLASH")
+            .ok()
+            .and_then(|v| Self::parse(&v))
+            .unwrap_or(Self::Trim)
+    }
+
+    /// 对应的路径规范化中间件（`keep` 时不启用）
+    ///
+    /// 只修改路径部分，查询字符串（如 `/download?url=...`）保持不变。
+    pub fn middleware(self) -> Condition<NormalizePath> {
+        let trailing_slash = match self {
+            Self::Trim | Self::Keep => TrailingSlash::Trim,
+            Self::Merge => TrailingSlash::MergeOnly,
+        };
+        Condition::new(self != Self::Keep, NormalizePath::new(trailing_slash))
+    }
+}
+
+static TRAILING_SLASH_MODE: OnceLock<TrailingSlashMode> = OnceLock::new();
+
+/// 获取全局末尾斜杠处理方式
+pub fn get_trailing_slash_mode() -> TrailingSlashMode {
+    *TRAILING_SLASH_MODE.get_or_init(TrailingSlashMode::from_env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            TrailingSlashMode::parse(" Trim "),
+            Some(TrailingSlashMode::Trim)
+        );
+        assert_eq!(
+            TrailingSlashMode::parse("merge"),
+            Some(TrailingSlashMode::Merge)
+        );
+        assert_eq!(
+            TrailingSlashMode::parse("KEEP"),
+            Some(TrailingSlashMode::Keep)
+        );
+        assert_eq!(TrailingSlashMode::parse("always"), None);
+    }
+}
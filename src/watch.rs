@@ -0,0 +1,206 @@
+use crate::error::AppError;
+use crate::handlers::fetch_latest_release;
+use crate::models::WatchEvent;
+use actix_web::web::Bytes;
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::{HashMap, VecDeque};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::{interval, Interval, MissedTickBehavior};
+
+/// Release 监听（SSE）配置
+#[derive(Clone, Debug)]
+pub struct WatchConfig {
+    /// 轮询间隔（秒）
+    pub poll_interval_secs: u64,
+    /// 每个连接最多监听的仓库数
+    pub max_repos: usize,
+    /// 所有监听连接合计最多同时请求的仓库数
+    pub max_concurrent_fetches: usize,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: 60,
+            max_repos: 20,
+            max_concurrent_fetches: 4,
+        }
+    }
+}
+
+impl WatchConfig {
+    /// 从环境变量加载配置
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        let poll_interval_secs = std::env::var("WATCH_POLL_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(default.poll_interval_secs);
+
+        let max_repos = std::env::var("WATCH_MAX_REPOS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(default.max_repos);
+
+        let max_concurrent_fetches = std::env::var("WATCH_MAX_CONCURRENT_FETCHES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(default.max_concurrent_fetches);
+
+        Self {
+            poll_interval_secs,
+            max_repos,
+            max_concurrent_fetches,
+        }
+    }
+}
+
+/// 将事件格式化为 SSE 消息
+pub fn format_sse_event(event: &str, data: &str) -> Bytes {
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
+// SSE 心跳（注释行），没有新版本时发送，用于保持连接并及时发现客户端断开
+const SSE_HEARTBEAT: &str = ": keep-alive\n\n";
+
+static WATCH_FETCH_LIMIT: OnceLock<Semaphore> = OnceLock::new();
+
+// 所有监听连接共享的并发请求限制，容量取第一次使用时的配置
+fn watch_fetch_limit(config: &WatchConfig) -> &'static Semaphore {
+    WATCH_FETCH_LIMIT.get_or_init(|| Semaphore::new(config.max_concurrent_fetches))
+}
+
+// 监听流的内部状态
+struct WatchState {
+    repos: Vec<(String, String)>,
+    // 每个仓库最后一次看到的版本号
+    last_seen: HashMap<String, String>,
+    // 等待发送的消息
+    pending: VecDeque<Bytes>,
+    interval: Interval,
+    max_concurrent_fetches: usize,
+    fetch_limit: &'static Semaphore,
+}
+
+impl WatchState {
+    // 轮询一次所有仓库，将版本号发生变化的仓库加入待发送队列
+    async fn poll(&mut self) {
+        let max_concurrent = self.max_concurrent_fetches;
+        let fetch_limit = self.fetch_limit;
+        let results: Vec<_> = stream::iter(self.repos.iter())
+            .map(|(owner, repo)| async move {
+                // 信号量不会被关闭
+                let _permit = fetch_limit.acquire().await.ok();
+                (
+                    format!("{}/{}", owner, repo),
+                    fetch_latest_release(owner, repo).await,
+                )
+            })
+            .buffer_unordered(max_concurrent)
+            .collect()
+            .await;
+
+        for (repo, result) in results {
+            match result {
+                Ok(mut release) => {
+                    if self.last_seen.get(&repo) == Some(&release.latest_version) {
+                        continue;
+                    }
+                    self.last_seen
+                        .insert(repo.clone(), release.latest_version.clone());
+                    release.apply_include_source(false);
+                    let event = WatchEvent { repo, release };
+                    match serde_json::to_string(&event) {
+                        Ok(data) => self.pending.push_back(format_sse_event("release", &data)),
+                        Err(e) => log::warn!("无法序列化监听事件: {}", e),
+                    }
+                }
+                Err(e) => {
+                    log::debug!("监听仓库获取最新 release 失败: {} ({})", repo, e);
+                }
+            }
+        }
+
+        if self.pending.is_empty() {
+            self.pending.push_back(Bytes::from_static(SSE_HEARTBEAT.as_bytes()));
+        }
+    }
+}
+
+/// 创建 release 监听流
+///
+/// 按配置的间隔轮询每个仓库的最新 release，只有版本号与上次不同时才发送 `release` 事件
+/// （连接建立后的第一轮会发送每个仓库的当前版本）。客户端断开时流会被丢弃，轮询随之停止。
+/// 同时进行的请求数受所有连接共享的 `max_concurrent_fetches` 限制。
+pub fn release_event_stream(
+    repos: Vec<(String, String)>,
+    config: &WatchConfig,
+) -> impl Stream<Item = Result<Bytes, AppError>> {
+    let mut poll_interval = interval(Duration::from_secs(config.poll_interval_secs));
+    poll_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let state = WatchState {
+        repos,
+        last_seen: HashMap::new(),
+        pending: VecDeque::new(),
+        interval: poll_interval,
+        max_concurrent_fetches: config.max_concurrent_fetches,
+        fetch_limit: watch_fetch_limit(config),
+    };
+
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(message) = state.pending.pop_front() {
+                return Some((Ok(message), state));
+            }
+            state.interval.tick().await;
+            state.poll().await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_config_default() {
+        let config = WatchConfig::default();
+        assert_eq!(config.poll_interval_secs, 60);
+        assert_eq!(config.max_repos, 20);
+        assert_eq!(config.max_concurrent_fetches, 4);
+    }
+
+    #[tokio::test]
+    async fn test_poll_waits_for_shared_fetch_limit() {
+        let fetch_limit: &'static Semaphore = Box::leak(Box::new(Semaphore::new(0)));
+        let mut state = WatchState {
+            repos: vec![("owner".to_string(), "repo".to_string())],
+            last_seen: HashMap::new(),
+            pending: VecDeque::new(),
+            interval: interval(Duration::from_secs(60)),
+            max_concurrent_fetches: 4,
+            fetch_limit,
+        };
+
+        // 其他连接占满共享额度时，本连接的轮询不会发起请求
+        let poll = tokio::time::timeout(Duration::from_millis(100), state.poll()).await;
+        assert!(poll.is_err());
+        assert!(state.pending.is_empty());
+    }
+
+    #[test]
+    fn test_format_sse_event() {
+        let bytes = format_sse_event("release", r#"{"repo":"owner/repo"}"#);
+        assert_eq!(
+            bytes,
+            Bytes::from("event: release\ndata: {\"repo\":\"owner/repo\"}\n\n")
+        );
+    }
+}
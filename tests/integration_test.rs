@@ -1,11 +1,28 @@
-use actix_web::{test, App};
+use actix_web::{test, web, App};
+use gh_info_rs::allow::unmatched_route;
 use gh_info_rs::handlers::{
-    batch_get_repos, batch_get_repos_map, download_attachment, get_latest_release, get_releases, get_repo_info,
+    batch_get_latest_versions, batch_get_repos, batch_get_repos_map, download_attachment, get_cache_stats, get_generated_release_notes, get_latest_release,
+    get_latest_release_badge, get_latest_release_notes, health,
+    get_release_assets, get_releases, get_repo_info,
+    get_stats, list_cached_repos, resolve_latest_asset, watch_releases_sse,
 };
-use gh_info_rs::models::{BatchRequest, BatchResponse, BatchResponseMap};
+use gh_info_rs::models::{BatchLatestRequest, BatchLatestResponse, BatchRequest, BatchResponse, BatchResponseMap, StatsResponse};
+use gh_info_rs::trailing_slash::TrailingSlashMode;
+use utoipa_swagger_ui::SwaggerUi;
+
+// 将持久化缓存（以及与其同级的文件缓存目录）指向临时目录，避免测试在工作目录中写入 cache.json；
+// 必须在第一次访问缓存之前调用，所有测试设置相同的值，因此与执行顺序无关
+fn init_test_env() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        let dir = std::env::temp_dir().join(format!("gh-info-rs-it-{}", std::process::id()));
+        std::env::set_var("CACHE_FILE", dir.join("cache.json"));
+    });
+}
 
 #[actix_web::test]
 async fn test_get_repo_info_route() {
+    init_test_env();
     let app = test::init_service(App::new().service(get_repo_info)).await;
 
     // 使用一个真实的GitHub仓库进行测试（如果API可用）
@@ -23,6 +40,7 @@ async fn test_get_repo_info_route() {
 
 #[actix_web::test]
 async fn test_get_releases_route() {
+    init_test_env();
     let app = test::init_service(App::new().service(get_releases)).await;
 
     let req = test::TestRequest::get()
@@ -35,6 +53,7 @@ async fn test_get_releases_route() {
 
 #[actix_web::test]
 async fn test_get_latest_release_route() {
+    init_test_env();
     let app = test::init_service(App::new().service(get_latest_release)).await;
 
     let req = test::TestRequest::get()
@@ -47,6 +66,7 @@ async fn test_get_latest_release_route() {
 
 #[actix_web::test]
 async fn test_batch_get_repos_route() {
+    init_test_env();
     let app = test::init_service(App::new().service(batch_get_repos)).await;
 
     let batch_request = BatchRequest {
@@ -71,6 +91,7 @@ async fn test_batch_get_repos_route() {
 
 #[actix_web::test]
 async fn test_batch_get_repos_empty_list() {
+    init_test_env();
     let app = test::init_service(App::new().service(batch_get_repos)).await;
 
     let batch_request = BatchRequest {
@@ -91,6 +112,7 @@ async fn test_batch_get_repos_empty_list() {
 
 #[actix_web::test]
 async fn test_batch_get_repos_map_route() {
+    init_test_env();
     let app = test::init_service(App::new().service(batch_get_repos_map)).await;
 
     let batch_request = BatchRequest {
@@ -112,8 +134,39 @@ async fn test_batch_get_repos_map_route() {
     }
 }
 
+#[actix_web::test]
+async fn test_batch_get_latest_versions_route() {
+    init_test_env();
+    let app = test::init_service(App::new().service(batch_get_latest_versions)).await;
+
+    // 格式错误的仓库不访问 GitHub，直接返回错误信息；重复的仓库只出现一次
+    let req = test::TestRequest::post()
+        .uri("/repos/batch/latest")
+        .set_json(&BatchLatestRequest {
+            repos: vec!["invalid-format".to_string(), "invalid-format".to_string()],
+        })
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let body: BatchLatestResponse = test::read_body_json(resp).await;
+    assert_eq!(body.0.len(), 1);
+    assert!(matches!(
+        body.0["invalid-format"],
+        Some(gh_info_rs::models::LatestVersionResult::Error { .. })
+    ));
+
+    // 空列表返回 400
+    let req = test::TestRequest::post()
+        .uri("/repos/batch/latest")
+        .set_json(&BatchLatestRequest { repos: vec![] })
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
+
 #[actix_web::test]
 async fn test_batch_get_repos_invalid_format() {
+    init_test_env();
     let app = test::init_service(App::new().service(batch_get_repos)).await;
 
     let batch_request = BatchRequest {
@@ -139,6 +192,7 @@ async fn test_batch_get_repos_invalid_format() {
 
 #[actix_web::test]
 async fn test_batch_get_repos_multiple_repos() {
+    init_test_env();
     let app = test::init_service(App::new().service(batch_get_repos)).await;
 
     let batch_request = BatchRequest {
@@ -165,6 +219,7 @@ async fn test_batch_get_repos_multiple_repos() {
 
 #[actix_web::test]
 async fn test_download_single_file() {
+    init_test_env();
     // 测试单个小文件下载（使用 GitHub raw 文件，通常很小）
     let app = test::init_service(App::new().service(download_attachment)).await;
 
@@ -188,6 +243,7 @@ async fn test_download_single_file() {
 
 #[actix_web::test]
 async fn test_download_missing_url() {
+    init_test_env();
     let app = test::init_service(App::new().service(download_attachment)).await;
 
     let req = test::TestRequest::get()
@@ -200,8 +256,37 @@ async fn test_download_missing_url() {
     assert!(resp.status().is_client_error());
 }
 
+#[actix_web::test]
+async fn test_download_rejects_overlong_url() {
+    init_test_env();
+    let app = test::init_service(App::new().service(download_attachment)).await;
+
+    // 超过 MAX_DOWNLOAD_URL_LEN（默认 2048）的 URL 在任何处理之前返回 400
+    let url = format!("https://example.com/{}", "a".repeat(2048));
+    let req = test::TestRequest::get()
+        .uri(&format!("/download?url={}", url))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_download_invalid_max_age() {
+    init_test_env();
+    let app = test::init_service(App::new().service(download_attachment)).await;
+
+    let req = test::TestRequest::get()
+        .uri("/download?url=https://example.com/file.txt&max_age=abc")
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
+
 #[actix_web::test]
 async fn test_download_concurrent_limit() {
+    init_test_env();
     // 测试并发下载限制
     // 设置较小的并发限制以便测试
     std::env::set_var("MAX_CONCURRENT_DOWNLOADS", "2");
@@ -209,7 +294,7 @@ async fn test_download_concurrent_limit() {
     let app = test::init_service(App::new().service(download_attachment)).await;
 
     // 使用几个小的 GitHub raw 文件进行测试
-    let test_urls = vec![
+    let test_urls = [
         "https://raw.githubusercontent.com/octocat/Hello-World/master/README",
         "https://raw.githubusercontent.com/octocat/Hello-World/master/LICENSE",
         "https://raw.githubusercontent.com/octocat/Hello-World/master/.gitignore",
@@ -243,6 +328,7 @@ async fn test_download_concurrent_limit() {
 
 #[actix_web::test]
 async fn test_download_concurrent_limit_small() {
+    init_test_env();
     // 测试严格的并发限制（设置为 1）
     std::env::set_var("MAX_CONCURRENT_DOWNLOADS", "1");
 
@@ -276,3 +362,322 @@ async fn test_download_concurrent_limit_small() {
     // 清理环境变量
     std::env::remove_var("MAX_CONCURRENT_DOWNLOADS");
 }
+
+#[actix_web::test]
+async fn test_watch_sse_invalid_repos() {
+    init_test_env();
+    let app = test::init_service(
+        App::new()
+            .service(watch_releases_sse)
+            .service(get_repo_info),
+    )
+    .await;
+
+    // 缺少 repos 参数
+    let req = test::TestRequest::get().uri("/repos/watch/sse").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+    // 仓库格式错误
+    let req = test::TestRequest::get()
+        .uri("/repos/watch/sse?repos=owner/repo,invalid")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_batch_get_repos_pretty() {
+    init_test_env();
+    let app = test::init_service(App::new().service(batch_get_repos)).await;
+
+    // 使用格式错误的仓库，无需访问 GitHub 即可得到响应
+    let batch_request = BatchRequest {
+        repos: vec!["invalid-format".to_string()],
+        fields: vec![],
+    };
+
+    let req = test::TestRequest::post()
+        .uri("/repos/batch?pretty=true")
+        .set_json(&batch_request)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let body = test::read_body(resp).await;
+    assert!(body.contains(&b'\n'), "pretty=true 时应返回缩进格式的 JSON");
+
+    // 默认返回紧凑格式
+    let req = test::TestRequest::post()
+        .uri("/repos/batch")
+        .set_json(&batch_request)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let body = test::read_body(resp).await;
+    assert!(!body.contains(&b'\n'));
+}
+
+#[actix_web::test]
+async fn test_list_cached_repos_requires_admin_token() {
+    init_test_env();
+    let app = test::init_service(App::new().service(list_cached_repos)).await;
+
+    let req = test::TestRequest::get().uri("/cache/repos").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_cache_stats_requires_admin_token() {
+    init_test_env();
+    let app = test::init_service(App::new().service(get_cache_stats)).await;
+
+    let req = test::TestRequest::get().uri("/cache/stats").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_get_stats_route() {
+    init_test_env();
+    let app = test::init_service(App::new().service(get_stats)).await;
+
+    let req = test::TestRequest::get().uri("/stats").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+
+    let stats: StatsResponse = test::read_body_json(resp).await;
+    assert!(stats.downloads.max_concurrent_downloads > 0);
+    // 默认按每小时 4500 次限制发往 GitHub 的请求
+    assert!(stats.github_tokens_remaining.is_some());
+}
+
+#[actix_web::test]
+async fn test_get_releases_invalid_since() {
+    init_test_env();
+    let app = test::init_service(App::new().service(get_releases)).await;
+
+    let req = test::TestRequest::get()
+        .uri("/repos/octocat/Hello-World/releases?since=yesterday")
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_options_returns_allow_header() {
+    init_test_env();
+    use actix_web::middleware::from_fn;
+    use gh_info_rs::allow::allow_header_middleware;
+
+    let app = test::init_service(
+        App::new()
+            .wrap(from_fn(allow_header_middleware))
+            .service(get_stats)
+            .service(batch_get_repos),
+    )
+    .await;
+
+    let req = test::TestRequest::default()
+        .method(actix_web::http::Method::OPTIONS)
+        .uri("/stats")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NO_CONTENT);
+    assert_eq!(resp.headers().get("Allow").unwrap(), "GET, OPTIONS");
+
+    let req = test::TestRequest::default()
+        .method(actix_web::http::Method::OPTIONS)
+        .uri("/repos/batch")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NO_CONTENT);
+    assert_eq!(resp.headers().get("Allow").unwrap(), "POST, OPTIONS");
+}
+
+#[actix_web::test]
+async fn test_resolve_latest_asset_requires_pattern() {
+    init_test_env();
+    let app = test::init_service(App::new().service(resolve_latest_asset)).await;
+
+    let req = test::TestRequest::get()
+        .uri("/repos/owner/repo/releases/latest/resolve")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    // 缺少 asset 参数时在请求 GitHub 之前返回 400
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_release_assets_rejects_invalid_query() {
+    init_test_env();
+    let app = test::init_service(App::new().service(get_release_assets)).await;
+
+    // 缺少 pattern 或分页参数无效时在请求 GitHub 之前返回 400
+    for uri in [
+        "/repos/owner/repo/assets",
+        "/repos/owner/repo/assets?pattern=*.dmg&page=0",
+        "/repos/owner/repo/assets?pattern=*.dmg&per_page=101",
+    ] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST, "{}", uri);
+    }
+}
+
+#[actix_web::test]
+async fn test_latest_release_notes_rejects_unknown_format() {
+    init_test_env();
+    let app = test::init_service(App::new().service(get_latest_release_notes)).await;
+
+    let req = test::TestRequest::get()
+        .uri("/repos/owner/repo/releases/latest/notes?format=pdf")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    // 无法识别的格式在请求 GitHub 之前返回 400
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_latest_release_badge_rejects_unknown_color() {
+    init_test_env();
+    let app = test::init_service(App::new().service(get_latest_release_badge)).await;
+
+    let req = test::TestRequest::get()
+        .uri("/repos/owner/repo/releases/latest/badge.svg?color=not-a-color")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    // 无法识别的颜色在请求 GitHub 之前返回 400
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_generated_release_notes_requires_tag() {
+    init_test_env();
+    let app = test::init_service(App::new().service(get_generated_release_notes)).await;
+
+    let req = test::TestRequest::get()
+        .uri("/repos/owner/repo/releases/notes")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    // 缺少 tag 参数时在请求 GitHub 之前返回 400
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_latest_release_rejects_unknown_channel() {
+    init_test_env();
+    let app = test::init_service(App::new().service(get_latest_release)).await;
+
+    let req = test::TestRequest::get()
+        .uri("/repos/owner/repo/releases/latest?channel=lts")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    // 无法识别的渠道在请求 GitHub 之前返回 400
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_unmatched_routes_return_json_errors() {
+    init_test_env();
+    let app = test::init_service(
+        App::new()
+            .service(get_repo_info)
+            .default_service(web::to(unmatched_route)),
+    )
+    .await;
+
+    let req = test::TestRequest::get().uri("/nonexistent").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "not_found");
+    assert!(body["error"].as_str().unwrap().contains("/nonexistent"));
+
+    // 路径存在但方法不支持时返回 405 和 Allow 头
+    let req = test::TestRequest::post().uri("/repos/owner/repo").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(resp.headers().get("allow").unwrap(), "GET, OPTIONS");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "method_not_allowed");
+}
+
+#[actix_web::test]
+async fn test_trailing_slash_is_trimmed() {
+    init_test_env();
+    let app = test::init_service(
+        App::new()
+            .wrap(TrailingSlashMode::Trim.middleware())
+            .service(
+                SwaggerUi::new("/swagger-ui/{_:.*}")
+                    .url("/api-doc/openapi.json", gh_info_rs::endpoints::enabled_openapi()),
+            )
+            .service(web::redirect("/swagger-ui", "/swagger-ui/index.html"))
+            .service(get_release_assets)
+            .service(download_attachment)
+            .default_service(web::to(unmatched_route)),
+    )
+    .await;
+
+    // 带末尾斜杠的路径匹配到仓库路由（缺少 pattern 参数，在请求 GitHub 之前返回 400 而不是 404）
+    let req = test::TestRequest::get()
+        .uri("/repos/octocat/Hello-World/assets/")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+    // 查询字符串保持不变
+    let req = test::TestRequest::get()
+        .uri("/download/?url=ftp://example.com/file.zip")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["error"].as_str().unwrap().contains("ftp"), "{}", body);
+
+    // Swagger UI 首页重定向到 index.html，静态文件不受影响
+    let req = test::TestRequest::get().uri("/swagger-ui/").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_redirection());
+    assert_eq!(resp.headers().get("location").unwrap(), "/swagger-ui/index.html");
+    let req = test::TestRequest::get().uri("/swagger-ui/index.html").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_health_returns_304_for_matching_etag() {
+    init_test_env();
+    let app = test::init_service(App::new().service(health)).await;
+
+    let req = test::TestRequest::get().uri("/health").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    let etag = resp.headers().get("etag").unwrap().clone();
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], "ok");
+
+    // 携带匹配的 If-None-Match 时返回不带响应体的 304
+    let req = test::TestRequest::get()
+        .uri("/health")
+        .insert_header(("If-None-Match", etag.clone()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_MODIFIED);
+    assert_eq!(resp.headers().get("etag").unwrap(), &etag);
+    assert!(test::read_body(resp).await.is_empty());
+
+    // ETag 不匹配时返回完整响应
+    let req = test::TestRequest::get()
+        .uri("/health")
+        .insert_header(("If-None-Match", "\"other\""))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+}